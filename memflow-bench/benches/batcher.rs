@@ -27,6 +27,7 @@ impl PhysicalMemory for NullMem {
         PhysicalMemoryMetadata {
             size: 0,
             readonly: true,
+            capabilities: PhysicalMemoryCapabilities::NONE,
         }
     }
 }
@@ -8,6 +8,8 @@ struct ConnectorFactoryArgs {
     name: String,
     #[darling(default)]
     version: Option<String>,
+    #[darling(default)]
+    args: Option<String>,
 }
 
 // We should add conditional compilation for the crate-type here
@@ -20,6 +22,11 @@ struct ConnectorFactoryArgs {
 // See https://github.com/rust-lang/rust/issues/20267 for the tracking issue.
 //
 // #[cfg(crate_type = "cdylib")]
+/// Exports the annotated function as a memflow connector.
+///
+/// The optional `args` parameter contains the path to a static slice of
+/// `ConnectorArgSpec`s which describes all arguments accepted by the connector:
+/// `#[connector(name = "coredump", args = "CONNECTOR_ARGS")]`
 #[proc_macro_attribute]
 pub fn connector(args: TokenStream, input: TokenStream) -> TokenStream {
     let attr_args = parse_macro_input!(args as AttributeArgs);
@@ -30,6 +37,15 @@ pub fn connector(args: TokenStream, input: TokenStream) -> TokenStream {
 
     let connector_name = args.name;
 
+    // path to a static list of `ConnectorArgSpec`s describing the arguments of the connector
+    let connector_args = match args.args {
+        Some(path) => match syn::parse_str::<syn::Path>(&path) {
+            Ok(path) => quote!(#path),
+            Err(e) => return TokenStream::from(e.to_compile_error()),
+        },
+        None => quote!(&[]),
+    };
+
     let func = parse_macro_input!(input as ItemFn);
     let func_name = &func.sig.ident;
 
@@ -44,6 +60,7 @@ pub fn connector(args: TokenStream, input: TokenStream) -> TokenStream {
         pub static MEMFLOW_CONNECTOR: ::memflow::connector::ConnectorDescriptor = ::memflow::connector::ConnectorDescriptor {
             connector_version: ::memflow::connector::MEMFLOW_CONNECTOR_VERSION,
             name: CONNECTOR_NAME,
+            args: #connector_args,
             factory: connector_factory,
         };
 
@@ -79,7 +96,15 @@ pub fn byteswap_derive(input: TokenStream) -> TokenStream {
                     ));
                 }
             }
-            _ => unimplemented!(),
+            Fields::Unnamed(unnamed) => {
+                for idx in 0..unnamed.unnamed.len() {
+                    let idx = syn::Index::from(idx);
+                    gen_inner.extend(quote!(
+                        self.#idx.byte_swap();
+                    ));
+                }
+            }
+            Fields::Unit => {}
         },
         _ => unimplemented!(),
     };
@@ -34,5 +34,25 @@ struct ByteSwapDeriveStructGenericSlice<T: ByteSwap> {
     pub slice: [ByteSwapDeriveGeneric<T>; 128],
 }
 
+#[derive(ByteSwap)]
+struct ByteSwapDeriveTuple(u16, u64);
+
+#[derive(ByteSwap)]
+struct ByteSwapDeriveUnit;
+
 #[test]
 pub fn compiles() {}
+
+#[test]
+pub fn swaps_fields() {
+    let mut named = ByteSwapDerive { val: 0x1122_3344 };
+    named.byte_swap();
+    assert_eq!(named.val, 0x4433_2211);
+
+    let mut tuple = ByteSwapDeriveTuple(0x1234, 0x1122_3344_5566_7788);
+    tuple.byte_swap();
+    assert_eq!(tuple.0, 0x3412);
+    assert_eq!(tuple.1, 0x8877_6655_4433_2211);
+
+    ByteSwapDeriveUnit.byte_swap();
+}
@@ -124,9 +124,38 @@ typedef struct PhysicalAddress {
     uint8_t page_size_log2;
 } PhysicalAddress;
 
+/**
+ * Describes the features a `PhysicalMemory` object supports using a bitflag.
+ *
+ * Wrappers and tools can use these flags to adapt their behavior to the connector,
+ * e.g. caches do not have to revalidate pages of non-volatile memory.
+ */
+typedef uint8_t PhysicalMemoryCapabilities;
+/**
+ * The memory explicitly has no capabilities.
+ */
+#define PhysicalMemoryCapabilities_NONE (uint8_t)0
+/**
+ * The memory belongs to a running system and may change at any time (as opposed to a dump file).
+ */
+#define PhysicalMemoryCapabilities_VOLATILE (uint8_t)1
+/**
+ * The memory can be written to.
+ */
+#define PhysicalMemoryCapabilities_WRITE (uint8_t)2
+/**
+ * `PhysicalMemory::cpu_state()` provides access to the cpu state of the target.
+ */
+#define PhysicalMemoryCapabilities_CPU_STATE (uint8_t)4
+/**
+ * `PhysicalMemory::prefetch()` is implemented and not just a no-op.
+ */
+#define PhysicalMemoryCapabilities_PREFETCH (uint8_t)8
+
 typedef struct PhysicalMemoryMetadata {
     uintptr_t size;
     bool readonly;
+    PhysicalMemoryCapabilities capabilities;
 } PhysicalMemoryMetadata;
 
 /**
@@ -144,6 +173,8 @@ extern const ArchitectureObj *X86_32_PAE;
 
 extern const ArchitectureObj *X86_64;
 
+extern const ArchitectureObj *X86_64_LA57;
+
 void log_init(int32_t level_num);
 
 /**
@@ -9,6 +9,9 @@ pub static X86_32_PAE: &ArchitectureObj = &x86::x32_pae::ARCH;
 #[no_mangle]
 pub static X86_64: &ArchitectureObj = &x86::x64::ARCH;
 
+#[no_mangle]
+pub static X86_64_LA57: &ArchitectureObj = &x86::x64_la57::ARCH;
+
 #[no_mangle]
 pub extern "C" fn is_x86_arch(arch: &ArchitectureObj) -> bool {
     x86::is_x86_arch(*arch)
@@ -0,0 +1,107 @@
+use std::prelude::v1::*;
+
+use std::{convert, fmt, result};
+
+#[cfg(feature = "std")]
+use std::error;
+
+// forward declare partial result extension from core for easier access
+pub use memflow::error::PartialResultExt;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Error {
+    /// Generic error type containing a string
+    Other(&'static str),
+    /// Invalid Architecture error.
+    ///
+    /// The architecture provided is not a valid argument for the given function.
+    /// `bits` contains the pointer width of the offending architecture.
+    InvalidArchitecture {
+        bits: u8,
+    },
+    /// Symbol error.
+    ///
+    /// Raised when a System.map or kallsyms file could not be parsed
+    /// or when a required symbol is missing.
+    Symbol(&'static str),
+    ProcessInfo,
+    /// memflow core error.
+    ///
+    /// Catch-all for memflow core related errors.
+    Core(memflow::error::Error),
+    /// Linked list corruption error.
+    ///
+    /// The task list did not terminate within the maximum number of entries.
+    /// This usually indicates a malformed or looping list.
+    ListCorruption(&'static str),
+}
+
+/// Convert from &str to error
+impl convert::From<&'static str> for Error {
+    fn from(error: &'static str) -> Self {
+        Error::Other(error)
+    }
+}
+
+/// Convert from flow_core::Error
+impl From<memflow::error::Error> for Error {
+    fn from(error: memflow::error::Error) -> Error {
+        Error::Core(error)
+    }
+}
+
+/// Convert from flow_core::PartialError
+impl<T> From<memflow::error::PartialError<T>> for Error {
+    fn from(_error: memflow::error::PartialError<T>) -> Error {
+        Error::Core(memflow::error::Error::Partial)
+    }
+}
+
+impl Error {
+    /// Returns a tuple representing the error description and its string value.
+    pub fn to_str_pair(self) -> (&'static str, Option<&'static str>) {
+        match self {
+            Error::Other(e) => ("other error", Some(e)),
+            Error::InvalidArchitecture { .. } => ("invalid architecture", None),
+            Error::Symbol(e) => ("error handling symbols", Some(e)),
+            Error::ProcessInfo => ("error retrieving process info", None),
+            Error::Core(e) => e.to_str_pair(),
+            Error::ListCorruption(e) => ("linked list corruption", Some(e)),
+        }
+    }
+
+    /// Returns a simple string representation of the error.
+    pub fn to_str(self) -> &'static str {
+        self.to_str_pair().0
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (desc, value) = self.to_str_pair();
+
+        match self {
+            Error::InvalidArchitecture { bits }
+            | Error::Core(memflow::error::Error::InvalidArchitecture { bits }) => {
+                write!(f, "{}: {}-bit", desc, bits)
+            }
+            _ => {
+                if let Some(value) = value {
+                    write!(f, "{}: {}", desc, value)
+                } else {
+                    f.write_str(desc)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        self.to_str()
+    }
+}
+
+/// Specialized `Result` type for memflow_linux errors.
+pub type Result<T> = result::Result<T, Error>;
@@ -0,0 +1,22 @@
+/*!
+This crate contains memflow's linux implementation.
+It is used to interface with linux targets.
+*/
+
+#![cfg_attr(not(feature = "std"), no_std)]
+extern crate no_std_compat as std;
+
+pub mod error;
+
+pub mod offsets;
+
+pub mod linux;
+
+pub mod prelude {
+    pub mod v1 {
+        pub use crate::error::*;
+        pub use crate::linux::*;
+        pub use crate::offsets::*;
+    }
+    pub use v1::*;
+}
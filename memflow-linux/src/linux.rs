@@ -0,0 +1,5 @@
+pub mod kernel;
+pub mod process;
+
+pub use kernel::{LinuxKernel, LinuxKernelInfo};
+pub use process::LinuxProcessInfo;
@@ -0,0 +1,358 @@
+use std::prelude::v1::*;
+
+use super::process::{LinuxProcessInfo, TASK_COMM_LEN};
+
+use crate::error::{Error, Result};
+use crate::offsets::LinuxOffsets;
+
+use log::trace;
+
+use memflow::architecture::{x86, ArchitectureObj};
+use memflow::connector::CpuState;
+use memflow::mem::{PhysicalMemory, VirtualDMA, VirtualMemory, VirtualTranslate};
+use memflow::process::{OperatingSystem, OsInfo, OsKind, PID};
+use memflow::types::Address;
+
+const MAX_ITER_COUNT: usize = 65536;
+
+/// Basic information about a running linux kernel.
+///
+/// Unlike on windows there is no generic way of finding the kernel in physical memory yet,
+/// therefore all fields have to be supplied by the user.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub struct LinuxKernelInfo {
+    pub arch: ArchitectureObj,
+    /// Physical address of the kernel page tables (e.g. `swapper_pg_dir`)
+    pub dtb: Address,
+    /// Offset that has to be added to all symbol addresses taken from a System.map
+    pub kaslr_offset: u64,
+}
+
+#[derive(Clone)]
+pub struct LinuxKernel<T, V> {
+    pub phys_mem: T,
+    pub vat: V,
+    pub offsets: LinuxOffsets,
+
+    pub kernel_info: LinuxKernelInfo,
+}
+
+impl<T: PhysicalMemory, V: VirtualTranslate> OperatingSystem for LinuxKernel<T, V> {
+    type ProcessInfoType = LinuxProcessInfo;
+    type Error = Error;
+
+    fn process_info_list(&mut self) -> Result<Vec<LinuxProcessInfo>> {
+        LinuxKernel::process_info_list(self)
+    }
+
+    fn process_info_by_pid(&mut self, pid: PID) -> Result<LinuxProcessInfo> {
+        self.process_info_pid(pid)
+    }
+
+    fn process_info_by_name(&mut self, name: &str) -> Result<LinuxProcessInfo> {
+        self.process_info(name)
+    }
+
+    /// The kernel version is not determined yet, `OsInfo::version` is always `None`.
+    fn os_info(&mut self) -> Result<OsInfo> {
+        Ok(OsInfo {
+            kind: OsKind::Linux,
+            version: None,
+            arch: self.kernel_info.arch,
+        })
+    }
+}
+
+impl<T: PhysicalMemory, V: VirtualTranslate> LinuxKernel<T, V> {
+    pub fn new(phys_mem: T, vat: V, offsets: LinuxOffsets, kernel_info: LinuxKernelInfo) -> Self {
+        Self {
+            phys_mem,
+            vat,
+            offsets,
+            kernel_info,
+        }
+    }
+
+    /// Consume the self object and return the containing memory connection
+    pub fn destroy(self) -> T {
+        self.phys_mem
+    }
+
+    /// Returns access to the cpu state of the target if the underlying connector supports it.
+    ///
+    /// This can be used to retrieve the page table base that is currently active on each cpu.
+    pub fn cpu_state(&mut self) -> Option<&mut dyn CpuState> {
+        self.phys_mem.cpu_state()
+    }
+
+    /// Returns the address of `init_task` with the kaslr offset applied.
+    pub fn init_task(&self) -> Address {
+        Address::from(
+            self.offsets
+                .init_task
+                .as_u64()
+                .wrapping_add(self.kernel_info.kaslr_offset),
+        )
+    }
+
+    /// Returns the addresses of all `task_struct`s in the task list.
+    ///
+    /// The list starts with `init_task` (the idle task with pid 0).
+    pub fn task_list(&mut self) -> Result<Vec<Address>> {
+        self.task_list_with_limit(MAX_ITER_COUNT)
+    }
+
+    /// Returns the addresses of all `task_struct`s in the task list.
+    ///
+    /// In case the list contains more than `max_count` entries an `Error::ListCorruption` is returned.
+    pub fn task_list_with_limit(&mut self, max_count: usize) -> Result<Vec<Address>> {
+        let arch = self.kernel_info.arch;
+        let init_task = self.init_task();
+
+        let mut reader = VirtualDMA::with_vat(
+            &mut self.phys_mem,
+            arch,
+            x86::new_translator(self.kernel_info.dtb, arch)?,
+            &mut self.vat,
+        );
+
+        let list_start = init_task + self.offsets.task_tasks;
+        let mut list_entry = reader.virt_read_addr_arch(arch, list_start)?;
+
+        let mut tasks = vec![init_task];
+        while !list_entry.is_null() && list_entry != list_start {
+            if tasks.len() >= max_count {
+                return Err(Error::ListCorruption(
+                    "task list exceeds the maximum number of entries",
+                ));
+            }
+
+            let task = list_entry - self.offsets.task_tasks;
+            trace!("found task {:x}", task);
+            tasks.push(task);
+
+            list_entry = reader.virt_read_addr_arch(arch, list_entry)?;
+        }
+
+        Ok(tasks)
+    }
+
+    pub fn process_info_from_task(&mut self, task: Address) -> Result<LinuxProcessInfo> {
+        let arch = self.kernel_info.arch;
+
+        let (pid, name, mm, pgd) = {
+            let mut reader = VirtualDMA::with_vat(
+                &mut self.phys_mem,
+                arch,
+                x86::new_translator(self.kernel_info.dtb, arch)?,
+                &mut self.vat,
+            );
+
+            let pid: PID = reader.virt_read(task + self.offsets.task_pid)?;
+            trace!("pid={}", pid);
+            let name = reader.virt_read_cstr(task + self.offsets.task_comm, TASK_COMM_LEN)?;
+            trace!("name={}", name);
+
+            // kernel threads do not own a mm_struct
+            let mm = reader.virt_read_addr_arch(arch, task + self.offsets.task_mm)?;
+            trace!("mm={:x}", mm);
+            let pgd = if !mm.is_null() {
+                reader.virt_read_addr_arch(arch, mm + self.offsets.mm_pgd)?
+            } else {
+                Address::NULL
+            };
+            trace!("pgd={:x}", pgd);
+
+            (pid, name, mm, pgd)
+        };
+
+        // mm_struct::pgd is a kernel virtual address
+        let dtb = if !pgd.is_null() {
+            let dtb = self
+                .vat
+                .virt_to_phys(
+                    &mut self.phys_mem,
+                    &x86::new_translator(self.kernel_info.dtb, arch)?,
+                    pgd,
+                )?
+                .address();
+            trace!("dtb={:x}", dtb);
+            Some(dtb)
+        } else {
+            None
+        };
+
+        Ok(LinuxProcessInfo {
+            address: task,
+
+            pid,
+            name,
+            mm,
+            pgd,
+            dtb,
+
+            sys_arch: arch,
+            proc_arch: arch,
+        })
+    }
+
+    /// Retrieves a list of `LinuxProcessInfo` structs for all tasks
+    /// that can be found on the target system.
+    pub fn process_info_list(&mut self) -> Result<Vec<LinuxProcessInfo>> {
+        let tasks = self.task_list()?;
+        Ok(tasks
+            .into_iter()
+            .filter_map(|task| self.process_info_from_task(task).ok())
+            .collect())
+    }
+
+    /// Finds a process by its name and returns the `LinuxProcessInfo` struct.
+    /// If no process with the specified name can be found this function will return an Error.
+    ///
+    /// The name is compared against `task_struct::comm` which is clamped to 15 characters.
+    pub fn process_info(&mut self, name: &str) -> Result<LinuxProcessInfo> {
+        let comm = &name[..name.len().min(TASK_COMM_LEN - 1)];
+        self.process_info_list()?
+            .into_iter()
+            .inspect(|process| trace!("{} {}", process.pid, process.name))
+            .find(|process| process.name == comm)
+            .ok_or(Error::ProcessInfo)
+    }
+
+    /// Finds a process by its process id and returns the `LinuxProcessInfo` struct.
+    /// If no process with the specified PID can be found this function will return an Error.
+    pub fn process_info_pid(&mut self, pid: PID) -> Result<LinuxProcessInfo> {
+        self.process_info_list()?
+            .into_iter()
+            .inspect(|process| trace!("{} {}", process.pid, process.name))
+            .find(|process| process.pid == pid)
+            .ok_or(Error::ProcessInfo)
+    }
+
+    /// Constructs a virtual memory object for the address space of the given process
+    /// by borrowing this kernel instance.
+    ///
+    /// Kernel threads do not have an address space on their own, in this case an error is returned.
+    pub fn process_virt_mem(
+        &mut self,
+        proc_info: &LinuxProcessInfo,
+    ) -> Result<impl VirtualMemory + '_> {
+        let dtb = proc_info
+            .dtb
+            .ok_or(Error::Other("kernel threads do not have an address space"))?;
+        Ok(VirtualDMA::with_vat(
+            &mut self.phys_mem,
+            proc_info.proc_arch,
+            x86::new_translator(dtb, proc_info.sys_arch)?,
+            &mut self.vat,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use memflow::architecture::x86::x64;
+    use memflow::mem::dummy::DummyMemory;
+    use memflow::mem::DirectTranslate;
+    use memflow::types::size;
+
+    const KERNEL_BASE: u64 = 0xffff_ffff_8100_0000;
+    const KASLR_OFFSET: u64 = 0x0020_0000;
+
+    fn offsets() -> LinuxOffsets {
+        LinuxOffsets {
+            init_task: Address::from(KERNEL_BASE - KASLR_OFFSET),
+            task_tasks: 0x10,
+            task_pid: 0x20,
+            task_comm: 0x30,
+            task_mm: 0x40,
+            mm_pgd: 0x8,
+        }
+    }
+
+    fn write_task(buf: &mut [u8], offset: usize, pid: PID, comm: &str, mm: u64, next: u64) {
+        let offsets = offsets();
+        let addr = |field: usize| offset + field;
+        buf[addr(offsets.task_tasks)..addr(offsets.task_tasks) + 8]
+            .copy_from_slice(&(KERNEL_BASE + next + offsets.task_tasks as u64).to_le_bytes());
+        buf[addr(offsets.task_pid)..addr(offsets.task_pid) + 4].copy_from_slice(&pid.to_le_bytes());
+        buf[addr(offsets.task_comm)..addr(offsets.task_comm) + comm.len()]
+            .copy_from_slice(comm.as_bytes());
+        buf[addr(offsets.task_mm)..addr(offsets.task_mm) + 8].copy_from_slice(&mm.to_le_bytes());
+    }
+
+    fn build_kernel(loop_list: bool) -> LinuxKernel<DummyMemory, DirectTranslate> {
+        let mut buf = vec![0u8; size::kb(8)];
+
+        // init_task -> systemd -> kthreadd -> init_task
+        write_task(&mut buf, 0x0, 0, "swapper/0", 0, 0x100);
+        write_task(&mut buf, 0x100, 1, "systemd", KERNEL_BASE + 0x400, 0x200);
+        write_task(
+            &mut buf,
+            0x200,
+            2,
+            "kthreadd",
+            0,
+            if loop_list { 0x100 } else { 0x0 },
+        );
+
+        // mm_struct of systemd with its pgd located in the second page
+        let offsets = offsets();
+        buf[0x400 + offsets.mm_pgd..0x400 + offsets.mm_pgd + 8]
+            .copy_from_slice(&(KERNEL_BASE + 0x1000).to_le_bytes());
+
+        let mut mem = DummyMemory::new(size::mb(16));
+        let dtb = mem.alloc_dtb_const_base(Address::from(KERNEL_BASE), buf.len(), &buf);
+
+        LinuxKernel::new(
+            mem,
+            DirectTranslate::new(),
+            offsets,
+            LinuxKernelInfo {
+                arch: x64::ARCH,
+                dtb,
+                kaslr_offset: KASLR_OFFSET,
+            },
+        )
+    }
+
+    #[test]
+    fn process_info_list() {
+        let mut kernel = build_kernel(false);
+        assert_eq!(kernel.init_task(), Address::from(KERNEL_BASE));
+
+        let list = kernel.process_info_list().unwrap();
+        assert_eq!(list.len(), 3);
+
+        assert_eq!(list[0].pid, 0);
+        assert_eq!(list[0].name, "swapper/0");
+        assert!(list[0].is_kernel_thread());
+        assert_eq!(list[0].dtb, None);
+
+        assert_eq!(list[2].pid, 2);
+        assert_eq!(list[2].name, "kthreadd");
+        assert_eq!(list[2].dtb, None);
+
+        let systemd = kernel.process_info("systemd").unwrap();
+        assert_eq!(systemd.pid, 1);
+        assert_eq!(systemd.pgd, Address::from(KERNEL_BASE + 0x1000));
+
+        let dtb = kernel.kernel_info.dtb;
+        let pgd_phys = DirectTranslate::new()
+            .virt_to_phys(&mut kernel.phys_mem, &x64::new_translator(dtb), systemd.pgd)
+            .unwrap();
+        assert_eq!(systemd.dtb, Some(pgd_phys.address()));
+        assert!(kernel.process_virt_mem(&systemd).is_ok());
+    }
+
+    #[test]
+    fn task_list_corruption() {
+        let mut kernel = build_kernel(true);
+        assert_eq!(
+            kernel.task_list_with_limit(16).unwrap_err(),
+            Error::ListCorruption("task list exceeds the maximum number of entries")
+        );
+    }
+}
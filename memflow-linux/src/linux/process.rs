@@ -0,0 +1,54 @@
+use std::prelude::v1::*;
+
+use memflow::architecture::ArchitectureObj;
+use memflow::process::{OsProcessInfo, PID};
+use memflow::types::Address;
+
+/// Length of the `task_struct::comm` field including the nul terminator.
+pub const TASK_COMM_LEN: usize = 16;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub struct LinuxProcessInfo {
+    pub address: Address, // task_struct
+
+    pub pid: PID,     // task_struct::pid
+    pub name: String, // task_struct::comm
+    pub mm: Address,  // task_struct::mm
+    pub pgd: Address, // mm_struct::pgd
+    pub dtb: Option<Address>,
+
+    pub sys_arch: ArchitectureObj,
+    pub proc_arch: ArchitectureObj,
+}
+
+impl LinuxProcessInfo {
+    /// Returns true if this task is a kernel thread.
+    ///
+    /// Kernel threads do not have their own address space and therefore no dtb.
+    pub fn is_kernel_thread(&self) -> bool {
+        self.mm.is_null()
+    }
+}
+
+impl OsProcessInfo for LinuxProcessInfo {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn pid(&self) -> PID {
+        self.pid
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn sys_arch(&self) -> ArchitectureObj {
+        self.sys_arch
+    }
+
+    fn proc_arch(&self) -> ArchitectureObj {
+        self.proc_arch
+    }
+}
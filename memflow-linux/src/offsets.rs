@@ -0,0 +1,116 @@
+/*!
+Symbol and structure offsets for linux targets.
+
+Symbol addresses are retrieved from a `System.map` or `/proc/kallsyms` file of the target kernel.
+Neither of these files contain type information, therefore the offsets into kernel structures
+have to be supplied separately (e.g. obtained via `pahole` or the kernel's BTF information).
+*/
+
+use std::prelude::v1::*;
+
+use crate::error::{Error, Result};
+
+use std::collections::BTreeMap;
+
+use memflow::types::Address;
+
+/// Contains the addresses of all symbols found in a `System.map` or `/proc/kallsyms` file.
+///
+/// The addresses are stored as they are found in the file.
+/// In case the file was taken from a file on disk the kaslr offset of the running kernel has
+/// to be applied (see `LinuxKernelInfo::kaslr_offset`).
+#[derive(Debug, Clone, Default)]
+pub struct LinuxSymbols {
+    symbol_map: BTreeMap<String, Address>,
+}
+
+impl LinuxSymbols {
+    /// Parses the contents of a `System.map` or `/proc/kallsyms` file.
+    ///
+    /// Each line has the format `<address> <type> <name> [module]`.
+    /// Lines which do not match this format are skipped.
+    pub fn parse(system_map: &str) -> Result<Self> {
+        let mut symbol_map = BTreeMap::new();
+
+        for line in system_map.lines() {
+            let mut parts = line.split_whitespace();
+            let (addr, name) = match (parts.next(), parts.next(), parts.next()) {
+                (Some(addr), Some(_), Some(name)) => (addr, name),
+                _ => continue,
+            };
+
+            if let Ok(addr) = u64::from_str_radix(addr, 16) {
+                symbol_map.insert(name.to_string(), Address::from(addr));
+            }
+        }
+
+        if symbol_map.is_empty() {
+            return Err(Error::Symbol("no symbols found"));
+        }
+
+        // /proc/kallsyms reports zeroed addresses for unprivileged users
+        if symbol_map.values().all(|addr| addr.is_null()) {
+            return Err(Error::Symbol("all symbol addresses are zero"));
+        }
+
+        Ok(Self { symbol_map })
+    }
+
+    /// Returns the address of the given symbol.
+    pub fn find_symbol(&self, name: &str) -> Option<Address> {
+        self.symbol_map.get(name).copied()
+    }
+}
+
+/// Offsets required to walk the task list of a linux kernel.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct LinuxOffsets {
+    /// Address of the `init_task` symbol without the kaslr offset applied
+    pub init_task: Address,
+
+    pub task_tasks: usize, // task_struct::tasks
+    pub task_pid: usize,   // task_struct::pid
+    pub task_comm: usize,  // task_struct::comm
+    pub task_mm: usize,    // task_struct::mm
+
+    pub mm_pgd: usize, // mm_struct::pgd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_system_map() {
+        let symbols = LinuxSymbols::parse(
+            "ffffffff81000000 T _text\n\
+             ffffffff82613940 D init_task\n\
+             invalid line\n\
+             ffffffffc0a01000 t ext4_fill_super\t[ext4]\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            symbols.find_symbol("init_task"),
+            Some(Address::from(0xffff_ffff_8261_3940_u64))
+        );
+        assert_eq!(
+            symbols.find_symbol("ext4_fill_super"),
+            Some(Address::from(0xffff_ffff_c0a0_1000_u64))
+        );
+        assert_eq!(symbols.find_symbol("invalid"), None);
+    }
+
+    #[test]
+    fn parse_restricted_kallsyms() {
+        let result = LinuxSymbols::parse(
+            "0000000000000000 T _text\n\
+             0000000000000000 D init_task\n",
+        );
+        assert_eq!(
+            result.unwrap_err(),
+            Error::Symbol("all symbol addresses are zero")
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use clap::*;
+use log::Level;
+
+use memflow::connector::*;
+use memflow::mem::{CachedMemoryAccess, PhysicalMemory, VirtualTranslate};
+use memflow::types::size;
+
+use memflow_win32::win32::Kernel;
+
+pub fn main() {
+    let matches = App::new("process_list example")
+        .version(crate_version!())
+        .author(crate_authors!())
+        .arg(Arg::with_name("verbose").short("v").multiple(true))
+        .arg(
+            Arg::with_name("connector")
+                .long("connector")
+                .short("c")
+                .takes_value(true)
+                .required_unless("target"),
+        )
+        .arg(
+            Arg::with_name("args")
+                .long("args")
+                .short("a")
+                .takes_value(true)
+                .default_value(""),
+        )
+        .arg(
+            Arg::with_name("target")
+                .long("target")
+                .short("t")
+                .help("connector and arguments as url, e.g. coredump://./dump.raw?format=dmp")
+                .takes_value(true)
+                .conflicts_with("connector"),
+        )
+        .arg(
+            Arg::with_name("cache")
+                .long("cache")
+                .help("cache between connector and os: default, none or page[:size=64mb]")
+                .takes_value(true)
+                .default_value("default"),
+        )
+        .get_matches();
+
+    // set log level
+    let level = match matches.occurrences_of("verbose") {
+        0 => Level::Error,
+        1 => Level::Warn,
+        2 => Level::Info,
+        3 => Level::Debug,
+        4 => Level::Trace,
+        _ => Level::Trace,
+    };
+    simple_logger::SimpleLogger::new()
+        .with_level(level.to_level_filter())
+        .init()
+        .unwrap();
+
+    // create inventory + connector
+    let inventory = unsafe { ConnectorInventory::scan() };
+    let (connector_name, connector_args) = match matches.value_of("target") {
+        Some(target) => ConnectorArgs::from_url(target).unwrap(),
+        None => (
+            matches.value_of("connector").unwrap().to_string(),
+            ConnectorArgs::parse(matches.value_of("args").unwrap()).unwrap(),
+        ),
+    };
+    let connector =
+        unsafe { inventory.create_connector(&connector_name, &connector_args) }.unwrap();
+
+    // creating the kernel object with the requested caches
+    let builder = Kernel::builder(connector);
+    let cache = matches.value_of("cache").unwrap();
+    let (cache_type, cache_args) = match cache.find(':') {
+        Some(idx) => (&cache[..idx], &cache[idx + 1..]),
+        None => (cache, ""),
+    };
+    match cache_type {
+        "default" => print_process_list(builder.build_default_caches().build().unwrap()),
+        "none" => print_process_list(builder.build().unwrap()),
+        "page" => {
+            let cache_size = ConnectorArgs::parse(cache_args)
+                .unwrap()
+                .get("size")
+                .map(|s| parse_size(s).expect("invalid cache size"));
+            print_process_list(
+                builder
+                    .build_page_cache(move |connector, arch| {
+                        let builder = CachedMemoryAccess::builder(connector).arch(arch);
+                        let builder = match cache_size {
+                            Some(cache_size) => builder.cache_size(cache_size),
+                            None => builder,
+                        };
+                        builder.build().unwrap()
+                    })
+                    .build()
+                    .unwrap(),
+            )
+        }
+        _ => panic!("unknown cache type: {}", cache_type),
+    }
+}
+
+fn print_process_list<T: PhysicalMemory, V: VirtualTranslate>(mut kernel: Kernel<T, V>) {
+    let process_list = kernel.process_info_list().unwrap();
+
+    // print process list formatted
+    println!(
+        "{:>5} {:<10} {:<12} {:<11} {:<}",
+        "PID", "PROTECTION", "SIGNER", "INTEGRITY", "NAME"
+    );
+    for p in process_list.iter() {
+        let protection = if p.protection.is_protected_light() {
+            "PPL"
+        } else if p.protection.is_protected() {
+            "PP"
+        } else {
+            "-"
+        };
+        let signer = format!("{:?}", p.protection.signer());
+        let integrity_level = p
+            .integrity_level
+            .map(|level| format!("{:?}", level))
+            .unwrap_or_else(|| "-".to_string());
+
+        println!(
+            "{:>5} {:<10} {:<12} {:<11} {}",
+            p.pid, protection, signer, integrity_level, p.name
+        );
+    }
+}
+
+/// Parses sizes like `4096`, `0x1000`, `64kb` or `64mb`.
+fn parse_size(s: &str) -> Option<usize> {
+    let s = s.to_lowercase();
+    let (num, mul) = if let Some(num) = s.strip_suffix("gb") {
+        (num, size::gb(1))
+    } else if let Some(num) = s.strip_suffix("mb") {
+        (num, size::mb(1))
+    } else if let Some(num) = s.strip_suffix("kb") {
+        (num, size::kb(1))
+    } else {
+        (s.as_str(), 1)
+    };
+    let num = match num.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16).ok()?,
+        None => num.parse().ok()?,
+    };
+    num.checked_mul(mul)
+}
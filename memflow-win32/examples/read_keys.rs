@@ -59,7 +59,7 @@ pub fn main() {
         .unwrap();
 
     // fetch keyboard state
-    let kbd = Keyboard::try_with(&mut kernel).unwrap();
+    let kbd = Keyboard::with_kernel(&mut kernel).unwrap();
 
     loop {
         let kbs = kbd.state_with_kernel(&mut kernel).unwrap();
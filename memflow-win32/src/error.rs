@@ -19,9 +19,25 @@ pub enum Error {
     /// Invalid Architecture error.
     ///
     /// The architecture provided is not a valid argument for the given function.
-    InvalidArchitecture,
+    /// `bits` contains the pointer width of the offending architecture.
+    InvalidArchitecture {
+        bits: u8,
+    },
     Initialization(&'static str),
     SymbolStore(&'static str),
+    /// Symbol store could not be reached.
+    ///
+    /// The symbol server did not respond or the connection failed (e.g. when running offline).
+    /// Callers can fall back to embedded offsets or a local pdb in this case.
+    SymbolStoreUnreachable(&'static str),
+    /// The requested pdb does not exist.
+    ///
+    /// Either the symbol server does not know the pdb or a user-supplied pdb file is missing.
+    PdbNotFound,
+    /// The pdb could not be parsed.
+    ///
+    /// The pdb was found but its contents are malformed or incomplete.
+    PdbParse(&'static str),
     ProcessInfo,
     ModuleInfo,
     /// memflow core error.
@@ -41,6 +57,16 @@ pub enum Error {
     ///
     /// Encapsulates all unicode related reading errors.
     Unicode(&'static str),
+    /// Linked list corruption error.
+    ///
+    /// A linked list (e.g. the EPROCESS or module list) did not terminate
+    /// within the maximum number of entries. This usually indicates a malformed or looping list.
+    ListCorruption(&'static str),
+    /// Tree corruption error.
+    ///
+    /// A tree (e.g. the VAD tree of a process) contains a cycle or exceeds
+    /// the maximum depth or number of nodes. This usually indicates malformed child pointers.
+    TreeCorruption(&'static str),
 }
 
 /// Convert from &str to error
@@ -84,9 +110,12 @@ impl Error {
         match self {
             Error::Other(e) => ("other error", Some(e)),
             Error::Bounds => ("out of bounds", None),
-            Error::InvalidArchitecture => ("invalid architecture", None),
+            Error::InvalidArchitecture { .. } => ("invalid architecture", None),
             Error::Initialization(e) => ("error during initialization", Some(e)),
             Error::SymbolStore(e) => ("error in symbol store", Some(e)),
+            Error::SymbolStoreUnreachable(e) => ("symbol store unreachable", Some(e)),
+            Error::PdbNotFound => ("pdb not found", None),
+            Error::PdbParse(e) => ("unable to parse pdb", Some(e)),
             Error::ProcessInfo => ("error retrieving process info", None),
             Error::ModuleInfo => ("error retrieving module info", None),
             Error::Core(e) => e.to_str_pair(),
@@ -94,6 +123,8 @@ impl Error {
             Error::PE(e) => ("error handling pe", Some(e.to_str())),
             Error::Encoding => ("encoding error", None),
             Error::Unicode(e) => ("error reading unicode string", Some(e)),
+            Error::ListCorruption(e) => ("linked list corruption", Some(e)),
+            Error::TreeCorruption(e) => ("tree corruption", Some(e)),
         }
     }
 
@@ -107,10 +138,18 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let (desc, value) = self.to_str_pair();
 
-        if let Some(value) = value {
-            write!(f, "{}: {}", desc, value)
-        } else {
-            f.write_str(desc)
+        match self {
+            Error::InvalidArchitecture { bits }
+            | Error::Core(memflow::error::Error::InvalidArchitecture { bits }) => {
+                write!(f, "{}: {}-bit", desc, bits)
+            }
+            _ => {
+                if let Some(value) = value {
+                    write!(f, "{}: {}", desc, value)
+                } else {
+                    f.write_str(desc)
+                }
+            }
         }
     }
 }
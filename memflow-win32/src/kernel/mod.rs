@@ -4,7 +4,7 @@ pub mod sysproc;
 
 use std::prelude::v1::*;
 
-pub use start_block::StartBlock;
+pub use start_block::{StartBlock, StartBlockSource};
 
 use std::cmp::{Ord, Ordering, PartialEq};
 use std::fmt;
@@ -145,3 +145,42 @@ impl fmt::Display for Win32Version {
         }
     }
 }
+
+/// Extended version information of a windows kernel.
+///
+/// In addition to the `Win32Version` this also contains the revision and product type of the target.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+#[repr(C)]
+pub struct Win32VersionInfo {
+    pub major: u32,
+    pub minor: u32,
+    pub build: u32,
+    /// Raw value of the `CmNtCSDVersion` export.
+    ///
+    /// On older windows versions this contains the service pack version.
+    pub revision: u32,
+    /// Set if `KUSER_SHARED_DATA::NtProductType` denotes a server or domain controller.
+    pub is_server: bool,
+}
+
+impl Win32VersionInfo {
+    /// Returns the `Win32Version` part of this version info.
+    pub fn version(&self) -> Win32Version {
+        Win32Version::new(self.major, self.minor, self.build)
+    }
+}
+
+impl fmt::Display for Win32VersionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}.{}.{}.{}",
+            self.major, self.minor, self.build, self.revision
+        )?;
+        if self.is_server {
+            write!(f, " (server)")?;
+        }
+        Ok(())
+    }
+}
@@ -5,12 +5,14 @@ mod x86;
 
 use super::{StartBlock, Win32GUID, Win32Version};
 use crate::error::{Error, PartialResultExt, Result};
+use crate::win32::KUserSharedData;
 
 use std::convert::TryInto;
 use std::prelude::v1::*;
 
 use log::{info, warn};
 
+use memflow::architecture::ArchitectureObj;
 use memflow::mem::VirtualMemory;
 use memflow::types::Address;
 
@@ -42,6 +44,41 @@ pub fn find<T: VirtualMemory>(
     Err(Error::Initialization("unable to find ntoskrnl.exe"))
 }
 
+/// Returns all ntoskrnl.exe images that can be found in the given virtual memory.
+///
+/// In contrast to `find()` the search does not stop at the first image that has been found.
+pub fn find_all<T: VirtualMemory>(
+    virt_mem: &mut T,
+    start_block: &StartBlock,
+) -> Vec<(Address, usize)> {
+    let mut kernels = Vec::new();
+
+    if start_block.arch.bits() == 64 {
+        if !start_block.kernel_hint.is_null() {
+            match x64::find_with_va_hint(virt_mem, start_block) {
+                Ok(b) => kernels.push(b),
+                Err(e) => warn!("x64::find_with_va_hint() error: {}", e),
+            }
+        }
+
+        kernels.extend(x64::find_all(virt_mem, start_block));
+    } else if start_block.arch.bits() == 32 {
+        match x86::find(virt_mem, start_block) {
+            Ok(b) => kernels.push(b),
+            Err(e) => warn!("x86::find() error: {}", e),
+        }
+    }
+
+    // the image found via the va hint is usually part of the page map as well
+    let mut unique: Vec<(Address, usize)> = Vec::new();
+    for kernel in kernels.into_iter() {
+        if !unique.iter().any(|(base, _)| *base == kernel.0) {
+            unique.push(kernel);
+        }
+    }
+    unique
+}
+
 // TODO: move to pe::...
 pub fn find_guid<T: VirtualMemory>(virt_mem: &mut T, kernel_base: Address) -> Result<Win32GUID> {
     let image = pehelper::try_get_pe_image(virt_mem, kernel_base)?;
@@ -93,6 +130,7 @@ fn get_export(pe: &PeView, name: &str) -> Result<usize> {
 
 pub fn find_winver<T: VirtualMemory>(
     virt_mem: &mut T,
+    arch: ArchitectureObj,
     kernel_base: Address,
 ) -> Result<Win32Version> {
     let image = pehelper::try_get_pe_image(virt_mem, kernel_base)?;
@@ -108,15 +146,15 @@ pub fn find_winver<T: VirtualMemory>(
         return Err(Error::Initialization("unable to fetch nt build number"));
     }
 
-    // TODO: these reads should be optional
     // try to find major/minor version
     // read from KUSER_SHARED_DATA. these fields exist since nt 4.0 so they have to exist in case NtBuildNumber exists.
-    let mut nt_major_version: u32 = virt_mem
-        .virt_read((0x7ffe0000 + 0x026C).into())
-        .data_part()?;
-    let mut nt_minor_version: u32 = virt_mem
-        .virt_read((0x7ffe0000 + 0x0270).into())
-        .data_part()?;
+    let (mut nt_major_version, mut nt_minor_version) = match KUserSharedData::read(virt_mem, arch) {
+        Ok(shared_data) => (shared_data.nt_major_version, shared_data.nt_minor_version),
+        Err(_) => {
+            warn!("unable to read KUSER_SHARED_DATA");
+            (0, 0)
+        }
+    };
 
     // fallback on x64: try to parse RtlGetVersion assembly
     if nt_major_version == 0 && rtl_get_version_ref.is_ok() {
@@ -104,3 +104,28 @@ pub fn find<T: VirtualMemory>(
         )),
     }
 }
+
+/// Returns all ntoskrnl.exe images that can be found in the kernel address space.
+pub fn find_all<T: VirtualMemory>(
+    virt_mem: &mut T,
+    start_block: &StartBlock,
+) -> Vec<(Address, usize)> {
+    debug!("x64::find_all: trying to find all ntoskrnl.exe images with page map",);
+
+    let page_map = virt_mem.virt_page_map_range(
+        size::mb(2),
+        (!0u64 - (1u64 << (start_block.arch.address_space_bits() - 1))).into(),
+        (!0u64).into(),
+    );
+
+    page_map
+        .into_iter()
+        .flat_map(|(va, size)| size.page_chunks(va, size::mb(2)))
+        .filter(|&(_, size)| size == size::mb(2))
+        .filter_map(|(va, _)| {
+            let addr = Address::from(find_with_va(virt_mem, va.as_u64()).ok()?);
+            let size_of_image = pehelper::try_get_pe_size(virt_mem, addr).ok()?;
+            Some((addr, size_of_image))
+        })
+        .collect()
+}
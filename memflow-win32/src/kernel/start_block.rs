@@ -5,14 +5,30 @@ mod x86pae;
 use std::prelude::v1::*;
 
 use crate::error::{Error, Result};
+use crate::win32::vat::Win32VirtualTranslate;
+
+use std::convert::TryInto;
 
 use log::warn;
 
 use memflow::architecture;
 use memflow::architecture::ArchitectureObj;
-use memflow::mem::PhysicalMemory;
+use memflow::mem::{DirectTranslate, PhysicalMemory, VirtualDMA, VirtualMemory};
 use memflow::types::{size, Address, PhysicalAddress};
 
+/// Describes how the values of a `StartBlock` have been determined.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub enum StartBlockSource {
+    /// Read from the processor start block in the low stub (< 1M).
+    /// The architecture is derived from the stored CR4 register.
+    LowStub,
+    /// Found by scanning the low 16M of physical memory for a page table with the expected layout.
+    PageTableScan,
+    /// Provided by the user (e.g. via `KernelBuilder::arch`, `dtb` and `kernel_hint`).
+    UserSupplied,
+}
+
 // PROCESSOR_START_BLOCK
 #[derive(Debug, Copy, Clone)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize))]
@@ -20,15 +36,164 @@ pub struct StartBlock {
     pub arch: ArchitectureObj,
     pub kernel_hint: Address,
     pub dtb: Address,
+    pub source: StartBlockSource,
+}
+
+impl StartBlock {
+    /// Returns the architecture of the kernel.
+    pub fn arch(&self) -> ArchitectureObj {
+        self.arch
+    }
+
+    /// Returns the directory table base that is used to find the kernel.
+    pub fn dtb(&self) -> Address {
+        self.dtb
+    }
+
+    /// Returns a virtual address near the kernel base or `Address::NULL` if it is unknown.
+    pub fn kernel_hint(&self) -> Address {
+        self.kernel_hint
+    }
+
+    /// Returns how the start block has been determined.
+    pub fn source(&self) -> StartBlockSource {
+        self.source
+    }
+
+    /// Checks that the start block is consistent with the given physical memory.
+    ///
+    /// This verifies that the dtb contains the self-referencing entries windows creates
+    /// for the paging mode of the architecture and that the kernel hint (if any) is mapped.
+    /// A start block that passes this check is not guaranteed to lead to a kernel,
+    /// but a failing check indicates a corrupt snapshot or wrongly supplied hints.
+    pub fn validate<T: PhysicalMemory + ?Sized>(&self, mem: &mut T) -> Result<()> {
+        if self.dtb.is_null() || self.dtb.as_usize() >= mem.metadata().size {
+            return Err(Error::Initialization(
+                "start_block: dtb is outside of physical memory",
+            ));
+        }
+
+        if architecture::x86::is_x64_arch(self.arch) {
+            validate_x64(mem, self.dtb)?;
+        } else if self.arch == architecture::x86::x32_pae::ARCH {
+            validate_x86pae(mem, self.dtb)?;
+        } else if self.arch == architecture::x86::x32::ARCH {
+            validate_x86(mem, self.dtb)?;
+        } else {
+            return Err(Error::InvalidArchitecture {
+                bits: self.arch.bits(),
+            });
+        }
+
+        if !self.kernel_hint.is_null() {
+            let mut virt_mem = VirtualDMA::with_vat(
+                mem,
+                self.arch,
+                Win32VirtualTranslate::new(self.arch, self.dtb),
+                DirectTranslate::new(),
+            );
+            virt_mem.virt_page_info(self.kernel_hint).map_err(|_| {
+                Error::Initialization("start_block: kernel hint is not mapped by the dtb")
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+fn read_dtb_page<T: PhysicalMemory + ?Sized>(mem: &mut T, addr: Address) -> Result<Vec<u8>> {
+    let mut page = vec![0u8; size::kb(4)];
+    mem.phys_read_raw_into(addr.into(), &mut page)?;
+    Ok(page)
+}
+
+fn pte_frame(pte: u64) -> u64 {
+    pte & 0x000f_ffff_ffff_f000
+}
+
+// the pml4 (or pml5) references itself in one of the kernel entries
+fn validate_x64<T: PhysicalMemory + ?Sized>(mem: &mut T, dtb: Address) -> Result<()> {
+    if dtb.as_page_aligned(size::kb(4)) != dtb {
+        return Err(Error::Initialization(
+            "start_block: x64 dtb is not page aligned",
+        ));
+    }
+
+    let page = read_dtb_page(mem, dtb)?;
+    page[0x800..]
+        .chunks_exact(8)
+        .map(|c| u64::from_le_bytes(c.try_into().unwrap()))
+        .find(|&pte| pte & 1 != 0 && pte_frame(pte) == dtb.as_u64())
+        .map(|_| ())
+        .ok_or(Error::Initialization(
+            "start_block: x64 dtb does not contain a self-referencing entry",
+        ))
+}
+
+// the page directory covering 0xc0000000 maps all 4 page directories of the pdpt
+fn validate_x86pae<T: PhysicalMemory + ?Sized>(mem: &mut T, dtb: Address) -> Result<()> {
+    if dtb.as_page_aligned(0x20) != dtb {
+        return Err(Error::Initialization(
+            "start_block: x86_pae dtb is not aligned to 32 bytes",
+        ));
+    }
+
+    let mut pdpt = [0u8; 0x20];
+    mem.phys_read_raw_into(dtb.into(), &mut pdpt)?;
+    let pdpt = pdpt
+        .chunks_exact(8)
+        .map(|c| u64::from_le_bytes(c.try_into().unwrap()))
+        .collect::<Vec<_>>();
+    if pdpt.iter().any(|&pdpte| pdpte & 1 == 0) {
+        return Err(Error::Initialization(
+            "start_block: x86_pae dtb contains non-present entries",
+        ));
+    }
+
+    let page = read_dtb_page(mem, pte_frame(pdpt[3]).into())?;
+    let self_mapped = page[..0x20]
+        .chunks_exact(8)
+        .map(|c| u64::from_le_bytes(c.try_into().unwrap()))
+        .zip(pdpt.iter())
+        .all(|(pde, &pdpte)| pde & 1 != 0 && pte_frame(pde) == pte_frame(pdpte));
+    if self_mapped {
+        Ok(())
+    } else {
+        Err(Error::Initialization(
+            "start_block: x86_pae page directories are not self-mapped",
+        ))
+    }
+}
+
+// the page directory references itself at the entry covering 0xc0000000
+fn validate_x86<T: PhysicalMemory + ?Sized>(mem: &mut T, dtb: Address) -> Result<()> {
+    if dtb.as_page_aligned(size::kb(4)) != dtb {
+        return Err(Error::Initialization(
+            "start_block: x86 dtb is not page aligned",
+        ));
+    }
+
+    let page = read_dtb_page(mem, dtb)?;
+    let pde = u32::from_le_bytes(page[0xc00..0xc04].try_into().unwrap());
+    if pde & 1 != 0 && (pde & 0xffff_f000) == dtb.as_u32() {
+        Ok(())
+    } else {
+        Err(Error::Initialization(
+            "start_block: x86 dtb does not contain a self-referencing entry",
+        ))
+    }
 }
 
 pub fn find_fallback<T: PhysicalMemory>(mem: &mut T, arch: ArchitectureObj) -> Result<StartBlock> {
-    if arch == architecture::x86::x64::ARCH {
+    if architecture::x86::is_x64_arch(arch) {
         // read low 16mb stub
         let mut low16m = vec![0; size::mb(16)];
         mem.phys_read_raw_into(PhysicalAddress::NULL, &mut low16m)?;
 
-        x64::find(&low16m)
+        x64::find(&low16m).map(|start_block| StartBlock {
+            arch,
+            ..start_block
+        })
     } else {
         Err(Error::Initialization(
             "start_block: fallback not implemented for given arch",
@@ -39,7 +204,7 @@ pub fn find_fallback<T: PhysicalMemory>(mem: &mut T, arch: ArchitectureObj) -> R
 // bcdedit /set firstmegabytepolicyuseall
 pub fn find<T: PhysicalMemory>(mem: &mut T, arch: Option<ArchitectureObj>) -> Result<StartBlock> {
     if let Some(arch) = arch {
-        if arch == architecture::x86::x64::ARCH {
+        if architecture::x86::is_x64_arch(arch) {
             // read low 1mb stub
             let mut low1m = vec![0; size::mb(1)];
             mem.phys_read_raw_into(PhysicalAddress::NULL, &mut low1m)?;
@@ -64,7 +229,7 @@ pub fn find<T: PhysicalMemory>(mem: &mut T, arch: Option<ArchitectureObj>) -> Re
             mem.phys_read_raw_into(PhysicalAddress::NULL, &mut low16m)?;
             x86::find(&low16m)
         } else {
-            Err(Error::InvalidArchitecture)
+            Err(Error::InvalidArchitecture { bits: arch.bits() })
         }
     } else {
         find(mem, Some(architecture::x86::x64::ARCH))
@@ -73,3 +238,99 @@ pub fn find<T: PhysicalMemory>(mem: &mut T, arch: Option<ArchitectureObj>) -> Re
             .map_err(|_| Error::Initialization("unable to find dtb"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use memflow::architecture::x86::{x32, x32_pae, x64};
+    use memflow::mem::SparseMemory;
+
+    fn start_block(arch: ArchitectureObj, dtb: u64, kernel_hint: u64) -> StartBlock {
+        StartBlock {
+            arch,
+            kernel_hint: kernel_hint.into(),
+            dtb: dtb.into(),
+            source: StartBlockSource::UserSupplied,
+        }
+    }
+
+    #[test]
+    fn validate_x64() {
+        let mut mem = (1..=5)
+            .map(|i| (Address::from(i * 0x1000), vec![0u8; 0x1000]))
+            .collect::<SparseMemory>();
+
+        // map 0x1000 via pml4 (0x1000) -> pdpt (0x2000) -> pd (0x3000) -> pt (0x4000) to 0x5000
+        mem.phys_write(0x1000.into(), &0x2007_u64).unwrap();
+        mem.phys_write(0x2000.into(), &0x3007_u64).unwrap();
+        mem.phys_write(0x3000.into(), &0x4007_u64).unwrap();
+        mem.phys_write(0x4008.into(), &0x5007_u64).unwrap();
+
+        let sb = start_block(x64::ARCH, 0x1000, 0x1000);
+        assert!(matches!(
+            sb.validate(&mut mem),
+            Err(Error::Initialization(_))
+        ));
+
+        // add the self-referencing entry
+        mem.phys_write((0x1000 + 0x1ed * 8).into(), &0x1063_u64)
+            .unwrap();
+        sb.validate(&mut mem).unwrap();
+
+        // the kernel hint has to be mapped
+        assert!(start_block(x64::ARCH, 0x1000, 0x2000)
+            .validate(&mut mem)
+            .is_err());
+        assert!(start_block(x64::ARCH, 0x1008, 0)
+            .validate(&mut mem)
+            .is_err());
+        assert!(start_block(x64::ARCH, 0x10000, 0)
+            .validate(&mut mem)
+            .is_err());
+
+        // a x64 pml4 does not match the non-pae x86 paging mode
+        assert!(start_block(x32::ARCH, 0x1000, 0)
+            .validate(&mut mem)
+            .is_err());
+    }
+
+    #[test]
+    fn validate_x86() {
+        let mut mem = SparseMemory::from(vec![(Address::from(0x1000), vec![0u8; 0x1000])]);
+        mem.phys_write(0x1c00.into(), &0x1063_u32).unwrap();
+
+        start_block(x32::ARCH, 0x1000, 0)
+            .validate(&mut mem)
+            .unwrap();
+        assert!(start_block(x32_pae::ARCH, 0x1000, 0)
+            .validate(&mut mem)
+            .is_err());
+    }
+
+    #[test]
+    fn validate_x86pae() {
+        let mut mem = (1..=5)
+            .map(|i| (Address::from(i * 0x1000), vec![0u8; 0x1000]))
+            .collect::<SparseMemory>();
+
+        // the pdpt at 0x1000 points to the page directories at 0x2000 - 0x5000
+        for i in 0..4u64 {
+            mem.phys_write((0x1000 + i * 8).into(), &(0x2001 + i * 0x1000))
+                .unwrap();
+        }
+        assert!(start_block(x32_pae::ARCH, 0x1000, 0)
+            .validate(&mut mem)
+            .is_err());
+
+        for i in 0..4u64 {
+            mem.phys_write((0x5000 + i * 8).into(), &(0x2063 + i * 0x1000))
+                .unwrap();
+        }
+        start_block(x32_pae::ARCH, 0x1000, 0)
+            .validate(&mut mem)
+            .unwrap();
+        assert!(start_block(x64::ARCH, 0x1000, 0)
+            .validate(&mut mem)
+            .is_err());
+    }
+}
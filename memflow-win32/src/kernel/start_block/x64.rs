@@ -1,9 +1,9 @@
 use crate::error::{Error, Result};
-use crate::kernel::StartBlock;
+use crate::kernel::{StartBlock, StartBlockSource};
 
 use std::convert::TryInto;
 
-use memflow::architecture::x86::x64;
+use memflow::architecture::x86::{self, x64};
 use memflow::types::{size, Address};
 
 // https://github.com/ufrisk/MemProcFS/blob/f2d15cf4fe4f19cfeea3dad52971fae2e491064b/vmm/vmmwininit.c#L560
@@ -23,9 +23,11 @@ pub fn find_lowstub(stub: &[u8]) -> Result<StartBlock> {
             (0xffff_ff00_0000_0fff & u64::from_le_bytes(c[0xa0..0xa0 + 8].try_into().unwrap())) == 0
         }) // pml4
         .map(|c| StartBlock {
-            arch: x64::ARCH,
+            // CR4 directly follows CR3 in the special registers of the start block
+            arch: x86::x64_arch_from_cr4(u64::from_le_bytes(c[0xa8..0xa8 + 8].try_into().unwrap())),
             kernel_hint: u64::from_le_bytes(c[0x70..0x70 + 8].try_into().unwrap()).into(),
             dtb: u64::from_le_bytes(c[0xa0..0xa0 + 8].try_into().unwrap()).into(),
+            source: StartBlockSource::LowStub,
         })
         .ok_or_else(|| Error::Initialization("unable to find x64 dtb in lowstub < 1M"))?)
 }
@@ -66,6 +68,7 @@ pub fn find(mem: &[u8]) -> Result<StartBlock> {
             arch: x64::ARCH,
             kernel_hint: 0.into(),
             dtb: addr,
+            source: StartBlockSource::PageTableScan,
         })
         .next()
         .ok_or_else(|| Error::Initialization("unable to find x64 dtb in lowstub < 16M"))
@@ -1,5 +1,5 @@
 use crate::error::{Error, Result};
-use crate::kernel::StartBlock;
+use crate::kernel::{StartBlock, StartBlockSource};
 
 use std::convert::TryInto;
 
@@ -26,6 +26,7 @@ pub fn find(mem: &[u8]) -> Result<StartBlock> {
             arch: x32_pae::ARCH,
             kernel_hint: 0.into(),
             dtb: a,
+            source: StartBlockSource::PageTableScan,
         })
         .ok_or_else(|| Error::Initialization("unable to find x86_pae dtb in lowstub < 16M"))
 }
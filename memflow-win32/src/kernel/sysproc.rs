@@ -69,7 +69,7 @@ pub fn find_exported<T: VirtualMemory>(
             virt_mem.virt_read_raw_into(sys_proc, &mut buf)?;
             u32::from_le_bytes(buf[0..4].try_into().unwrap()).into()
         }
-        _ => return Err(Error::InvalidArchitecture),
+        bits => return Err(Error::InvalidArchitecture { bits }),
     };
     Ok(sys_proc_addr)
 }
@@ -3,6 +3,9 @@ use std::convert::TryFrom;
 #[cfg(feature = "symstore")]
 use super::symstore::SymbolStore;
 
+#[cfg(feature = "std")]
+use super::cache::Win32OffsetsCache;
+
 use super::offset_table::Win32OffsetFile;
 use super::{Win32Offsets, Win32OffsetsArchitecture};
 
@@ -25,6 +28,9 @@ pub struct Win32OffsetBuilder {
     #[cfg(feature = "symstore")]
     symbol_store: Option<SymbolStore>,
 
+    #[cfg(feature = "std")]
+    offsets_cache: Option<Win32OffsetsCache>,
+
     guid: Option<Win32GUID>,
     winver: Option<Win32Version>,
     arch: Option<Win32OffsetsArchitecture>,
@@ -36,6 +42,9 @@ impl Default for Win32OffsetBuilder {
             #[cfg(feature = "symstore")]
             symbol_store: Some(SymbolStore::default()),
 
+            #[cfg(feature = "std")]
+            offsets_cache: None,
+
             guid: None,
             winver: None,
             arch: None,
@@ -55,19 +64,68 @@ impl Win32OffsetBuilder {
             ));
         }
 
-        // try to build via symbol store
-        if let Ok(offs) = self.build_with_symbol_store() {
+        // try to use offsets that have been built for the same pdb before
+        if let Some(offs) = self.cached_offsets() {
             return Ok(offs);
         }
 
+        // try to build via symbol store
+        let symstore_err = match self.build_with_symbol_store() {
+            Ok(offs) => {
+                let offs = self.apply_winver(offs);
+                self.cache_offsets(&offs);
+                return Ok(offs);
+            }
+            Err(err) => err,
+        };
+
         // use static offset list
         if let Ok(offs) = self.build_with_offset_list() {
+            let offs = self.apply_winver(offs);
+            self.cache_offsets(&offs);
             return Ok(offs);
         }
 
-        Err(Error::Other("not found"))
+        // forward symbol store failures so callers can decide how to fall back
+        match symstore_err {
+            Error::SymbolStoreUnreachable(_) | Error::PdbNotFound | Error::PdbParse(_) => {
+                Err(symstore_err)
+            }
+            _ => Err(Error::Other("not found")),
+        }
+    }
+
+    /// Stores the version of the target in the offsets in case it is known.
+    fn apply_winver(&self, offsets: Win32Offsets) -> Win32Offsets {
+        match self.winver {
+            Some(winver) if winver.major_version() != 0 => offsets.with_version(winver),
+            _ => offsets,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn cached_offsets(&self) -> Option<Win32Offsets> {
+        match (&self.offsets_cache, &self.guid) {
+            (Some(cache), Some(guid)) => cache.get(guid),
+            _ => None,
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn cached_offsets(&self) -> Option<Win32Offsets> {
+        None
+    }
+
+    #[cfg(feature = "std")]
+    fn cache_offsets(&self, offsets: &Win32Offsets) {
+        if let (Some(cache), Some(guid)) = (&self.offsets_cache, &self.guid) {
+            cache.insert(guid, offsets.clone());
+        }
     }
 
+    #[cfg(not(feature = "std"))]
+    fn cache_offsets(&self, _offsets: &Win32Offsets) {}
+
     #[cfg(feature = "embed_offsets")]
     fn build_with_offset_list(&self) -> Result<Win32Offsets> {
         // # Safety
@@ -86,6 +144,11 @@ impl Win32OffsetBuilder {
                     if target_guid.file_name == file && target_guid.guid == guid {
                         return Ok(Win32Offsets {
                             0: offset.offsets.clone(),
+                            1: Win32Version::new(
+                                offset.nt_major_version,
+                                offset.nt_minor_version,
+                                offset.nt_build_number,
+                            ),
                         });
                     }
                 }
@@ -107,6 +170,7 @@ impl Win32OffsetBuilder {
                     prev_build_number = offset.nt_build_number;
                     closest_match = Some(Win32Offsets {
                         0: offset.offsets.clone(),
+                        1: *winver,
                     });
                 }
             }
@@ -163,6 +227,16 @@ impl Win32OffsetBuilder {
         self
     }
 
+    /// Configures a cache that is shared between multiple builders.
+    ///
+    /// The offsets are looked up in the cache by the guid of the pdb before they are built.
+    /// Newly built offsets are stored in the cache afterwards.
+    #[cfg(feature = "std")]
+    pub fn offsets_cache(mut self, offsets_cache: Win32OffsetsCache) -> Self {
+        self.offsets_cache = Some(offsets_cache);
+        self
+    }
+
     pub fn guid(mut self, guid: Win32GUID) -> Self {
         self.guid = Some(guid);
         self
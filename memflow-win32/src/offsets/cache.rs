@@ -0,0 +1,118 @@
+/*!
+Cache for offsets that can be shared between multiple kernels.
+
+Building the offsets for a kernel might require downloading and parsing the pdb of the kernel.
+When working with multiple targets in the same process (e.g. several dumps of different windows builds)
+a `Win32OffsetsCache` can be handed to every `Win32OffsetBuilder` or `KernelBuilder`
+so that the offsets for each pdb are only built once.
+
+The cache is reference counted, cloning it will return a handle to the same cache.
+
+# Examples
+
+```
+use memflow::mem::PhysicalMemory;
+use memflow_win32::offsets::Win32OffsetsCache;
+use memflow_win32::win32::Kernel;
+
+fn open_all<T: PhysicalMemory>(connectors: Vec<T>) {
+    let cache = Win32OffsetsCache::new();
+
+    for connector in connectors.into_iter() {
+        let _kernel = Kernel::builder(connector)
+            .offsets_cache(cache.clone())
+            .build()
+            .unwrap();
+    }
+}
+```
+*/
+
+use std::prelude::v1::*;
+
+use super::Win32Offsets;
+use crate::kernel::Win32GUID;
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Shared cache of offsets keyed by the guid of the kernel pdb.
+#[derive(Debug, Clone, Default)]
+pub struct Win32OffsetsCache {
+    offsets: Arc<Mutex<BTreeMap<(String, String), Win32Offsets>>>,
+}
+
+impl Win32OffsetsCache {
+    /// Creates a new and empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the cached offsets for the given guid.
+    pub fn get(&self, guid: &Win32GUID) -> Option<Win32Offsets> {
+        self.offsets
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&(guid.file_name.clone(), guid.guid.clone()))
+            .cloned()
+    }
+
+    /// Stores the offsets for the given guid in the cache.
+    pub fn insert(&self, guid: &Win32GUID, offsets: Win32Offsets) {
+        self.offsets
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert((guid.file_name.clone(), guid.guid.clone()), offsets);
+    }
+
+    /// Returns the number of cached offsets.
+    pub fn len(&self) -> usize {
+        self.offsets
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    /// Returns true if the cache does not contain any offsets.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all offsets from the cache.
+    pub fn clear(&self) {
+        self.offsets
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::offsets::Win32OffsetBuilder;
+    use crate::win32::DUMMY_OFFSETS;
+
+    #[test]
+    fn shared_cache() {
+        let guid = Win32GUID::new("ntkrnlmp.pdb", "00000000000000000000000000000000F");
+
+        let cache = Win32OffsetsCache::new();
+        let other = cache.clone();
+        assert!(cache.get(&guid).is_none());
+
+        other.insert(&guid, Win32Offsets::from(DUMMY_OFFSETS));
+        assert_eq!(cache.len(), 1);
+
+        // the builder returns the cached offsets without consulting the symbol store
+        let offsets = Win32OffsetBuilder::new()
+            .offsets_cache(cache.clone())
+            .guid(guid)
+            .build()
+            .unwrap();
+        assert_eq!(offsets.eproc_link(), DUMMY_OFFSETS.eproc_link as usize);
+
+        cache.clear();
+        assert!(other.is_empty());
+    }
+}
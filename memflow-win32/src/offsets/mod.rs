@@ -1,9 +1,16 @@
 pub mod builder;
 pub use builder::Win32OffsetBuilder;
 
+#[cfg(feature = "std")]
+pub mod cache;
+#[cfg(feature = "std")]
+pub use cache::Win32OffsetsCache;
+
 #[cfg(feature = "symstore")]
 pub mod pdb_struct;
 #[cfg(feature = "symstore")]
+pub mod pdb_symbols;
+#[cfg(feature = "symstore")]
 pub mod symstore;
 
 pub mod offset_table;
@@ -11,7 +18,7 @@ pub mod offset_table;
 pub use offset_table::{Win32OffsetFile, Win32OffsetTable, Win32OffsetsArchitecture};
 
 #[cfg(feature = "symstore")]
-pub use {pdb_struct::PdbStruct, symstore::*};
+pub use {pdb_struct::PdbStruct, pdb_symbols::PdbSymbols, symstore::*};
 
 use std::prelude::v1::*;
 
@@ -19,19 +26,47 @@ use std::prelude::v1::*;
 use std::{fs::File, io::Read, path::Path};
 
 use crate::error::{Error, Result};
-use crate::kernel::Win32GUID;
+use crate::kernel::{Win32GUID, Win32Version};
 use memflow::architecture::{self, ArchitectureObj};
 
 #[derive(Debug, Copy, Clone)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize))]
 pub struct Win32ArchOffsets {
-    pub peb_ldr: usize,            // _PEB::Ldr
-    pub ldr_list: usize,           // _PEB_LDR_DATA::InLoadOrderModuleList
-    pub ldr_data_base: usize,      // _LDR_DATA_TABLE_ENTRY::DllBase
-    pub ldr_data_size: usize,      // _LDR_DATA_TABLE_ENTRY::SizeOfImage
-    pub ldr_data_full_name: usize, // _LDR_DATA_TABLE_ENTRY::FullDllName
-    pub ldr_data_base_name: usize, // _LDR_DATA_TABLE_ENTRY::BaseDllName
+    pub peb_ldr: usize,                       // _PEB::Ldr
+    pub ldr_list: usize,                      // _PEB_LDR_DATA::InLoadOrderModuleList
+    pub ldr_data_base: usize,                 // _LDR_DATA_TABLE_ENTRY::DllBase
+    pub ldr_data_size: usize,                 // _LDR_DATA_TABLE_ENTRY::SizeOfImage
+    pub ldr_data_full_name: usize,            // _LDR_DATA_TABLE_ENTRY::FullDllName
+    pub ldr_data_base_name: usize,            // _LDR_DATA_TABLE_ENTRY::BaseDllName
+    pub teb_stack_base: usize,                // _TEB::NtTib.StackBase
+    pub teb_stack_limit: usize,               // _TEB::NtTib.StackLimit
+    pub teb_last_error: usize,                // _TEB::LastErrorValue
+    pub teb_tls_slots: usize,                 // _TEB::TlsSlots
+    pub peb_number_of_heaps: usize,           // _PEB::NumberOfHeaps
+    pub peb_process_heaps: usize,             // _PEB::ProcessHeaps
+    pub heap_segment_list: usize,             // _HEAP::SegmentList
+    pub heap_segment_list_entry: usize,       // _HEAP_SEGMENT::SegmentListEntry
+    pub heap_segment_base_address: usize,     // _HEAP_SEGMENT::BaseAddress
+    pub heap_segment_number_of_pages: usize,  // _HEAP_SEGMENT::NumberOfPages
+    pub heap_segment_first_entry: usize,      // _HEAP_SEGMENT::FirstEntry
+    pub heap_segment_last_valid_entry: usize, // _HEAP_SEGMENT::LastValidEntry
+    pub peb_process_parameters: usize,        // _PEB::ProcessParameters
+    pub params_flags: usize,                  // _RTL_USER_PROCESS_PARAMETERS::Flags
+    pub params_console_handle: usize,         // _RTL_USER_PROCESS_PARAMETERS::ConsoleHandle
+    pub params_standard_input: usize,         // _RTL_USER_PROCESS_PARAMETERS::StandardInput
+    pub params_standard_output: usize,        // _RTL_USER_PROCESS_PARAMETERS::StandardOutput
+    pub params_standard_error: usize,         // _RTL_USER_PROCESS_PARAMETERS::StandardError
+    pub params_cur_dir: usize,                // _RTL_USER_PROCESS_PARAMETERS::CurrentDirectory
+    pub params_cur_dir_handle: usize,         // _CURDIR::Handle
+    pub params_dll_path: usize,               // _RTL_USER_PROCESS_PARAMETERS::DllPath
+    pub params_image_path_name: usize,        // _RTL_USER_PROCESS_PARAMETERS::ImagePathName
+    pub params_command_line: usize,           // _RTL_USER_PROCESS_PARAMETERS::CommandLine
+    pub params_environment: usize,            // _RTL_USER_PROCESS_PARAMETERS::Environment
+    pub params_window_title: usize,           // _RTL_USER_PROCESS_PARAMETERS::WindowTitle
+    pub params_desktop_info: usize,           // _RTL_USER_PROCESS_PARAMETERS::DesktopInfo
+    pub params_shell_info: usize,             // _RTL_USER_PROCESS_PARAMETERS::ShellInfo
+    pub params_environment_size: usize,       // _RTL_USER_PROCESS_PARAMETERS::EnvironmentSize
 }
 
 pub const X86: Win32ArchOffsets = Win32ArchOffsets {
@@ -41,6 +76,34 @@ pub const X86: Win32ArchOffsets = Win32ArchOffsets {
     ldr_data_size: 0x20,
     ldr_data_full_name: 0x24,
     ldr_data_base_name: 0x2c,
+    teb_stack_base: 0x4,
+    teb_stack_limit: 0x8,
+    teb_last_error: 0x34,
+    teb_tls_slots: 0xe10,
+    peb_number_of_heaps: 0x88,
+    peb_process_heaps: 0x90,
+    heap_segment_list: 0xa4,
+    heap_segment_list_entry: 0x10,
+    heap_segment_base_address: 0x1c,
+    heap_segment_number_of_pages: 0x20,
+    heap_segment_first_entry: 0x24,
+    heap_segment_last_valid_entry: 0x28,
+    peb_process_parameters: 0x10,
+    params_flags: 0x8,
+    params_console_handle: 0x10,
+    params_standard_input: 0x18,
+    params_standard_output: 0x1c,
+    params_standard_error: 0x20,
+    params_cur_dir: 0x24,
+    params_cur_dir_handle: 0x2c,
+    params_dll_path: 0x30,
+    params_image_path_name: 0x38,
+    params_command_line: 0x40,
+    params_environment: 0x48,
+    params_window_title: 0x70,
+    params_desktop_info: 0x78,
+    params_shell_info: 0x80,
+    params_environment_size: 0x290,
 };
 
 pub const X64: Win32ArchOffsets = Win32ArchOffsets {
@@ -50,6 +113,34 @@ pub const X64: Win32ArchOffsets = Win32ArchOffsets {
     ldr_data_size: 0x40,
     ldr_data_full_name: 0x48,
     ldr_data_base_name: 0x58,
+    teb_stack_base: 0x8,
+    teb_stack_limit: 0x10,
+    teb_last_error: 0x68,
+    teb_tls_slots: 0x1480,
+    peb_number_of_heaps: 0xe8,
+    peb_process_heaps: 0xf0,
+    heap_segment_list: 0x120,
+    heap_segment_list_entry: 0x18,
+    heap_segment_base_address: 0x30,
+    heap_segment_number_of_pages: 0x38,
+    heap_segment_first_entry: 0x40,
+    heap_segment_last_valid_entry: 0x48,
+    peb_process_parameters: 0x20,
+    params_flags: 0x8,
+    params_console_handle: 0x10,
+    params_standard_input: 0x20,
+    params_standard_output: 0x28,
+    params_standard_error: 0x30,
+    params_cur_dir: 0x38,
+    params_cur_dir_handle: 0x48,
+    params_dll_path: 0x50,
+    params_image_path_name: 0x60,
+    params_command_line: 0x70,
+    params_environment: 0x80,
+    params_window_title: 0xb0,
+    params_desktop_info: 0xc0,
+    params_shell_info: 0xd0,
+    params_environment_size: 0x3f0,
 };
 
 impl Win32OffsetsArchitecture {
@@ -69,14 +160,19 @@ impl From<ArchitectureObj> for Win32ArchOffsets {
     }
 }
 
-#[repr(transparent)]
+/// Offsets of a specific kernel together with the version they have been resolved for.
+///
+/// The version is zero if it is unknown (e.g. when the offsets were parsed from a pdb directly).
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize))]
-pub struct Win32Offsets(pub Win32OffsetTable);
+pub struct Win32Offsets(pub Win32OffsetTable, pub Win32Version);
 
 impl From<Win32OffsetTable> for Win32Offsets {
     fn from(other: Win32OffsetTable) -> Self {
-        Self { 0: other }
+        Self {
+            0: other,
+            1: Win32Version::default(),
+        }
     }
 }
 
@@ -90,7 +186,7 @@ impl From<ArchitectureObj> for Win32OffsetsArchitecture {
     fn from(arch: ArchitectureObj) -> Win32OffsetsArchitecture {
         if arch == architecture::x86::x32::ARCH || arch == architecture::x86::x32_pae::ARCH {
             Self::X86
-        } else if arch == architecture::x86::x64::ARCH {
+        } else if architecture::x86::is_x64_arch(arch) {
             Self::X64
         } else {
             // We do not have AArch64, but that is in the plans...
@@ -102,8 +198,7 @@ impl From<ArchitectureObj> for Win32OffsetsArchitecture {
 impl Win32Offsets {
     #[cfg(feature = "symstore")]
     pub fn from_pdb<P: AsRef<Path>>(pdb_path: P) -> Result<Self> {
-        let mut file = File::open(pdb_path)
-            .map_err(|_| Error::PDB("unable to open user-supplied pdb file"))?;
+        let mut file = File::open(pdb_path).map_err(|_| Error::PdbNotFound)?;
         let mut buffer = Vec::new();
         file.read_to_end(&mut buffer)
             .map_err(|_| Error::PDB("unable to read user-supplied pdb file"))?;
@@ -113,16 +208,17 @@ impl Win32Offsets {
     #[cfg(feature = "symstore")]
     pub fn from_pdb_slice(pdb_slice: &[u8]) -> Result<Self> {
         let list = PdbStruct::with(pdb_slice, "_LIST_ENTRY")
-            .map_err(|_| Error::PDB("_LIST_ENTRY not found"))?;
-        let kproc = PdbStruct::with(pdb_slice, "_KPROCESS")
-            .map_err(|_| Error::PDB("_KPROCESS not found"))?;
-        let eproc = PdbStruct::with(pdb_slice, "_EPROCESS")
-            .map_err(|_| Error::PDB("_EPROCESS not found"))?;
+            .map_err(|_| Error::PdbParse("_LIST_ENTRY"))?;
+        let kproc =
+            PdbStruct::with(pdb_slice, "_KPROCESS").map_err(|_| Error::PdbParse("_KPROCESS"))?;
+        let eproc =
+            PdbStruct::with(pdb_slice, "_EPROCESS").map_err(|_| Error::PdbParse("_EPROCESS"))?;
         let ethread =
-            PdbStruct::with(pdb_slice, "_ETHREAD").map_err(|_| Error::PDB("_ETHREAD not found"))?;
+            PdbStruct::with(pdb_slice, "_ETHREAD").map_err(|_| Error::PdbParse("_ETHREAD"))?;
         let kthread =
-            PdbStruct::with(pdb_slice, "_KTHREAD").map_err(|_| Error::PDB("_KTHREAD not found"))?;
-        let teb = PdbStruct::with(pdb_slice, "_TEB").map_err(|_| Error::PDB("_TEB not found"))?;
+            PdbStruct::with(pdb_slice, "_KTHREAD").map_err(|_| Error::PdbParse("_KTHREAD"))?;
+        let teb = PdbStruct::with(pdb_slice, "_TEB").map_err(|_| Error::PdbParse("_TEB"))?;
+        let symbols = PdbSymbols::new(pdb_slice).map_err(|_| Error::PdbParse("symbols"))?;
 
         let list_blink = list
             .find_field("Blink")
@@ -172,6 +268,150 @@ impl Win32Offsets {
             None => 0,
         };
 
+        // process protection only exists since windows 8.1
+        let eproc_protection = match eproc.find_field("Protection") {
+            Some(f) => f.offset as _,
+            None => 0,
+        };
+
+        // token
+        let eproc_token = match eproc.find_field("Token") {
+            Some(f) => f.offset as _,
+            None => 0,
+        };
+
+        // the full image path is only stored since windows xp
+        let eproc_audit_info = match eproc.find_field("SeAuditProcessCreationInfo") {
+            Some(f) => f.offset as _,
+            None => 0,
+        };
+        let token = PdbStruct::with(pdb_slice, "_TOKEN").ok();
+        let token_user_and_groups = match token.as_ref().and_then(|s| s.find_field("UserAndGroups"))
+        {
+            Some(f) => f.offset as _,
+            None => 0,
+        };
+        // integrity levels only exist since windows vista
+        let token_integrity_level_index = match token
+            .as_ref()
+            .and_then(|s| s.find_field("IntegrityLevelIndex"))
+        {
+            Some(f) => f.offset as _,
+            None => 0,
+        };
+
+        // registry hives
+        // the hive list head is a global kernel variable and stored as a relative address
+        let cmp_hive_list_head = symbols.find_symbol("CmpHiveListHead").unwrap_or(0);
+        let cmhive = PdbStruct::with(pdb_slice, "_CMHIVE").ok();
+        let cmhive_hive_list = match cmhive.as_ref().and_then(|s| s.find_field("HiveList")) {
+            Some(f) => f.offset as _,
+            None => 0,
+        };
+        let cmhive_file_full_path = match cmhive.as_ref().and_then(|s| s.find_field("FileFullPath"))
+        {
+            Some(f) => f.offset as _,
+            None => 0,
+        };
+        let hhive = PdbStruct::with(pdb_slice, "_HHIVE").ok();
+        let hhive_base_block = match hhive.as_ref().and_then(|s| s.find_field("BaseBlock")) {
+            Some(f) => f.offset as _,
+            None => 0,
+        };
+
+        // session space
+        let eproc_session = match eproc.find_field("Session") {
+            Some(f) => f.offset as _,
+            None => 0,
+        };
+        let mm_session = PdbStruct::with(pdb_slice, "_MM_SESSION_SPACE").ok();
+        let mm_session_id = match mm_session.as_ref().and_then(|s| s.find_field("SessionId")) {
+            Some(f) => f.offset as _,
+            None => 0,
+        };
+        let mm_session_image_list =
+            match mm_session.as_ref().and_then(|s| s.find_field("ImageList")) {
+                Some(f) => f.offset as _,
+                None => 0,
+            };
+        let image_entry = PdbStruct::with(pdb_slice, "_IMAGE_ENTRY_IN_SESSION").ok();
+        let image_entry_data_table_entry = match image_entry
+            .as_ref()
+            .and_then(|s| s.find_field("DataTableEntry"))
+        {
+            Some(f) => f.offset as _,
+            None => 0,
+        };
+
+        // memory usage
+        let eproc_vm = match eproc.find_field("Vm") {
+            Some(f) => f.offset as _,
+            None => 0,
+        };
+        // since windows 10 the working set size is stored in _MMSUPPORT_FULL::Instance
+        let mmsupport_working_set_size = match PdbStruct::with(pdb_slice, "_MMSUPPORT")
+            .ok()
+            .and_then(|s| s.find_field("WorkingSetSize").cloned())
+        {
+            Some(f) => f.offset as _,
+            None => {
+                let instance = PdbStruct::with(pdb_slice, "_MMSUPPORT_INSTANCE").ok();
+                let full = PdbStruct::with(pdb_slice, "_MMSUPPORT_FULL").ok();
+                match (
+                    instance
+                        .as_ref()
+                        .and_then(|s| s.find_field("WorkingSetSize")),
+                    full.as_ref().and_then(|s| s.find_field("Instance")),
+                ) {
+                    (Some(size), Some(instance)) => (instance.offset + size.offset) as _,
+                    _ => 0,
+                }
+            }
+        };
+        let eproc_virtual_size = match eproc.find_field("VirtualSize") {
+            Some(f) => f.offset as _,
+            None => 0,
+        };
+        let eproc_commit_charge = match eproc.find_field("CommitCharge") {
+            Some(f) => f.offset as _,
+            None => 0,
+        };
+
+        // pfn database
+        // both globals are stored as relative addresses
+        let mm_pfn_database = symbols.find_symbol("MmPfnDatabase").unwrap_or(0);
+        let mm_highest_physical_page = symbols.find_symbol("MmHighestPhysicalPage").unwrap_or(0);
+        let mmpfn = PdbStruct::with(pdb_slice, "_MMPFN").ok();
+        let mmpfn_size = mmpfn.as_ref().map(|s| s.size()).unwrap_or(0) as _;
+        let mmpfn_pte_address = match mmpfn.as_ref().and_then(|s| s.find_field("PteAddress")) {
+            Some(f) => f.offset as _,
+            None => 0,
+        };
+        let mmpfn_u3 = match mmpfn.as_ref().and_then(|s| s.find_field("u3")) {
+            Some(f) => f.offset as _,
+            None => 0,
+        };
+
+        // idle process and processors
+        // PsIdleProcess and KiProcessorBlock are stored as relative addresses
+        let ps_idle_process = symbols.find_symbol("PsIdleProcess").unwrap_or(0);
+        let ki_processor_block = symbols.find_symbol("KiProcessorBlock").unwrap_or(0);
+        let kprcb = PdbStruct::with(pdb_slice, "_KPRCB").ok();
+        let kprcb_current_thread = match kprcb.as_ref().and_then(|s| s.find_field("CurrentThread"))
+        {
+            Some(f) => f.offset as _,
+            None => 0,
+        };
+        // _KTHREAD::ApcState.Process
+        let kapc_state = PdbStruct::with(pdb_slice, "_KAPC_STATE").ok();
+        let kthread_process = match (
+            kthread.find_field("ApcState"),
+            kapc_state.as_ref().and_then(|s| s.find_field("Process")),
+        ) {
+            (Some(apc_state), Some(process)) => (apc_state.offset + process.offset) as _,
+            _ => 0,
+        };
+
         // threads
         let kthread_teb = kthread
             .find_field("Teb")
@@ -186,7 +426,7 @@ impl Win32Offsets {
             .ok_or_else(|| Error::PDB("_TEB::ProcessEnvironmentBlock not found"))?
             .offset as _;
         let teb_peb_x86 = if let Ok(teb32) =
-            PdbStruct::with(pdb_slice, "_TEB32").map_err(|_| Error::PDB("_TEB32 not found"))
+            PdbStruct::with(pdb_slice, "_TEB32").map_err(|_| Error::PdbParse("_TEB32"))
         {
             teb32
                 .find_field("ProcessEnvironmentBlock")
@@ -210,20 +450,80 @@ impl Win32Offsets {
                 eproc_exit_status,
                 eproc_thread_list,
                 eproc_wow64,
+                eproc_protection,
+                eproc_token,
+                eproc_audit_info,
+
+                token_user_and_groups,
+                token_integrity_level_index,
+
+                cmp_hive_list_head,
+                cmhive_hive_list,
+                cmhive_file_full_path,
+                hhive_base_block,
+
+                eproc_session,
+                mm_session_id,
+                mm_session_image_list,
+                image_entry_data_table_entry,
+
+                eproc_vm,
+                mmsupport_working_set_size,
+                eproc_virtual_size,
+                eproc_commit_charge,
+
+                mm_pfn_database,
+                mm_highest_physical_page,
+                mmpfn_size,
+                mmpfn_pte_address,
+                mmpfn_u3,
+
+                ps_idle_process,
+                ki_processor_block,
+                kprcb_current_thread,
+                kthread_process,
 
                 kthread_teb,
                 ethread_list_entry,
                 teb_peb,
                 teb_peb_x86,
             },
+            1: Win32Version::default(),
         })
     }
 
+    /// Returns the windows version these offsets have been resolved for.
+    pub fn version(&self) -> Win32Version {
+        self.1
+    }
+
+    /// Sets the windows version these offsets have been resolved for.
+    pub fn with_version(mut self, version: Win32Version) -> Self {
+        self.1 = version;
+        self
+    }
+
+    /// Returns the given offset if the resolved version is at least `major.minor`.
+    ///
+    /// In case the version is unknown the offset is only returned if it has been resolved (non-zero).
+    fn since_version(&self, offset: usize, major: u32, minor: u32) -> Option<usize> {
+        if self.1.major_version() != 0 && self.1 < Win32Version::from((major, minor)) {
+            None
+        } else if offset != 0 {
+            Some(offset)
+        } else {
+            None
+        }
+    }
+
     /// _LIST_ENTRY::Blink offset
     pub fn list_blink(&self) -> usize {
         self.0.list_blink as usize
     }
-    /// _LIST_ENTRY::Flink offset
+    /// _EPROCESS::ActiveProcessLinks offset
+    ///
+    /// `PsActiveProcessHead` and the links of all nodes point to this field of the next `_EPROCESS`,
+    /// so the same offset is used to enter the list and to get from a link back to its `_EPROCESS`.
     pub fn eproc_link(&self) -> usize {
         self.0.eproc_link as usize
     }
@@ -268,6 +568,152 @@ impl Win32Offsets {
     pub fn eproc_wow64(&self) -> usize {
         self.0.eproc_wow64 as usize
     }
+    /// _EPROCESS::WoW64Process offset if the field exists in the resolved version
+    /// Exists since version 5.2
+    pub fn eproc_wow64_checked(&self) -> Option<usize> {
+        self.since_version(self.eproc_wow64(), 5, 2)
+    }
+    /// _EPROCESS::Protection offset
+    /// Exists since version 6.3
+    pub fn eproc_protection(&self) -> usize {
+        self.0.eproc_protection as usize
+    }
+    /// _EPROCESS::Protection offset if the field exists in the resolved version
+    /// Exists since version 6.3
+    pub fn eproc_protection_checked(&self) -> Option<usize> {
+        self.since_version(self.eproc_protection(), 6, 3)
+    }
+    /// _EPROCESS::Token offset
+    /// Exists since version 5.0
+    pub fn eproc_token(&self) -> usize {
+        self.0.eproc_token as usize
+    }
+    /// _EPROCESS::SeAuditProcessCreationInfo offset
+    /// Exists since version 5.1
+    pub fn eproc_audit_info(&self) -> usize {
+        self.0.eproc_audit_info as usize
+    }
+
+    /// _TOKEN::UserAndGroups offset
+    /// Exists since version 5.0
+    pub fn token_user_and_groups(&self) -> usize {
+        self.0.token_user_and_groups as usize
+    }
+    /// _TOKEN::IntegrityLevelIndex offset
+    /// Exists since version 6.0
+    pub fn token_integrity_level_index(&self) -> usize {
+        self.0.token_integrity_level_index as usize
+    }
+
+    /// CmpHiveListHead relative virtual address
+    /// Exists since version 5.0
+    pub fn cmp_hive_list_head(&self) -> usize {
+        self.0.cmp_hive_list_head as usize
+    }
+    /// _CMHIVE::HiveList offset
+    /// Exists since version 5.0
+    pub fn cmhive_hive_list(&self) -> usize {
+        self.0.cmhive_hive_list as usize
+    }
+    /// _CMHIVE::FileFullPath offset
+    /// Exists since version 5.0
+    pub fn cmhive_file_full_path(&self) -> usize {
+        self.0.cmhive_file_full_path as usize
+    }
+    /// _HHIVE::BaseBlock offset
+    /// Exists since version 5.0
+    pub fn hhive_base_block(&self) -> usize {
+        self.0.hhive_base_block as usize
+    }
+
+    /// _EPROCESS::Session offset
+    /// Exists since version 5.0
+    pub fn eproc_session(&self) -> usize {
+        self.0.eproc_session as usize
+    }
+    /// _MM_SESSION_SPACE::SessionId offset
+    /// Exists since version 5.0
+    pub fn mm_session_id(&self) -> usize {
+        self.0.mm_session_id as usize
+    }
+    /// _MM_SESSION_SPACE::ImageList offset
+    /// Exists since version 5.0
+    pub fn mm_session_image_list(&self) -> usize {
+        self.0.mm_session_image_list as usize
+    }
+    /// _IMAGE_ENTRY_IN_SESSION::DataTableEntry offset
+    /// Exists since version 6.2
+    pub fn image_entry_data_table_entry(&self) -> usize {
+        self.0.image_entry_data_table_entry as usize
+    }
+
+    /// _EPROCESS::Vm offset
+    /// Exists since version 5.0
+    pub fn eproc_vm(&self) -> usize {
+        self.0.eproc_vm as usize
+    }
+    /// _MMSUPPORT::WorkingSetSize offset (relative to _EPROCESS::Vm)
+    /// Exists since version 5.0
+    pub fn mmsupport_working_set_size(&self) -> usize {
+        self.0.mmsupport_working_set_size as usize
+    }
+    /// _EPROCESS::VirtualSize offset
+    /// Exists since version 3.10
+    pub fn eproc_virtual_size(&self) -> usize {
+        self.0.eproc_virtual_size as usize
+    }
+    /// _EPROCESS::CommitCharge offset
+    /// Exists since version 3.10
+    pub fn eproc_commit_charge(&self) -> usize {
+        self.0.eproc_commit_charge as usize
+    }
+
+    /// MmPfnDatabase relative virtual address
+    /// Exists since version 5.0
+    pub fn mm_pfn_database(&self) -> usize {
+        self.0.mm_pfn_database as usize
+    }
+    /// MmHighestPhysicalPage relative virtual address
+    /// Exists since version 5.0
+    pub fn mm_highest_physical_page(&self) -> usize {
+        self.0.mm_highest_physical_page as usize
+    }
+    /// _MMPFN size
+    /// Exists since version 5.0
+    pub fn mmpfn_size(&self) -> usize {
+        self.0.mmpfn_size as usize
+    }
+    /// _MMPFN::PteAddress offset
+    /// Exists since version 5.0
+    pub fn mmpfn_pte_address(&self) -> usize {
+        self.0.mmpfn_pte_address as usize
+    }
+    /// _MMPFN::u3 offset
+    /// Exists since version 5.0
+    pub fn mmpfn_u3(&self) -> usize {
+        self.0.mmpfn_u3 as usize
+    }
+
+    /// PsIdleProcess relative virtual address
+    /// Exists since version 3.10
+    pub fn ps_idle_process(&self) -> usize {
+        self.0.ps_idle_process as usize
+    }
+    /// KiProcessorBlock relative virtual address
+    /// Exists since version 5.0
+    pub fn ki_processor_block(&self) -> usize {
+        self.0.ki_processor_block as usize
+    }
+    /// _KPRCB::CurrentThread offset
+    /// Exists since version 5.0
+    pub fn kprcb_current_thread(&self) -> usize {
+        self.0.kprcb_current_thread as usize
+    }
+    /// _KTHREAD::ApcState.Process offset
+    /// Exists since version 5.0
+    pub fn kthread_process(&self) -> usize {
+        self.0.kthread_process as usize
+    }
 
     /// _KTHREAD::Teb offset
     /// Exists since version 6.2
@@ -327,4 +773,46 @@ mod tests {
         assert_eq!(offsets.0.teb_peb, 96);
         assert_eq!(offsets.0.teb_peb_x86, 48);
     }
+
+    #[test]
+    fn version_gated_offsets() {
+        let offsets = Win32Offsets::from(crate::win32::DUMMY_OFFSETS);
+        assert_eq!(offsets.version().major_version(), 0);
+
+        // without a version only resolved offsets are returned
+        assert_eq!(offsets.eproc_wow64_checked(), None);
+        assert_eq!(offsets.eproc_protection_checked(), Some(0x3e0));
+
+        let offsets = offsets.with_version(Win32Version::new(6, 1, 7601));
+        assert_eq!(offsets.eproc_protection_checked(), None);
+
+        let offsets = offsets.with_version(Win32Version::new(10, 0, 19041));
+        assert_eq!(offsets.eproc_protection_checked(), Some(0x3e0));
+    }
+
+    #[test]
+    fn symbol_store_unreachable() {
+        let guid = Win32GUID {
+            file_name: "ntkrnlmp.pdb".to_string(),
+            guid: "00000000000000000000000000000000F".to_string(),
+        };
+        let err = Win32Offsets::builder()
+            .symbol_store(SymbolStore::new().base_url("http://127.0.0.1:1").no_cache())
+            .guid(guid)
+            .build()
+            .unwrap_err();
+
+        match err {
+            Error::SymbolStoreUnreachable(_) => {}
+            err => panic!("unexpected error: {}", err),
+        }
+    }
+
+    #[test]
+    fn pdb_parse_error() {
+        assert_eq!(
+            Win32Offsets::from_pdb_slice(&[0u8; 0x100]).unwrap_err(),
+            Error::PdbParse("_LIST_ENTRY")
+        );
+    }
 }
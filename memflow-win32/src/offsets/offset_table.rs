@@ -157,6 +157,13 @@ impl<'de> ::serde::de::Deserialize<'de> for BinaryString {
     }
 }
 
+/// Offsets of the kernel structures of a specific windows build.
+///
+/// Fields marked with `serde(default)` are not contained in the offset files that are embedded
+/// into this crate and are zero when the offsets are loaded from one of them.
+/// The features depending on these fields (process protection and integrity level, image paths,
+/// registry hives, sessions, memory usage, the PFN database and the current process of a cpu)
+/// are only available when the offsets have been generated from a pdb.
 #[repr(C, align(4))]
 #[derive(Debug, Clone, Pod)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
@@ -180,6 +187,90 @@ pub struct Win32OffsetTable {
     pub eproc_thread_list: u32,
     /// Since version 5.0
     pub eproc_wow64: u32,
+    /// Since version 6.3
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub eproc_protection: u32,
+    /// Since version 5.0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub eproc_token: u32,
+    /// Since version 5.1
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub eproc_audit_info: u32,
+
+    /// Since version 5.0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub token_user_and_groups: u32,
+    /// Since version 6.0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub token_integrity_level_index: u32,
+
+    /// Since version 5.0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub cmp_hive_list_head: u32,
+    /// Since version 5.0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub cmhive_hive_list: u32,
+    /// Since version 5.0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub cmhive_file_full_path: u32,
+    /// Since version 5.0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub hhive_base_block: u32,
+
+    /// Since version 5.0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub eproc_session: u32,
+    /// Since version 5.0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mm_session_id: u32,
+    /// Since version 5.0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mm_session_image_list: u32,
+    /// Since version 6.2
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub image_entry_data_table_entry: u32,
+
+    /// Since version 5.0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub eproc_vm: u32,
+    /// Since version 5.0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mmsupport_working_set_size: u32,
+    /// Since version 3.10
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub eproc_virtual_size: u32,
+    /// Since version 3.10
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub eproc_commit_charge: u32,
+
+    /// Since version 5.0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mm_pfn_database: u32,
+    /// Since version 5.0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mm_highest_physical_page: u32,
+    /// Since version 5.0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mmpfn_size: u32,
+    /// Since version 5.0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mmpfn_pte_address: u32,
+    /// Since version 5.0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mmpfn_u3: u32,
+
+    /// Since version 3.10
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ps_idle_process: u32,
+    /// Since version 5.0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ki_processor_block: u32,
+    /// Since version 5.0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub kprcb_current_thread: u32,
+    /// Since version 5.0
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub kthread_process: u32,
 
     /// Since version 6.2
     pub kthread_teb: u32,
@@ -16,6 +16,7 @@ pub struct PdbField {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PdbStruct {
+    size: usize,
     field_map: HashMap<String, PdbField>,
 }
 
@@ -29,6 +30,7 @@ impl PdbStruct {
 
         let mut needed_types = TypeSet::new();
         let mut data = data::Data::new();
+        let mut size = 0;
 
         let mut type_iter = type_information.iter();
         while let Some(typ) = type_iter.next()? {
@@ -39,6 +41,7 @@ impl PdbStruct {
                 if class.name.as_bytes() == class_name.as_bytes()
                     && !class.properties.forward_reference()
                 {
+                    size = class.size as usize;
                     data.add(&type_finder, typ.index(), &mut needed_types)?;
                     break;
                 }
@@ -77,7 +80,12 @@ impl PdbStruct {
             });
         }
 
-        Ok(Self { field_map })
+        Ok(Self { size, field_map })
+    }
+
+    /// Returns the size of the struct in bytes.
+    pub fn size(&self) -> usize {
+        self.size
     }
 
     pub fn find_field(&self, name: &str) -> Option<&PdbField> {
@@ -0,0 +1,40 @@
+use std::prelude::v1::*;
+
+use super::pdb_struct::PdbSourceBuffer;
+use std::collections::HashMap;
+
+use pdb::{FallibleIterator, Result, SymbolData, PDB};
+
+/// Contains the relative virtual addresses of all public symbols found in a pdb.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdbSymbols {
+    symbol_map: HashMap<String, u32>,
+}
+
+impl PdbSymbols {
+    pub fn new(pdb_slice: &[u8]) -> Result<Self> {
+        let pdb_buffer = PdbSourceBuffer::new(pdb_slice);
+        let mut pdb = PDB::open(pdb_buffer)?;
+
+        let symbol_table = pdb.global_symbols()?;
+        let address_map = pdb.address_map()?;
+
+        let mut symbol_map = HashMap::new();
+
+        let mut symbols = symbol_table.iter();
+        while let Some(symbol) = symbols.next()? {
+            if let Ok(SymbolData::Public(data)) = symbol.parse() {
+                if let Some(rva) = data.offset.to_rva(&address_map) {
+                    symbol_map.insert(data.name.to_string().into_owned(), rva.0);
+                }
+            }
+        }
+
+        Ok(Self { symbol_map })
+    }
+
+    /// Returns the relative virtual address of the given symbol.
+    pub fn find_symbol(&self, name: &str) -> Option<u32> {
+        self.symbol_map.get(name).copied()
+    }
+}
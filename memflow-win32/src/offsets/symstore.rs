@@ -44,7 +44,7 @@ fn read_to_end<T: Read>(reader: &mut T, len: usize) -> Result<Vec<u8>> {
 
     reader
         .read_to_end(&mut buffer)
-        .map_err(|_| Error::SymbolStore("unable to read from http request"))?;
+        .map_err(|_| Error::SymbolStoreUnreachable("unable to read from http request"))?;
     finished.store(true, Ordering::Relaxed);
     thread.join().unwrap();
 
@@ -54,7 +54,9 @@ fn read_to_end<T: Read>(reader: &mut T, len: usize) -> Result<Vec<u8>> {
 #[cfg(not(feature = "download_progress"))]
 fn read_to_end<T: Read>(reader: &mut T, _len: usize) -> Result<Vec<u8>> {
     let mut buffer = vec![];
-    reader.read_to_end(&mut buffer)?;
+    reader
+        .read_to_end(&mut buffer)
+        .map_err(|_| Error::SymbolStoreUnreachable("unable to read from http request"))?;
     Ok(buffer)
 }
 
@@ -126,27 +128,43 @@ impl SymbolStore {
     fn download(&self, guid: &Win32GUID) -> Result<Vec<u8>> {
         let pdb_url = format!("{}/{}/{}", self.base_url, guid.file_name, guid.guid);
 
+        // only fall back to the file pointer if the server is reachable but does not have the pdb
         self.download_file(&format!("{}/{}", pdb_url, guid.file_name))
-            .or_else(|_| self.download_file(&format!("{}/{}", pdb_url, "file.ptr")))
+            .or_else(|err| match err {
+                Error::PdbNotFound => self.download_file(&format!("{}/{}", pdb_url, "file.ptr")),
+                err => Err(err),
+            })
     }
 
     fn download_file(&self, url: &str) -> Result<Vec<u8>> {
         info!("downloading pdb from {}", url);
         let resp = ureq::get(url).call();
-        if !resp.ok() {
-            return Err(Error::SymbolStore("unable to download pdb"));
+        if resp.synthetic() {
+            // synthetic responses are created by ureq for transport errors (dns, connect, tls, ...)
+            return Err(Error::SymbolStoreUnreachable(
+                "unable to connect to symbol server",
+            ));
+        } else if resp.status() == 404 {
+            return Err(Error::PdbNotFound);
+        } else if !resp.ok() {
+            return Err(Error::SymbolStoreUnreachable("unable to download pdb"));
         }
 
-        assert!(resp.has("Content-Length"));
         let len = resp
             .header("Content-Length")
             .and_then(|s| s.parse::<usize>().ok())
-            .unwrap();
+            .ok_or(Error::SymbolStoreUnreachable(
+                "symbol server did not send a content length",
+            ))?;
 
         let mut reader = resp.into_reader();
         let buffer = read_to_end(&mut reader, len)?;
 
-        assert_eq!(buffer.len(), len);
+        if buffer.len() != len {
+            return Err(Error::SymbolStoreUnreachable(
+                "pdb download was interrupted",
+            ));
+        }
         Ok(buffer)
     }
 
@@ -2,18 +2,38 @@ pub mod kernel;
 pub mod kernel_builder;
 pub mod kernel_info;
 
-pub use kernel::Kernel;
+pub use kernel::{EProcessIter, Kernel};
 pub use kernel_builder::KernelBuilder;
-pub use kernel_info::KernelInfo;
+pub use kernel_info::{rank_dtbs, KernelInfo};
 
+#[cfg(any(feature = "dummy_mem", test))]
+pub mod dummy;
+pub mod hive;
 pub mod keyboard;
+pub mod kuser_shared_data;
+pub mod list;
 pub mod module;
+pub mod pagefile;
+pub mod pfn;
 pub mod process;
+#[cfg(feature = "std")]
+pub mod procfs;
+pub mod tree;
 pub mod unicode_string;
 pub mod vat;
 
+#[cfg(any(feature = "dummy_mem", test))]
+pub use dummy::*;
+pub use hive::*;
 pub use keyboard::*;
+pub use kuser_shared_data::*;
+pub use list::*;
 pub use module::*;
+pub use pagefile::*;
+pub use pfn::*;
 pub use process::*;
+#[cfg(feature = "std")]
+pub use procfs::*;
+pub use tree::*;
 pub use unicode_string::*;
 pub use vat::*;
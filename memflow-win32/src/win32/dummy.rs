@@ -0,0 +1,909 @@
+/*!
+Dummy win32 target backed by a `DummyMemory`.
+
+The `DummyWin32Builder` lays out a minimal set of kernel structures
+(an EPROCESS list, ETHREADs, TEBs, PEBs, loader data tables, registry hives, a PFN database
+and the processor control blocks)
+inside of a `DummyMemory`.
+The resulting `Kernel` can be used to exercise the win32 abstractions without a real memory dump.
+
+The kernel mapping starts with the headers of a minimal ntoskrnl PE image.
+It does not contain any code, its export table only lists
+`PsInitialSystemProcess`, `PsLoadedModuleList`, `NtBuildNumber` and `CmNtCSDVersion`.
+A `KUSER_SHARED_DATA` page containing the version of the target is mapped as well.
+*/
+
+use std::prelude::v1::*;
+
+use super::{
+    process::EXIT_STATUS_STILL_ACTIVE, process::IMAGE_FILE_NAME_LENGTH, IntegrityLevel, Kernel,
+    KernelInfo, PfnState, Win32Protection, KUSER_SHARED_DATA_X64, NT_PRODUCT_WIN_NT,
+};
+use crate::error::{Error, Result};
+use crate::kernel::{StartBlock, StartBlockSource, Win32Version};
+use crate::offsets::{Win32OffsetTable, Win32Offsets};
+
+use memflow::architecture::x86::x64;
+use memflow::mem::dummy::DummyMemory;
+use memflow::mem::{DirectTranslate, VirtualDMA, VirtualMemory};
+use memflow::process::{ProcessMemoryInfo, PID};
+use memflow::types::{size, Address};
+
+/// Virtual address the dummy kernel structures are mapped at.
+const KERNEL_BASE: u64 = 0xffff_f800_0000_0000;
+
+/// Virtual address the first module of each dummy process is placed at.
+///
+/// The modules of a process are placed consecutively, aligned to 16mb.
+pub const MODULE_BASE: u64 = 0x7ff7_0000_0000;
+
+/// Virtual address the first session driver is placed at.
+pub const SESSION_MODULE_BASE: u64 = 0xffff_d000_0000_0000;
+
+const EPROCESS_SIZE: usize = 0x400;
+const ETHREAD_SIZE: usize = 0x500;
+const CMHIVE_SIZE: usize = 0x400;
+const MM_SESSION_SPACE_SIZE: usize = 0x100;
+const KPRCB_SIZE: usize = 0x100;
+
+/// Offsets of the structures laid out by the `DummyWin32Builder`.
+///
+/// The WoW64 field is disabled so all processes are treated as native x64 processes.
+/// `ps_idle_process`, `cmp_hive_list_head`, `mm_pfn_database`, `mm_highest_physical_page`
+/// and `ki_processor_block` are relative to the kernel base and will be filled in by `DummyWin32Builder::build()`.
+pub const DUMMY_OFFSETS: Win32OffsetTable = Win32OffsetTable {
+    list_blink: 0x8,
+    eproc_link: 0x188,
+
+    kproc_dtb: 0x28,
+
+    eproc_pid: 0x180,
+    eproc_name: 0x2e0,
+    eproc_peb: 0x338,
+    eproc_section_base: 0x3c0,
+    eproc_exit_status: 0x3d0,
+    eproc_thread_list: 0x308,
+    eproc_wow64: 0,
+    eproc_protection: 0x3e0,
+    eproc_token: 0x358,
+    eproc_audit_info: 0x3f0,
+
+    token_user_and_groups: 0x98,
+    token_integrity_level_index: 0xd0,
+
+    cmp_hive_list_head: 0,
+    cmhive_hive_list: 0x300,
+    cmhive_file_full_path: 0x70,
+    hhive_base_block: 0x10,
+
+    eproc_session: 0x3f8,
+    mm_session_id: 0x8,
+    mm_session_image_list: 0x40,
+    image_entry_data_table_entry: 0x30,
+
+    eproc_vm: 0x200,
+    mmsupport_working_set_size: 0x58,
+    eproc_virtual_size: 0x1a8,
+    eproc_commit_charge: 0x1b8,
+
+    mm_pfn_database: 0,
+    mm_highest_physical_page: 0,
+    mmpfn_size: 0x30,
+    mmpfn_pte_address: 0x10,
+    mmpfn_u3: 0x20,
+
+    ps_idle_process: 0,
+    ki_processor_block: 0,
+    kprcb_current_thread: 0x8,
+    kthread_process: 0x220,
+
+    kthread_teb: 0xb8,
+    ethread_list_entry: 0x420,
+    teb_peb: 0x60,
+    teb_peb_x86: 0x30,
+};
+
+/// Description of a module inside of a `DummyWin32Process`.
+#[derive(Debug, Clone)]
+pub struct DummyWin32Module {
+    pub name: String,
+    pub size: usize,
+}
+
+/// Description of a process that will be laid out by the `DummyWin32Builder`.
+#[derive(Debug, Clone)]
+pub struct DummyWin32Process {
+    pub pid: PID,
+    pub name: String,
+    pub modules: Vec<DummyWin32Module>,
+    pub protection: Win32Protection,
+    pub integrity_level: Option<IntegrityLevel>,
+    pub image_path: Option<String>,
+    pub session_id: Option<u32>,
+    pub memory_info: ProcessMemoryInfo,
+    pub command_line: Option<String>,
+    pub environment: Vec<String>,
+}
+
+impl DummyWin32Process {
+    pub fn new(pid: PID, name: &str) -> Self {
+        Self {
+            pid,
+            name: name.to_string(),
+            modules: Vec::new(),
+            protection: Win32Protection::default(),
+            integrity_level: None,
+            image_path: None,
+            session_id: None,
+            memory_info: ProcessMemoryInfo::default(),
+            command_line: None,
+            environment: Vec::new(),
+        }
+    }
+
+    /// Sets the raw `_PS_PROTECTION` value of the process.
+    pub fn protection(mut self, protection: Win32Protection) -> Self {
+        self.protection = protection;
+        self
+    }
+
+    /// Sets the integrity level of the process token.
+    ///
+    /// Processes without an integrity level get a token with an invalid `IntegrityLevelIndex`.
+    pub fn integrity_level(mut self, integrity_level: IntegrityLevel) -> Self {
+        self.integrity_level = Some(integrity_level);
+        self
+    }
+
+    /// Sets the full path of the image file that is stored in `_EPROCESS::SeAuditProcessCreationInfo`.
+    pub fn image_path(mut self, image_path: &str) -> Self {
+        self.image_path = Some(image_path.to_string());
+        self
+    }
+
+    /// Attaches the process to the session with the given id.
+    pub fn session(mut self, session_id: u32) -> Self {
+        self.session_id = Some(session_id);
+        self
+    }
+
+    /// Sets the memory usage of the process.
+    ///
+    /// The working set size and private bytes are stored as page counts and rounded down to full pages.
+    pub fn memory_info(mut self, memory_info: ProcessMemoryInfo) -> Self {
+        self.memory_info = memory_info;
+        self
+    }
+
+    /// Sets the command line that is stored in the process parameters of the PEB.
+    pub fn command_line(mut self, command_line: &str) -> Self {
+        self.command_line = Some(command_line.to_string());
+        self
+    }
+
+    /// Adds a `NAME=value` entry to the environment block of the process.
+    pub fn environment(mut self, entry: &str) -> Self {
+        self.environment.push(entry.to_string());
+        self
+    }
+
+    /// Adds a module to the process.
+    ///
+    /// The first module that is added is treated as the main module of the process.
+    pub fn module(mut self, name: &str, size: usize) -> Self {
+        self.modules.push(DummyWin32Module {
+            name: name.to_string(),
+            size,
+        });
+        self
+    }
+}
+
+/// Builder for a `Kernel` on top of a `DummyMemory`.
+///
+/// A `System` process with pid 4 is always placed at the start of the EPROCESS list.
+/// Just like on a real target it does not contain a PEB,
+/// its module list is the list of loaded kernel modules which only contains ntoskrnl.exe.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "dummy_mem")]
+/// # {
+/// use memflow::types::size;
+/// use memflow_win32::win32::{DummyWin32Builder, DummyWin32Process};
+///
+/// let mut kernel = DummyWin32Builder::new()
+///     .process(DummyWin32Process::new(1234, "notepad.exe")
+///         .module("notepad.exe", size::kb(256))
+///         .module("ntdll.dll", size::mb(2)))
+///     .build()
+///     .unwrap();
+///
+/// let proc_info = kernel.process_info_pid(1234).unwrap();
+/// assert_eq!(proc_info.name, "notepad.exe");
+/// # }
+/// ```
+pub struct DummyWin32Builder {
+    offsets: Win32OffsetTable,
+    mem_size: usize,
+    seed: u64,
+    winver: Win32Version,
+    revision: u32,
+    product_type: u32,
+    processes: Vec<DummyWin32Process>,
+    hives: Vec<String>,
+    session_modules: Vec<DummyWin32Module>,
+    pfns: Vec<(PfnState, Address)>,
+    processors: Vec<PID>,
+}
+
+impl Default for DummyWin32Builder {
+    fn default() -> Self {
+        Self {
+            offsets: DUMMY_OFFSETS,
+            mem_size: size::mb(64),
+            seed: 0,
+            winver: Win32Version::new(10, 0, 19041),
+            revision: 0,
+            product_type: NT_PRODUCT_WIN_NT,
+            processes: Vec::new(),
+            hives: Vec::new(),
+            session_modules: Vec::new(),
+            pfns: Vec::new(),
+            processors: Vec::new(),
+        }
+    }
+}
+
+impl DummyWin32Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the offsets the kernel structures are laid out with.
+    ///
+    /// This allows to mimic the layout of different windows builds.
+    /// All structures have to fit into the sizes used by `DUMMY_OFFSETS`.
+    pub fn offsets(mut self, offsets: Win32OffsetTable) -> Self {
+        self.offsets = offsets;
+        self
+    }
+
+    /// Sets the size of the underlying physical memory.
+    pub fn mem_size(mut self, mem_size: usize) -> Self {
+        self.mem_size = mem_size;
+        self
+    }
+
+    /// Sets the seed that is used for randomizing the physical page layout.
+    ///
+    /// The same seed will always result in the same layout.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Sets the windows version that is reported in the `KernelInfo`.
+    pub fn winver(mut self, winver: Win32Version) -> Self {
+        self.winver = winver;
+        self
+    }
+
+    /// Sets the value of the `CmNtCSDVersion` export.
+    pub fn revision(mut self, revision: u32) -> Self {
+        self.revision = revision;
+        self
+    }
+
+    /// Sets the `NtProductType` in `KUSER_SHARED_DATA` (see `NT_PRODUCT_WIN_NT`).
+    pub fn product_type(mut self, product_type: u32) -> Self {
+        self.product_type = product_type;
+        self
+    }
+
+    /// Adds a process to the end of the EPROCESS list.
+    pub fn process(mut self, process: DummyWin32Process) -> Self {
+        self.processes.push(process);
+        self
+    }
+
+    /// Adds a registry hive with the given file name to the end of the hive list.
+    ///
+    /// Volatile hives can be added by passing an empty file name.
+    pub fn hive(mut self, file_name: &str) -> Self {
+        self.hives.push(file_name.to_string());
+        self
+    }
+
+    /// Adds a driver to the image list of every session (e.g. `win32kbase.sys`).
+    pub fn session_module(mut self, name: &str, size: usize) -> Self {
+        self.session_modules.push(DummyWin32Module {
+            name: name.to_string(),
+            size,
+        });
+        self
+    }
+
+    /// Adds an entry with the given state and pte address to the end of the PFN database.
+    ///
+    /// The first entry describes page frame number 0.
+    pub fn pfn(mut self, state: PfnState, pte_address: Address) -> Self {
+        self.pfns.push((state, pte_address));
+        self
+    }
+
+    /// Adds a processor that is currently executing a thread of the process with the given pid.
+    ///
+    /// The first processor has the index 0. The pid has to belong to a process
+    /// that has been added to the builder or to the System process (pid 4).
+    pub fn processor(mut self, pid: PID) -> Self {
+        self.processors.push(pid);
+        self
+    }
+
+    pub fn build(self) -> Result<Kernel<DummyMemory, DirectTranslate>> {
+        let mut offsets = self.offsets;
+        let mut layout = DummyLayout::new(Address::from(KERNEL_BASE));
+
+        // ntoskrnl.exe headers, they are filled in once the size of the image is known
+        let image_header = layout.alloc(size::kb(4));
+
+        // PsActiveProcessHead
+        let list_head = layout.alloc(0x10);
+
+        let system = DummyWin32Process::new(4, "System");
+        let eprocs = Some(&system)
+            .into_iter()
+            .chain(self.processes.iter())
+            .map(|process| layout.write_process(process, &offsets))
+            .collect::<Vec<_>>();
+
+        let links = eprocs
+            .iter()
+            .map(|&eprocess| eprocess + offsets.eproc_link as usize)
+            .collect::<Vec<_>>();
+        layout.write_list(list_head, &links, &offsets);
+
+        // PsIdleProcess, the idle process is not part of the active process list
+        let idle_process = layout.alloc(0x8);
+        offsets.ps_idle_process = (idle_process - layout.base) as u32;
+        let idle = layout.write_process(&DummyWin32Process::new(0, "Idle"), &offsets);
+        layout.write_addr(idle_process, idle);
+
+        // CmpHiveListHead
+        let hive_head = layout.alloc(0x10);
+        offsets.cmp_hive_list_head = (hive_head - layout.base) as u32;
+
+        let links = self
+            .hives
+            .iter()
+            .map(|file_name| {
+                layout.write_hive(file_name, &offsets) + offsets.cmhive_hive_list as usize
+            })
+            .collect::<Vec<_>>();
+        layout.write_list(hive_head, &links, &offsets);
+
+        // MmPfnDatabase and MmHighestPhysicalPage
+        if !self.pfns.is_empty() {
+            let pfn_database = layout.alloc(0x8);
+            let highest_physical_page = layout.alloc(0x8);
+            offsets.mm_pfn_database = (pfn_database - layout.base) as u32;
+            offsets.mm_highest_physical_page = (highest_physical_page - layout.base) as u32;
+
+            let entries = layout.write_pfn_database(&self.pfns, &offsets);
+            layout.write_addr(pfn_database, entries);
+            layout.write_addr(
+                highest_physical_page,
+                Address::from(self.pfns.len() as u64 - 1),
+            );
+        }
+
+        // KiProcessorBlock, the slot after the last processor is left empty
+        if !self.processors.is_empty() {
+            let processor_block = layout.alloc(0x8 * (self.processors.len() + 1));
+            offsets.ki_processor_block = (processor_block - layout.base) as u32;
+
+            for (i, &pid) in self.processors.iter().enumerate() {
+                let eprocess = Some(&system)
+                    .into_iter()
+                    .chain(self.processes.iter())
+                    .zip(eprocs.iter())
+                    .find(|(process, _)| process.pid == pid)
+                    .map(|(_, &eprocess)| eprocess)
+                    .ok_or(Error::Other("processor pid not found"))?;
+
+                let kprcb = layout.alloc(KPRCB_SIZE);
+                let kthread = layout.alloc(ETHREAD_SIZE);
+                layout.write_addr(processor_block + i * 0x8, kprcb);
+                layout.write_addr(kprcb + offsets.kprcb_current_thread as usize, kthread);
+                layout.write_addr(kthread + offsets.kthread_process as usize, eprocess);
+            }
+        }
+
+        // _MM_SESSION_SPACE of every session
+        let mut session_ids = self
+            .processes
+            .iter()
+            .filter_map(|process| process.session_id)
+            .collect::<Vec<_>>();
+        session_ids.sort_unstable();
+        session_ids.dedup();
+        for session_id in session_ids.into_iter() {
+            let session = layout.write_session(session_id, &self.session_modules, &offsets);
+            for (process, &eprocess) in self.processes.iter().zip(eprocs.iter().skip(1)) {
+                if process.session_id == Some(session_id) {
+                    layout.write_addr(eprocess + offsets.eproc_session as usize, session);
+                }
+            }
+        }
+
+        // PsLoadedModuleList, the size of ntoskrnl.exe is filled in once it is known
+        let loaded_module_list = layout.alloc(0x10);
+        let ntoskrnl = DummyWin32Module {
+            name: "ntoskrnl.exe".to_string(),
+            size: 0,
+        };
+        let (ntoskrnl_entry, _) = layout.write_ldr_entries(layout.base, &[ntoskrnl])[0];
+        layout.write_list(loaded_module_list, &[ntoskrnl_entry], &offsets);
+
+        // PsInitialSystemProcess
+        let initial_system_process = layout.alloc(0x8);
+        layout.write_addr(initial_system_process, eprocs[0]);
+
+        // NtBuildNumber of a free build
+        let nt_build_number = layout.alloc(0x4);
+        layout.write_bytes(
+            nt_build_number,
+            &(0xf000_0000 | self.winver.build_number()).to_le_bytes(),
+        );
+
+        // CmNtCSDVersion
+        let csd_version = layout.alloc(0x4);
+        layout.write_bytes(csd_version, &self.revision.to_le_bytes());
+
+        let kernel_size = layout.write_image(
+            image_header,
+            &[
+                ("CmNtCSDVersion", csd_version),
+                ("NtBuildNumber", nt_build_number),
+                ("PsInitialSystemProcess", initial_system_process),
+                ("PsLoadedModuleList", loaded_module_list),
+            ],
+        );
+        layout.write_addr(ntoskrnl_entry + 0x40, Address::from(kernel_size));
+
+        let mut mem = DummyMemory::with_seed(self.mem_size, self.seed);
+        let dtb = mem.alloc_dtb_const_base(layout.base, kernel_size, &layout.buf);
+
+        // KUSER_SHARED_DATA::NtProductType, ProductTypeIsValid, NtMajorVersion and NtMinorVersion
+        let mut shared_data = vec![0u8; size::kb(4)];
+        shared_data[0x264..0x268].copy_from_slice(&self.product_type.to_le_bytes());
+        shared_data[0x268] = 1;
+        shared_data[0x26c..0x270].copy_from_slice(&self.winver.major_version().to_le_bytes());
+        shared_data[0x270..0x274].copy_from_slice(&self.winver.minor_version().to_le_bytes());
+        mem.map_const_base(
+            dtb,
+            Address::from(KUSER_SHARED_DATA_X64),
+            size::kb(4),
+            &shared_data,
+        );
+
+        // all processes share the kernel page tables
+        {
+            let mut virt_mem = VirtualDMA::new(&mut mem, x64::ARCH, x64::new_translator(dtb));
+            for &eprocess in eprocs.iter().chain(Some(&idle)) {
+                virt_mem.virt_write(eprocess + offsets.kproc_dtb as usize, &dtb.as_u64())?;
+            }
+        }
+
+        let kernel_info = KernelInfo {
+            start_block: StartBlock {
+                arch: x64::ARCH,
+                kernel_hint: layout.base,
+                dtb,
+                source: StartBlockSource::UserSupplied,
+            },
+
+            kernel_base: layout.base,
+            kernel_size,
+
+            kernel_guid: None,
+            kernel_winver: self.winver,
+
+            eprocess_base: eprocs[0],
+        };
+
+        Ok(Kernel::new(
+            mem,
+            DirectTranslate::new(),
+            Win32Offsets::from(offsets),
+            kernel_info,
+        ))
+    }
+}
+
+/// Linear buffer containing the contents of the dummy kernel mapping.
+struct DummyLayout {
+    base: Address,
+    buf: Vec<u8>,
+}
+
+impl DummyLayout {
+    fn new(base: Address) -> Self {
+        Self {
+            base,
+            buf: Vec::new(),
+        }
+    }
+
+    fn alloc(&mut self, size: usize) -> Address {
+        let offset = (self.buf.len() + 0xf) & !0xf;
+        self.buf.resize(offset + size, 0);
+        self.base + offset
+    }
+
+    fn write_bytes(&mut self, addr: Address, data: &[u8]) {
+        let offset = addr - self.base;
+        self.buf[offset..offset + data.len()].copy_from_slice(data);
+    }
+
+    fn write_addr(&mut self, addr: Address, value: Address) {
+        self.write_bytes(addr, &value.as_u64().to_le_bytes());
+    }
+
+    fn write_unicode_string(&mut self, addr: Address, string: &str) {
+        let content = string
+            .encode_utf16()
+            .flat_map(|c| c.to_le_bytes().to_vec())
+            .collect::<Vec<u8>>();
+
+        let buffer = self.alloc(content.len() + 2);
+        self.write_bytes(buffer, &content);
+
+        self.write_bytes(addr, &(content.len() as u16).to_le_bytes());
+        self.write_bytes(addr + 2, &(content.len() as u16 + 2).to_le_bytes());
+        self.write_addr(addr + 8, buffer);
+    }
+
+    /// Links all entries together, the list head is part of the ring.
+    fn write_list(&mut self, head: Address, links: &[Address], offsets: &Win32OffsetTable) {
+        let links = Some(head)
+            .into_iter()
+            .chain(links.iter().copied())
+            .collect::<Vec<_>>();
+        for (i, &link) in links.iter().enumerate() {
+            let flink = links[(i + 1) % links.len()];
+            let blink = links[(i + links.len() - 1) % links.len()];
+            self.write_addr(link, flink);
+            self.write_addr(link + offsets.list_blink as usize, blink);
+        }
+    }
+
+    /// Writes a `_CMHIVE` with an empty base block and returns its address.
+    fn write_hive(&mut self, file_name: &str, offsets: &Win32OffsetTable) -> Address {
+        let cmhive = self.alloc(CMHIVE_SIZE);
+
+        // _HBASE_BLOCK::Signature
+        let base_block = self.alloc(size::kb(4));
+        self.write_bytes(base_block, b"regf");
+        self.write_addr(cmhive + offsets.hhive_base_block as usize, base_block);
+
+        if !file_name.is_empty() {
+            self.write_unicode_string(cmhive + offsets.cmhive_file_full_path as usize, file_name);
+        }
+
+        cmhive
+    }
+
+    /// Writes a normalized x64 `_RTL_USER_PROCESS_PARAMETERS` and returns its address.
+    fn write_process_parameters(&mut self, command_line: &str, environment: &[String]) -> Address {
+        let params = self.alloc(0x400);
+        self.write_bytes(params + 0x8, &1u32.to_le_bytes()); // RTL_USER_PROC_PARAMS_NORMALIZED
+        self.write_unicode_string(params + 0x70, command_line);
+
+        let block = environment
+            .iter()
+            .flat_map(|entry| entry.encode_utf16().chain(Some(0)))
+            .chain(Some(0))
+            .flat_map(|c| c.to_le_bytes().to_vec())
+            .collect::<Vec<u8>>();
+        let environment = self.alloc(block.len());
+        self.write_bytes(environment, &block);
+        self.write_addr(params + 0x80, environment);
+        self.write_bytes(params + 0x3f0, &(block.len() as u64).to_le_bytes());
+
+        params
+    }
+
+    /// Writes an `_MMPFN` for every entry and returns the address of the first one.
+    fn write_pfn_database(
+        &mut self,
+        pfns: &[(PfnState, Address)],
+        offsets: &Win32OffsetTable,
+    ) -> Address {
+        let entry_size = offsets.mmpfn_size as usize;
+        let database = self.alloc(entry_size * pfns.len());
+        for (i, &(state, pte_address)) in pfns.iter().enumerate() {
+            let entry = database + i * entry_size;
+            self.write_addr(entry + offsets.mmpfn_pte_address as usize, pte_address);
+            // ReferenceCount and PageLocation
+            let u3 = entry + offsets.mmpfn_u3 as usize;
+            if state == PfnState::Active {
+                self.write_bytes(u3, &1u16.to_le_bytes());
+            }
+            self.write_bytes(u3 + 2, &[state as u8]);
+        }
+        database
+    }
+
+    /// Writes a `_LDR_DATA_TABLE_ENTRY` for every module and returns the entries and the module bases.
+    ///
+    /// The modules are placed consecutively starting at `module_base`, aligned to 16mb.
+    fn write_ldr_entries(
+        &mut self,
+        mut module_base: Address,
+        modules: &[DummyWin32Module],
+    ) -> Vec<(Address, Address)> {
+        modules
+            .iter()
+            .map(|module| {
+                let entry = self.alloc(0x100);
+                self.write_addr(entry + 0x30, module_base);
+                self.write_addr(entry + 0x40, Address::from(module.size));
+                self.write_unicode_string(
+                    entry + 0x48,
+                    &format!("C:\\Windows\\System32\\{}", module.name),
+                );
+                self.write_unicode_string(entry + 0x58, &module.name);
+
+                let base = module_base;
+                module_base += (module.size + size::mb(16)) & !(size::mb(16) - 1);
+                (entry, base)
+            })
+            .collect()
+    }
+
+    /// Writes the export directory of the kernel image and fills in the PE headers at `header`.
+    ///
+    /// This has to be called after all other structures have been written
+    /// since the image spans the entire layout. Returns the size of the image.
+    fn write_image(&mut self, header: Address, exports: &[(&str, Address)]) -> usize {
+        let mut exports = exports.to_vec();
+        exports.sort_by_key(|&(name, _)| name);
+        let count = exports.len();
+
+        // _IMAGE_EXPORT_DIRECTORY, names have to be sorted for the binary search
+        let directory = self.alloc(0x28);
+        let functions = self.alloc(4 * count);
+        let names = self.alloc(4 * count);
+        let ordinals = self.alloc(2 * count);
+        let dll_name = self.alloc(0x10);
+        self.write_bytes(dll_name, b"ntoskrnl.exe\0");
+        for (i, &(name, addr)) in exports.iter().enumerate() {
+            let name_buf = self.alloc(name.len() + 1);
+            self.write_bytes(name_buf, name.as_bytes());
+            self.write_bytes(
+                functions + i * 4,
+                &((addr - self.base) as u32).to_le_bytes(),
+            );
+            self.write_bytes(
+                names + i * 4,
+                &((name_buf - self.base) as u32).to_le_bytes(),
+            );
+            self.write_bytes(ordinals + i * 2, &(i as u16).to_le_bytes());
+        }
+        let directory_rva = (directory - self.base) as u32;
+        let directory_size = (self.buf.len() - (directory - self.base)) as u32;
+        self.write_bytes(
+            directory + 0xc,
+            &((dll_name - self.base) as u32).to_le_bytes(),
+        );
+        self.write_bytes(directory + 0x10, &1u32.to_le_bytes()); // Base
+        self.write_bytes(directory + 0x14, &(count as u32).to_le_bytes());
+        self.write_bytes(directory + 0x18, &(count as u32).to_le_bytes());
+        self.write_bytes(
+            directory + 0x1c,
+            &((functions - self.base) as u32).to_le_bytes(),
+        );
+        self.write_bytes(
+            directory + 0x20,
+            &((names - self.base) as u32).to_le_bytes(),
+        );
+        self.write_bytes(
+            directory + 0x24,
+            &((ordinals - self.base) as u32).to_le_bytes(),
+        );
+
+        let image_size = (self.buf.len() + size::kb(4) - 1) & !(size::kb(4) - 1);
+
+        // _IMAGE_DOS_HEADER::e_lfanew
+        self.write_bytes(header, b"MZ");
+        self.write_bytes(header + 0x3c, &0x40u32.to_le_bytes());
+
+        // _IMAGE_FILE_HEADER, a single section follows the optional header
+        let nt_headers = header + 0x40;
+        self.write_bytes(nt_headers, b"PE\0\0");
+        self.write_bytes(nt_headers + 0x4, &0x8664u16.to_le_bytes()); // Machine
+        self.write_bytes(nt_headers + 0x6, &1u16.to_le_bytes()); // NumberOfSections
+        self.write_bytes(nt_headers + 0x14, &0xf0u16.to_le_bytes()); // SizeOfOptionalHeader
+        self.write_bytes(nt_headers + 0x16, &0x22u16.to_le_bytes()); // Characteristics
+
+        // _IMAGE_OPTIONAL_HEADER64
+        let optional_header = nt_headers + 0x18;
+        self.write_bytes(optional_header, &0x20bu16.to_le_bytes()); // Magic
+        self.write_addr(optional_header + 0x18, self.base); // ImageBase
+        self.write_bytes(optional_header + 0x20, &0x1000u32.to_le_bytes()); // SectionAlignment
+        self.write_bytes(optional_header + 0x24, &0x200u32.to_le_bytes()); // FileAlignment
+        self.write_bytes(optional_header + 0x38, &(image_size as u32).to_le_bytes()); // SizeOfImage
+        self.write_bytes(optional_header + 0x3c, &0x1000u32.to_le_bytes()); // SizeOfHeaders
+        self.write_bytes(optional_header + 0x44, &1u16.to_le_bytes()); // Subsystem
+        self.write_bytes(optional_header + 0x6c, &16u32.to_le_bytes()); // NumberOfRvaAndSizes
+        self.write_bytes(optional_header + 0x70, &directory_rva.to_le_bytes());
+        self.write_bytes(optional_header + 0x74, &directory_size.to_le_bytes());
+
+        // _IMAGE_SECTION_HEADER covering everything after the headers
+        let section = optional_header + 0xf0;
+        let section_size = (image_size - 0x1000) as u32;
+        self.write_bytes(section, b".data");
+        self.write_bytes(section + 0x8, &section_size.to_le_bytes()); // VirtualSize
+        self.write_bytes(section + 0xc, &0x1000u32.to_le_bytes()); // VirtualAddress
+        self.write_bytes(section + 0x10, &section_size.to_le_bytes()); // SizeOfRawData
+        self.write_bytes(section + 0x14, &0x1000u32.to_le_bytes()); // PointerToRawData
+        self.write_bytes(section + 0x24, &0xc000_0040u32.to_le_bytes()); // Characteristics
+
+        image_size
+    }
+
+    /// Writes a `_MM_SESSION_SPACE` whose image list contains the given modules and returns its address.
+    fn write_session(
+        &mut self,
+        session_id: u32,
+        modules: &[DummyWin32Module],
+        offsets: &Win32OffsetTable,
+    ) -> Address {
+        let session = self.alloc(MM_SESSION_SPACE_SIZE);
+        self.write_bytes(
+            session + offsets.mm_session_id as usize,
+            &session_id.to_le_bytes(),
+        );
+
+        // _IMAGE_ENTRY_IN_SESSION
+        let links = self
+            .write_ldr_entries(Address::from(SESSION_MODULE_BASE), modules)
+            .into_iter()
+            .map(|(entry, _)| {
+                let image = self.alloc(0x40);
+                self.write_addr(image + offsets.image_entry_data_table_entry as usize, entry);
+                image
+            })
+            .collect::<Vec<_>>();
+        self.write_list(
+            session + offsets.mm_session_image_list as usize,
+            &links,
+            offsets,
+        );
+
+        session
+    }
+
+    fn write_token(
+        &mut self,
+        eprocess: Address,
+        integrity_level: Option<IntegrityLevel>,
+        offsets: &Win32OffsetTable,
+    ) {
+        let token = self.alloc(0x100);
+
+        // _SID_AND_ATTRIBUTES[2] with a user sid and a mandatory label
+        let groups = self.alloc(0x20);
+        let user_sid = self.alloc(0x10);
+        let label_sid = self.alloc(0x10);
+        self.write_bytes(user_sid, &[1, 1, 0, 0, 0, 0, 0, 5, 18, 0, 0, 0]); // S-1-5-18
+        self.write_bytes(label_sid, &[1, 1, 0, 0, 0, 0, 0, 16]); // S-1-16-X
+        self.write_addr(groups, user_sid);
+        self.write_addr(groups + 0x10, label_sid);
+        self.write_addr(token + offsets.token_user_and_groups as usize, groups);
+
+        let index = match integrity_level {
+            Some(integrity_level) => {
+                self.write_bytes(label_sid + 8, &integrity_level.rid().to_le_bytes());
+                1
+            }
+            None => u32::MAX,
+        };
+        self.write_bytes(
+            token + offsets.token_integrity_level_index as usize,
+            &index.to_le_bytes(),
+        );
+
+        // set some reference count bits of the _EX_FAST_REF
+        self.write_addr(eprocess + offsets.eproc_token as usize, token + 0xb);
+    }
+
+    fn write_process(
+        &mut self,
+        process: &DummyWin32Process,
+        offsets: &Win32OffsetTable,
+    ) -> Address {
+        let eprocess = self.alloc(EPROCESS_SIZE);
+
+        self.write_bytes(
+            eprocess + offsets.eproc_pid as usize,
+            &process.pid.to_le_bytes(),
+        );
+
+        let name = process.name.as_bytes();
+        let name = &name[..name.len().min(IMAGE_FILE_NAME_LENGTH - 1)];
+        self.write_bytes(eprocess + offsets.eproc_name as usize, name);
+
+        self.write_bytes(
+            eprocess + offsets.eproc_exit_status as usize,
+            &EXIT_STATUS_STILL_ACTIVE.to_le_bytes(),
+        );
+
+        self.write_bytes(
+            eprocess + offsets.eproc_protection as usize,
+            &[process.protection.0],
+        );
+        self.write_token(eprocess, process.integrity_level, offsets);
+
+        let memory_info = &process.memory_info;
+        self.write_bytes(
+            eprocess + (offsets.eproc_vm + offsets.mmsupport_working_set_size) as usize,
+            &(memory_info.working_set_size as u64 / size::kb(4) as u64).to_le_bytes(),
+        );
+        self.write_bytes(
+            eprocess + offsets.eproc_virtual_size as usize,
+            &(memory_info.virtual_size as u64).to_le_bytes(),
+        );
+        self.write_bytes(
+            eprocess + offsets.eproc_commit_charge as usize,
+            &(memory_info.private_bytes as u64 / size::kb(4) as u64).to_le_bytes(),
+        );
+
+        if let Some(image_path) = &process.image_path {
+            // _OBJECT_NAME_INFORMATION
+            let name_info = self.alloc(0x10);
+            self.write_unicode_string(name_info, image_path);
+            self.write_addr(eprocess + offsets.eproc_audit_info as usize, name_info);
+        }
+
+        // a single thread with a teb, the x64 _TEB is 0x1838 bytes large
+        let ethread = self.alloc(ETHREAD_SIZE);
+        let teb = self.alloc(0x1838);
+        self.write_addr(ethread + offsets.kthread_teb as usize, teb);
+        self.write_addr(
+            eprocess + offsets.eproc_thread_list as usize,
+            ethread + offsets.ethread_list_entry as usize,
+        );
+
+        // the idle and the system process do not have a peb
+        if process.pid == 0 || process.pid == 4 {
+            return eprocess;
+        }
+
+        let peb = self.alloc(0x100);
+        let ldr = self.alloc(0x100);
+        self.write_addr(teb + offsets.teb_peb as usize, peb);
+        self.write_addr(eprocess + offsets.eproc_peb as usize, peb);
+        self.write_addr(peb + 0x18, ldr); // _PEB::Ldr
+
+        // _LDR_DATA_TABLE_ENTRY
+        // _PEB_LDR_DATA::InLoadOrderModuleList
+        let entries = self.write_ldr_entries(Address::from(MODULE_BASE), &process.modules);
+        let links = entries.iter().map(|&(entry, _)| entry).collect::<Vec<_>>();
+        self.write_list(ldr + 0x10, &links, offsets);
+
+        if let Some(&(_, base)) = entries.first() {
+            self.write_addr(eprocess + offsets.eproc_section_base as usize, base);
+        }
+
+        if let Some(command_line) = &process.command_line {
+            let params = self.write_process_parameters(command_line, &process.environment);
+            self.write_addr(peb + 0x20, params); // _PEB::ProcessParameters
+        }
+
+        eprocess
+    }
+}
@@ -0,0 +1,46 @@
+use std::prelude::v1::*;
+
+use memflow::types::Address;
+
+/// Describes a registry hive that is loaded by the configuration manager.
+///
+/// Hives are retrieved by walking the `CmpHiveListHead` list (see `Kernel::hive_list()`).
+#[derive(Debug, Clone)]
+pub struct Win32HiveInfo {
+    pub address: Address,    // _CMHIVE
+    pub base_block: Address, // _HHIVE::BaseBlock
+    pub file_name: String,   // _CMHIVE::FileFullPath
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::win32::DummyWin32Builder;
+    use memflow::architecture::x86::x64;
+    use memflow::mem::{VirtualDMA, VirtualMemory};
+
+    #[test]
+    fn hive_list() {
+        let mut kernel = DummyWin32Builder::new()
+            .hive("")
+            .hive("\\SystemRoot\\System32\\Config\\SOFTWARE")
+            .hive("\\??\\C:\\Users\\user\\ntuser.dat")
+            .build()
+            .unwrap();
+
+        let hives = kernel.hive_list().unwrap();
+        assert_eq!(hives.len(), 3);
+        assert_eq!(hives[0].file_name, "");
+        assert_eq!(
+            hives[1].file_name,
+            "\\SystemRoot\\System32\\Config\\SOFTWARE"
+        );
+        assert_eq!(hives[2].file_name, "\\??\\C:\\Users\\user\\ntuser.dat");
+
+        let mut signature = [0u8; 4];
+        let dtb = kernel.kernel_info.start_block.dtb;
+        VirtualDMA::new(&mut kernel.phys_mem, x64::ARCH, x64::new_translator(dtb))
+            .virt_read_raw_into(hives[1].base_block, &mut signature)
+            .unwrap();
+        assert_eq!(&signature, b"regf");
+    }
+}
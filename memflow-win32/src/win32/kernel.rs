@@ -1,25 +1,48 @@
 use std::prelude::v1::*;
 
 use super::{
-    process::EXIT_STATUS_STILL_ACTIVE, process::IMAGE_FILE_NAME_LENGTH, KernelBuilder, KernelInfo,
-    Win32ExitStatus, Win32ModuleListInfo, Win32Process, Win32ProcessInfo, Win32VirtualTranslate,
+    process::user_address_end, process::EXIT_STATUS_STILL_ACTIVE, process::IMAGE_FILE_NAME_LENGTH,
+    process::TEB_WOW64_OFFSET, walk_list_entry, IntegrityLevel, KUserSharedData, KernelBuilder,
+    KernelInfo, ListEntryIter, PfnDatabase, PfnEntry, PfnIter, ProcessParameters, SymbolLocation,
+    VirtualReadUnicodeString, Win32ExitStatus, Win32HiveInfo, Win32ImagePath, Win32ModuleInfo,
+    Win32ModuleListInfo, Win32Process, Win32ProcessInfo, Win32Protection, Win32VirtualTranslate,
 };
 
-use crate::error::{Error, Result};
+use crate::error::{Error, PartialResultExt, Result};
+use crate::kernel::{ntos, Win32VersionInfo};
 use crate::offsets::Win32Offsets;
 
-use log::{info, trace};
+use log::{info, trace, warn};
+use std::collections::BTreeMap;
 use std::fmt;
+use std::mem::size_of;
 
-use memflow::architecture::x86;
-use memflow::mem::{DirectTranslate, PhysicalMemory, VirtualDMA, VirtualMemory, VirtualTranslate};
-use memflow::process::{OperatingSystem, OsProcessInfo, OsProcessModuleInfo, PID};
+use memflow::architecture::{x86, ArchitectureObj};
+use memflow::connector::CpuState;
+use memflow::mem::{
+    DirectTranslate, PhysicalMemory, StructReader, VirtualDMA, VirtualMemory, VirtualTranslate,
+};
+#[cfg(feature = "std")]
+use memflow::mem::{SharedPhysicalMemory, SharedVirtualTranslate};
+use memflow::process::{
+    OperatingSystem, OsInfo, OsKind, OsProcessInfo, OsProcessModuleInfo, ProcessMemoryInfo, PID,
+};
 use memflow::types::Address;
 
+use dataview::Pod;
 use pelite::{self, pe64::exports::Export, PeView};
 
 const MAX_ITER_COUNT: usize = 65536;
 
+/// Maximum number of eprocess addresses whose dtb is cached
+const DTB_CACHE_SIZE: usize = 1024;
+
+/// A handle to a running windows kernel.
+///
+/// Cloning a `Kernel` clones the underlying memory connection and translator.
+/// In case those contain caches (e.g. `CachedMemoryAccess` or `CachedVirtualTranslate`)
+/// each clone receives a copy of the cache in its current state which is not shared with the other clones.
+/// Use `into_shared()` to construct a kernel whose clones all share the same caches.
 #[derive(Clone)]
 pub struct Kernel<T, V> {
     pub phys_mem: T,
@@ -28,9 +51,39 @@ pub struct Kernel<T, V> {
 
     pub kernel_info: KernelInfo,
     pub sysproc_dtb: Address,
+
+    dtb_cache: DtbCache,
+    exports: Option<BTreeMap<String, Address>>,
 }
 
-impl<T: PhysicalMemory, V: VirtualTranslate> OperatingSystem for Kernel<T, V> {}
+impl<T: PhysicalMemory, V: VirtualTranslate> OperatingSystem for Kernel<T, V> {
+    type ProcessInfoType = Win32ProcessInfo;
+    type Error = Error;
+
+    fn process_info_list(&mut self) -> Result<Vec<Win32ProcessInfo>> {
+        Kernel::process_info_list(self)
+    }
+
+    fn process_info_by_pid(&mut self, pid: PID) -> Result<Win32ProcessInfo> {
+        self.process_info_pid(pid)
+    }
+
+    fn process_info_by_name(&mut self, name: &str) -> Result<Win32ProcessInfo> {
+        self.process_info(name)
+    }
+
+    /// The version is taken from the `KernelInfo` and is formatted like `Win32Version`, e.g. `10.0.19041`.
+    /// It is `None` in case the version could not be determined while scanning for the kernel.
+    fn os_info(&mut self) -> Result<OsInfo> {
+        Ok(OsInfo {
+            kind: OsKind::Windows,
+            version: Some(self.kernel_info.kernel_winver)
+                .filter(|winver| winver.build_number() != 0)
+                .map(|winver| winver.to_string()),
+            arch: self.kernel_info.start_block.arch,
+        })
+    }
+}
 
 impl<T: PhysicalMemory, V: VirtualTranslate> Kernel<T, V> {
     pub fn new(
@@ -71,6 +124,9 @@ impl<T: PhysicalMemory, V: VirtualTranslate> Kernel<T, V> {
 
             kernel_info,
             sysproc_dtb,
+
+            dtb_cache: DtbCache::default(),
+            exports: None,
         }
     }
 
@@ -79,89 +135,233 @@ impl<T: PhysicalMemory, V: VirtualTranslate> Kernel<T, V> {
         self.phys_mem
     }
 
-    pub fn eprocess_list(&mut self) -> Result<Vec<Address>> {
-        let mut eprocs = Vec::new();
-        self.eprocess_list_extend(&mut eprocs)?;
-        trace!("found {} eprocesses", eprocs.len());
-        Ok(eprocs)
+    /// Drops all cached process dtbs.
+    ///
+    /// `process_info_from_eprocess` caches the dtb of every eprocess it reads
+    /// and only re-reads it when the pid of the eprocess changed.
+    /// This function can be used to force re-reading the dtbs, e.g. after restoring a snapshot.
+    pub fn clear_dtb_cache(&mut self) {
+        self.dtb_cache.clear();
     }
 
-    pub fn eprocess_list_extend<E: Extend<Address>>(&mut self, eprocs: &mut E) -> Result<()> {
-        // TODO: create a VirtualDMA constructor for kernel_info
-        let mut reader = VirtualDMA::with_vat(
+    /// Moves the memory connection and translator of this kernel behind shared references.
+    ///
+    /// All clones of the returned kernel operate on the same memory connection and translator.
+    /// This allows multiple threads to benefit from a single warmed up page and translation cache.
+    /// Accesses from multiple clones are serialized.
+    #[cfg(feature = "std")]
+    pub fn into_shared(self) -> Kernel<SharedPhysicalMemory<T>, SharedVirtualTranslate<V>> {
+        Kernel {
+            phys_mem: SharedPhysicalMemory::new(self.phys_mem),
+            vat: SharedVirtualTranslate::new(self.vat),
+            offsets: self.offsets,
+
+            kernel_info: self.kernel_info,
+            sysproc_dtb: self.sysproc_dtb,
+
+            dtb_cache: self.dtb_cache,
+            exports: self.exports,
+        }
+    }
+
+    /// Returns a virtual memory reader for the kernel address space.
+    ///
+    /// Addresses are translated with the dtb of the System process and the architecture of the target.
+    /// This allows reading arbitrary kernel addresses, like driver globals or pool allocations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memflow::mem::{PhysicalMemory, VirtualMemory, VirtualTranslate};
+    /// use memflow_win32::win32::Kernel;
+    ///
+    /// fn test<T: PhysicalMemory, V: VirtualTranslate>(kernel: &mut Kernel<T, V>) {
+    ///     let kernel_base = kernel.kernel_info.kernel_base;
+    ///     let mz = kernel.kernel_virt_mem().virt_read::<u16>(kernel_base).unwrap();
+    ///     assert_eq!(mz, 0x5a4d);
+    /// }
+    /// ```
+    pub fn kernel_virt_mem(&mut self) -> VirtualDMA<&mut T, &mut V, Win32VirtualTranslate> {
+        VirtualDMA::with_vat(
             &mut self.phys_mem,
             self.kernel_info.start_block.arch,
             Win32VirtualTranslate::new(self.kernel_info.start_block.arch, self.sysproc_dtb),
             &mut self.vat,
-        );
+        )
+    }
 
-        let list_start = self.kernel_info.eprocess_base + self.offsets.eproc_link();
-        let mut list_entry = list_start;
+    /// Consumes the kernel and returns a virtual memory reader for the kernel address space.
+    ///
+    /// See `kernel_virt_mem()` for details.
+    pub fn into_virtual_memory(self) -> VirtualDMA<T, V, Win32VirtualTranslate> {
+        VirtualDMA::with_vat(
+            self.phys_mem,
+            self.kernel_info.start_block.arch,
+            Win32VirtualTranslate::new(self.kernel_info.start_block.arch, self.sysproc_dtb),
+            self.vat,
+        )
+    }
 
-        for _ in 0..MAX_ITER_COUNT {
-            let eprocess = list_entry - self.offsets.eproc_link();
-            trace!("eprocess={}", eprocess);
+    /// Returns access to the cpu state of the target if the underlying connector supports it.
+    ///
+    /// This can be used to retrieve the page table base that is currently active on each cpu.
+    pub fn cpu_state(&mut self) -> Option<&mut dyn CpuState> {
+        self.phys_mem.cpu_state()
+    }
 
-            // test flink + blink before adding the process
-            let flink_entry =
-                reader.virt_read_addr_arch(self.kernel_info.start_block.arch, list_entry)?;
-            trace!("flink_entry={}", flink_entry);
-            let blink_entry = reader.virt_read_addr_arch(
-                self.kernel_info.start_block.arch,
-                list_entry + self.offsets.list_blink(),
-            )?;
-            trace!("blink_entry={}", blink_entry);
+    /// Returns the current system time of the target as a `FILETIME`
+    /// (100-nanosecond intervals since January 1, 1601 UTC).
+    ///
+    /// The time is read from `KUSER_SHARED_DATA::SystemTime`.
+    pub fn system_time(&mut self) -> Result<u64> {
+        Ok(self.kuser_shared_data()?.system_time)
+    }
 
-            if flink_entry.is_null()
-                || blink_entry.is_null()
-                || flink_entry == list_start
-                || flink_entry == list_entry
-            {
-                break;
+    /// Returns the time the target was booted at as a `FILETIME`
+    /// (100-nanosecond intervals since January 1, 1601 UTC).
+    ///
+    /// The time is read from the `KeBootTime` global if it is exported by the kernel.
+    /// Otherwise it is calculated from the current system time and
+    /// the time since boot (`KUSER_SHARED_DATA::InterruptTime`).
+    pub fn boot_time(&mut self) -> Result<u64> {
+        if let Ok(boot_time) = self.read_global::<u64>("KeBootTime") {
+            if boot_time != 0 {
+                return Ok(boot_time);
+            }
+        }
+
+        self.kuser_shared_data()?.boot_time()
+    }
+
+    /// Reads the `KUSER_SHARED_DATA` page of the target.
+    ///
+    /// The page is mapped at a fixed address in the kernel address space
+    /// and contains the version, product type and time values of the system.
+    /// Fields that do not exist on the version of the target are set to `None`.
+    ///
+    /// The time values are updated by the kernel without a lock,
+    /// the page is read repeatedly until a consistent snapshot is found.
+    pub fn kuser_shared_data(&mut self) -> Result<KUserSharedData> {
+        let arch = self.kernel_info.start_block.arch;
+        KUserSharedData::read(&mut self.kernel_virt_mem(), arch)
+    }
+
+    /// Retrieves the version information of the target kernel.
+    ///
+    /// Besides the major, minor and build number this also contains
+    /// the revision and whether the target is a server system.
+    /// The revision is read from the `CmNtCSDVersion` export (see `export_address()`).
+    pub fn winver(&mut self) -> Result<Win32VersionInfo> {
+        let arch = self.kernel_info.start_block.arch;
+
+        // the version might be unknown in case the scan failed initially
+        let version = if self.kernel_info.kernel_winver.build_number() != 0 {
+            self.kernel_info.kernel_winver
+        } else {
+            let kernel_base = self.kernel_info.kernel_base;
+            ntos::find_winver(&mut self.kernel_virt_mem(), arch, kernel_base)?
+        };
+
+        let revision = match self.export_address("CmNtCSDVersion") {
+            Ok(csd_version) => self.kernel_virt_mem().virt_read::<u32>(csd_version)?,
+            Err(_) => {
+                warn!("unable to find CmNtCSDVersion export");
+                0
             }
+        };
+        info!("revision: {:x}", revision);
 
-            trace!("found eprocess {:x}", eprocess);
-            eprocs.extend(Some(eprocess).into_iter());
+        let shared_data = self.kuser_shared_data()?;
+        info!(
+            "nt_product_type: {} (valid={})",
+            shared_data.nt_product_type, shared_data.product_type_is_valid
+        );
 
-            // continue
-            list_entry = flink_entry;
-        }
+        Ok(Win32VersionInfo {
+            major: version.major_version(),
+            minor: version.minor_version(),
+            build: version.build_number(),
+            revision,
+            is_server: shared_data.is_server(),
+        })
+    }
+
+    pub fn eprocess_list(&mut self) -> Result<Vec<Address>> {
+        let mut eprocs = Vec::new();
+        self.eprocess_list_extend(&mut eprocs)?;
+        trace!("found {} eprocesses", eprocs.len());
+        Ok(eprocs)
+    }
 
+    pub fn eprocess_list_extend<E: Extend<Address>>(&mut self, eprocs: &mut E) -> Result<()> {
+        for eprocess in self.eprocess_iter() {
+            eprocs.extend(Some(eprocess?).into_iter());
+        }
         Ok(())
     }
 
-    pub fn kernel_process_info(&mut self) -> Result<Win32ProcessInfo> {
+    /// Returns an iterator that lazily walks the EPROCESS list of the target.
+    ///
+    /// The iterator will yield at most `MAX_ITER_COUNT` (65536) entries.
+    /// If the list is longer than that an `Error::ListCorruption` is yielded and iteration stops.
+    /// See `eprocess_iter_with_limit` for specifying a custom limit.
+    pub fn eprocess_iter(&mut self) -> EProcessIter<&mut T, &mut V> {
+        self.eprocess_iter_with_limit(MAX_ITER_COUNT)
+    }
+
+    /// Returns an iterator that lazily walks the EPROCESS list of the target
+    /// and yields at most `max_count` entries.
+    ///
+    /// In case the list contains more than `max_count` entries the iterator yields
+    /// an `Error::ListCorruption` and stops. This guards against malformed or looping lists.
+    pub fn eprocess_iter_with_limit(&mut self, max_count: usize) -> EProcessIter<&mut T, &mut V> {
         // TODO: create a VirtualDMA constructor for kernel_info
-        let mut reader = VirtualDMA::with_vat(
+        let reader = VirtualDMA::with_vat(
             &mut self.phys_mem,
             self.kernel_info.start_block.arch,
             Win32VirtualTranslate::new(self.kernel_info.start_block.arch, self.sysproc_dtb),
             &mut self.vat,
         );
 
-        // TODO: cache pe globally
+        // the list is walked starting at the system process, the list head is the last entry of the ring
+        let list_start = self.kernel_info.eprocess_base + self.offsets.eproc_link();
+
+        EProcessIter {
+            list: ListEntryIter::with_first_entry(
+                reader,
+                self.kernel_info.start_block.arch,
+                list_start,
+                self.offsets.eproc_link(),
+                self.offsets.list_blink(),
+                max_count,
+            ),
+        }
+    }
+
+    /// Retrieves a synthetic `Win32ProcessInfo` for the kernel image.
+    ///
+    /// The kernel itself is not a process, the returned info uses the kernel base as its address,
+    /// the dtb of the System process and the list of loaded kernel modules.
+    /// Since it does not have a process id it will always report pid 0.
+    pub fn kernel_process_info(&mut self) -> Result<Win32ProcessInfo> {
+        let module_info = self.kernel_module_list_info()?;
+        Ok(self.kernel_process_info_with(module_info))
+    }
+
+    /// Reads the head of the loaded kernel module list (`PsLoadedModuleList`).
+    fn kernel_module_list_info(&mut self) -> Result<Win32ModuleListInfo> {
         // find PsLoadedModuleList
-        let loaded_module_list = {
-            let image =
-                reader.virt_read_raw(self.kernel_info.kernel_base, self.kernel_info.kernel_size)?;
-            let pe = PeView::from_bytes(&image).map_err(Error::PE)?;
-            match pe
-                .get_export_by_name("PsLoadedModuleList")
-                .map_err(Error::PE)?
-            {
-                Export::Symbol(s) => self.kernel_info.kernel_base + *s as usize,
-                Export::Forward(_) => {
-                    return Err(Error::Other(
-                        "PsLoadedModuleList found but it was a forwarded export",
-                    ))
-                }
-            }
-        };
+        let loaded_module_list = self.export_address("PsLoadedModuleList")?;
 
-        let kernel_modules =
-            reader.virt_read_addr_arch(self.kernel_info.start_block.arch, loaded_module_list)?;
+        let kernel_modules = self
+            .kernel_virt_mem()
+            .virt_read_addr_arch(self.kernel_info.start_block.arch, loaded_module_list)?;
 
-        Ok(Win32ProcessInfo {
+        Win32ModuleListInfo::with_base(kernel_modules, self.kernel_info.start_block.arch)
+    }
+
+    fn kernel_process_info_with(&self, module_info: Win32ModuleListInfo) -> Win32ProcessInfo {
+        Win32ProcessInfo {
             address: self.kernel_info.kernel_base,
 
             pid: 0,
@@ -172,20 +372,142 @@ impl<T: PhysicalMemory, V: VirtualTranslate> Kernel<T, V> {
             ethread: Address::NULL, // TODO: see below
             wow64: Address::NULL,
 
+            protection: Win32Protection::default(),
+            integrity_level: None,
+
             teb: None,
             teb_wow64: None,
 
             peb_native: Address::NULL,
             peb_wow64: None,
 
-            module_info_native: Win32ModuleListInfo::with_base(
-                kernel_modules,
-                self.kernel_info.start_block.arch,
-            )?,
+            module_info_native: module_info,
             module_info_wow64: None,
 
+            memory_info: ProcessMemoryInfo::default(),
+
             sys_arch: self.kernel_info.start_block.arch,
             proc_arch: self.kernel_info.start_block.arch,
+        }
+    }
+
+    /// Resolves an export of ntoskrnl (e.g. `KdDebuggerDataBlock`) to its virtual address.
+    ///
+    /// The export table of the kernel image is parsed on the first call and cached afterwards,
+    /// subsequent lookups do not access the memory of the target.
+    /// Forwarded exports are not contained in the table.
+    pub fn export_address(&mut self, name: &str) -> Result<Address> {
+        if self.exports.is_none() {
+            let kernel_base = self.kernel_info.kernel_base;
+            let kernel_size = self.kernel_info.kernel_size;
+            let exports =
+                Self::read_exports(&mut self.kernel_virt_mem(), kernel_base, kernel_size)?;
+            self.exports = Some(exports);
+        }
+
+        self.exports
+            .as_ref()
+            .and_then(|exports| exports.get(name))
+            .copied()
+            .ok_or(Error::Other("kernel export not found"))
+    }
+
+    /// Reads a kernel global variable that is exported by ntoskrnl.
+    ///
+    /// The export is resolved via `export_address()` and the value is read from the kernel address space.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memflow::mem::{PhysicalMemory, VirtualTranslate};
+    /// use memflow_win32::win32::Kernel;
+    ///
+    /// fn test<T: PhysicalMemory, V: VirtualTranslate>(kernel: &mut Kernel<T, V>) {
+    ///     let global_flag = kernel.read_global::<u32>("NtGlobalFlag").unwrap();
+    ///     println!("NtGlobalFlag: {:x}", global_flag);
+    /// }
+    /// ```
+    pub fn read_global<D: Pod>(&mut self, name: &str) -> Result<D> {
+        let addr = self.export_address(name)?;
+        Ok(self.kernel_virt_mem().virt_read(addr)?)
+    }
+
+    fn read_exports<M: VirtualMemory>(
+        reader: &mut M,
+        kernel_base: Address,
+        kernel_size: usize,
+    ) -> Result<BTreeMap<String, Address>> {
+        let image = reader.virt_read_raw(kernel_base, kernel_size)?;
+        let pe = PeView::from_bytes(&image).map_err(Error::PE)?;
+
+        let mut exports = BTreeMap::new();
+        for (name, export) in pe.exports()?.by()?.iter_names() {
+            if let (Ok(name), Ok(Export::Symbol(&rva))) = (name, export) {
+                exports.insert(name.to_str()?.to_string(), kernel_base + rva as usize);
+            }
+        }
+        trace!("found {} kernel exports", exports.len());
+
+        Ok(exports)
+    }
+
+    /// Retrieves the `Win32ProcessInfo` of the System Idle process (pid 0).
+    ///
+    /// The Idle process is not part of the active process list,
+    /// its EPROCESS is found via the `PsIdleProcess` global instead.
+    /// The offset of this global is only available when the offsets have been generated from a pdb.
+    /// Without it the synthetic kernel process info (see `kernel_process_info()`) is returned,
+    /// named `Idle`.
+    pub fn idle_process_info(&mut self) -> Result<Win32ProcessInfo> {
+        if self.offsets.ps_idle_process() == 0 {
+            trace!("PsIdleProcess offset not available; using the kernel process info");
+            let module_info = self.kernel_module_list_info_or_empty()?;
+            return Ok(Win32ProcessInfo {
+                name: "Idle".to_string(),
+                ..self.kernel_process_info_with(module_info)
+            });
+        }
+
+        let arch = self.kernel_info.start_block.arch;
+        let ps_idle_process = self.kernel_info.kernel_base + self.offsets.ps_idle_process();
+        let eprocess = self
+            .kernel_virt_mem()
+            .virt_read_addr_arch(arch, ps_idle_process)?
+            .non_null()
+            .ok_or(Error::Other("PsIdleProcess is not set"))?;
+        trace!("idle eprocess={:x}", eprocess);
+
+        self.process_info_from_eprocess(eprocess)
+    }
+
+    /// Retrieves the `Win32ProcessInfo` of the System process (usually pid 4).
+    ///
+    /// The System process is the first entry of the active process list.
+    /// Unlike user processes it does not have a PEB, the module list of this
+    /// process contains the loaded kernel modules instead.
+    pub fn system_process_info(&mut self) -> Result<Win32ProcessInfo> {
+        let proc_info = self.process_info_from_eprocess(self.kernel_info.eprocess_base)?;
+        let module_info = self.kernel_module_list_info_or_empty()?;
+
+        Ok(Win32ProcessInfo {
+            peb_native: Address::NULL,
+            peb_wow64: None,
+
+            module_info_native: module_info,
+            module_info_wow64: None,
+
+            ..proc_info
+        })
+    }
+
+    /// Reads the head of the loaded kernel module list.
+    ///
+    /// The kernel module list might not be resolvable (e.g. when the kernel image is paged out),
+    /// in this case an empty module list is returned.
+    fn kernel_module_list_info_or_empty(&mut self) -> Result<Win32ModuleListInfo> {
+        self.kernel_module_list_info().or_else(|err| {
+            trace!("unable to read kernel module list: {}", err);
+            Win32ModuleListInfo::with_base(Address::NULL, self.kernel_info.start_block.arch)
         })
     }
 
@@ -198,118 +520,208 @@ impl<T: PhysicalMemory, V: VirtualTranslate> Kernel<T, V> {
             &mut self.vat,
         );
 
-        let pid: PID = reader.virt_read(eprocess + self.offsets.eproc_pid())?;
+        let sys_arch = self.kernel_info.start_block.arch;
+
+        // the dtb of a process never changes, it only has to be read again
+        // when the eprocess has been reused for a different process
+        let cached_dtb = self.dtb_cache.get(eprocess);
+
+        // read all direct fields of the eprocess in a single batch
+        let mut eproc_reader = StructReader::new(eprocess)
+            .field(self.offsets.eproc_pid(), size_of::<PID>())
+            .field(self.offsets.eproc_name(), IMAGE_FILE_NAME_LENGTH)
+            .field_addr(sys_arch, self.offsets.eproc_peb())
+            .field_addr(sys_arch, self.offsets.eproc_section_base())
+            .field(
+                self.offsets.eproc_exit_status(),
+                size_of::<Win32ExitStatus>(),
+            )
+            .field_addr(sys_arch, self.offsets.eproc_thread_list());
+        let eproc_wow64 = self.offsets.eproc_wow64_checked();
+        if let Some(eproc_wow64) = eproc_wow64 {
+            eproc_reader = eproc_reader.field_addr(sys_arch, eproc_wow64);
+        }
+        let eproc_protection = self.offsets.eproc_protection_checked();
+        if let Some(eproc_protection) = eproc_protection {
+            eproc_reader = eproc_reader.field(eproc_protection, 1);
+        }
+        // the working set size and commit charge are page counts, the lower 32 bits are sufficient
+        // and cover both the ULONG and the SIZE_T variants of these fields.
+        let eproc_working_set_size =
+            if self.offsets.eproc_vm() != 0 && self.offsets.mmsupport_working_set_size() != 0 {
+                Some(self.offsets.eproc_vm() + self.offsets.mmsupport_working_set_size())
+            } else {
+                None
+            };
+        if let Some(eproc_working_set_size) = eproc_working_set_size {
+            eproc_reader = eproc_reader.field(eproc_working_set_size, size_of::<u32>());
+        }
+        if self.offsets.eproc_virtual_size() != 0 {
+            eproc_reader = eproc_reader.field_addr(sys_arch, self.offsets.eproc_virtual_size());
+        }
+        if self.offsets.eproc_commit_charge() != 0 {
+            eproc_reader = eproc_reader.field(self.offsets.eproc_commit_charge(), size_of::<u32>());
+        }
+        if cached_dtb.is_none() {
+            eproc_reader = eproc_reader.field_addr(sys_arch, self.offsets.kproc_dtb());
+        }
+        // fields that are paged out are zero-filled, only pid, name, exit status and dtb are mandatory
+        let eproc = eproc_reader.read(&mut reader).data_part()?;
+
+        let pid: PID = eproc.get(self.offsets.eproc_pid())?;
         trace!("pid={}", pid);
 
-        let name =
-            reader.virt_read_cstr(eprocess + self.offsets.eproc_name(), IMAGE_FILE_NAME_LENGTH)?;
+        let mut name = eproc.get_cstr(self.offsets.eproc_name())?;
         trace!("name={}", name);
 
-        let dtb = reader.virt_read_addr_arch(
-            self.kernel_info.start_block.arch,
-            eprocess + self.offsets.kproc_dtb(),
-        )?;
+        // the short name is blank for some processes (e.g. minimal processes or during creation)
+        if name.trim().is_empty() {
+            if let Some(image_path) =
+                Self::read_audit_image_path(&mut reader, &self.offsets, sys_arch, eprocess)
+            {
+                name = image_file_name(&image_path).to_string();
+                trace!("name={} (from image path)", name);
+            }
+        }
+
+        let dtb = match cached_dtb {
+            Some((cached_pid, dtb)) if cached_pid == pid => dtb,
+            Some(_) => {
+                // the eprocess has been reused, the dtb was not part of the batch
+                let dtb = reader
+                    .virt_read_addr_arch(sys_arch, eprocess + self.offsets.kproc_dtb())
+                    .data_part()?;
+                self.dtb_cache.insert(eprocess, pid, dtb);
+                dtb
+            }
+            None => {
+                let dtb = eproc.get_addr(sys_arch, self.offsets.kproc_dtb())?;
+                self.dtb_cache.insert(eprocess, pid, dtb);
+                dtb
+            }
+        };
         trace!("dtb={:x}", dtb);
 
-        let wow64 = if self.offsets.eproc_wow64() == 0 {
-            trace!("eproc_wow64=null; skipping wow64 detection");
-            Address::null()
-        } else {
+        let wow64 = if let Some(eproc_wow64) = eproc_wow64 {
             trace!(
                 "eproc_wow64={:x}; trying to read wow64 pointer",
-                self.offsets.eproc_wow64()
+                eproc_wow64
             );
-            reader.virt_read_addr_arch(
-                self.kernel_info.start_block.arch,
-                eprocess + self.offsets.eproc_wow64(),
-            )?
+            eproc.get_addr(sys_arch, eproc_wow64).unwrap_or_default()
+        } else {
+            trace!("eproc_wow64 not available; skipping wow64 detection");
+            Address::null()
         };
-        trace!("wow64={:x}", wow64);
+        trace!("wow64={:#}", wow64.format_for(sys_arch));
 
         // determine process architecture
-        let sys_arch = self.kernel_info.start_block.arch;
         trace!("sys_arch={:?}", sys_arch);
-        let proc_arch = match sys_arch.bits() {
-            64 => {
-                if wow64.is_null() {
-                    x86::x64::ARCH
-                } else {
-                    x86::x32::ARCH
-                }
-            }
-            32 => x86::x32::ARCH,
-            _ => return Err(Error::InvalidArchitecture),
-        };
+        let proc_arch = x86::process_arch(sys_arch, !wow64.is_null())?;
         trace!("proc_arch={:?}", proc_arch);
 
-        // read native_peb (either the process peb or the peb containing the wow64 helpers)
-        let native_peb = reader.virt_read_addr_arch(
-            self.kernel_info.start_block.arch,
-            eprocess + self.offsets.eproc_peb(),
-        )?;
-        trace!("native_peb={:x}", native_peb);
-
-        let section_base = reader.virt_read_addr_arch(
-            self.kernel_info.start_block.arch,
-            eprocess + self.offsets.eproc_section_base(),
-        )?;
-        trace!("section_base={:x}", section_base);
+        let section_base = eproc
+            .get_addr(sys_arch, self.offsets.eproc_section_base())
+            .unwrap_or_default();
+        trace!("section_base={:#}", section_base.format_for(proc_arch));
 
-        let exit_status: Win32ExitStatus =
-            reader.virt_read(eprocess + self.offsets.eproc_exit_status())?;
+        // a process whose exit status can not be read must not be reported as alive
+        let exit_status: Win32ExitStatus = eproc.get(self.offsets.eproc_exit_status())?;
         trace!("exit_status={}", exit_status);
 
+        let protection = if let Some(eproc_protection) = eproc_protection {
+            eproc
+                .get(eproc_protection)
+                .map(Win32Protection)
+                .unwrap_or_default()
+        } else {
+            trace!("eproc_protection not available; skipping protection detection");
+            Win32Protection::default()
+        };
+        trace!("protection={:?}", protection);
+
+        let integrity_level =
+            Self::read_integrity_level(&mut reader, &self.offsets, sys_arch, eprocess)
+                .unwrap_or_else(|err| {
+                    trace!("unable to read integrity level: {}", err);
+                    None
+                });
+        trace!("integrity_level={:?}", integrity_level);
+
+        // memory usage is optional, values that can not be read are left at zero
+        let page_size = sys_arch.page_size();
+        let memory_info = ProcessMemoryInfo {
+            working_set_size: eproc_working_set_size
+                .and_then(|offs| eproc.get::<u32>(offs).ok())
+                .unwrap_or_default() as usize
+                * page_size,
+            virtual_size: match self.offsets.eproc_virtual_size() {
+                0 => 0,
+                offs => eproc
+                    .get_addr(sys_arch, offs)
+                    .map(Address::as_usize)
+                    .unwrap_or_default(),
+            },
+            private_bytes: match self.offsets.eproc_commit_charge() {
+                0 => 0,
+                offs => eproc.get::<u32>(offs).unwrap_or_default() as usize * page_size,
+            },
+        };
+        trace!("memory_info={:?}", memory_info);
+
         // find first ethread
-        let ethread = reader.virt_read_addr_arch(
-            self.kernel_info.start_block.arch,
-            eprocess + self.offsets.eproc_thread_list(),
-        )? - self.offsets.ethread_list_entry();
-        trace!("ethread={:x}", ethread);
+        let ethread = eproc
+            .get_addr(sys_arch, self.offsets.eproc_thread_list())
+            .ok()
+            .and_then(Address::non_null)
+            .map(|entry| entry - self.offsets.ethread_list_entry())
+            .unwrap_or_default();
+        trace!("ethread={:#}", ethread.format_for(sys_arch));
 
-        let peb_native = reader
-            .virt_read_addr_arch(
-                self.kernel_info.start_block.arch,
-                eprocess + self.offsets.eproc_peb(),
-            )?
-            .non_null()
-            .ok_or(Error::Other("Could not retrieve peb_native"))?;
+        // read native_peb (either the process peb or the peb containing the wow64 helpers)
+        // the peb might be paged out, in this case the process is still reported without modules
+        let peb_native = eproc
+            .get_addr(sys_arch, self.offsets.eproc_peb())
+            .unwrap_or_default();
 
         let mut peb_wow64 = None;
 
         // TODO: does this need to be read with the process ctx?
-        let (teb, teb_wow64) = if self.kernel_info.kernel_winver >= (6, 2).into() {
-            let teb = reader.virt_read_addr_arch(
-                self.kernel_info.start_block.arch,
-                ethread + self.offsets.kthread_teb(),
-            )?;
-
-            trace!("teb={:x}", teb);
-
-            if !teb.is_null() {
-                (
-                    Some(teb),
-                    if wow64.is_null() {
-                        None
-                    } else {
-                        Some(teb + 0x2000)
-                    },
-                )
+        let (teb, teb_wow64) =
+            if self.kernel_info.kernel_winver >= (6, 2).into() && !ethread.is_null() {
+                let teb = reader
+                    .virt_read_addr_arch(
+                        self.kernel_info.start_block.arch,
+                        ethread + self.offsets.kthread_teb(),
+                    )
+                    .unwrap_or_default();
+
+                trace!("teb={:#}", teb.format_for(sys_arch));
+
+                if !teb.is_null() {
+                    (
+                        Some(teb),
+                        if wow64.is_null() {
+                            None
+                        } else {
+                            Some(teb + TEB_WOW64_OFFSET)
+                        },
+                    )
+                } else {
+                    (None, None)
+                }
             } else {
                 (None, None)
-            }
-        } else {
-            (None, None)
-        };
+            };
 
         std::mem::drop(reader);
 
-        // construct reader with process dtb
-        // TODO: can tlb be used here already?
+        // construct reader with process dtb, translations are cached per dtb
+        // so the kernel vat can be shared with the process context
         let mut proc_reader = VirtualDMA::with_vat(
             &mut self.phys_mem,
             proc_arch,
             Win32VirtualTranslate::new(self.kernel_info.start_block.arch, dtb),
-            DirectTranslate::new(),
+            &mut self.vat,
         );
 
         if let Some(teb) = teb_wow64 {
@@ -319,20 +731,47 @@ impl<T: PhysicalMemory, V: VirtualTranslate> Kernel<T, V> {
                 .virt_read_addr_arch(
                     self.kernel_info.start_block.arch,
                     teb + self.offsets.teb_peb_x86(),
-                )?
-                .non_null();
+                )
+                .ok()
+                .and_then(Address::non_null);
 
             trace!("peb_wow64={:?}", peb_wow64);
         }
 
         trace!("peb_native={:?}", peb_native);
 
+        // the peb might be paged out, in this case the module list will be empty
         let module_info_native =
-            Win32ModuleListInfo::with_peb(&mut proc_reader, peb_native, sys_arch)?;
+            Win32ModuleListInfo::with_peb(&mut proc_reader, peb_native, sys_arch).or_else(
+                |err| {
+                    trace!("unable to read native module list: {}", err);
+                    Win32ModuleListInfo::with_base(Address::NULL, sys_arch)
+                },
+            )?;
 
-        let module_info_wow64 = peb_wow64
-            .map(|peb| Win32ModuleListInfo::with_peb(&mut proc_reader, peb, proc_arch))
-            .transpose()?;
+        let module_info_wow64 = peb_wow64.and_then(|peb| {
+            Win32ModuleListInfo::with_peb(&mut proc_reader, peb, proc_arch)
+                .map_err(|err| trace!("unable to read wow64 module list: {}", err))
+                .ok()
+        });
+
+        // as a last resort the name is taken from the primary module
+        if name.trim().is_empty() && !section_base.is_null() {
+            if let Some(module) = module_info_native
+                .module_entry_list(&mut proc_reader, sys_arch)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|entry| {
+                    module_info_native
+                        .module_info_from_entry(entry, eprocess, &mut proc_reader, sys_arch)
+                        .ok()
+                })
+                .find(|module| module.base == section_base)
+            {
+                name = module.name;
+                trace!("name={} (from primary module)", name);
+            }
+        }
 
         Ok(Win32ProcessInfo {
             address: eprocess,
@@ -345,6 +784,9 @@ impl<T: PhysicalMemory, V: VirtualTranslate> Kernel<T, V> {
             ethread,
             wow64,
 
+            protection,
+            integrity_level,
+
             teb,
             teb_wow64,
 
@@ -354,11 +796,54 @@ impl<T: PhysicalMemory, V: VirtualTranslate> Kernel<T, V> {
             module_info_native,
             module_info_wow64,
 
+            memory_info,
+
             sys_arch,
             proc_arch,
         })
     }
 
+    fn read_integrity_level<V: VirtualMemory>(
+        reader: &mut V,
+        offsets: &Win32Offsets,
+        arch: ArchitectureObj,
+        eprocess: Address,
+    ) -> Result<Option<IntegrityLevel>> {
+        if offsets.eproc_token() == 0 || offsets.token_integrity_level_index() == 0 {
+            return Ok(None);
+        }
+
+        // _EPROCESS::Token is an _EX_FAST_REF, the lower bits contain the reference count
+        let fast_ref_mask = if arch.bits() == 64 { 0xf } else { 0x7 };
+        let token = reader.virt_read_addr_arch(arch, eprocess + offsets.eproc_token())?;
+        let token = Address::from(token.as_u64() & !fast_ref_mask);
+        trace!("token={:x}", token);
+        if token.is_null() {
+            return Ok(None);
+        }
+
+        let index: u32 = reader.virt_read(token + offsets.token_integrity_level_index())?;
+        trace!("integrity_level_index={}", index);
+        if index == u32::MAX {
+            return Ok(None);
+        }
+
+        // _TOKEN::UserAndGroups points to an array of _SID_AND_ATTRIBUTES
+        let groups = reader.virt_read_addr_arch(arch, token + offsets.token_user_and_groups())?;
+        let sid =
+            reader.virt_read_addr_arch(arch, groups + index as usize * arch.size_addr() * 2)?;
+        trace!("integrity_level_sid={:x}", sid);
+
+        // the rid is the last entry in _SID::SubAuthority
+        let sub_authority_count: u8 = reader.virt_read(sid + 1)?;
+        if sub_authority_count == 0 {
+            return Ok(None);
+        }
+        let rid: u32 = reader.virt_read(sid + 8 + (sub_authority_count as usize - 1) * 4)?;
+
+        Ok(Some(IntegrityLevel::from(rid)))
+    }
+
     pub fn process_info_list_extend<E: Extend<Win32ProcessInfo>>(
         &mut self,
         list: &mut E,
@@ -366,7 +851,7 @@ impl<T: PhysicalMemory, V: VirtualTranslate> Kernel<T, V> {
         let mut vec = Vec::new();
         self.eprocess_list_extend(&mut vec)?;
         for eprocess in vec.into_iter() {
-            if let Ok(prc) = self.process_info_from_eprocess(eprocess) {
+            if let Ok(prc) = self.eprocess_process_info(eprocess) {
                 list.extend(Some(prc).into_iter());
             }
         }
@@ -381,22 +866,117 @@ impl<T: PhysicalMemory, V: VirtualTranslate> Kernel<T, V> {
         Ok(list)
     }
 
+    /// Retrieves a list of all processes that can be found on the target system.
+    ///
+    /// In contrast to `process_info_list()` processes that could not be resolved are not dropped.
+    /// Instead the address of their eprocess is returned together with the error that occured.
+    /// The list is in the same order as the eprocess list.
+    pub fn process_info_list_partial(
+        &mut self,
+    ) -> Result<Vec<std::result::Result<Win32ProcessInfo, (Address, Error)>>> {
+        let eprocs = self.eprocess_list()?;
+        Ok(eprocs
+            .into_iter()
+            .map(|eprocess| {
+                self.eprocess_process_info(eprocess)
+                    .map_err(|err| (eprocess, err))
+            })
+            .collect())
+    }
+
+    fn eprocess_process_info(&mut self, eprocess: Address) -> Result<Win32ProcessInfo> {
+        // the system process does not have a peb and needs special treatment
+        if eprocess == self.kernel_info.eprocess_base {
+            self.system_process_info()
+        } else {
+            self.process_info_from_eprocess(eprocess)
+        }
+    }
+
+    /// Reads the process id and the short name (`_EPROCESS::ImageFileName`) of the given eprocess.
+    ///
+    /// This only requires a single batched read and is a lot cheaper than
+    /// resolving the entire process with `process_info_from_eprocess()`.
+    pub fn eprocess_pid_name(&mut self, eprocess: Address) -> Result<(PID, String)> {
+        let mut reader = VirtualDMA::with_vat(
+            &mut self.phys_mem,
+            self.kernel_info.start_block.arch,
+            Win32VirtualTranslate::new(self.kernel_info.start_block.arch, self.sysproc_dtb),
+            &mut self.vat,
+        );
+
+        let eproc = StructReader::new(eprocess)
+            .field(self.offsets.eproc_pid(), size_of::<PID>())
+            .field(self.offsets.eproc_name(), IMAGE_FILE_NAME_LENGTH)
+            .read(&mut reader)?;
+
+        Ok((
+            eproc.get(self.offsets.eproc_pid())?,
+            eproc.get_cstr(self.offsets.eproc_name())?,
+        ))
+    }
+
+    /// Retrieves a list of `Win32ProcessInfo` structs for all processes
+    /// that pass the given predicate.
+    ///
+    /// The predicate receives the process id and the short name (clamped to 14 characters) of each process.
+    /// Only processes for which the predicate returns `true` are fully resolved,
+    /// which saves a lot of reads when only a small subset of the processes is of interest.
+    pub fn process_info_list_filter<F: FnMut(PID, &str) -> bool>(
+        &mut self,
+        mut filter: F,
+    ) -> Result<Vec<Win32ProcessInfo>> {
+        let mut list = Vec::new();
+        for eprocess in self.eprocess_list()?.into_iter() {
+            match self.eprocess_pid_name(eprocess) {
+                Ok((pid, name)) => {
+                    if !filter(pid, &name) {
+                        continue;
+                    }
+                }
+                Err(err) => {
+                    trace!("unable to read eprocess {:x}: {}", eprocess, err);
+                    continue;
+                }
+            }
+
+            if let Ok(prc) = self.eprocess_process_info(eprocess) {
+                list.push(prc);
+            }
+        }
+        Ok(list)
+    }
+
     /// Finds a process by it's name and returns the `Win32ProcessInfo` struct.
     /// If no process with the specified name can be found this function will return an Error.
+    ///
+    /// In case exactly one of the processes with a matching short name has a matching
+    /// full image name (see `process_image_path()`) it is returned right away.
+    /// Otherwise the module lists of all candidates are walked to find the process.
     pub fn process_info(&mut self, name: &str) -> Result<Win32ProcessInfo> {
         let name16 = name[..name.len().min(IMAGE_FILE_NAME_LENGTH - 1)].to_lowercase();
 
-        let process_info_list = self.process_info_list()?;
-        let candidates = process_info_list
-            .iter()
-            .inspect(|process| trace!("{} {}", process.pid(), process.name()))
-            .filter(|process| {
-                // strip process name to IMAGE_FILE_NAME_LENGTH without trailing \0
-                process.name().to_lowercase() == name16
-            })
-            .collect::<Vec<_>>();
+        let candidates = self.process_info_list_filter(|pid, name| {
+            trace!("{} {}", pid, name);
+            // strip process name to IMAGE_FILE_NAME_LENGTH without trailing \0
+            name.to_lowercase() == name16
+        })?;
+
+        let mut audit_matches = Vec::new();
+        for candidate in candidates.iter() {
+            if let Some(image_path) = self.eprocess_audit_image_path(candidate.address) {
+                let image_name = image_path.rsplit('\\').next().unwrap_or_default();
+                if image_name.to_lowercase() == name.to_lowercase() {
+                    audit_matches.push(candidate);
+                }
+            }
+        }
+        if let [candidate] = audit_matches[..] {
+            trace!("found unique process by image path: {:?}", candidate);
+            return Ok(candidate.clone());
+        }
 
-        for &candidate in candidates.iter() {
+        for candidate in candidates.iter() {
             // TODO: properly probe pe header here and check ImageBase
             // TODO: this wont work with tlb
             trace!("inspecting candidate process: {:?}", candidate);
@@ -416,23 +996,454 @@ impl<T: PhysicalMemory, V: VirtualTranslate> Kernel<T, V> {
         Err(Error::ProcessInfo)
     }
 
-    /// Finds a process by it's process id and returns the `Win32ProcessInfo` struct.
-    /// If no process with the specified PID can be found this function will return an Error.
+    /// Retrieves the full path of the image file of the given process.
     ///
-    /// If the specified PID is 0 the kernel process is returned.
+    /// The path is read from `_EPROCESS::SeAuditProcessCreationInfo` and is returned
+    /// as a `Win32ImagePath::Device` (e.g. `\Device\HarddiskVolume3\Windows\System32\lsass.exe`).
+    /// This field is only available when the offsets have been generated from a pdb.
+    /// If it is not available the `FullDllName` of the main module is returned
+    /// as a `Win32ImagePath::Dos` instead.
+    pub fn process_image_path(&mut self, proc_info: &Win32ProcessInfo) -> Result<Win32ImagePath> {
+        if let Some(image_path) = self.eprocess_audit_image_path(proc_info.address) {
+            return Ok(Win32ImagePath::Device(image_path));
+        }
+
+        let mut process = Win32Process::with_kernel_ref(self, proc_info.clone());
+        Ok(Win32ImagePath::Dos(process.main_module_info()?.path))
+    }
+
+    /// Reads the image path from `_EPROCESS::SeAuditProcessCreationInfo`,
+    /// returns `None` if it is not available.
+    fn eprocess_audit_image_path(&mut self, eprocess: Address) -> Option<String> {
+        let arch = self.kernel_info.start_block.arch;
+        let mut reader = VirtualDMA::with_vat(
+            &mut self.phys_mem,
+            arch,
+            Win32VirtualTranslate::new(arch, self.sysproc_dtb),
+            &mut self.vat,
+        );
+        Self::read_audit_image_path(&mut reader, &self.offsets, arch, eprocess)
+    }
+
+    fn read_audit_image_path<M: VirtualMemory>(
+        reader: &mut M,
+        offsets: &Win32Offsets,
+        arch: ArchitectureObj,
+        eprocess: Address,
+    ) -> Option<String> {
+        if offsets.eproc_audit_info() == 0 {
+            return None;
+        }
+
+        // _SE_AUDIT_PROCESS_CREATION_INFO::ImageFileName points to an _OBJECT_NAME_INFORMATION
+        reader
+            .virt_read_addr_arch(arch, eprocess + offsets.eproc_audit_info())
+            .ok()
+            .and_then(Address::non_null)
+            .and_then(|name_info| reader.virt_read_unicode_string(arch, name_info).ok())
+            .filter(|image_path| !image_path.is_empty())
+    }
+
+    /// Finds a process by the full path of its image file and returns the `Win32ProcessInfo` struct.
+    /// If no process with the specified path can be found this function will return an Error.
+    ///
+    /// The path is compared case-insensitively against the path returned by `process_image_path()`,
+    /// so it has to be supplied in the same form.
+    pub fn process_info_by_path(&mut self, path: &str) -> Result<Win32ProcessInfo> {
+        let path = path.to_lowercase();
+
+        let process_info_list = self.process_info_list()?;
+        for candidate in process_info_list.into_iter() {
+            match self.process_image_path(&candidate) {
+                Ok(image_path) => {
+                    trace!("{} {:?}", candidate.pid(), image_path);
+                    if image_path.as_str().to_lowercase() == path {
+                        return Ok(candidate);
+                    }
+                }
+                Err(err) => trace!(
+                    "unable to retrieve image path of {}: {}",
+                    candidate.pid(),
+                    err
+                ),
+            }
+        }
+
+        Err(Error::ProcessInfo)
+    }
+
+    /// Finds a process by it's process id and returns the `Win32ProcessInfo` struct.
+    /// If no process with the specified PID can be found this function will return an Error.
+    ///
+    /// If the specified PID is 0 the System Idle process is returned (see `idle_process_info()`).
+    /// The System process (pid 4) is part of the regular process list (see `system_process_info()`).
     pub fn process_info_pid(&mut self, pid: PID) -> Result<Win32ProcessInfo> {
         if pid > 0 {
             // regular pid
-            let process_info_list = self.process_info_list()?;
-            process_info_list
-                .into_iter()
-                .inspect(|process| trace!("{} {}", process.pid(), process.name()))
-                .find(|process| process.pid == pid)
-                .ok_or_else(|| Error::Other("pid not found"))
+            self.process_info_list_filter(|process_pid, name| {
+                trace!("{} {}", process_pid, name);
+                process_pid == pid
+            })?
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::Other("pid not found"))
         } else {
-            // kernel pid
-            self.kernel_process_info()
+            // idle process
+            self.idle_process_info()
+        }
+    }
+
+    /// Finds a process by the address of its page directory and returns the `Win32ProcessInfo` struct.
+    /// If no process with the specified dtb can be found this function will return an Error.
+    ///
+    /// The lower flag bits (e.g. the PCID) and the upper bits of the dtb are ignored when comparing,
+    /// this allows passing in a raw CR3 value that has been read from a virtual cpu.
+    /// Note that with kva shadowing enabled the user mode CR3 of a process will not be matched.
+    pub fn process_info_dtb(&mut self, dtb: Address) -> Result<Win32ProcessInfo> {
+        let sys_arch = self.kernel_info.start_block.arch;
+        let dtb_mask = if x86::is_x64_arch(sys_arch) {
+            0x000f_ffff_ffff_f000
+        } else if sys_arch == x86::x32_pae::ARCH {
+            0xffff_ffe0
+        } else {
+            0xffff_f000
+        };
+        let dtb = dtb.as_u64() & dtb_mask;
+
+        let eprocs = self.eprocess_list()?;
+        for eprocess in eprocs.into_iter() {
+            let mut reader = VirtualDMA::with_vat(
+                &mut self.phys_mem,
+                sys_arch,
+                Win32VirtualTranslate::new(sys_arch, self.sysproc_dtb),
+                &mut self.vat,
+            );
+
+            match reader.virt_read_addr_arch(sys_arch, eprocess + self.offsets.kproc_dtb()) {
+                Ok(process_dtb) if process_dtb.as_u64() & dtb_mask == dtb => {
+                    // the dtb has just been read, a possibly stale cache entry must not be used
+                    self.dtb_cache.remove(eprocess);
+                }
+                Ok(_) => continue,
+                Err(err) => {
+                    trace!("unable to read dtb of eprocess {:x}: {}", eprocess, err);
+                    continue;
+                }
+            }
+
+            if let Ok(prc) = self.eprocess_process_info(eprocess) {
+                return Ok(prc);
+            }
+        }
+
+        Err(Error::Other("dtb not found"))
+    }
+
+    /// Reads the `_RTL_USER_PROCESS_PARAMETERS` (command line, environment, current directory, ...)
+    /// of the process with the given EPROCESS.
+    ///
+    /// See `Win32Process::process_parameters()` for details.
+    pub fn process_parameters(&mut self, eprocess: Address) -> Result<ProcessParameters> {
+        let proc_info = self.process_info_from_eprocess(eprocess)?;
+        Win32Process::with_kernel_ref(self, proc_info).process_parameters()
+    }
+
+    /// Retrieves a list of all registry hives that are currently loaded on the target.
+    ///
+    /// The hives are found by walking the `CmpHiveListHead` list of the kernel.
+    /// This requires the `cmp_hive_list_head` offset which is only available when the offsets
+    /// have been generated from a pdb. Volatile hives do not have a backing file and
+    /// will report an empty file name.
+    pub fn hive_list(&mut self) -> Result<Vec<Win32HiveInfo>> {
+        if self.offsets.cmp_hive_list_head() == 0 {
+            return Err(Error::Other("CmpHiveListHead offset is not available"));
+        }
+
+        // TODO: create a VirtualDMA constructor for kernel_info
+        let mut reader = VirtualDMA::with_vat(
+            &mut self.phys_mem,
+            self.kernel_info.start_block.arch,
+            Win32VirtualTranslate::new(self.kernel_info.start_block.arch, self.sysproc_dtb),
+            &mut self.vat,
+        );
+        let arch = self.kernel_info.start_block.arch;
+
+        let list_head = self.kernel_info.kernel_base + self.offsets.cmp_hive_list_head();
+        let cmhives = walk_list_entry(
+            &mut reader,
+            arch,
+            list_head,
+            self.offsets.cmhive_hive_list(),
+            self.offsets.list_blink(),
+            MAX_ITER_COUNT,
+        )
+        .collect::<Result<Vec<_>>>()?;
+
+        let mut hives = Vec::new();
+        for cmhive in cmhives.into_iter() {
+            // _CMHIVE starts with the _HHIVE
+            trace!("found cmhive {:x}", cmhive);
+
+            let base_block =
+                reader.virt_read_addr_arch(arch, cmhive + self.offsets.hhive_base_block())?;
+            let file_name = reader
+                .virt_read_unicode_string(arch, cmhive + self.offsets.cmhive_file_full_path())
+                .unwrap_or_default();
+
+            hives.push(Win32HiveInfo {
+                address: cmhive,
+                base_block,
+                file_name,
+            });
+        }
+
+        Ok(hives)
+    }
+
+    /// Resolves the location and layout of the PFN database of the target.
+    ///
+    /// This requires the `MmPfnDatabase`, `MmHighestPhysicalPage` and `_MMPFN` offsets
+    /// which are only available when the offsets have been generated from a pdb.
+    pub fn pfn_database(&mut self) -> Result<PfnDatabase> {
+        // TODO: create a VirtualDMA constructor for kernel_info
+        let mut reader = VirtualDMA::with_vat(
+            &mut self.phys_mem,
+            self.kernel_info.start_block.arch,
+            Win32VirtualTranslate::new(self.kernel_info.start_block.arch, self.sysproc_dtb),
+            &mut self.vat,
+        );
+        PfnDatabase::with(
+            &mut reader,
+            self.kernel_info.start_block.arch,
+            self.kernel_info.kernel_base,
+            &self.offsets,
+        )
+    }
+
+    /// Retrieves the state of the physical page with the given page frame number
+    /// from the PFN database.
+    ///
+    /// See `pfn_database()` for the requirements. An `Error::Bounds` is returned
+    /// if `pfn` exceeds `MmHighestPhysicalPage`.
+    pub fn pfn_info(&mut self, pfn: u64) -> Result<PfnEntry> {
+        let database = self.pfn_database()?;
+        database.entry(&mut self.kernel_virt_mem(), pfn)
+    }
+
+    /// Returns an iterator that lazily decodes all entries of the PFN database,
+    /// starting at page frame number 0 up to and including `MmHighestPhysicalPage`.
+    ///
+    /// See `pfn_database()` for the requirements.
+    pub fn pfn_iter(
+        &mut self,
+    ) -> Result<PfnIter<VirtualDMA<&mut T, &mut V, Win32VirtualTranslate>>> {
+        let database = self.pfn_database()?;
+        Ok(PfnIter::new(self.kernel_virt_mem(), database))
+    }
+
+    /// Retrieves the process that is currently executing on the processor with the given index.
+    ///
+    /// The `_KPRCB` of the processor is looked up in `KiProcessorBlock`, its `CurrentThread`
+    /// is followed to the owning `_KPROCESS` which is then resolved like any other EPROCESS.
+    /// An idle processor executes its idle thread which belongs to the idle process.
+    ///
+    /// This requires the `KiProcessorBlock` and `_KPRCB` offsets which are only available
+    /// when the offsets have been generated from a pdb.
+    /// Processors that do not exist on the target have an empty slot in `KiProcessorBlock`
+    /// and will return an error.
+    pub fn current_process(&mut self, cpu_id: usize) -> Result<Win32ProcessInfo> {
+        if self.offsets.ki_processor_block() == 0
+            || self.offsets.kprcb_current_thread() == 0
+            || self.offsets.kthread_process() == 0
+        {
+            return Err(Error::Other("KiProcessorBlock offsets are not available"));
         }
+
+        // TODO: create a VirtualDMA constructor for kernel_info
+        let mut reader = VirtualDMA::with_vat(
+            &mut self.phys_mem,
+            self.kernel_info.start_block.arch,
+            Win32VirtualTranslate::new(self.kernel_info.start_block.arch, self.sysproc_dtb),
+            &mut self.vat,
+        );
+        let arch = self.kernel_info.start_block.arch;
+
+        let kprcb = reader
+            .virt_read_addr_arch(
+                arch,
+                self.kernel_info.kernel_base
+                    + self.offsets.ki_processor_block()
+                    + cpu_id * arch.size_addr(),
+            )?
+            .non_null()
+            .ok_or(Error::Other("processor not found"))?;
+        trace!("kprcb={:x}", kprcb);
+
+        let kthread = reader
+            .virt_read_addr_arch(arch, kprcb + self.offsets.kprcb_current_thread())?
+            .non_null()
+            .ok_or(Error::Other("processor does not have a current thread"))?;
+        trace!("kthread={:x}", kthread);
+
+        let eprocess = reader
+            .virt_read_addr_arch(arch, kthread + self.offsets.kthread_process())?
+            .non_null()
+            .ok_or(Error::Other("thread is not attached to a process"))?;
+        trace!("eprocess={:x}", eprocess);
+
+        self.process_info_from_eprocess(eprocess)
+    }
+
+    /// Returns the id of the session the given process is attached to.
+    ///
+    /// This requires the session offsets which are only available when the offsets
+    /// have been generated from a pdb. Processes that do not belong to a session
+    /// (e.g. the System process) will return an error.
+    pub fn process_session_id(&mut self, proc_info: &Win32ProcessInfo) -> Result<u32> {
+        if self.offsets.eproc_session() == 0 {
+            return Err(Error::Other("_EPROCESS::Session offset is not available"));
+        }
+
+        // TODO: create a VirtualDMA constructor for kernel_info
+        let mut reader = VirtualDMA::with_vat(
+            &mut self.phys_mem,
+            self.kernel_info.start_block.arch,
+            Win32VirtualTranslate::new(self.kernel_info.start_block.arch, self.sysproc_dtb),
+            &mut self.vat,
+        );
+        let arch = self.kernel_info.start_block.arch;
+
+        let session = reader
+            .virt_read_addr_arch(arch, proc_info.address + self.offsets.eproc_session())?
+            .non_null()
+            .ok_or(Error::Other("process is not attached to a session"))?;
+        trace!("session={:x}", session);
+
+        Ok(reader.virt_read(session + self.offsets.mm_session_id())?)
+    }
+
+    /// Retrieves the list of drivers that are loaded into the given session (e.g. `win32kbase.sys`).
+    ///
+    /// Session drivers are only mapped in the address space of processes that are attached to the session.
+    /// The list is therefore read with the dtb of the first process that belongs to the session
+    /// by walking the `ImageList` of its `_MM_SESSION_SPACE`.
+    ///
+    /// This requires the session offsets which are only available when the offsets
+    /// have been generated from a pdb.
+    pub fn session_module_list(&mut self, session_id: u32) -> Result<Vec<Win32ModuleInfo>> {
+        if self.offsets.mm_session_image_list() == 0
+            || self.offsets.image_entry_data_table_entry() == 0
+        {
+            return Err(Error::Other("session image list offsets are not available"));
+        }
+
+        let proc_info = self
+            .process_info_list()?
+            .into_iter()
+            .find(|proc_info| self.process_session_id(proc_info).ok() == Some(session_id))
+            .ok_or(Error::Other("no process is attached to the given session"))?;
+        trace!("session proxy process: {:?}", proc_info);
+
+        let arch = self.kernel_info.start_block.arch;
+        let session = {
+            let mut reader = VirtualDMA::with_vat(
+                &mut self.phys_mem,
+                arch,
+                Win32VirtualTranslate::new(arch, self.sysproc_dtb),
+                &mut self.vat,
+            );
+            reader.virt_read_addr_arch(arch, proc_info.address + self.offsets.eproc_session())?
+        };
+
+        // session space is only mapped with the dtb of the process
+        let mut reader = VirtualDMA::with_vat(
+            &mut self.phys_mem,
+            arch,
+            Win32VirtualTranslate::new(arch, proc_info.dtb),
+            &mut self.vat,
+        );
+        let list_info = Win32ModuleListInfo::with_base(Address::NULL, arch)?;
+
+        // _IMAGE_ENTRY_IN_SESSION::Link is the first field of the entry
+        let list_head = session + self.offsets.mm_session_image_list();
+        let images = walk_list_entry(
+            &mut reader,
+            arch,
+            list_head,
+            0,
+            self.offsets.list_blink(),
+            MAX_ITER_COUNT,
+        )
+        .collect::<Result<Vec<_>>>()?;
+
+        let mut modules = Vec::new();
+        for image in images.into_iter() {
+            let data_table_entry = reader
+                .virt_read_addr_arch(arch, image + self.offsets.image_entry_data_table_entry())?;
+            trace!("found session image {:x}", data_table_entry);
+
+            if !data_table_entry.is_null() {
+                match list_info.module_info_from_entry(
+                    data_table_entry,
+                    proc_info.address,
+                    &mut reader,
+                    arch,
+                ) {
+                    Ok(module) => modules.push(module),
+                    Err(err) => trace!("unable to read session module: {}", err),
+                }
+            }
+        }
+
+        Ok(modules)
+    }
+
+    /// Resolves an address to the module containing it.
+    ///
+    /// Kernel addresses are looked up in the list of loaded kernel modules (`PsLoadedModuleList`).
+    /// User addresses are looked up in the module list of the process with the given dtb
+    /// (see `process_info_dtb()`), in this case the dtb is required.
+    ///
+    /// If the address is not part of any module the returned location does not contain a module.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memflow::mem::{PhysicalMemory, VirtualTranslate};
+    /// use memflow::types::Address;
+    /// use memflow_win32::win32::Kernel;
+    ///
+    /// fn test<T: PhysicalMemory, V: VirtualTranslate>(kernel: &mut Kernel<T, V>, cr3: Address, rip: Address) {
+    ///     // e.g. `ntdll.dll+0x9d0e4`
+    ///     println!("{}", kernel.symbolize(Some(cr3), rip).unwrap());
+    /// }
+    /// ```
+    pub fn symbolize(&mut self, dtb: Option<Address>, addr: Address) -> Result<SymbolLocation> {
+        let user_end = user_address_end(self.kernel_info.start_block.arch)?;
+        let (pid, proc_info) = if addr < user_end {
+            let dtb = dtb.ok_or(Error::Other(
+                "a dtb is required to symbolize user mode addresses",
+            ))?;
+            let proc_info = self.process_info_dtb(dtb)?;
+            (Some(proc_info.pid), proc_info)
+        } else {
+            (None, self.kernel_process_info()?)
+        };
+
+        let modules = Win32Process::with_kernel_ref(self, proc_info).module_list()?;
+        let module = modules
+            .iter()
+            .find(|module| addr >= module.base && addr < module.base + module.size);
+
+        Ok(SymbolLocation {
+            address: addr,
+            pid,
+            module: module.map(|module| module.name.clone()),
+            offset: match module {
+                Some(module) => addr - module.base,
+                None => addr.as_usize(),
+            },
+        })
     }
 
     /// Constructs a `Win32Process` struct for the targets kernel by borrowing this kernel instance.
@@ -528,3 +1539,899 @@ impl<T: PhysicalMemory, V: VirtualTranslate> fmt::Debug for Kernel<T, V> {
         write!(f, "{:?}", self.kernel_info)
     }
 }
+
+/// Lazy iterator over the EPROCESS list of a `Kernel`.
+///
+/// This iterator is created by `Kernel::eprocess_iter` and `Kernel::eprocess_iter_with_limit`.
+pub struct EProcessIter<T, V> {
+    list: ListEntryIter<VirtualDMA<T, V, Win32VirtualTranslate>>,
+}
+
+impl<T: PhysicalMemory, V: VirtualTranslate> Iterator for EProcessIter<T, V> {
+    type Item = Result<Address>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.next()
+    }
+}
+
+/// Bounded cache of eprocess addresses to their pid and dtb.
+///
+/// Once the cache is full the least recently used entry is evicted.
+#[derive(Debug, Clone, Default)]
+struct DtbCache {
+    entries: BTreeMap<Address, (PID, Address, u64)>,
+    tick: u64,
+}
+
+impl DtbCache {
+    fn get(&mut self, eprocess: Address) -> Option<(PID, Address)> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.entries
+            .get_mut(&eprocess)
+            .map(|(pid, dtb, last_used)| {
+                *last_used = tick;
+                (*pid, *dtb)
+            })
+    }
+
+    fn insert(&mut self, eprocess: Address, pid: PID, dtb: Address) {
+        if self.entries.len() >= DTB_CACHE_SIZE && !self.entries.contains_key(&eprocess) {
+            let lru = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, _, last_used))| *last_used)
+                .map(|(addr, _)| *addr);
+            if let Some(lru) = lru {
+                self.entries.remove(&lru);
+            }
+        }
+
+        self.tick += 1;
+        self.entries.insert(eprocess, (pid, dtb, self.tick));
+    }
+
+    fn remove(&mut self, eprocess: Address) {
+        self.entries.remove(&eprocess);
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Returns the file name component of a (nt or dos) path.
+fn image_file_name(path: &str) -> &str {
+    path.rsplit('\\').next().unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel::Win32Version;
+    use crate::offsets::Win32OffsetTable;
+    use crate::win32::{
+        DummyWin32Builder, DummyWin32Process, Win32ProtectionSigner, DUMMY_OFFSETS,
+        KUSER_SHARED_DATA_X64, MODULE_BASE, NT_PRODUCT_SERVER, SESSION_MODULE_BASE,
+    };
+    use memflow::architecture::x86::x64;
+    use memflow::mem::dummy::DummyMemory;
+    use memflow::types::size;
+
+    fn build_kernel() -> Kernel<DummyMemory, DirectTranslate> {
+        DummyWin32Builder::new()
+            .process(
+                DummyWin32Process::new(1000, "explorer.exe")
+                    .module("explorer.exe", size::mb(4))
+                    .module("ntdll.dll", size::mb(2))
+                    .module("kernel32.dll", size::kb(768)),
+            )
+            .process(
+                DummyWin32Process::new(1004, "averyverylongprocessname.exe")
+                    .module("averyverylongprocessname.exe", size::kb(64)),
+            )
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn eprocess_list() {
+        let mut kernel = build_kernel();
+        let eprocs = kernel.eprocess_list().unwrap();
+        assert_eq!(eprocs.len(), 3);
+        assert_eq!(eprocs[0], kernel.kernel_info.eprocess_base);
+    }
+
+    #[test]
+    fn eprocess_list_corruption() {
+        let mut kernel = build_kernel();
+        let eprocs = kernel.eprocess_list().unwrap();
+
+        // let the last process point back to the second one, the list head is never reached again
+        let link = DUMMY_OFFSETS.eproc_link as usize;
+        let dtb = kernel.kernel_info.start_block.dtb;
+        VirtualDMA::new(&mut kernel.phys_mem, x64::ARCH, x64::new_translator(dtb))
+            .virt_write(eprocs[2] + link, &(eprocs[1] + link).as_u64())
+            .unwrap();
+
+        let result = kernel
+            .eprocess_iter_with_limit(16)
+            .collect::<Result<Vec<_>>>();
+        assert!(matches!(result, Err(Error::ListCorruption(_))));
+    }
+
+    #[test]
+    fn process_info_list() {
+        let mut kernel = build_kernel();
+        let list = kernel.process_info_list().unwrap();
+        assert_eq!(list.len(), 3);
+
+        assert_eq!(list[0].pid, 4);
+        assert_eq!(list[0].name, "System");
+        assert_eq!(list[0].address, kernel.kernel_info.eprocess_base);
+
+        assert_eq!(list[1].pid, 1000);
+        assert_eq!(list[1].name, "explorer.exe");
+        assert_eq!(list[1].dtb, kernel.kernel_info.start_block.dtb);
+        assert_eq!(list[1].exit_status, EXIT_STATUS_STILL_ACTIVE);
+        assert!(list[1].teb.is_some());
+
+        assert_eq!(list[2].pid, 1004);
+        assert_eq!(list[2].name, "averyverylongp");
+    }
+
+    #[test]
+    fn operating_system() {
+        fn pids<O: OperatingSystem>(os: &mut O) -> Vec<PID> {
+            os.process_info_list()
+                .ok()
+                .unwrap()
+                .iter()
+                .map(|process| process.pid())
+                .collect()
+        }
+
+        let mut kernel = build_kernel();
+        assert_eq!(pids(&mut kernel), vec![4, 1000, 1004]);
+
+        let os = &mut kernel as &mut dyn OperatingSystem<ProcessInfoType = _, Error = Error>;
+        assert_eq!(os.process_info_by_pid(1004).unwrap().name, "averyverylongp");
+        assert_eq!(
+            os.process_info_by_name("averyverylongprocessname.exe")
+                .unwrap()
+                .pid,
+            1004
+        );
+        assert!(os.process_info_by_pid(1234).is_err());
+
+        let os_info = os.os_info().unwrap();
+        assert_eq!(os_info.kind, OsKind::Windows);
+        assert_eq!(os_info.version.as_deref(), Some("10.0.19041"));
+        assert_eq!(os_info.arch, x64::ARCH);
+    }
+
+    #[test]
+    fn idle_and_system_process() {
+        let mut kernel = build_kernel();
+
+        let idle = kernel.process_info_pid(0).unwrap();
+        assert_eq!(idle.pid, 0);
+        assert_eq!(idle.name, "Idle");
+        assert_ne!(idle.address, kernel.kernel_info.kernel_base);
+        assert_eq!(idle.dtb, kernel.kernel_info.start_block.dtb);
+        assert!(idle.peb_native.is_null());
+        assert_eq!(kernel.idle_process_info().unwrap().address, idle.address);
+
+        let system = kernel.process_info_pid(4).unwrap();
+        assert_eq!(system.pid, 4);
+        assert_eq!(system.name, "System");
+        assert_eq!(system.address, kernel.kernel_info.eprocess_base);
+        assert_eq!(system.dtb, kernel.kernel_info.start_block.dtb);
+        assert_eq!(
+            kernel.system_process_info().unwrap().address,
+            system.address
+        );
+
+        // the idle process is not part of the active process list
+        assert!(kernel
+            .process_info_list()
+            .unwrap()
+            .iter()
+            .all(|proc_info| proc_info.address != idle.address));
+    }
+
+    #[test]
+    fn idle_process_without_offset() {
+        let mut kernel = build_kernel();
+        kernel.offsets.0.ps_idle_process = 0;
+
+        // the kernel process info is used in place of the idle eprocess
+        let idle = kernel.process_info_pid(0).unwrap();
+        assert_eq!(idle.pid, 0);
+        assert_eq!(idle.name, "Idle");
+        assert_eq!(idle.address, kernel.kernel_info.kernel_base);
+        assert_eq!(idle.dtb, kernel.sysproc_dtb);
+
+        let mut process = Win32Process::with_kernel_ref(&mut kernel, idle);
+        let modules = process.module_list().unwrap();
+        assert_eq!(modules.len(), 1);
+        assert_eq!(modules[0].name(), "ntoskrnl.exe");
+    }
+
+    #[test]
+    fn system_process_modules() {
+        let mut kernel = build_kernel();
+
+        // the peb of the system process is ignored, its modules are the loaded kernel modules
+        let eprocess = kernel.kernel_info.eprocess_base;
+        kernel
+            .kernel_virt_mem()
+            .virt_write(eprocess + DUMMY_OFFSETS.eproc_peb as usize, &0x1000_u64)
+            .unwrap();
+
+        let system = kernel.system_process_info().unwrap();
+        assert!(system.peb_native.is_null());
+        assert_eq!(system.peb_wow64, None);
+        assert_eq!(system.exit_status, EXIT_STATUS_STILL_ACTIVE);
+
+        let mut process = Win32Process::with_kernel_ref(&mut kernel, system);
+        let modules = process.module_list().unwrap();
+        assert_eq!(modules.len(), 1);
+        assert_eq!(modules[0].name(), "ntoskrnl.exe");
+
+        // the dtb of the system process is cached like the one of any other process
+        let kproc_dtb = eprocess + DUMMY_OFFSETS.kproc_dtb as usize;
+        kernel
+            .kernel_virt_mem()
+            .virt_write(kproc_dtb, &0x7a_b000_u64)
+            .unwrap();
+        assert_eq!(
+            kernel.system_process_info().unwrap().dtb,
+            kernel.kernel_info.start_block.dtb
+        );
+    }
+
+    #[test]
+    fn process_info_list_partial() {
+        let mut kernel = build_kernel();
+        let list = kernel.process_info_list_partial().unwrap();
+        assert_eq!(list.len(), 3);
+
+        // the system process is resolved without the kernel module list
+        let system = list[0].as_ref().unwrap();
+        assert_eq!(system.pid, 4);
+        assert_eq!(system.address, kernel.kernel_info.eprocess_base);
+        assert_eq!(list[1].as_ref().unwrap().pid, 1000);
+        assert_eq!(list[2].as_ref().unwrap().pid, 1004);
+    }
+
+    #[test]
+    fn process_info_paged_out() {
+        let mut kernel = build_kernel();
+        let proc_info = kernel.process_info_pid(1000).unwrap();
+
+        // simulate a paged out peb and thread list
+        let dtb = kernel.kernel_info.start_block.dtb;
+        let mut virt_mem =
+            VirtualDMA::new(&mut kernel.phys_mem, x64::ARCH, x64::new_translator(dtb));
+        virt_mem
+            .virt_write(proc_info.address + DUMMY_OFFSETS.eproc_peb as usize, &0u64)
+            .unwrap();
+        virt_mem
+            .virt_write(
+                proc_info.address + DUMMY_OFFSETS.eproc_thread_list as usize,
+                &0u64,
+            )
+            .unwrap();
+        std::mem::drop(virt_mem);
+
+        let proc_info = kernel.process_info_pid(1000).unwrap();
+        assert_eq!(proc_info.name, "explorer.exe");
+        assert_eq!(proc_info.dtb, dtb);
+        assert!(proc_info.peb_native.is_null());
+        assert!(proc_info.ethread.is_null());
+        assert_eq!(proc_info.teb, None);
+        assert_eq!(kernel.process_info_list().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn process_memory_info() {
+        let memory_info = ProcessMemoryInfo {
+            working_set_size: size::mb(24),
+            virtual_size: size::gb(2) + size::kb(4),
+            private_bytes: size::mb(8),
+        };
+        let mut kernel = DummyWin32Builder::new()
+            .process(DummyWin32Process::new(1000, "explorer.exe").memory_info(memory_info))
+            .process(DummyWin32Process::new(1004, "notepad.exe"))
+            .build()
+            .unwrap();
+
+        let proc_info = kernel.process_info_pid(1000).unwrap();
+        assert_eq!(proc_info.memory_info, memory_info);
+        assert_eq!(
+            kernel.process_info_pid(1004).unwrap().memory_info(),
+            ProcessMemoryInfo::default()
+        );
+    }
+
+    #[test]
+    fn kernel_virt_mem() {
+        let mut kernel = build_kernel();
+        let proc_info = kernel.process_info_pid(1000).unwrap();
+
+        let pid_addr = proc_info.address + DUMMY_OFFSETS.eproc_pid as usize;
+        let mut virt_mem = kernel.kernel_virt_mem();
+        assert_eq!(virt_mem.virt_read::<u32>(pid_addr).unwrap(), 1000);
+        virt_mem.virt_write(pid_addr, &1008u32).unwrap();
+
+        assert!(kernel.process_info_pid(1008).is_ok());
+
+        let mut virt_mem = kernel.into_virtual_memory();
+        assert_eq!(virt_mem.virt_read::<u32>(pid_addr).unwrap(), 1008);
+    }
+
+    #[test]
+    fn kernel_exports() {
+        let mut kernel = build_kernel();
+        let kernel_base = kernel.kernel_info.kernel_base;
+
+        let loaded_module_list = kernel.export_address("PsLoadedModuleList").unwrap();
+        let nt_build_number = kernel.read_global::<u32>("NtBuildNumber").unwrap();
+        assert_eq!(nt_build_number, 0xf000_0000 | 19041);
+        assert!(kernel.export_address("KdDebuggerDataBlock").is_err());
+
+        let proc_info = kernel.kernel_process_info().unwrap();
+        let mut process = Win32Process::with_kernel_ref(&mut kernel, proc_info);
+        let modules = process.module_list().unwrap();
+        assert_eq!(modules.len(), 1);
+        assert_eq!(modules[0].name(), "ntoskrnl.exe");
+        assert_eq!(modules[0].base(), kernel_base);
+        assert_eq!(modules[0].size(), kernel.kernel_info.kernel_size);
+
+        // the export table is cached, lookups keep working once the image can no longer be parsed
+        let mut uncached = build_kernel();
+        for kernel in [&mut kernel, &mut uncached].iter_mut() {
+            kernel
+                .kernel_virt_mem()
+                .virt_write_raw(kernel_base, &[0, 0])
+                .unwrap();
+        }
+        assert_eq!(
+            kernel.export_address("PsLoadedModuleList").unwrap(),
+            loaded_module_list
+        );
+        assert_eq!(
+            kernel.read_global::<u32>("NtBuildNumber").unwrap(),
+            nt_build_number
+        );
+        assert!(uncached.export_address("PsLoadedModuleList").is_err());
+    }
+
+    #[test]
+    fn winver() {
+        let mut kernel = DummyWin32Builder::new()
+            .winver(Win32Version::new(10, 0, 19042))
+            .revision(0x508)
+            .build()
+            .unwrap();
+
+        let winver = kernel.winver().unwrap();
+        assert_eq!(
+            winver,
+            Win32VersionInfo {
+                major: 10,
+                minor: 0,
+                build: 19042,
+                revision: 0x508,
+                is_server: false,
+            }
+        );
+        assert_eq!(winver.to_string(), "10.0.19042.1288");
+
+        // the version is read from the kernel in case the scan did not find it
+        kernel.kernel_info.kernel_winver = Win32Version::default();
+        assert_eq!(kernel.winver().unwrap(), winver);
+
+        let mut kernel = DummyWin32Builder::new()
+            .product_type(NT_PRODUCT_SERVER)
+            .build()
+            .unwrap();
+        let winver = kernel.winver().unwrap();
+        assert!(winver.is_server);
+        assert_eq!(winver.to_string(), "10.0.19041.0 (server)");
+    }
+
+    #[test]
+    fn eprocess_list_offsets() {
+        // ActiveProcessLinks directly follows UniqueProcessId, e.g. on windows 10 1809
+        let offsets_1809 = Win32OffsetTable {
+            eproc_pid: 0x2e0,
+            eproc_link: 0x2e8,
+            eproc_name: 0x180,
+            ..DUMMY_OFFSETS
+        };
+
+        for &offsets in [DUMMY_OFFSETS, offsets_1809].iter() {
+            let mut kernel = DummyWin32Builder::new()
+                .offsets(offsets)
+                .process(
+                    DummyWin32Process::new(1000, "explorer.exe")
+                        .module("explorer.exe", size::mb(4)),
+                )
+                .process(
+                    DummyWin32Process::new(1004, "notepad.exe")
+                        .module("notepad.exe", size::kb(256)),
+                )
+                .build()
+                .unwrap();
+
+            let eprocs = kernel.eprocess_list().unwrap();
+            assert_eq!(eprocs.len(), 3);
+            assert_eq!(eprocs[0], kernel.kernel_info.eprocess_base);
+
+            // entering the list through PsActiveProcessHead yields the same eprocesses
+            let link = offsets.eproc_link as usize;
+            let blink = offsets.list_blink as usize;
+            let mut virt_mem = kernel.kernel_virt_mem();
+            let head = virt_mem.virt_read_addr64(eprocs[0] + link + blink).unwrap();
+            let list = walk_list_entry(&mut virt_mem, x64::ARCH, head, link, blink, 16)
+                .collect::<Result<Vec<_>>>()
+                .unwrap();
+            assert_eq!(list, eprocs);
+
+            let names = kernel
+                .process_info_list()
+                .unwrap()
+                .into_iter()
+                .map(|proc_info| (proc_info.pid, proc_info.name))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                names,
+                vec![
+                    (4, "System".to_string()),
+                    (1000, "explorer.exe".to_string()),
+                    (1004, "notepad.exe".to_string())
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn process_info_list_filter() {
+        let mut kernel = build_kernel();
+
+        let mut seen = Vec::new();
+        let list = kernel
+            .process_info_list_filter(|pid, name| {
+                seen.push(pid);
+                name == "averyverylongp"
+            })
+            .unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].pid, 1004);
+        assert_eq!(seen.len(), 3);
+
+        assert_eq!(kernel.process_info_pid(1000).unwrap().name, "explorer.exe");
+        assert!(kernel
+            .process_info_list_filter(|_, _| false)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn process_info_dtb() {
+        let mut kernel = build_kernel();
+        let proc_info = kernel.process_info_pid(1004).unwrap();
+
+        // give the process its own dtb
+        let kproc_dtb = proc_info.address + DUMMY_OFFSETS.kproc_dtb as usize;
+        let dtb = kernel.kernel_info.start_block.dtb;
+        VirtualDMA::new(&mut kernel.phys_mem, x64::ARCH, x64::new_translator(dtb))
+            .virt_write(kproc_dtb, &0x7a_b000_u64)
+            .unwrap();
+
+        // the pcid and the no-flush bit of cr3 are ignored
+        let cr3 = Address::from(0x8000_0000_007a_b005_u64);
+        let proc_info = kernel.process_info_dtb(cr3).unwrap();
+        assert_eq!(proc_info.pid, 1004);
+        assert_eq!(proc_info.dtb, Address::from(0x7a_b000));
+
+        assert!(kernel.process_info_dtb(Address::from(0x7a_c000)).is_err());
+    }
+
+    #[test]
+    fn dtb_cache() {
+        let mut kernel = build_kernel();
+        let proc_info = kernel.process_info_pid(1004).unwrap();
+        let eprocess = proc_info.address;
+
+        let kproc_dtb = eprocess + DUMMY_OFFSETS.kproc_dtb as usize;
+        let eproc_pid = eprocess + DUMMY_OFFSETS.eproc_pid as usize;
+        let dtb = kernel.kernel_info.start_block.dtb;
+        let write = |kernel: &mut Kernel<DummyMemory, DirectTranslate>, pid: u32, pdtb: u64| {
+            let mut virt_mem =
+                VirtualDMA::new(&mut kernel.phys_mem, x64::ARCH, x64::new_translator(dtb));
+            virt_mem.virt_write(eproc_pid, &pid).unwrap();
+            virt_mem.virt_write(kproc_dtb, &pdtb).unwrap();
+        };
+
+        // the cached dtb is used as long as the pid did not change
+        write(&mut kernel, 1004, 0x7a_b000);
+        let proc_info = kernel.process_info_from_eprocess(eprocess).unwrap();
+        assert_eq!(proc_info.dtb, dtb);
+
+        kernel.clear_dtb_cache();
+        let proc_info = kernel.process_info_from_eprocess(eprocess).unwrap();
+        assert_eq!(proc_info.dtb, Address::from(0x7a_b000));
+
+        // the eprocess is reused by another process
+        write(&mut kernel, 1010, 0x7a_c000);
+        let proc_info = kernel.process_info_from_eprocess(eprocess).unwrap();
+        assert_eq!(proc_info.pid, 1010);
+        assert_eq!(proc_info.dtb, Address::from(0x7a_c000));
+    }
+
+    #[test]
+    fn shared_kernel() {
+        let mut kernel = build_kernel().into_shared();
+        let mut other = kernel.clone();
+        assert_eq!(kernel.phys_mem.handle_count(), 2);
+
+        let eprocs = std::thread::spawn(move || other.eprocess_list().unwrap())
+            .join()
+            .unwrap();
+        assert_eq!(eprocs, kernel.eprocess_list().unwrap());
+
+        // writes through one clone are visible in all other clones
+        let proc_info = kernel.process_info_pid(1000).unwrap();
+        let mut other = kernel.clone();
+        let name = proc_info.address + DUMMY_OFFSETS.eproc_name as usize;
+        let dtb = kernel.kernel_info.start_block.dtb;
+        VirtualDMA::new(&mut kernel.phys_mem, x64::ARCH, x64::new_translator(dtb))
+            .virt_write_raw(name, b"notepad.exe\0")
+            .unwrap();
+        assert_eq!(other.process_info_pid(1000).unwrap().name, "notepad.exe");
+    }
+
+    #[test]
+    fn process_protection() {
+        let mut kernel = DummyWin32Builder::new()
+            .process(
+                DummyWin32Process::new(700, "lsass.exe")
+                    .module("lsass.exe", size::kb(64))
+                    .protection(Win32Protection(0x41))
+                    .integrity_level(IntegrityLevel::System),
+            )
+            .process(
+                DummyWin32Process::new(1000, "explorer.exe").module("explorer.exe", size::mb(4)),
+            )
+            .build()
+            .unwrap();
+
+        let lsass = kernel.process_info_pid(700).unwrap();
+        assert!(lsass.protection.is_protected_light());
+        assert_eq!(lsass.protection.signer(), Win32ProtectionSigner::Lsa);
+        assert_eq!(lsass.integrity_level, Some(IntegrityLevel::System));
+
+        let explorer = kernel.process_info_pid(1000).unwrap();
+        assert!(!explorer.protection.is_protected());
+        assert_eq!(explorer.integrity_level, None);
+    }
+
+    #[test]
+    fn process_info_by_path() {
+        let mut kernel = DummyWin32Builder::new()
+            .process(
+                DummyWin32Process::new(600, "lsass.exe")
+                    .image_path("\\Device\\HarddiskVolume3\\Windows\\System32\\lsass.exe")
+                    .module("lsass.exe", size::kb(64)),
+            )
+            .process(
+                DummyWin32Process::new(1200, "lsass.exe")
+                    .image_path("\\Device\\HarddiskVolume3\\Users\\Public\\lsass.exe")
+                    .module("lsass.exe", size::kb(64)),
+            )
+            .process(
+                DummyWin32Process::new(1300, "notepad.exe").module("notepad.exe", size::kb(64)),
+            )
+            .build()
+            .unwrap();
+
+        let proc_info = kernel
+            .process_info_by_path("\\device\\harddiskvolume3\\users\\public\\LSASS.EXE")
+            .unwrap();
+        assert_eq!(proc_info.pid, 1200);
+        assert_eq!(
+            kernel.process_image_path(&proc_info).unwrap(),
+            Win32ImagePath::Device("\\Device\\HarddiskVolume3\\Users\\Public\\lsass.exe".into())
+        );
+
+        // falls back to the path of the main module
+        let proc_info = kernel
+            .process_info_by_path("C:\\Windows\\System32\\notepad.exe")
+            .unwrap();
+        assert_eq!(proc_info.pid, 1300);
+        assert_eq!(
+            kernel.process_image_path(&proc_info).unwrap(),
+            Win32ImagePath::Dos("C:\\Windows\\System32\\notepad.exe".into())
+        );
+
+        // the path has to be supplied in the form it is available in
+        assert!(kernel
+            .process_info_by_path("\\Device\\HarddiskVolume3\\Windows\\System32\\notepad.exe")
+            .is_err());
+        assert_eq!(
+            kernel.process_info_by_path("C:\\Windows\\System32\\lsass.exe"),
+            Err(Error::ProcessInfo)
+        );
+    }
+
+    #[test]
+    fn process_info_blank_name() {
+        let mut kernel = DummyWin32Builder::new()
+            .process(
+                DummyWin32Process::new(800, "")
+                    .image_path("\\Device\\HarddiskVolume3\\Windows\\System32\\smss.exe"),
+            )
+            .process(DummyWin32Process::new(900, " ").module("fontdrvhost.exe", size::kb(64)))
+            .process(DummyWin32Process::new(1000, ""))
+            .build()
+            .unwrap();
+
+        assert_eq!(kernel.process_info_pid(800).unwrap().name, "smss.exe");
+        assert_eq!(
+            kernel.process_info_pid(900).unwrap().name,
+            "fontdrvhost.exe"
+        );
+        assert_eq!(kernel.process_info_pid(1000).unwrap().name, "");
+    }
+
+    #[test]
+    fn process_info_image_path() {
+        let mut kernel = DummyWin32Builder::new()
+            .process(
+                DummyWin32Process::new(600, "SearchProtocolHost.exe").image_path(
+                    "\\Device\\HarddiskVolume3\\Windows\\System32\\SearchProtocolHost.exe",
+                ),
+            )
+            .process(
+                DummyWin32Process::new(700, "SearchProtocolHelper.exe").image_path(
+                    "\\Device\\HarddiskVolume3\\Windows\\System32\\SearchProtocolHelper.exe",
+                ),
+            )
+            .process(
+                DummyWin32Process::new(800, "svchost.exe")
+                    .image_path("\\Device\\HarddiskVolume3\\Windows\\System32\\svchost.exe")
+                    .module("svchost.exe", size::kb(64)),
+            )
+            .process(
+                DummyWin32Process::new(900, "svchost.exe")
+                    .image_path("\\Device\\HarddiskVolume3\\Windows\\System32\\svchost.exe")
+                    .module("svchost.exe", size::kb(64)),
+            )
+            .build()
+            .unwrap();
+
+        // the processes do not have any modules and can only be found by their image path
+        assert_eq!(
+            kernel.process_info("searchprotocolhost.exe").unwrap().pid,
+            600
+        );
+        assert_eq!(
+            kernel.process_info("SearchProtocolHelper.exe").unwrap().pid,
+            700
+        );
+        assert!(kernel.process_info("SearchProtocolHandler.exe").is_err());
+
+        // ambiguous image paths fall back to the module list
+        assert_eq!(kernel.process_info("svchost.exe").unwrap().pid, 800);
+    }
+
+    #[test]
+    fn symbolize() {
+        let mut kernel = build_kernel();
+        let proc_info = kernel.process_info_pid(1000).unwrap();
+
+        // give the process its own dtb that shares the kernel half of the address space
+        let kernel_dtb = kernel.kernel_info.start_block.dtb;
+        let dtb =
+            kernel
+                .phys_mem
+                .alloc_dtb_const_base(Address::from(MODULE_BASE), size::kb(4), &[]);
+        let mut pml4 = vec![0u8; size::kb(2)];
+        kernel
+            .phys_mem
+            .phys_read_raw_into((kernel_dtb + size::kb(2)).into(), &mut pml4)
+            .unwrap();
+        kernel
+            .phys_mem
+            .phys_write_raw((dtb + size::kb(2)).into(), &pml4)
+            .unwrap();
+        VirtualDMA::new(
+            &mut kernel.phys_mem,
+            x64::ARCH,
+            x64::new_translator(kernel_dtb),
+        )
+        .virt_write(
+            proc_info.address + DUMMY_OFFSETS.kproc_dtb as usize,
+            &dtb.as_u64(),
+        )
+        .unwrap();
+
+        let addr = Address::from(MODULE_BASE) + size::mb(16) + 0x1234;
+        let location = kernel.symbolize(Some(dtb), addr).unwrap();
+        assert_eq!(location.pid, Some(1000));
+        assert_eq!(location.module.as_deref(), Some("ntdll.dll"));
+        assert_eq!(location.offset, 0x1234);
+        assert_eq!(location.to_string(), "ntdll.dll+0x1234");
+
+        // the gap between explorer.exe and ntdll.dll
+        let addr = Address::from(MODULE_BASE) + size::mb(8);
+        let location = kernel.symbolize(Some(dtb), addr).unwrap();
+        assert_eq!(location.module, None);
+        assert_eq!(location.to_string(), format!("{:#x}", addr.as_u64()));
+
+        // user mode addresses can only be resolved in the context of a process
+        assert!(kernel.symbolize(None, addr).is_err());
+
+        // kernel addresses are resolved against PsLoadedModuleList regardless of the dtb
+        let addr = kernel.export_address("PsLoadedModuleList").unwrap();
+        let offset = addr - kernel.kernel_info.kernel_base;
+        for dtb in [None, Some(dtb)].iter() {
+            let location = kernel.symbolize(*dtb, addr).unwrap();
+            assert_eq!(location.pid, None);
+            assert_eq!(location.module.as_deref(), Some("ntoskrnl.exe"));
+            assert_eq!(location.offset, offset);
+            assert_eq!(location.to_string(), format!("ntoskrnl.exe+{:#x}", offset));
+        }
+
+        // kernel addresses past the end of ntoskrnl.exe do not belong to any driver
+        let addr = kernel.kernel_info.kernel_base + size::gb(1);
+        let location = kernel.symbolize(None, addr).unwrap();
+        assert_eq!(location.pid, None);
+        assert_eq!(location.module, None);
+    }
+
+    #[test]
+    fn process_parameters() {
+        let mut kernel = DummyWin32Builder::new()
+            .process(
+                DummyWin32Process::new(2000, "cmd.exe")
+                    .module("cmd.exe", size::kb(256))
+                    .command_line("\"C:\\Windows\\System32\\cmd.exe\" /k echo")
+                    .environment("=C:=C:\\Users\\user")
+                    .environment("PATH=C:\\Windows\\System32;C:\\Windows"),
+            )
+            .build()
+            .unwrap();
+
+        let proc_info = kernel.process_info_pid(2000).unwrap();
+        let params = kernel.process_parameters(proc_info.address).unwrap();
+        assert_eq!(
+            params.command_line,
+            "\"C:\\Windows\\System32\\cmd.exe\" /k echo"
+        );
+        assert_eq!(
+            params.environment,
+            vec![
+                "=C:=C:\\Users\\user".to_string(),
+                "PATH=C:\\Windows\\System32;C:\\Windows".to_string()
+            ]
+        );
+        assert_eq!(params.window_title, "");
+        assert_eq!(params.standard_input, 0);
+
+        // processes of build_kernel() do not have any process parameters
+        let mut kernel = build_kernel();
+        let proc_info = kernel.process_info_pid(1000).unwrap();
+        assert!(kernel.process_parameters(proc_info.address).is_err());
+    }
+
+    #[test]
+    fn unterminated_environment() {
+        let mut kernel = DummyWin32Builder::new()
+            .process(
+                DummyWin32Process::new(2000, "cmd.exe")
+                    .module("cmd.exe", size::kb(256))
+                    .command_line("cmd.exe")
+                    .environment("=C:=C:\\Users\\user"),
+            )
+            .build()
+            .unwrap();
+
+        // EnvironmentSize ends in the middle of the first entry
+        let proc_info = kernel.process_info_pid(2000).unwrap();
+        let params = kernel.process_parameters(proc_info.address).unwrap();
+        kernel
+            .kernel_virt_mem()
+            .virt_write(params.address + 0x3f0, &8u64)
+            .unwrap();
+
+        let params = kernel.process_parameters(proc_info.address).unwrap();
+        assert_eq!(params.environment, vec!["=C:=".to_string()]);
+    }
+
+    #[test]
+    fn boot_time() {
+        let mut kernel = build_kernel();
+        let kuser_shared_data = Address::from(KUSER_SHARED_DATA_X64);
+
+        // KSYSTEM_TIME { LowPart, High1Time, High2Time }
+        let interrupt_time = 0x0000_0012_3456_7890_u64;
+        let system_time = 0x01d6_9a2b_c3d4_e5f6_u64;
+        let mut virt_mem = kernel.kernel_virt_mem();
+        for &(offset, time) in [(0x8, interrupt_time), (0x14, system_time)].iter() {
+            let parts = [time as u32, (time >> 32) as u32, (time >> 32) as u32];
+            virt_mem
+                .virt_write(kuser_shared_data + offset, &parts)
+                .unwrap();
+        }
+
+        assert_eq!(kernel.system_time().unwrap(), system_time);
+        assert_eq!(kernel.boot_time().unwrap(), system_time - interrupt_time);
+
+        let shared_data = kernel.kuser_shared_data().unwrap();
+        assert_eq!(shared_data.interrupt_time, interrupt_time);
+        assert_eq!(shared_data.nt_major_version, 10);
+        assert_eq!(shared_data.nt_minor_version, 0);
+        assert!(!shared_data.is_server());
+
+        // torn reads are detected
+        kernel
+            .kernel_virt_mem()
+            .virt_write(kuser_shared_data + 0x14 + 8, &0u32)
+            .unwrap();
+        assert!(kernel.system_time().is_err());
+    }
+
+    #[test]
+    fn current_process() {
+        let mut kernel = DummyWin32Builder::new()
+            .process(DummyWin32Process::new(1234, "notepad.exe"))
+            .process(DummyWin32Process::new(5678, "explorer.exe"))
+            .processor(5678)
+            .processor(4)
+            .build()
+            .unwrap();
+
+        let proc_info = kernel.current_process(0).unwrap();
+        assert_eq!(proc_info.pid, 5678);
+        assert_eq!(proc_info.name, "explorer.exe");
+        assert_eq!(kernel.current_process(1).unwrap().pid, 4);
+
+        // processors that do not exist have an empty slot
+        assert!(kernel.current_process(2).is_err());
+
+        // KiProcessorBlock is only available when the offsets have been resolved
+        assert!(build_kernel().current_process(0).is_err());
+
+        assert!(DummyWin32Builder::new().processor(1234).build().is_err());
+    }
+
+    #[test]
+    fn session_module_list() {
+        let mut kernel = DummyWin32Builder::new()
+            .process(DummyWin32Process::new(500, "wininit.exe").session(0))
+            .process(DummyWin32Process::new(600, "winlogon.exe").session(1))
+            .process(DummyWin32Process::new(700, "explorer.exe").session(1))
+            .session_module("win32k.sys", size::kb(512))
+            .session_module("win32kbase.sys", size::mb(2))
+            .build()
+            .unwrap();
+
+        let proc_info = kernel.process_info("winlogon.exe").unwrap();
+        assert_eq!(kernel.process_session_id(&proc_info).unwrap(), 1);
+        let proc_info = kernel.process_info("wininit.exe").unwrap();
+        assert_eq!(kernel.process_session_id(&proc_info).unwrap(), 0);
+
+        let modules = kernel.session_module_list(1).unwrap();
+        assert_eq!(modules.len(), 2);
+        assert_eq!(modules[1].name, "win32kbase.sys");
+        assert_eq!(modules[1].size, size::mb(2));
+        assert_eq!(modules[0].base, Address::from(SESSION_MODULE_BASE));
+
+        assert!(kernel.session_module_list(2).is_err());
+    }
+}
@@ -1,19 +1,25 @@
 use std::prelude::v1::*;
 
-use super::{Kernel, KernelInfo};
-use crate::error::Result;
+use super::{rank_dtbs, Kernel, KernelInfo};
+use crate::error::{Error, Result};
+use crate::kernel::{start_block, StartBlock, StartBlockSource};
 use crate::offsets::Win32Offsets;
 
 #[cfg(feature = "symstore")]
 use crate::offsets::SymbolStore;
 
-use memflow::architecture::ArchitectureObj;
+#[cfg(feature = "std")]
+use crate::offsets::Win32OffsetsCache;
+
+use memflow::architecture::{x86, ArchitectureObj};
 use memflow::mem::{
     CachedMemoryAccess, CachedVirtualTranslate, DefaultCacheValidator, DirectTranslate,
     PhysicalMemory, VirtualTranslate,
 };
 use memflow::types::Address;
 
+use log::info;
+
 /// Builder for a Windows Kernel structure.
 ///
 /// This function encapsulates the entire setup process for a Windows target
@@ -113,12 +119,17 @@ pub struct KernelBuilder<T, TK, VK> {
     connector: T,
 
     arch: Option<ArchitectureObj>,
+    arch_override: Option<ArchitectureObj>,
     kernel_hint: Option<Address>,
     dtb: Option<Address>,
+    redetect_arch: bool,
 
     #[cfg(feature = "symstore")]
     symbol_store: Option<SymbolStore>,
 
+    #[cfg(feature = "std")]
+    offsets_cache: Option<Win32OffsetsCache>,
+
     build_page_cache: Box<dyn FnOnce(T, ArchitectureObj) -> TK>,
     build_vat_cache: Box<dyn FnOnce(DirectTranslate, ArchitectureObj) -> VK>,
 }
@@ -132,12 +143,17 @@ where
             connector,
 
             arch: None,
+            arch_override: None,
             kernel_hint: None,
             dtb: None,
+            redetect_arch: false,
 
             #[cfg(feature = "symstore")]
             symbol_store: Some(SymbolStore::default()),
 
+            #[cfg(feature = "std")]
+            offsets_cache: None,
+
             build_page_cache: Box::new(|connector, _| connector),
             build_vat_cache: Box::new(|vat, _| vat),
         }
@@ -152,17 +168,53 @@ where
 {
     pub fn build(mut self) -> Result<Kernel<TK, VK>> {
         // find kernel_info
-        let mut kernel_scanner = KernelInfo::scanner(&mut self.connector);
-        if let Some(arch) = self.arch {
-            kernel_scanner = kernel_scanner.arch(arch);
+        let mut kernel_info = match self.scan_kernel_info(self.arch) {
+            Err(err)
+                if self.redetect_arch
+                    && (matches!(
+                        err,
+                        Error::InvalidArchitecture { .. }
+                            | Error::Core(memflow::error::Error::InvalidArchitecture { .. })
+                    ) || !self.arch_hint_matches()) =>
+            {
+                info!("{}, redetecting the architecture", err);
+                self.redetect_kernel_info()?
+            }
+            result => result?,
+        };
+
+        // correct a misdetected architecture before anything is built on top of it
+        if let Some(arch) = self.arch_override {
+            if !x86::is_x86_arch(arch) {
+                return Err(Error::InvalidArchitecture { bits: arch.bits() });
+            }
+            info!(
+                "overriding arch={:?} with arch={:?}",
+                kernel_info.start_block.arch, arch
+            );
+            kernel_info.start_block.arch = arch;
         }
-        if let Some(kernel_hint) = self.kernel_hint {
-            kernel_scanner = kernel_scanner.kernel_hint(kernel_hint);
-        }
-        if let Some(dtb) = self.dtb {
-            kernel_scanner = kernel_scanner.dtb(dtb);
+
+        // the dtb of an inconsistent start block might be stale,
+        // pick the candidate that is able to translate the most known kernel addresses
+        if self.dtb.is_none() {
+            if let Err(err) = kernel_info.start_block.validate(&mut self.connector) {
+                info!("start block is unreliable: {}", err);
+
+                let mut candidates = vec![kernel_info.start_block.dtb];
+                if let Ok(start_block) =
+                    start_block::find_fallback(&mut self.connector, kernel_info.start_block.arch)
+                {
+                    candidates.push(start_block.dtb);
+                }
+
+                let ranked = rank_dtbs(&mut self.connector, &kernel_info, &candidates);
+                if let Some(&(dtb, score)) = ranked.first() {
+                    info!("using dtb={:x} with score {}", dtb, score);
+                    kernel_info.start_block.dtb = dtb;
+                }
+            }
         }
-        let kernel_info = kernel_scanner.scan()?;
 
         // acquire offsets from the symbol store
         let offsets = self.build_offsets(&kernel_info)?;
@@ -184,20 +236,86 @@ where
         ))
     }
 
-    #[cfg(feature = "symstore")]
-    fn build_offsets(&self, kernel_info: &KernelInfo) -> Result<Win32Offsets> {
-        let mut builder = Win32Offsets::builder();
-        if let Some(store) = &self.symbol_store {
-            builder = builder.symbol_store(store.clone());
-        } else {
-            builder = builder.no_symbol_store();
+    fn scan_kernel_info(&mut self, arch: Option<ArchitectureObj>) -> Result<KernelInfo> {
+        let mut kernel_scanner = KernelInfo::scanner(&mut self.connector);
+        if let Some(arch) = arch {
+            kernel_scanner = kernel_scanner.arch(arch);
         }
-        builder.kernel_info(kernel_info).build()
+        if let Some(kernel_hint) = self.kernel_hint {
+            kernel_scanner = kernel_scanner.kernel_hint(kernel_hint);
+        }
+        if let Some(dtb) = self.dtb {
+            kernel_scanner = kernel_scanner.dtb(dtb);
+        }
+        kernel_scanner.scan()
+    }
+
+    /// Checks if the start block of the architecture hint matches the paging structures in memory.
+    fn arch_hint_matches(&mut self) -> bool {
+        let arch = match self.arch {
+            Some(arch) => arch,
+            None => return true,
+        };
+
+        let start_block = match self.dtb {
+            Some(dtb) => StartBlock {
+                arch,
+                kernel_hint: self.kernel_hint.unwrap_or(Address::NULL),
+                dtb,
+                source: StartBlockSource::UserSupplied,
+            },
+            None => match start_block::find(&mut self.connector, Some(arch)) {
+                Ok(start_block) => start_block,
+                Err(_) => return false,
+            },
+        };
+        start_block.validate(&mut self.connector).is_ok()
+    }
+
+    /// Scans for the kernel again while ignoring the architecture hint.
+    ///
+    /// A user supplied dtb is kept, in this case the architecture is derived
+    /// from the paging structures the dtb points to.
+    fn redetect_kernel_info(&mut self) -> Result<KernelInfo> {
+        if let Some(dtb) = self.dtb {
+            let kernel_hint = self.kernel_hint.unwrap_or(Address::NULL);
+            for &arch in [x86::x64::ARCH, x86::x32_pae::ARCH, x86::x32::ARCH].iter() {
+                let start_block = StartBlock {
+                    arch,
+                    kernel_hint,
+                    dtb,
+                    source: StartBlockSource::UserSupplied,
+                };
+                if start_block.validate(&mut self.connector).is_ok() {
+                    info!("dtb={:x} matches arch={:?}", dtb, arch);
+                    return self.scan_kernel_info(Some(arch));
+                }
+            }
+        }
+
+        self.scan_kernel_info(None)
     }
 
-    #[cfg(not(feature = "symstore"))]
     fn build_offsets(&self, kernel_info: &KernelInfo) -> Result<Win32Offsets> {
-        Win32Offsets::builder().kernel_info(&kernel_info).build()
+        let mut builder = Win32Offsets::builder();
+
+        #[cfg(feature = "symstore")]
+        {
+            if let Some(store) = &self.symbol_store {
+                builder = builder.symbol_store(store.clone());
+            } else {
+                builder = builder.no_symbol_store();
+            }
+        }
+
+        #[cfg(feature = "std")]
+        {
+            if let Some(cache) = &self.offsets_cache {
+                builder = builder.offsets_cache(cache.clone());
+            }
+        }
+
+        builder.kernel_info(kernel_info).build()
     }
 
     pub fn arch(mut self, arch: ArchitectureObj) -> Self {
@@ -205,6 +323,33 @@ where
         self
     }
 
+    /// Overrides the architecture of the kernel after it has been found.
+    ///
+    /// Unlike `arch()`, which is only used as a hint while scanning for the kernel,
+    /// the given architecture replaces the detected one in `KernelInfo::start_block`
+    /// and is used for constructing the caches, the virtual address translation and the offsets.
+    /// This can be used to correct a misdetected architecture on unusual dumps.
+    ///
+    /// Only the x86 family of architectures is supported, `build()` will fail with
+    /// an `Error::InvalidArchitecture` for all other architectures.
+    pub fn arch_override(mut self, arch: ArchitectureObj) -> Self {
+        self.arch_override = Some(arch);
+        self
+    }
+
+    /// Re-scans the start block with automatic architecture detection
+    /// in case the kernel scan fails with an `Error::InvalidArchitecture`
+    /// or in case it fails and the start block of the architecture passed to `arch()`
+    /// does not pass `StartBlock::validate()`.
+    ///
+    /// The architecture passed to `arch()` is ignored for the second scan.
+    /// If a dtb has been supplied via `dtb()` the architecture is derived from its paging structures.
+    /// This allows recovering from a wrong architecture hint without reconstructing the builder.
+    pub fn redetect_arch(mut self) -> Self {
+        self.redetect_arch = true;
+        self
+    }
+
     pub fn kernel_hint(mut self, kernel_hint: Address) -> Self {
         self.kernel_hint = Some(kernel_hint);
         self
@@ -262,12 +407,40 @@ where
         self
     }
 
+    /// Configures a cache for the offsets that can be shared between multiple kernels.
+    ///
+    /// This allows multiple kernels of different windows builds to be constructed
+    /// without building the offsets for the same pdb multiple times.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memflow::mem::PhysicalMemory;
+    /// use memflow_win32::offsets::Win32OffsetsCache;
+    /// use memflow_win32::win32::Kernel;
+    ///
+    /// fn test<T: PhysicalMemory>(connector: T, cache: &Win32OffsetsCache) {
+    ///     let _kernel = Kernel::builder(connector)
+    ///         .offsets_cache(cache.clone())
+    ///         .build()
+    ///         .unwrap();
+    /// }
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn offsets_cache(mut self, offsets_cache: Win32OffsetsCache) -> Self {
+        self.offsets_cache = Some(offsets_cache);
+        self
+    }
+
     /// Creates the Kernel structure with default caching enabled.
     ///
     /// If this option is specified, the Kernel structure is generated
     /// with a (page level cache)[../index.html] with default settings.
     /// On top of the page level cache a [vat cache](../index.html) will be setupped.
     ///
+    /// Both caches are owned by the Kernel and shared with all process readers created from it.
+    /// Pages that are mapped into multiple processes (e.g. system dlls) are therefore only read once.
+    ///
     /// # Examples
     ///
     /// ```
@@ -292,12 +465,17 @@ where
             connector: self.connector,
 
             arch: self.arch,
+            arch_override: self.arch_override,
             kernel_hint: self.kernel_hint,
             dtb: self.dtb,
+            redetect_arch: self.redetect_arch,
 
             #[cfg(feature = "symstore")]
             symbol_store: self.symbol_store,
 
+            #[cfg(feature = "std")]
+            offsets_cache: self.offsets_cache,
+
             build_page_cache: Box::new(|connector, arch| {
                 CachedMemoryAccess::builder(connector)
                     .arch(arch)
@@ -347,12 +525,17 @@ where
             connector: self.connector,
 
             arch: self.arch,
+            arch_override: self.arch_override,
             kernel_hint: self.kernel_hint,
             dtb: self.dtb,
+            redetect_arch: self.redetect_arch,
 
             #[cfg(feature = "symstore")]
             symbol_store: self.symbol_store,
 
+            #[cfg(feature = "std")]
+            offsets_cache: self.offsets_cache,
+
             build_page_cache: Box::new(func),
             build_vat_cache: self.build_vat_cache,
         }
@@ -392,12 +575,17 @@ where
             connector: self.connector,
 
             arch: self.arch,
+            arch_override: self.arch_override,
             kernel_hint: self.kernel_hint,
             dtb: self.dtb,
+            redetect_arch: self.redetect_arch,
 
             #[cfg(feature = "symstore")]
             symbol_store: self.symbol_store,
 
+            #[cfg(feature = "std")]
+            offsets_cache: self.offsets_cache,
+
             build_page_cache: self.build_page_cache,
             build_vat_cache: Box::new(func),
         }
@@ -407,3 +595,59 @@ where
     // kernel_info_builder()
     // offset_builder()
 }
+
+#[cfg(all(test, feature = "embed_offsets"))]
+mod tests {
+    use super::*;
+    use crate::win32::DummyWin32Builder;
+    use memflow::architecture::x86::{x32, x32_pae, x64};
+    use memflow::mem::PhysicalMemory;
+
+    #[test]
+    #[cfg(feature = "embed_offsets")]
+    fn build_with_wrong_arch_hint() {
+        let kernel = DummyWin32Builder::new().build().unwrap();
+        let kernel_info = kernel.kernel_info.clone();
+        let dtb = kernel_info.start_block.dtb;
+        let mut mem = kernel.destroy();
+
+        // windows maps the pml4 into itself
+        mem.phys_write((dtb + 0x1ed * 8).into(), &(dtb.as_u64() | 0x63))
+            .unwrap();
+
+        let kernel = KernelBuilder::new(mem)
+            .arch(x32::ARCH)
+            .dtb(dtb)
+            .kernel_hint(kernel_info.kernel_base)
+            .redetect_arch()
+            .build()
+            .unwrap();
+        assert_eq!(kernel.kernel_info.start_block.arch, x64::ARCH);
+        assert_eq!(kernel.kernel_info.kernel_base, kernel_info.kernel_base);
+        assert_eq!(kernel.kernel_info.eprocess_base, kernel_info.eprocess_base);
+        assert_eq!(kernel.kernel_info.kernel_winver.build_number(), 19041);
+    }
+
+    #[test]
+    #[cfg(feature = "embed_offsets")]
+    fn build_with_arch_override() {
+        let kernel = DummyWin32Builder::new().build().unwrap();
+        let kernel_info = kernel.kernel_info.clone();
+        let dtb = kernel_info.start_block.dtb;
+        let mem = kernel.destroy();
+
+        let kernel = KernelBuilder::new(mem)
+            .arch(x64::ARCH)
+            .dtb(dtb)
+            .kernel_hint(kernel_info.kernel_base)
+            .arch_override(x32_pae::ARCH)
+            .build()
+            .unwrap();
+        assert_eq!(kernel.kernel_info.start_block.arch, x32_pae::ARCH);
+        assert_eq!(kernel.kernel_info.kernel_base, kernel_info.kernel_base);
+        assert_eq!(kernel.kernel_info.eprocess_base, kernel_info.eprocess_base);
+
+        // the offsets are picked for the overridden architecture
+        assert_eq!(kernel.offsets.list_blink(), 4);
+    }
+}
@@ -1,13 +1,17 @@
-use crate::error::Result;
-use crate::kernel::{self, StartBlock};
+use std::prelude::v1::*;
+
+use crate::error::{Error, Result};
+use crate::kernel::{self, StartBlock, StartBlockSource};
 use crate::kernel::{Win32GUID, Win32Version};
 
 use log::{info, warn};
 
-use memflow::architecture::ArchitectureObj;
-use memflow::mem::{DirectTranslate, PhysicalMemory, VirtualDMA};
+use memflow::architecture::{x86, ArchitectureObj, ScopedVirtualTranslate};
+use memflow::mem::{DirectTranslate, PhysicalMemory, VirtualDMA, VirtualMemory};
 use memflow::types::Address;
 
+use pelite::{pe64::exports::Export, PeView};
+
 use super::Win32VirtualTranslate;
 
 #[derive(Debug, Clone)]
@@ -48,6 +52,84 @@ impl<T: PhysicalMemory> KernelInfoScanner<T> {
     }
 
     pub fn scan(mut self) -> Result<KernelInfo> {
+        let start_block = self.start_block()?;
+
+        self.scan_block(start_block).or_else(|_| {
+            let start_block = kernel::start_block::find_fallback(&mut self.mem, start_block.arch)?;
+            self.scan_block(start_block)
+        })
+    }
+
+    /// Scans for all plausible kernels instead of stopping at the first one.
+    ///
+    /// Memory dumps might contain multiple copies of ntoskrnl.exe
+    /// (e.g. a hibernated and a live system or nested virtual machines).
+    /// The returned candidates are ranked by confidence, the most plausible candidate comes first.
+    /// A candidate is considered more plausible if its guid and version can be read
+    /// and if its `PsLoadedModuleList` can be resolved.
+    pub fn scan_all(mut self) -> Result<Vec<KernelInfo>> {
+        let mut start_blocks = Vec::new();
+        match self.start_block() {
+            Ok(start_block) => start_blocks.push(start_block),
+            Err(err) => warn!("unable to find start block: {}", err),
+        }
+
+        let arch = start_blocks
+            .first()
+            .map(|start_block| start_block.arch)
+            .or(self.arch)
+            .unwrap_or(x86::x64::ARCH);
+        match kernel::start_block::find_fallback(&mut self.mem, arch) {
+            Ok(start_block) => {
+                if !start_blocks.iter().any(|sb| sb.dtb == start_block.dtb) {
+                    start_blocks.push(start_block);
+                }
+            }
+            Err(err) => warn!("unable to find fallback start block: {}", err),
+        }
+
+        let mut candidates = Vec::new();
+        for start_block in start_blocks.into_iter() {
+            info!(
+                "arch={:?} kernel_hint={:x} dtb={:x}",
+                start_block.arch, start_block.kernel_hint, start_block.dtb
+            );
+
+            let mut virt_mem = VirtualDMA::with_vat(
+                &mut self.mem,
+                start_block.arch,
+                Win32VirtualTranslate::new(start_block.arch, start_block.dtb),
+                DirectTranslate::new(),
+            );
+
+            for (kernel_base, kernel_size) in kernel::ntos::find_all(&mut virt_mem, &start_block) {
+                match Self::kernel_info(&mut virt_mem, start_block, kernel_base, kernel_size) {
+                    Ok(kernel_info) => {
+                        let confidence = Self::confidence(&mut virt_mem, &kernel_info);
+                        info!(
+                            "kernel candidate at {:x} with confidence {}",
+                            kernel_base, confidence
+                        );
+                        candidates.push((confidence, kernel_info));
+                    }
+                    Err(err) => warn!("skipping kernel candidate at {:x}: {}", kernel_base, err),
+                }
+            }
+        }
+
+        if candidates.is_empty() {
+            return Err(Error::Initialization("unable to find ntoskrnl.exe"));
+        }
+
+        // the sort is stable, candidates with the same confidence stay in the order they have been found in
+        candidates.sort_by_key(|(confidence, _)| std::cmp::Reverse(*confidence));
+        Ok(candidates
+            .into_iter()
+            .map(|(_, kernel_info)| kernel_info)
+            .collect())
+    }
+
+    fn start_block(&mut self) -> Result<StartBlock> {
         let start_block = if let (Some(arch), Some(dtb), Some(kernel_hint)) =
             (self.arch, self.dtb, self.kernel_hint)
         {
@@ -56,6 +138,7 @@ impl<T: PhysicalMemory> KernelInfoScanner<T> {
                 arch,
                 kernel_hint,
                 dtb,
+                source: StartBlockSource::UserSupplied,
             }
         } else {
             let mut sb = kernel::start_block::find(&mut self.mem, self.arch)?;
@@ -65,11 +148,7 @@ impl<T: PhysicalMemory> KernelInfoScanner<T> {
             // dtb is always set in start_block::find()
             sb
         };
-
-        self.scan_block(start_block).or_else(|_| {
-            let start_block = kernel::start_block::find_fallback(&mut self.mem, start_block.arch)?;
-            self.scan_block(start_block)
-        })
+        Ok(start_block)
     }
 
     fn scan_block(&mut self, start_block: StartBlock) -> Result<KernelInfo> {
@@ -88,13 +167,22 @@ impl<T: PhysicalMemory> KernelInfoScanner<T> {
 
         // find ntoskrnl.exe base
         let (kernel_base, kernel_size) = kernel::ntos::find(&mut virt_mem, &start_block)?;
+        Self::kernel_info(&mut virt_mem, start_block, kernel_base, kernel_size)
+    }
+
+    fn kernel_info<V: VirtualMemory>(
+        virt_mem: &mut V,
+        start_block: StartBlock,
+        kernel_base: Address,
+        kernel_size: usize,
+    ) -> Result<KernelInfo> {
         info!("kernel_base={} kernel_size={}", kernel_base, kernel_size);
 
         // get ntoskrnl.exe guid
-        let kernel_guid = kernel::ntos::find_guid(&mut virt_mem, kernel_base).ok();
+        let kernel_guid = kernel::ntos::find_guid(virt_mem, kernel_base).ok();
         info!("kernel_guid={:?}", kernel_guid);
 
-        let kernel_winver = kernel::ntos::find_winver(&mut virt_mem, kernel_base).ok();
+        let kernel_winver = kernel::ntos::find_winver(virt_mem, start_block.arch, kernel_base).ok();
 
         if kernel_winver.is_none() {
             warn!("Failed to retrieve kernel version! Some features may be disabled.");
@@ -105,7 +193,7 @@ impl<T: PhysicalMemory> KernelInfoScanner<T> {
         info!("kernel_winver={:?}", kernel_winver);
 
         // find eprocess base
-        let eprocess_base = kernel::sysproc::find(&mut virt_mem, &start_block, kernel_base)?;
+        let eprocess_base = kernel::sysproc::find(virt_mem, &start_block, kernel_base)?;
         info!("eprocess_base={:x}", eprocess_base);
 
         // start_block only contains the winload's dtb which might
@@ -126,6 +214,39 @@ impl<T: PhysicalMemory> KernelInfoScanner<T> {
         })
     }
 
+    /// Rates how plausible the given kernel is.
+    fn confidence<V: VirtualMemory>(virt_mem: &mut V, kernel_info: &KernelInfo) -> u32 {
+        let mut confidence = 0;
+        if kernel_info.kernel_guid.is_some() {
+            confidence += 1;
+        }
+        if kernel_info.kernel_winver.major_version() != 0 {
+            confidence += 1;
+        }
+        match Self::check_loaded_module_list(virt_mem, kernel_info) {
+            Ok(_) => confidence += 2,
+            Err(err) => info!("unable to resolve PsLoadedModuleList: {}", err),
+        }
+        confidence
+    }
+
+    fn check_loaded_module_list<V: VirtualMemory>(
+        virt_mem: &mut V,
+        kernel_info: &KernelInfo,
+    ) -> Result<()> {
+        let list_head = loaded_module_list(virt_mem, kernel_info)?;
+
+        // the first entry has to point back to the list head
+        let arch = kernel_info.start_block.arch;
+        let flink = virt_mem.virt_read_addr_arch(arch, list_head)?;
+        let blink = virt_mem.virt_read_addr_arch(arch, flink + arch.size_addr())?;
+        if flink.is_null() || blink != list_head {
+            return Err(Error::Other("PsLoadedModuleList is not a valid list"));
+        }
+
+        Ok(())
+    }
+
     pub fn arch(mut self, arch: ArchitectureObj) -> Self {
         self.arch = Some(arch);
         self
@@ -141,3 +262,150 @@ impl<T: PhysicalMemory> KernelInfoScanner<T> {
         self
     }
 }
+
+/// Resolves the address of the `PsLoadedModuleList` export of the kernel.
+fn loaded_module_list<V: VirtualMemory>(
+    virt_mem: &mut V,
+    kernel_info: &KernelInfo,
+) -> Result<Address> {
+    let image = kernel::ntos::pehelper::try_get_pe_image(virt_mem, kernel_info.kernel_base)?;
+    let pe = PeView::from_bytes(&image).map_err(Error::PE)?;
+    match pe
+        .get_export_by_name("PsLoadedModuleList")
+        .map_err(Error::PE)?
+    {
+        Export::Symbol(s) => Ok(kernel_info.kernel_base + *s as usize),
+        Export::Forward(_) => Err(Error::Other(
+            "PsLoadedModuleList found but it was a forwarded export",
+        )),
+    }
+}
+
+/// Ranks the given dtb candidates by how many known kernel addresses they are able to translate.
+///
+/// The known addresses are the kernel base, the eprocess base of the System process
+/// and the `PsLoadedModuleList` export, if it can be resolved with the dtb of the start block.
+/// Every candidate is scored with the share of addresses it translates (`0.0` to `1.0`).
+///
+/// The candidates are returned sorted by their score, the most plausible dtb comes first.
+/// Candidates with the same score stay in the order they have been passed in.
+pub fn rank_dtbs<T: PhysicalMemory>(
+    mem: &mut T,
+    kernel_info: &KernelInfo,
+    candidates: &[Address],
+) -> Vec<(Address, f32)> {
+    let arch = kernel_info.start_block.arch;
+
+    let mut addrs = vec![kernel_info.kernel_base, kernel_info.eprocess_base];
+    {
+        let mut virt_mem = VirtualDMA::with_vat(
+            &mut *mem,
+            arch,
+            Win32VirtualTranslate::new(arch, kernel_info.start_block.dtb),
+            DirectTranslate::new(),
+        );
+        match loaded_module_list(&mut virt_mem, kernel_info) {
+            Ok(list_head) => addrs.push(list_head),
+            Err(err) => info!("unable to resolve PsLoadedModuleList: {}", err),
+        }
+    }
+
+    let mut ranked: Vec<(Address, f32)> = Vec::new();
+    for &dtb in candidates.iter() {
+        if ranked.iter().any(|&(d, _)| d == dtb) {
+            continue;
+        }
+
+        let translator = Win32VirtualTranslate::new(arch, dtb);
+        let translated = addrs
+            .iter()
+            .filter(|&&addr| translator.virt_to_phys(mem, addr).is_ok())
+            .count();
+        let score = translated as f32 / addrs.len() as f32;
+        info!("dtb candidate {:x} with score {}", dtb, score);
+        ranked.push((dtb, score));
+    }
+
+    ranked.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::win32::{DummyWin32Builder, DUMMY_OFFSETS, MODULE_BASE};
+    use memflow::architecture::x86::x64;
+    use memflow::types::size;
+
+    #[test]
+    fn scan_all_kernels() {
+        let mut kernel = DummyWin32Builder::new().build().unwrap();
+        let kernel_info = kernel.kernel_info.clone();
+        let kernel_base = kernel_info.kernel_base;
+        let list_head = kernel.export_address("PsLoadedModuleList").unwrap();
+        let dtb = kernel_info.start_block.dtb;
+        let mut mem = kernel.destroy();
+
+        // map a copy of ntoskrnl.exe into a 2mb page 256mb below the original image
+        let copy_base = kernel_base - size::mb(256);
+        let mut image = vec![0u8; size::mb(2)];
+        VirtualDMA::new(&mut mem, x64::ARCH, x64::new_translator(dtb))
+            .virt_read_raw_into(kernel_base, &mut image[..kernel_info.kernel_size])
+            .unwrap();
+        mem.map_const_base(dtb, copy_base, size::mb(2), &image);
+
+        // let the ldr entry of ntoskrnl.exe point back to the PsLoadedModuleList of the copy,
+        // only the copy has a consistent module list now
+        let copy_list_head = copy_base + (list_head - kernel_base);
+        {
+            let mut virt_mem = VirtualDMA::new(&mut mem, x64::ARCH, x64::new_translator(dtb));
+            let entry = virt_mem.virt_read_addr64(list_head).unwrap();
+            virt_mem
+                .virt_write(
+                    entry + DUMMY_OFFSETS.list_blink as usize,
+                    &copy_list_head.as_u64(),
+                )
+                .unwrap();
+        }
+
+        // the original image is found first via the kernel hint
+        let candidates = KernelInfo::scanner(&mut mem)
+            .arch(x64::ARCH)
+            .dtb(dtb)
+            .kernel_hint(kernel_base)
+            .scan_all()
+            .unwrap();
+        assert_eq!(
+            candidates
+                .iter()
+                .map(|kernel_info| kernel_info.kernel_base)
+                .collect::<Vec<_>>(),
+            vec![copy_base, kernel_base]
+        );
+        assert_eq!(candidates[0].eprocess_base, kernel_info.eprocess_base);
+        assert_eq!(candidates[0].kernel_winver.build_number(), 19041);
+    }
+
+    #[test]
+    fn rank_dtbs() {
+        let mut kernel = DummyWin32Builder::new().build().unwrap();
+        let kernel_dtb = kernel.kernel_info.start_block.dtb;
+
+        // a dtb that only maps user space and one outside of physical memory
+        let user_dtb =
+            kernel
+                .phys_mem
+                .alloc_dtb_const_base(Address::from(MODULE_BASE), size::kb(4), &[]);
+        let invalid_dtb = Address::from(size::gb(64));
+
+        let ranked = crate::win32::rank_dtbs(
+            &mut kernel.phys_mem,
+            &kernel.kernel_info,
+            &[user_dtb, invalid_dtb, kernel_dtb, user_dtb],
+        );
+        assert_eq!(
+            ranked,
+            vec![(kernel_dtb, 1.0), (user_dtb, 0.0), (invalid_dtb, 0.0)]
+        );
+    }
+}
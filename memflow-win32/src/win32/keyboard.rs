@@ -6,6 +6,8 @@ This array will internally be read by the [`GetAsyncKeyState()`](https://docs.mi
 
 Although the gafAsyncKeyState array is exported by the win32kbase.sys kernel module it is only properly mapped into user mode processes.
 Therefor the Keyboard will by default find the winlogon.exe or wininit.exe process and use it as a proxy to read the data.
+Since win32kbase.sys is a session driver it is looked up in the session of the proxy process first
+and only falls back to the kernel module list if the session modules can not be resolved.
 
 # Examples:
 
@@ -16,7 +18,7 @@ use memflow::mem::{PhysicalMemory, VirtualTranslate};
 use memflow_win32::win32::{Kernel, Keyboard};
 
 fn test<T: PhysicalMemory, V: VirtualTranslate>(kernel: &mut Kernel<T, V>) {
-    let kbd = Keyboard::try_with(kernel).unwrap();
+    let kbd = Keyboard::with_kernel(kernel).unwrap();
 
     loop {
         let kbs = kbd.state_with_kernel(kernel).unwrap();
@@ -26,7 +28,7 @@ fn test<T: PhysicalMemory, V: VirtualTranslate>(kernel: &mut Kernel<T, V>) {
 }
 ```
 */
-use super::{Kernel, Win32Process, Win32ProcessInfo};
+use super::{Kernel, Win32ModuleInfo, Win32Process, Win32ProcessInfo};
 use crate::error::{Error, Result};
 
 use std::convert::TryInto;
@@ -36,9 +38,9 @@ use log::debug;
 use memflow::error::PartialResultExt;
 use memflow::mem::{PhysicalMemory, VirtualMemory, VirtualTranslate};
 use memflow::process::OsProcessModuleInfo;
-use memflow::types::Address;
+use memflow::types::{size, Address};
 
-use pelite::{self, pe64::exports::Export, PeView};
+use pelite::{self, image::IMAGE_DIRECTORY_ENTRY_EXPORT, pe64::exports::Export, PeView};
 
 /// Interface for accessing the target's keyboard state.
 #[derive(Clone, Debug)]
@@ -56,34 +58,48 @@ pub struct KeyboardState {
 }
 
 impl Keyboard {
+    /// Same as `with_kernel()`.
     pub fn try_with<T: PhysicalMemory, V: VirtualTranslate>(
         kernel: &mut Kernel<T, V>,
     ) -> Result<Self> {
-        let kernel_process_info = kernel.kernel_process_info()?;
-        debug!("found ntoskrnl.exe: {:?}", kernel_process_info);
-
-        let win32kbase_module_info = {
-            let mut ntoskrnl_process = Win32Process::with_kernel_ref(kernel, kernel_process_info);
-            ntoskrnl_process.module_info("win32kbase.sys")?
-        };
-        debug!("found win32kbase.sys: {:?}", win32kbase_module_info);
+        Self::with_kernel(kernel)
+    }
 
+    /// Resolves the gafAsyncKeyState array with the given kernel.
+    ///
+    /// The win32kbase.sys module is looked up in the kernel's module list. Only its pe headers and
+    /// its export directory are read from the target, the full module image is only fetched
+    /// in case the export can not be found and the array has to be located via signature scanning.
+    ///
+    /// The resolved address is stored in the `Keyboard`, so polling the state afterwards
+    /// will just issue a single read per call.
+    pub fn with_kernel<T: PhysicalMemory, V: VirtualTranslate>(
+        kernel: &mut Kernel<T, V>,
+    ) -> Result<Self> {
         let user_process_info = kernel
             .process_info("winlogon.exe")
             .or_else(|_| kernel.process_info("wininit.exe"))?;
+
+        let win32kbase_module_info = Self::find_win32kbase(kernel, &user_process_info)?;
+        debug!("found win32kbase.sys: {:?}", win32kbase_module_info);
+
         let mut user_process = Win32Process::with_kernel_ref(kernel, user_process_info.clone());
         debug!("found user proxy process: {:?}", user_process);
 
         // read with user_process dtb
-        let module_buf = user_process
-            .virt_mem
-            .virt_read_raw(win32kbase_module_info.base(), win32kbase_module_info.size())
-            .data_part()?;
-        debug!("fetched {:x} bytes from win32kbase.sys", module_buf.len());
-
-        // TODO: lazy
         let export_addr =
-            Self::find_gaf_pe(&module_buf).or_else(|_| Self::find_gaf_sig(&module_buf))?;
+            match Self::find_gaf_export(&mut user_process.virt_mem, &win32kbase_module_info) {
+                Ok(export_addr) => export_addr,
+                Err(_) => {
+                    let module_buf = user_process
+                        .virt_mem
+                        .virt_read_raw(win32kbase_module_info.base(), win32kbase_module_info.size())
+                        .data_part()?;
+                    debug!("fetched {:x} bytes from win32kbase.sys", module_buf.len());
+
+                    Self::find_gaf_pe(&module_buf).or_else(|_| Self::find_gaf_sig(&module_buf))?
+                }
+            };
 
         Ok(Self {
             user_process_info,
@@ -91,6 +107,33 @@ impl Keyboard {
         })
     }
 
+    /// Looks up win32kbase.sys in the session of the given process
+    /// and falls back to the module list of the kernel.
+    fn find_win32kbase<T: PhysicalMemory, V: VirtualTranslate>(
+        kernel: &mut Kernel<T, V>,
+        user_process_info: &Win32ProcessInfo,
+    ) -> Result<Win32ModuleInfo> {
+        let session_module = kernel
+            .process_session_id(user_process_info)
+            .and_then(|session_id| kernel.session_module_list(session_id))
+            .map_err(|err| debug!("unable to read session modules: {}", err))
+            .ok()
+            .and_then(|modules| {
+                modules
+                    .into_iter()
+                    .find(|module| module.name.eq_ignore_ascii_case("win32kbase.sys"))
+            });
+        if let Some(module) = session_module {
+            return Ok(module);
+        }
+
+        let kernel_process_info = kernel.kernel_process_info()?;
+        debug!("found ntoskrnl.exe: {:?}", kernel_process_info);
+
+        let mut ntoskrnl_process = Win32Process::with_kernel_ref(kernel, kernel_process_info);
+        ntoskrnl_process.module_info("win32kbase.sys")
+    }
+
     /// Fetches the gafAsyncKeyState from the given virtual reader.
     /// This will use the given virtual memory reader to fetch
     /// the gafAsyncKeyState from the win32kbase.sys kernel module.
@@ -121,6 +164,54 @@ impl Keyboard {
         self.state(&mut process.virt_mem)
     }
 
+    /// Returns the address of the gafAsyncKeyState array.
+    pub fn key_state_addr(&self) -> Address {
+        self.key_state_addr
+    }
+
+    /// Reads the pe headers and the export directory of the module into a sparse image
+    /// and looks up the gafAsyncKeyState export in it.
+    fn find_gaf_export<T: VirtualMemory>(
+        virt_mem: &mut T,
+        module_info: &Win32ModuleInfo,
+    ) -> Result<usize> {
+        let mut image = vec![0u8; module_info.size()];
+
+        let header_size = image.len().min(size::kb(4));
+        virt_mem
+            .virt_read_raw_into(module_info.base(), &mut image[..header_size])
+            .data_part()?;
+
+        let (export_start, export_end) = {
+            let pe = PeView::from_bytes(&image).map_err(Error::from)?;
+            let export_dir = pe
+                .data_directory()
+                .get(IMAGE_DIRECTORY_ENTRY_EXPORT)
+                .ok_or_else(|| Error::Other("module does not contain an export directory"))?;
+            (
+                export_dir.VirtualAddress as usize,
+                export_dir.VirtualAddress as usize + export_dir.Size as usize,
+            )
+        };
+        if export_start < header_size || export_end > image.len() {
+            return Err(Error::Other("module export directory is out of bounds"));
+        }
+
+        virt_mem
+            .virt_read_raw_into(
+                module_info.base() + export_start,
+                &mut image[export_start..export_end],
+            )
+            .data_part()?;
+        debug!(
+            "fetched {:x} bytes of exports from {}",
+            export_end - export_start,
+            module_info.name()
+        );
+
+        Self::find_gaf_pe(&image)
+    }
+
     fn find_gaf_pe(module_buf: &[u8]) -> Result<usize> {
         let pe = PeView::from_bytes(module_buf).map_err(Error::from)?;
 
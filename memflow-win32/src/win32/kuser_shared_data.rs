@@ -0,0 +1,238 @@
+use std::prelude::v1::*;
+
+use crate::error::{Error, PartialResultExt, Result};
+
+use std::convert::TryInto;
+
+use memflow::architecture::ArchitectureObj;
+use memflow::mem::VirtualMemory;
+use memflow::types::Address;
+
+/// Kernel mode address of `KUSER_SHARED_DATA` on x64
+pub const KUSER_SHARED_DATA_X64: u64 = 0xffff_f780_0000_0000;
+/// Kernel mode address of `KUSER_SHARED_DATA` on x86
+pub const KUSER_SHARED_DATA_X86: u64 = 0xffdf_0000;
+
+/// Number of bytes of `KUSER_SHARED_DATA` that are parsed by `KUserSharedData::from_bytes`.
+pub const KUSER_SHARED_DATA_SIZE: usize = 0x3c4;
+
+/// Number of attempts to read `KUSER_SHARED_DATA` without a torn `KSYSTEM_TIME`
+const READ_RETRIES: usize = 16;
+
+/// Offsets into `_KUSER_SHARED_DATA`.
+///
+/// The structure has the same layout on x86 and x64 and fields are only ever appended,
+/// fields that were introduced later on are marked with the first version containing them.
+mod offsets {
+    pub const INTERRUPT_TIME: usize = 0x8;
+    pub const SYSTEM_TIME: usize = 0x14;
+    pub const NT_SYSTEM_ROOT: usize = 0x30;
+    pub const NT_SYSTEM_ROOT_LEN: usize = 260;
+    pub const NT_PRODUCT_TYPE: usize = 0x264;
+    pub const PRODUCT_TYPE_IS_VALID: usize = 0x268;
+    pub const NT_MAJOR_VERSION: usize = 0x26c;
+    pub const NT_MINOR_VERSION: usize = 0x270;
+    pub const KD_DEBUGGER_ENABLED: usize = 0x2d4;
+    pub const NUMBER_OF_PHYSICAL_PAGES: usize = 0x2e8;
+    /// nt 5.1 - 6.1
+    pub const SYSTEM_CALL_NT51: usize = 0x300;
+    /// nt 6.2+
+    pub const SYSTEM_CALL_NT62: usize = 0x308;
+    /// nt 5.2+
+    pub const COOKIE: usize = 0x330;
+    /// nt 6.1+
+    pub const ACTIVE_PROCESSOR_COUNT: usize = 0x3c0;
+}
+
+/// The value of `NtProductType` for a workstation.
+pub const NT_PRODUCT_WIN_NT: u32 = 1;
+/// The value of `NtProductType` for a domain controller.
+pub const NT_PRODUCT_LAN_MAN_NT: u32 = 2;
+/// The value of `NtProductType` for a server.
+pub const NT_PRODUCT_SERVER: u32 = 3;
+
+/// Typed view of the `KUSER_SHARED_DATA` page that the kernel shares with all processes.
+///
+/// Fields that do not exist on the version of the target
+/// (as reported by `nt_major_version` and `nt_minor_version`) are set to `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub struct KUserSharedData {
+    /// Time since boot in 100-nanosecond intervals
+    pub interrupt_time: u64,
+    /// Current system time as a `FILETIME` (100-nanosecond intervals since January 1, 1601 UTC)
+    pub system_time: u64,
+    /// The windows directory (e.g. `C:\Windows`)
+    pub nt_system_root: String,
+    /// Raw value of `NtProductType` (see `NT_PRODUCT_WIN_NT`)
+    pub nt_product_type: u32,
+    pub product_type_is_valid: bool,
+    pub nt_major_version: u32,
+    pub nt_minor_version: u32,
+    pub kd_debugger_enabled: bool,
+    pub number_of_physical_pages: u32,
+    /// Selects the system call stub (`int 2e` or `sysenter`), this is only used on x86
+    pub system_call: Option<u32>,
+    /// The security cookie that seeds `EncodePointer`
+    pub cookie: Option<u32>,
+    pub active_processor_count: Option<u32>,
+}
+
+impl KUserSharedData {
+    /// Returns the kernel mode address of `KUSER_SHARED_DATA` for the given architecture.
+    pub fn kernel_address(arch: ArchitectureObj) -> Result<Address> {
+        match arch.bits() {
+            64 => Ok(Address::from(KUSER_SHARED_DATA_X64)),
+            32 => Ok(Address::from(KUSER_SHARED_DATA_X86)),
+            bits => Err(Error::InvalidArchitecture { bits }),
+        }
+    }
+
+    /// Reads `KUSER_SHARED_DATA` from its kernel mode address.
+    ///
+    /// The time values are updated by the kernel without a lock,
+    /// the page is read repeatedly until a consistent snapshot is found.
+    pub fn read<V: VirtualMemory>(virt_mem: &mut V, arch: ArchitectureObj) -> Result<Self> {
+        let addr = Self::kernel_address(arch)?;
+
+        let mut buf = vec![0u8; KUSER_SHARED_DATA_SIZE];
+        let mut result = Err(Error::Other("unable to read KUSER_SHARED_DATA"));
+        for _ in 0..READ_RETRIES {
+            virt_mem.virt_read_raw_into(addr, &mut buf).data_part()?;
+            result = Self::from_bytes(&buf);
+            if result.is_ok() {
+                break;
+            }
+        }
+        result
+    }
+
+    /// Parses the first `KUSER_SHARED_DATA_SIZE` bytes of `KUSER_SHARED_DATA`.
+    ///
+    /// The time values are updated by the kernel without a lock.
+    /// In case a torn `KSYSTEM_TIME` is detected an error is returned and the page should be read again.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        use offsets::*;
+
+        if bytes.len() < KUSER_SHARED_DATA_SIZE {
+            return Err(Error::Bounds);
+        }
+
+        let nt_major_version = read_u32(bytes, NT_MAJOR_VERSION);
+        let nt_minor_version = read_u32(bytes, NT_MINOR_VERSION);
+        let version = (nt_major_version, nt_minor_version);
+
+        let nt_system_root = bytes[NT_SYSTEM_ROOT..NT_SYSTEM_ROOT + NT_SYSTEM_ROOT_LEN * 2]
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .take_while(|&c| c != 0)
+            .collect::<Vec<_>>();
+
+        Ok(Self {
+            interrupt_time: read_ksystem_time(bytes, INTERRUPT_TIME)?,
+            system_time: read_ksystem_time(bytes, SYSTEM_TIME)?,
+            nt_system_root: String::from_utf16_lossy(&nt_system_root),
+            nt_product_type: read_u32(bytes, NT_PRODUCT_TYPE),
+            product_type_is_valid: bytes[PRODUCT_TYPE_IS_VALID] != 0,
+            nt_major_version,
+            nt_minor_version,
+            kd_debugger_enabled: bytes[KD_DEBUGGER_ENABLED] != 0,
+            number_of_physical_pages: read_u32(bytes, NUMBER_OF_PHYSICAL_PAGES),
+            system_call: if version >= (6, 2) {
+                Some(read_u32(bytes, SYSTEM_CALL_NT62))
+            } else if version >= (5, 1) {
+                Some(read_u32(bytes, SYSTEM_CALL_NT51))
+            } else {
+                None
+            },
+            cookie: if version >= (5, 2) {
+                Some(read_u32(bytes, COOKIE))
+            } else {
+                None
+            },
+            active_processor_count: if version >= (6, 1) {
+                Some(read_u32(bytes, ACTIVE_PROCESSOR_COUNT))
+            } else {
+                None
+            },
+        })
+    }
+
+    /// Returns true if `NtProductType` denotes a server or domain controller.
+    pub fn is_server(&self) -> bool {
+        self.product_type_is_valid
+            && (self.nt_product_type == NT_PRODUCT_LAN_MAN_NT
+                || self.nt_product_type == NT_PRODUCT_SERVER)
+    }
+
+    /// Returns the time the target was booted at as a `FILETIME`.
+    pub fn boot_time(&self) -> Result<u64> {
+        self.system_time
+            .checked_sub(self.interrupt_time)
+            .ok_or(Error::Other("system time is older than the interrupt time"))
+    }
+}
+
+fn read_u32(bytes: &[u8], offs: usize) -> u32 {
+    u32::from_le_bytes(bytes[offs..offs + 4].try_into().unwrap())
+}
+
+/// Reads a `KSYSTEM_TIME { LowPart, High1Time, High2Time }`.
+///
+/// The high part is written twice so that a torn read can be detected by comparing both copies.
+fn read_ksystem_time(bytes: &[u8], offs: usize) -> Result<u64> {
+    let low = read_u32(bytes, offs);
+    let high1 = read_u32(bytes, offs + 4);
+    let high2 = read_u32(bytes, offs + 8);
+    if high1 != high2 {
+        return Err(Error::Other("unable to read a consistent KSYSTEM_TIME"));
+    }
+    Ok((u64::from(high1) << 32) | u64::from(low))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_u32(bytes: &mut [u8], offs: usize, value: u32) {
+        bytes[offs..offs + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    #[test]
+    fn parse_versions() {
+        let mut bytes = vec![0u8; KUSER_SHARED_DATA_SIZE];
+        for (i, c) in "C:\\Windows".encode_utf16().enumerate() {
+            bytes[offsets::NT_SYSTEM_ROOT + i * 2..offsets::NT_SYSTEM_ROOT + i * 2 + 2]
+                .copy_from_slice(&c.to_le_bytes());
+        }
+        write_u32(&mut bytes, offsets::NT_PRODUCT_TYPE, NT_PRODUCT_SERVER);
+        bytes[offsets::PRODUCT_TYPE_IS_VALID] = 1;
+        write_u32(&mut bytes, offsets::SYSTEM_CALL_NT51, 0x51);
+        write_u32(&mut bytes, offsets::SYSTEM_CALL_NT62, 0x62);
+        write_u32(&mut bytes, offsets::COOKIE, 0xc00c1e);
+        write_u32(&mut bytes, offsets::ACTIVE_PROCESSOR_COUNT, 8);
+
+        // windows xp
+        write_u32(&mut bytes, offsets::NT_MAJOR_VERSION, 5);
+        write_u32(&mut bytes, offsets::NT_MINOR_VERSION, 1);
+        let data = KUserSharedData::from_bytes(&bytes).unwrap();
+        assert_eq!(data.nt_system_root, "C:\\Windows");
+        assert!(data.is_server());
+        assert_eq!(data.system_call, Some(0x51));
+        assert_eq!(data.cookie, None);
+        assert_eq!(data.active_processor_count, None);
+
+        // windows 10
+        write_u32(&mut bytes, offsets::NT_MAJOR_VERSION, 10);
+        write_u32(&mut bytes, offsets::NT_MINOR_VERSION, 0);
+        let data = KUserSharedData::from_bytes(&bytes).unwrap();
+        assert_eq!(data.system_call, Some(0x62));
+        assert_eq!(data.cookie, Some(0xc00c1e));
+        assert_eq!(data.active_processor_count, Some(8));
+
+        // torn system time
+        write_u32(&mut bytes, offsets::SYSTEM_TIME + 4, 1);
+        assert!(KUserSharedData::from_bytes(&bytes).is_err());
+        assert!(KUserSharedData::from_bytes(&bytes[..0x100]).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+use crate::error::{Error, Result};
+
+use log::trace;
+
+use memflow::architecture::ArchitectureObj;
+use memflow::mem::VirtualMemory;
+use memflow::types::Address;
+
+/// Returns an iterator that lazily walks a doubly linked `_LIST_ENTRY` list.
+///
+/// `head` is the address of the `_LIST_ENTRY` that heads the list, it is not part of a containing struct
+/// and is therefore never yielded. For every other node the address of the containing struct
+/// (the address of the node minus `link_offset`) is yielded in the order of the `Flink` pointers.
+/// `blink_offset` is the offset of `_LIST_ENTRY::Blink` (see `Win32Offsets::list_blink`).
+///
+/// The walk stops at the first node that has a null `Flink` or `Blink` or links to itself,
+/// such a node is not yielded.
+/// In case the list contains more than `max_count` entries the iterator yields
+/// an `Error::ListCorruption` and stops. This guards against malformed or looping lists.
+///
+/// # Examples
+///
+/// ```
+/// use memflow::architecture::ArchitectureObj;
+/// use memflow::mem::VirtualMemory;
+/// use memflow::types::Address;
+/// use memflow_win32::error::Result;
+/// use memflow_win32::win32::walk_list_entry;
+///
+/// fn hives<V: VirtualMemory>(
+///     virt_mem: &mut V,
+///     arch: ArchitectureObj,
+///     hive_list_head: Address,
+///     cmhive_hive_list: usize,
+/// ) -> Result<Vec<Address>> {
+///     walk_list_entry(
+///         virt_mem,
+///         arch,
+///         hive_list_head,
+///         cmhive_hive_list,
+///         arch.size_addr(),
+///         1024,
+///     )
+///     .collect()
+/// }
+/// ```
+pub fn walk_list_entry<V: VirtualMemory>(
+    reader: V,
+    arch: ArchitectureObj,
+    head: Address,
+    link_offset: usize,
+    blink_offset: usize,
+    max_count: usize,
+) -> ListEntryIter<V> {
+    ListEntryIter {
+        reader,
+        arch,
+        head: Some(head),
+        link_offset,
+        blink_offset,
+        list_start: Address::NULL,
+        list_entry: Address::NULL,
+        count: 0,
+        max_count,
+        done: false,
+    }
+}
+
+/// Lazy iterator over the containing structs of a `_LIST_ENTRY` list.
+///
+/// This iterator is created by `walk_list_entry`.
+pub struct ListEntryIter<V> {
+    reader: V,
+    arch: ArchitectureObj,
+    head: Option<Address>,
+    link_offset: usize,
+    blink_offset: usize,
+    list_start: Address,
+    list_entry: Address,
+    count: usize,
+    max_count: usize,
+    done: bool,
+}
+
+impl<V: VirtualMemory> ListEntryIter<V> {
+    /// Creates an iterator that starts at the given node instead of the list head.
+    ///
+    /// The walk stops at the node whose `Flink` points back to `first_entry`, i.e. the list head.
+    pub(crate) fn with_first_entry(
+        reader: V,
+        arch: ArchitectureObj,
+        first_entry: Address,
+        link_offset: usize,
+        blink_offset: usize,
+        max_count: usize,
+    ) -> Self {
+        Self {
+            reader,
+            arch,
+            head: None,
+            link_offset,
+            blink_offset,
+            list_start: first_entry,
+            list_entry: first_entry,
+            count: 0,
+            max_count,
+            done: false,
+        }
+    }
+
+    fn next_entry(&mut self) -> Result<Option<Address>> {
+        if let Some(head) = self.head.take() {
+            let first_entry = self.reader.virt_read_addr_arch(self.arch, head)?;
+            if first_entry.is_null() || first_entry == head {
+                return Ok(None);
+            }
+            self.list_start = first_entry;
+            self.list_entry = first_entry;
+        }
+
+        let entry = self.list_entry - self.link_offset;
+        trace!("list_entry={:x}", self.list_entry);
+
+        // test flink + blink before yielding the entry
+        let flink_entry = self
+            .reader
+            .virt_read_addr_arch(self.arch, self.list_entry)?;
+        trace!("flink_entry={:x}", flink_entry);
+        let blink_entry = self
+            .reader
+            .virt_read_addr_arch(self.arch, self.list_entry + self.blink_offset)?;
+        trace!("blink_entry={:x}", blink_entry);
+
+        // for lists that are walked from their head the last node links back to the head,
+        // which itself links to the first entry again
+        if flink_entry.is_null()
+            || blink_entry.is_null()
+            || flink_entry == self.list_start
+            || flink_entry == self.list_entry
+        {
+            return Ok(None);
+        }
+
+        if self.count >= self.max_count {
+            return Err(Error::ListCorruption(
+                "list exceeds the maximum number of entries",
+            ));
+        }
+        self.count += 1;
+
+        // continue
+        self.list_entry = flink_entry;
+
+        Ok(Some(entry))
+    }
+}
+
+impl<V: VirtualMemory> Iterator for ListEntryIter<V> {
+    type Item = Result<Address>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.next_entry() {
+            Ok(Some(entry)) => Some(Ok(entry)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::win32::{DummyWin32Builder, DummyWin32Process};
+    use memflow::architecture::x86::x64;
+
+    #[test]
+    fn list_entry_walk() {
+        let mut kernel = DummyWin32Builder::new()
+            .process(DummyWin32Process::new(1000, "explorer.exe"))
+            .process(DummyWin32Process::new(1004, "notepad.exe"))
+            .build()
+            .unwrap();
+        let eprocs = kernel.eprocess_list().unwrap();
+
+        let link = kernel.offsets.eproc_link();
+        let blink = kernel.offsets.list_blink();
+        let mut virt_mem = kernel.kernel_virt_mem();
+
+        // the blink of the system process points to the list head
+        let head = virt_mem.virt_read_addr64(eprocs[0] + link + blink).unwrap();
+        let list = walk_list_entry(&mut virt_mem, x64::ARCH, head, link, blink, 16)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(list, eprocs);
+
+        let result = walk_list_entry(&mut virt_mem, x64::ARCH, head, link, blink, 2)
+            .collect::<Result<Vec<_>>>();
+        assert!(matches!(result, Err(Error::ListCorruption(_))));
+
+        // an empty list head points to itself
+        virt_mem.virt_write(head, &head.as_u64()).unwrap();
+        assert_eq!(
+            walk_list_entry(&mut virt_mem, x64::ARCH, head, link, blink, 16).count(),
+            0
+        );
+    }
+}
@@ -1,8 +1,17 @@
 use std::prelude::v1::*;
 
-use memflow::process::OsProcessModuleInfo;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use memflow::process::{OsProcessModuleInfo, PID};
 use memflow::types::Address;
 
+/// A module loaded into a process.
+///
+/// Two modules are considered equal if they are backed by the same physical memory,
+/// i.e. if their `physical_base` and `size` match. This allows deduplicating
+/// shared libraries that are loaded into multiple processes (e.g. by collecting them into a `HashSet`).
+/// Modules without a `physical_base` are only equal to themselves.
 #[derive(Debug, Clone)]
 pub struct Win32ModuleInfo {
     pub peb_entry: Address,
@@ -12,6 +21,36 @@ pub struct Win32ModuleInfo {
     pub size: usize,   // _LDR_DATA_TABLE_ENTRY::SizeOfImage
     pub path: String,  // _LDR_DATA_TABLE_ENTRY::FullDllName
     pub name: String,  // _LDR_DATA_TABLE_ENTRY::BaseDllName
+
+    /// Physical address of the module base, `None` if the first page is not present
+    pub physical_base: Option<Address>,
+}
+
+impl Win32ModuleInfo {
+    fn identity(&self) -> (Option<Address>, usize, Address, Address) {
+        match self.physical_base {
+            Some(physical_base) => (Some(physical_base), self.size, Address::NULL, Address::NULL),
+            None => (None, self.size, self.parent_eprocess, self.peb_entry),
+        }
+    }
+}
+
+impl PartialEq for Win32ModuleInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.identity() == other.identity()
+    }
+}
+
+impl Eq for Win32ModuleInfo {}
+
+impl Hash for Win32ModuleInfo {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let (physical_base, size, parent_eprocess, peb_entry) = self.identity();
+        physical_base.map(Address::as_u64).hash(state);
+        size.hash(state);
+        parent_eprocess.as_u64().hash(state);
+        peb_entry.as_u64().hash(state);
+    }
 }
 
 impl OsProcessModuleInfo for Win32ModuleInfo {
@@ -35,3 +74,59 @@ impl OsProcessModuleInfo for Win32ModuleInfo {
         self.name.clone()
     }
 }
+
+/// Human readable location of an address, see `Kernel::symbolize()`.
+///
+/// The location is formatted as `module+offset` (e.g. `ntdll.dll+0x1234`).
+/// Addresses outside of any module are formatted as the plain address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolLocation {
+    pub address: Address,
+    /// The process the address belongs to, `None` for kernel addresses
+    pub pid: Option<PID>,
+    /// Name of the module containing the address
+    pub module: Option<String>,
+    /// Offset relative to the module base, or the address itself if it is not part of a module
+    pub offset: usize,
+}
+
+impl fmt::Display for SymbolLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.module {
+            Some(module) => write!(f, "{}+{:#x}", module, self.offset),
+            None => write!(f, "{:#x}", self.address.as_u64()),
+        }
+    }
+}
+
+/// A section of a module as it is mapped into the address space of a process.
+#[derive(Debug, Clone)]
+pub struct Win32SectionInfo {
+    pub name: String,           // IMAGE_SECTION_HEADER::Name
+    pub virtual_address: usize, // IMAGE_SECTION_HEADER::VirtualAddress, relative to the module base
+    pub virtual_size: usize,    // IMAGE_SECTION_HEADER::VirtualSize
+    pub characteristics: u32,   // IMAGE_SECTION_HEADER::Characteristics
+}
+
+impl Win32SectionInfo {
+    /// Returns the address of the section inside of the given module.
+    pub fn address(&self, module: &Win32ModuleInfo) -> Address {
+        module.base + self.virtual_address
+    }
+
+    pub fn is_executable(&self) -> bool {
+        self.characteristics & IMAGE_SCN_MEM_EXECUTE != 0
+    }
+
+    pub fn is_readable(&self) -> bool {
+        self.characteristics & IMAGE_SCN_MEM_READ != 0
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.characteristics & IMAGE_SCN_MEM_WRITE != 0
+    }
+}
+
+const IMAGE_SCN_MEM_EXECUTE: u32 = 0x2000_0000;
+const IMAGE_SCN_MEM_READ: u32 = 0x4000_0000;
+const IMAGE_SCN_MEM_WRITE: u32 = 0x8000_0000;
@@ -0,0 +1,569 @@
+/*!
+Resolution of paged-out memory through a pagefile.
+
+A physical memory connector can only resolve pages that are currently resident.
+Kernel and process structures that have been paged out by windows are marked
+as not present in the page tables and can not be translated.
+
+The `Win32PagefileMemory` wrapper combines the physical memory with a secondary
+memory that contains the contents of the pagefile (e.g. a `pagefile.sys` that has been
+acquired alongside a memory dump). The pagefile is mapped above the physical memory at `PAGEFILE_BASE`.
+
+The `Win32PagefileTranslate` wrapper inspects the page table entries of all addresses that
+the underlying translator failed to resolve:
+- entries in transition (e.g. pages on the standby or modified list) are still resident and are read from the physical memory.
+- entries that point into the pagefile are read from the pagefile.
+
+Prototype entries and page tables which are paged out themselves are not resolved.
+
+Only the software page table entry layout up to Windows 10 1709 is decoded.
+Starting with build 17134 windows ships the L1TF mitigation which moves the pagefile index
+and swizzles the pagefile offset with an invalid pte mask, the translator refuses these builds.
+Older systems that received the mitigation through an update are not detected
+and will resolve pagefile pages to wrong offsets.
+
+# Examples
+
+```
+use memflow::mem::{DirectTranslate, PhysicalMemory};
+use memflow_win32::error::Result;
+use memflow_win32::kernel::Win32Version;
+use memflow_win32::win32::{Kernel, Win32PagefileMemory, Win32PagefileTranslate};
+
+fn test<T: PhysicalMemory + 'static, P: PhysicalMemory + 'static>(
+    connector: T,
+    pagefile: P,
+    version: Win32Version,
+) -> Result<()> {
+    let vat = Win32PagefileTranslate::new(DirectTranslate::new(), version)?;
+    let _kernel = Kernel::builder(connector)
+        .build_page_cache(move |connector, _| Win32PagefileMemory::new(connector, pagefile))
+        .build_vat_cache(move |_, _| vat)
+        .build()?;
+    Ok(())
+}
+```
+*/
+
+use std::prelude::v1::*;
+
+use memflow::{
+    architecture::{x86, ArchitectureObj, ScopedVirtualTranslate},
+    connector::CpuState,
+    error::{Error, Result},
+    iter::{PageChunks, SplitAtIndex},
+    mem::{
+        PhysicalMemory, PhysicalMemoryMetadata, PhysicalReadData, PhysicalWriteData,
+        VirtualTranslate,
+    },
+    types::{Address, PageType, PhysicalAddress},
+};
+
+use crate::kernel::Win32Version;
+
+/// Physical address at which the pagefile is mapped by `Win32PagefileMemory`.
+pub const PAGEFILE_BASE: u64 = 1 << 63;
+
+/// First build that swizzles software page table entries (Windows 10 1803).
+const SWIZZLED_PTE_BUILD: u32 = 17134;
+
+/// Wrapper that maps the given pagefile above the physical memory.
+///
+/// All accesses at or above `PAGEFILE_BASE` are forwarded to the pagefile,
+/// all other accesses are forwarded to the physical memory.
+///
+/// Since this wrapper implements `PhysicalMemory` it can be used as a replacement
+/// in all structs and functions that require a `PhysicalMemory` object.
+#[derive(Clone)]
+pub struct Win32PagefileMemory<T, P> {
+    mem: T,
+    pagefile: P,
+}
+
+impl<T: PhysicalMemory, P: PhysicalMemory> Win32PagefileMemory<T, P> {
+    /// Creates a new wrapper from the given physical memory and pagefile.
+    pub fn new(mem: T, pagefile: P) -> Self {
+        Self { mem, pagefile }
+    }
+
+    /// Consumes the wrapper and returns the underlying physical memory and pagefile.
+    pub fn destroy(self) -> (T, P) {
+        (self.mem, self.pagefile)
+    }
+}
+
+fn is_pagefile_addr(addr: PhysicalAddress) -> bool {
+    addr.as_u64() >= PAGEFILE_BASE
+}
+
+fn to_pagefile_addr(addr: PhysicalAddress) -> PhysicalAddress {
+    let pagefile_addr = Address::from(addr.as_u64() - PAGEFILE_BASE);
+    if addr.has_page() {
+        PhysicalAddress::with_page(pagefile_addr, addr.page_type(), addr.page_size())
+    } else {
+        pagefile_addr.into()
+    }
+}
+
+impl<T: PhysicalMemory, P: PhysicalMemory> PhysicalMemory for Win32PagefileMemory<T, P> {
+    fn phys_read_raw_list(&mut self, data: &mut [PhysicalReadData]) -> Result<()> {
+        if data
+            .iter()
+            .all(|PhysicalReadData(addr, _)| !is_pagefile_addr(*addr))
+        {
+            return self.mem.phys_read_raw_list(data);
+        }
+
+        let (mut pagefile_list, mut mem_list): (Vec<_>, Vec<_>) = data
+            .iter_mut()
+            .map(|PhysicalReadData(addr, buf)| PhysicalReadData(*addr, &mut buf[..]))
+            .partition(|PhysicalReadData(addr, _)| is_pagefile_addr(*addr));
+
+        for PhysicalReadData(addr, _) in pagefile_list.iter_mut() {
+            *addr = to_pagefile_addr(*addr);
+        }
+
+        if !mem_list.is_empty() {
+            self.mem.phys_read_raw_list(&mut mem_list)?;
+        }
+        self.pagefile.phys_read_raw_list(&mut pagefile_list)
+    }
+
+    fn phys_write_raw_list(&mut self, data: &[PhysicalWriteData]) -> Result<()> {
+        if data
+            .iter()
+            .all(|PhysicalWriteData(addr, _)| !is_pagefile_addr(*addr))
+        {
+            return self.mem.phys_write_raw_list(data);
+        }
+
+        let (pagefile_list, mem_list): (Vec<_>, Vec<_>) = data
+            .iter()
+            .copied()
+            .partition(|PhysicalWriteData(addr, _)| is_pagefile_addr(*addr));
+
+        let pagefile_list = pagefile_list
+            .into_iter()
+            .map(|PhysicalWriteData(addr, buf)| PhysicalWriteData(to_pagefile_addr(addr), buf))
+            .collect::<Vec<_>>();
+
+        if !mem_list.is_empty() {
+            self.mem.phys_write_raw_list(&mem_list)?;
+        }
+        self.pagefile.phys_write_raw_list(&pagefile_list)
+    }
+
+    fn metadata(&self) -> PhysicalMemoryMetadata {
+        self.mem.metadata()
+    }
+
+    // the pagefile is mapped beyond the size reported by `metadata()`,
+    // so the bounds checks of the default helpers are left to the underlying memories
+    fn phys_read_raw_into(&mut self, addr: PhysicalAddress, out: &mut [u8]) -> Result<()> {
+        if is_pagefile_addr(addr) {
+            self.pagefile
+                .phys_read_raw_into(to_pagefile_addr(addr), out)
+        } else {
+            self.mem.phys_read_raw_into(addr, out)
+        }
+    }
+
+    fn phys_write_raw(&mut self, addr: PhysicalAddress, data: &[u8]) -> Result<()> {
+        if is_pagefile_addr(addr) {
+            self.pagefile.phys_write_raw(to_pagefile_addr(addr), data)
+        } else {
+            self.mem.phys_write_raw(addr, data)
+        }
+    }
+
+    fn cpu_state(&mut self) -> Option<&mut dyn CpuState> {
+        self.mem.cpu_state()
+    }
+
+    fn prefetch(&mut self, ranges: &[(PhysicalAddress, usize)]) -> Result<()> {
+        let (pagefile_ranges, mem_ranges): (Vec<_>, Vec<_>) = ranges
+            .iter()
+            .copied()
+            .partition(|(addr, _)| is_pagefile_addr(*addr));
+
+        let pagefile_ranges = pagefile_ranges
+            .into_iter()
+            .map(|(addr, len)| (to_pagefile_addr(addr), len))
+            .collect::<Vec<_>>();
+
+        if !mem_ranges.is_empty() {
+            self.mem.prefetch(&mem_ranges)?;
+        }
+        if !pagefile_ranges.is_empty() {
+            self.pagefile.prefetch(&pagefile_ranges)?;
+        }
+        Ok(())
+    }
+}
+
+/// Layout of the software page table entries of a specific architecture.
+struct SoftwarePteFormat {
+    /// number of significant bits of a virtual address
+    virt_bits: u8,
+    /// index of the page walk step that reads the final page table entry
+    leaf_step: usize,
+    /// number of entries in a single page table
+    table_entries: usize,
+    /// index of the first bit of the page frame number inside the pagefile
+    pagefile_shift: u8,
+    /// mask of the page frame number of an entry in transition
+    pfn_mask: u64,
+}
+
+impl SoftwarePteFormat {
+    fn from_arch(arch: ArchitectureObj) -> Option<Self> {
+        let (virt_bits, leaf_step, table_entries, pagefile_shift, pfn_mask) =
+            if arch == x86::x64::ARCH {
+                (48, 3, 512, 32, 0x000f_ffff_ffff_f000)
+            } else if arch == x86::x64_la57::ARCH {
+                (57, 4, 512, 32, 0x000f_ffff_ffff_f000)
+            } else if arch == x86::x32_pae::ARCH {
+                (32, 2, 512, 32, 0x000f_ffff_ffff_f000)
+            } else if arch == x86::x32::ARCH {
+                (32, 1, 1024, 12, 0xffff_f000)
+            } else {
+                return None;
+            };
+
+        Some(Self {
+            virt_bits,
+            leaf_step,
+            table_entries,
+            pagefile_shift,
+            pfn_mask,
+        })
+    }
+
+    /// Checks if the address is inside the virtual address space.
+    ///
+    /// On 64-bit architectures the upper bits of the address have to be a sign extension.
+    fn is_canonical(&self, addr: Address) -> bool {
+        if self.virt_bits == 32 {
+            addr.as_u64() <= u64::from(u32::MAX)
+        } else {
+            let upper = addr.as_u64() >> (self.virt_bits - 1);
+            upper == 0 || upper == (1 << (65 - self.virt_bits)) - 1
+        }
+    }
+
+    /// Returns the size of the virtual memory region that is mapped by a single entry in the given step.
+    fn step_size(&self, page_size: usize, step: usize) -> usize {
+        (step..self.leaf_step).fold(page_size, |size, _| size.saturating_mul(self.table_entries))
+    }
+}
+
+/// Wrapper that resolves pages in transition and pages in the pagefile
+/// which could not be translated by the underlying translator.
+///
+/// The pagefile addresses returned by this translator can only be read
+/// through a `Win32PagefileMemory`.
+///
+/// Since this wrapper implements `VirtualTranslate` it can be used as a replacement
+/// in all structs and functions that require a `VirtualTranslate` object.
+#[derive(Clone)]
+pub struct Win32PagefileTranslate<V> {
+    vat: V,
+    pagefile_index: u8,
+}
+
+impl<V: VirtualTranslate> Win32PagefileTranslate<V> {
+    /// Creates a new wrapper that resolves pages from the first pagefile (`pagefile.sys`)
+    /// of a system running the given windows version.
+    ///
+    /// Returns an error for builds whose page table entries are swizzled.
+    pub fn new(vat: V, version: Win32Version) -> Result<Self> {
+        Self::with_pagefile_index(vat, version, 0)
+    }
+
+    /// Creates a new wrapper that resolves pages from the pagefile with the given index.
+    ///
+    /// Windows supports up to 16 pagefiles, entries referring to other pagefiles are not resolved.
+    pub fn with_pagefile_index(vat: V, version: Win32Version, pagefile_index: u8) -> Result<Self> {
+        if version.build_number() >= SWIZZLED_PTE_BUILD {
+            return Err(Error::Other(
+                "swizzled page table entries of this windows build are not supported",
+            ));
+        }
+
+        Ok(Self {
+            vat,
+            pagefile_index,
+        })
+    }
+
+    /// Returns the index of the pagefile that pages are resolved from.
+    pub fn pagefile_index(&self) -> u8 {
+        self.pagefile_index
+    }
+
+    /// Consumes the wrapper and returns the underlying translator.
+    pub fn destroy(self) -> V {
+        self.vat
+    }
+
+    /// Decodes a final page table entry that is not present.
+    fn decode_pte(
+        &self,
+        format: &SoftwarePteFormat,
+        pte: u64,
+        page_size: usize,
+    ) -> Option<Address> {
+        let prototype = pte & (1 << 10) != 0;
+        let transition = pte & (1 << 11) != 0;
+
+        if prototype {
+            None
+        } else if transition {
+            Some(Address::from(pte & format.pfn_mask))
+        } else {
+            let pagefile_index = (pte >> 1) & 0xf;
+            let pagefile_pfn = (pte & 0xffff_ffff_ffff_f000) >> format.pagefile_shift;
+
+            // a zero frame number denotes a demand zero page
+            if pagefile_index != u64::from(self.pagefile_index) || pagefile_pfn == 0 {
+                None
+            } else {
+                Some(Address::from(
+                    PAGEFILE_BASE + pagefile_pfn * page_size as u64,
+                ))
+            }
+        }
+    }
+
+    /// Resolves a single page that is not present.
+    ///
+    /// In case the page can not be resolved the size of the region that can not be resolved either is returned.
+    fn resolve_page<T, D>(
+        &self,
+        phys_mem: &mut T,
+        translator: &D,
+        format: &SoftwarePteFormat,
+        addr: Address,
+    ) -> std::result::Result<PhysicalAddress, usize>
+    where
+        T: PhysicalMemory + ?Sized,
+        D: ScopedVirtualTranslate,
+    {
+        let page_size = translator.arch().page_size();
+
+        if !format.is_canonical(addr) {
+            return Err(page_size);
+        }
+
+        let steps = translator
+            .virt_page_walk(phys_mem, addr)
+            .map_err(|_| page_size)?;
+        let last = steps.last().ok_or(page_size)?;
+
+        if last.present {
+            return Err(page_size);
+        }
+        if last.step != format.leaf_step {
+            // the entire region below this entry is not present
+            return Err(format.step_size(page_size, last.step));
+        }
+
+        let page_base = self
+            .decode_pte(format, last.pte, page_size)
+            .ok_or(page_size)?;
+        Ok(PhysicalAddress::with_page(
+            page_base + (addr.as_usize() & (page_size - 1)),
+            PageType::default(),
+            page_size,
+        ))
+    }
+}
+
+impl<V: VirtualTranslate> VirtualTranslate for Win32PagefileTranslate<V> {
+    fn virt_to_phys_iter<T, B, D, VI, VO, FO>(
+        &mut self,
+        phys_mem: &mut T,
+        translator: &D,
+        addrs: VI,
+        out: &mut VO,
+        out_fail: &mut FO,
+    ) where
+        T: PhysicalMemory + ?Sized,
+        B: SplitAtIndex,
+        D: ScopedVirtualTranslate,
+        VI: Iterator<Item = (Address, B)>,
+        VO: Extend<(PhysicalAddress, B)>,
+        FO: Extend<(Error, Address, B)>,
+    {
+        let mut fails = vec![];
+        self.vat
+            .virt_to_phys_iter(phys_mem, translator, addrs, out, &mut fails);
+
+        if fails.is_empty() {
+            return;
+        }
+
+        let format = match SoftwarePteFormat::from_arch(translator.arch()) {
+            Some(format) => format,
+            None => {
+                out_fail.extend(fails);
+                return;
+            }
+        };
+        let page_size = translator.arch().page_size();
+
+        for (err, addr, buf) in fails.into_iter() {
+            // only addresses with non present page table entries can be resolved
+            if err != Error::VirtualTranslate {
+                out_fail.extend(Some((err, addr, buf)));
+                continue;
+            }
+
+            let mut unresolved_end = Address::null();
+            for (page_addr, chunk) in buf.page_chunks(addr, page_size) {
+                if page_addr < unresolved_end {
+                    out_fail.extend(Some((err, page_addr, chunk)));
+                    continue;
+                }
+
+                match self.resolve_page(phys_mem, translator, &format, page_addr) {
+                    Ok(paddr) => out.extend(Some((paddr, chunk))),
+                    Err(size) => {
+                        unresolved_end = Address::from(
+                            page_addr
+                                .as_page_aligned(size)
+                                .as_u64()
+                                .saturating_add(size as u64),
+                        );
+                        out_fail.extend(Some((err, page_addr, chunk)));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use memflow::architecture::x86::x64;
+    use memflow::mem::{
+        assert_phys_mem_conformance, dummy::DummyMemory, DirectTranslate, VirtualDMA, VirtualMemory,
+    };
+    use memflow::types::size;
+
+    const PRESENT: u64 = 0b11;
+
+    fn version() -> Win32Version {
+        Win32Version::new(10, 0, 16299)
+    }
+
+    /// Creates page tables which map the first 4 pages at virtual address 0:
+    /// - a present page at 0x10000
+    /// - a page in the pagefile at 0x5000
+    /// - a page in transition at 0x11000
+    /// - a demand zero page
+    fn build_mem() -> (Win32PagefileMemory<DummyMemory, DummyMemory>, Address) {
+        let mut mem = DummyMemory::new(size::mb(1));
+        mem.phys_write(0x1000.into(), &(0x2000 | PRESENT)).unwrap();
+        mem.phys_write(0x2000.into(), &(0x3000 | PRESENT)).unwrap();
+        mem.phys_write(0x3000.into(), &(0x4000 | PRESENT)).unwrap();
+        mem.phys_write(0x4000.into(), &(0x10000 | PRESENT)).unwrap();
+        mem.phys_write(0x4008.into(), &((5u64 << 32) | (4 << 5)))
+            .unwrap();
+        mem.phys_write(0x4010.into(), &(0x11000u64 | (1 << 11) | (4 << 5)))
+            .unwrap();
+        mem.phys_write(0x4018.into(), &(4u64 << 5)).unwrap();
+
+        mem.phys_write(0x10ffc.into(), &0x1111_1111_u32).unwrap();
+        mem.phys_write(0x11000.into(), &0x3333_3333_u32).unwrap();
+
+        let mut pagefile = DummyMemory::new(size::mb(1));
+        pagefile
+            .phys_write(0x5000.into(), &0x2222_2222_u32)
+            .unwrap();
+
+        (
+            Win32PagefileMemory::new(mem, pagefile),
+            Address::from(0x1000),
+        )
+    }
+
+    #[test]
+    fn pagefile_memory() {
+        let (mut mem, _) = build_mem();
+        assert_eq!(
+            mem.phys_read::<u32>(Address::from(PAGEFILE_BASE + 0x5000).into())
+                .unwrap(),
+            0x2222_2222
+        );
+        assert_eq!(mem.phys_read::<u32>(0x11000.into()).unwrap(), 0x3333_3333);
+
+        mem.phys_write(
+            Address::from(PAGEFILE_BASE + 0x6000).into(),
+            &0xdead_beef_u32,
+        )
+        .unwrap();
+        let (_, mut pagefile) = mem.destroy();
+        assert_eq!(
+            pagefile.phys_read::<u32>(0x6000.into()).unwrap(),
+            0xdead_beef
+        );
+    }
+
+    #[test]
+    fn pagefile_translate() {
+        let (mem, dtb) = build_mem();
+        let vat = Win32PagefileTranslate::new(DirectTranslate::new(), version()).unwrap();
+        let mut virt_mem = VirtualDMA::with_vat(mem, x64::ARCH, x64::new_translator(dtb), vat);
+
+        assert_eq!(
+            virt_mem.virt_read::<u32>(0x1000.into()).unwrap(),
+            0x2222_2222
+        );
+        assert_eq!(
+            virt_mem.virt_read::<u32>(0x2000.into()).unwrap(),
+            0x3333_3333
+        );
+        assert!(virt_mem.virt_read::<u32>(0x3000.into()).is_err());
+
+        // reads across the boundary of a present page and a page in the pagefile
+        assert_eq!(
+            virt_mem.virt_read::<u64>(0xffc.into()).unwrap(),
+            0x2222_2222_1111_1111
+        );
+    }
+
+    #[test]
+    fn pagefile_index() {
+        let (mem, dtb) = build_mem();
+        let vat = Win32PagefileTranslate::with_pagefile_index(DirectTranslate::new(), version(), 1)
+            .unwrap();
+        let mut virt_mem = VirtualDMA::with_vat(mem, x64::ARCH, x64::new_translator(dtb), vat);
+
+        assert!(virt_mem.virt_read::<u32>(0x1000.into()).is_err());
+        assert_eq!(
+            virt_mem.virt_read::<u32>(0x2000.into()).unwrap(),
+            0x3333_3333
+        );
+    }
+
+    #[test]
+    fn swizzled_builds() {
+        assert!(Win32PagefileTranslate::new(
+            DirectTranslate::new(),
+            Win32Version::new(10, 0, 17134)
+        )
+        .is_err());
+        assert!(
+            Win32PagefileTranslate::new(DirectTranslate::new(), Win32Version::new(6, 1, 7601))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn conformance() {
+        let (mut mem, _) = build_mem();
+        assert_phys_mem_conformance(&mut mem);
+    }
+}
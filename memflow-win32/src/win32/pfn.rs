@@ -0,0 +1,329 @@
+use std::prelude::v1::*;
+
+use crate::error::{Error, Result};
+use crate::offsets::Win32Offsets;
+
+use std::convert::TryInto;
+
+use memflow::architecture::ArchitectureObj;
+use memflow::mem::VirtualMemory;
+use memflow::types::Address;
+
+/// Maximum number of `_MMPFN` entries that are read at once by the `PfnIter`.
+pub const PFN_ITER_CHUNK_COUNT: usize = 0x100;
+
+/// The list a physical page is currently on (`_MMPFN::u3.e1.PageLocation`).
+///
+/// The discriminants equal the values of the `_MMLISTS` enum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub enum PfnState {
+    /// The page is free and has been zeroed
+    Zeroed = 0,
+    /// The page is free but still contains stale data
+    Free = 1,
+    /// The page has been removed from a working set but its contents are still valid
+    Standby = 2,
+    /// Like `Standby` but the page has to be written to the paging file before it can be reused
+    Modified = 3,
+    /// Like `Modified` but the page must not be written to the paging file
+    ModifiedNoWrite = 4,
+    /// The page contains hardware errors and is not used
+    Bad = 5,
+    /// The page is mapped by a working set or in use by the kernel
+    Active = 6,
+    /// The page is currently being read from or written to disk
+    Transition = 7,
+}
+
+impl PfnState {
+    /// Converts the 3 bit `PageLocation` field into a `PfnState`.
+    pub fn from_page_location(location: u8) -> Self {
+        match location & 0x7 {
+            0 => PfnState::Zeroed,
+            1 => PfnState::Free,
+            2 => PfnState::Standby,
+            3 => PfnState::Modified,
+            4 => PfnState::ModifiedNoWrite,
+            5 => PfnState::Bad,
+            6 => PfnState::Active,
+            _ => PfnState::Transition,
+        }
+    }
+
+    /// Returns true if the page is on one of the free lists.
+    ///
+    /// The contents of free pages may still contain data of previous owners.
+    pub fn is_free(self) -> bool {
+        self == PfnState::Zeroed || self == PfnState::Free
+    }
+}
+
+/// Decoded entry of the PFN database describing a single physical page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub struct PfnEntry {
+    /// Page frame number of the physical page
+    pub pfn: u64,
+    /// Virtual address of the `_MMPFN`
+    pub address: Address,
+    pub state: PfnState,
+    /// The page has been modified since it was last written to disk
+    pub modified: bool,
+    /// Number of references that keep the page resident
+    pub reference_count: u16,
+    /// Virtual address of the pte that maps this page
+    pub pte_address: Address,
+}
+
+/// Location and layout of the PFN database of a `Kernel`.
+///
+/// This is resolved by `Kernel::pfn_database()`.
+#[derive(Debug, Clone, Copy)]
+pub struct PfnDatabase {
+    arch: ArchitectureObj,
+    base: Address,
+    highest_pfn: u64,
+    entry_size: usize,
+    pte_address: usize,
+    u3: usize,
+}
+
+impl PfnDatabase {
+    /// Reads the `MmPfnDatabase` and `MmHighestPhysicalPage` globals of the kernel.
+    pub(crate) fn with<V: VirtualMemory>(
+        reader: &mut V,
+        arch: ArchitectureObj,
+        kernel_base: Address,
+        offsets: &Win32Offsets,
+    ) -> Result<Self> {
+        if offsets.mm_pfn_database() == 0 || offsets.mm_highest_physical_page() == 0 {
+            return Err(Error::Other("MmPfnDatabase offset is not available"));
+        }
+        if offsets.mmpfn_size() == 0 || offsets.mmpfn_u3() == 0 {
+            return Err(Error::Other("_MMPFN offsets are not available"));
+        }
+
+        let base = reader.virt_read_addr_arch(arch, kernel_base + offsets.mm_pfn_database())?;
+        if base.is_null() {
+            return Err(Error::Other("MmPfnDatabase is not initialized"));
+        }
+
+        // PFN_NUMBER is pointer sized
+        let highest_pfn = reader
+            .virt_read_addr_arch(arch, kernel_base + offsets.mm_highest_physical_page())?
+            .as_u64();
+
+        Ok(Self {
+            arch,
+            base,
+            highest_pfn,
+            entry_size: offsets.mmpfn_size(),
+            pte_address: offsets.mmpfn_pte_address(),
+            u3: offsets.mmpfn_u3(),
+        })
+    }
+
+    /// Returns the address of the first `_MMPFN` entry.
+    pub fn base(&self) -> Address {
+        self.base
+    }
+
+    /// Returns the highest page frame number described by the database.
+    pub fn highest_pfn(&self) -> u64 {
+        self.highest_pfn
+    }
+
+    /// Returns the size of a single `_MMPFN` entry.
+    pub fn entry_size(&self) -> usize {
+        self.entry_size
+    }
+
+    /// Returns the address of the `_MMPFN` entry for the given page frame number.
+    pub fn entry_address(&self, pfn: u64) -> Result<Address> {
+        if pfn > self.highest_pfn {
+            return Err(Error::Bounds);
+        }
+        Ok(self.base + pfn as usize * self.entry_size)
+    }
+
+    /// Reads and decodes the `_MMPFN` entry for the given page frame number.
+    pub fn entry<V: VirtualMemory>(&self, reader: &mut V, pfn: u64) -> Result<PfnEntry> {
+        let address = self.entry_address(pfn)?;
+        let mut buf = vec![0u8; self.entry_size];
+        reader.virt_read_raw_into(address, &mut buf)?;
+        self.parse(pfn, &buf)
+    }
+
+    /// Decodes the raw bytes of the `_MMPFN` entry for the given page frame number.
+    pub fn parse(&self, pfn: u64, bytes: &[u8]) -> Result<PfnEntry> {
+        if bytes.len() < self.entry_size {
+            return Err(Error::Bounds);
+        }
+
+        let size_addr = self.arch.size_addr();
+        let pte_address = match size_addr {
+            8 => u64::from_le_bytes(
+                bytes[self.pte_address..self.pte_address + 8]
+                    .try_into()
+                    .unwrap(),
+            ),
+            4 => u64::from(u32::from_le_bytes(
+                bytes[self.pte_address..self.pte_address + 4]
+                    .try_into()
+                    .unwrap(),
+            )),
+            _ => {
+                return Err(Error::InvalidArchitecture {
+                    bits: self.arch.bits(),
+                })
+            }
+        };
+
+        // _MMPFN::u3 starts with the 16 bit ReferenceCount followed by the
+        // _MMPFNENTRY bitfield: PageLocation:3, WriteInProgress:1, Modified:1
+        let reference_count = u16::from_le_bytes([bytes[self.u3], bytes[self.u3 + 1]]);
+        let flags = bytes[self.u3 + 2];
+
+        Ok(PfnEntry {
+            pfn,
+            address: self.entry_address(pfn)?,
+            state: PfnState::from_page_location(flags),
+            modified: flags & 0x10 != 0,
+            reference_count,
+            // the lowest bits of PteAddress are used as a lock on newer versions
+            pte_address: Address::from(pte_address & !(size_addr as u64 - 1)),
+        })
+    }
+}
+
+/// Lazy iterator over all entries of the PFN database.
+///
+/// The entries are read in chunks of `PFN_ITER_CHUNK_COUNT`. In case a chunk can not be read
+/// completely (e.g. due to holes in the physical address space) its entries are read one by one
+/// and every entry that is not mapped yields an error.
+///
+/// This iterator is created by `Kernel::pfn_iter`.
+pub struct PfnIter<V> {
+    reader: V,
+    database: PfnDatabase,
+    next_pfn: u64,
+    chunk: Vec<u8>,
+    chunk_start: u64,
+    chunk_count: u64,
+    chunk_valid: bool,
+}
+
+impl<V: VirtualMemory> PfnIter<V> {
+    pub(crate) fn new(reader: V, database: PfnDatabase) -> Self {
+        Self {
+            reader,
+            database,
+            next_pfn: 0,
+            chunk: Vec::new(),
+            chunk_start: 0,
+            chunk_count: 0,
+            chunk_valid: false,
+        }
+    }
+
+    /// Reads the chunk starting at `next_pfn`.
+    fn read_chunk(&mut self) -> Result<()> {
+        let address = self.database.entry_address(self.next_pfn)?;
+        let count = std::cmp::min(
+            PFN_ITER_CHUNK_COUNT as u64,
+            self.database.highest_pfn - self.next_pfn + 1,
+        );
+        self.chunk
+            .resize(count as usize * self.database.entry_size, 0);
+        self.chunk_start = self.next_pfn;
+        self.chunk_count = count;
+        self.chunk_valid = self
+            .reader
+            .virt_read_raw_into(address, &mut self.chunk)
+            .is_ok();
+        Ok(())
+    }
+}
+
+impl<V: VirtualMemory> Iterator for PfnIter<V> {
+    type Item = Result<PfnEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next_pfn > self.database.highest_pfn {
+            return None;
+        }
+
+        let pfn = self.next_pfn;
+        if pfn >= self.chunk_start + self.chunk_count {
+            if let Err(err) = self.read_chunk() {
+                self.next_pfn = self.database.highest_pfn + 1;
+                return Some(Err(err));
+            }
+        }
+        self.next_pfn += 1;
+
+        if self.chunk_valid {
+            let offset = (pfn - self.chunk_start) as usize * self.database.entry_size;
+            Some(self.database.parse(pfn, &self.chunk[offset..]))
+        } else {
+            Some(self.database.entry(&mut self.reader, pfn))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::win32::DummyWin32Builder;
+
+    #[test]
+    fn pfn_database() {
+        let pte_address = Address::from(0xffff_f680_0000_1000u64);
+        let mut builder = DummyWin32Builder::new()
+            .pfn(PfnState::Zeroed, Address::NULL)
+            .pfn(PfnState::Active, pte_address + 1)
+            .pfn(PfnState::Standby, pte_address + 8);
+        for _ in 0..PFN_ITER_CHUNK_COUNT {
+            builder = builder.pfn(PfnState::Modified, pte_address + 0x10);
+        }
+        let mut kernel = builder.build().unwrap();
+
+        let database = kernel.pfn_database().unwrap();
+        assert_eq!(database.highest_pfn(), PFN_ITER_CHUNK_COUNT as u64 + 2);
+
+        // the lock bit is stripped from the pte address
+        let entry = kernel.pfn_info(1).unwrap();
+        assert_eq!(entry.state, PfnState::Active);
+        assert_eq!(entry.pte_address, pte_address);
+        assert_eq!(entry.reference_count, 1);
+        assert_eq!(entry.address, database.base() + database.entry_size());
+        assert!(kernel.pfn_info(0).unwrap().state.is_free());
+        assert_eq!(
+            kernel.pfn_info(PFN_ITER_CHUNK_COUNT as u64 + 3),
+            Err(Error::Bounds)
+        );
+
+        // the last entries are located in a second chunk
+        let entries = kernel
+            .pfn_iter()
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(entries.len(), PFN_ITER_CHUNK_COUNT + 3);
+        assert_eq!(entries[1], kernel.pfn_info(1).unwrap());
+        assert_eq!(entries[2].state, PfnState::Standby);
+        assert!(entries[3..]
+            .iter()
+            .all(|entry| entry.state == PfnState::Modified
+                && entry.pte_address == pte_address + 0x10));
+        assert_eq!(entries.last().unwrap().pfn, PFN_ITER_CHUNK_COUNT as u64 + 2);
+
+        // the database is only available when the offsets have been resolved
+        assert!(DummyWin32Builder::new()
+            .build()
+            .unwrap()
+            .pfn_info(0)
+            .is_err());
+    }
+}
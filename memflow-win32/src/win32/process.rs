@@ -1,17 +1,20 @@
 use std::prelude::v1::*;
 
-use super::{Kernel, Win32ModuleInfo};
-use crate::error::{Error, Result};
+use super::{walk_list_entry, Kernel, ListEntryIter, Win32ModuleInfo, Win32SectionInfo};
+use crate::error::{Error, PartialResultExt, Result};
 use crate::offsets::Win32ArchOffsets;
 use crate::win32::VirtualReadUnicodeString;
 
 use log::trace;
 use std::fmt;
+use std::mem::size_of;
 
 use memflow::architecture::ArchitectureObj;
-use memflow::mem::{PhysicalMemory, VirtualDMA, VirtualMemory, VirtualTranslate};
-use memflow::process::{OsProcessInfo, OsProcessModuleInfo, PID};
-use memflow::types::Address;
+use memflow::mem::{PhysicalMemory, StructReader, VirtualDMA, VirtualMemory, VirtualTranslate};
+use memflow::process::{OsProcessInfo, OsProcessModuleInfo, ProcessMemoryInfo, PID};
+use memflow::types::{size, Address};
+
+use pelite::{self, PeView};
 
 use super::Win32VirtualTranslate;
 
@@ -26,6 +29,264 @@ pub const IMAGE_FILE_NAME_LENGTH: usize = 15;
 
 const MAX_ITER_COUNT: usize = 65536;
 
+/// Upper bound for `_PEB::NumberOfHeaps`
+const MAX_HEAP_COUNT: u32 = 1024;
+
+/// Maximum size of the environment block of a process that will be read
+const MAX_ENVIRONMENT_SIZE: usize = size::mb(1);
+
+/// `_RTL_USER_PROCESS_PARAMETERS::Flags` bit that is set once all string buffers contain absolute pointers
+const RTL_USER_PROC_PARAMS_NORMALIZED: u32 = 0x1;
+
+/// Number of TLS slots that are stored directly in the TEB
+pub const TLS_MINIMUM_AVAILABLE: usize = 64;
+
+/// Offset of the 32-bit TEB of a WoW64 thread relative to its native TEB
+pub const TEB_WOW64_OFFSET: usize = 0x2000;
+
+/// Type of protection a process is running with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub enum Win32ProtectionType {
+    None,
+    ProtectedLight,
+    Protected,
+    Unknown(u8),
+}
+
+/// Signer of a protected process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub enum Win32ProtectionSigner {
+    None,
+    Authenticode,
+    CodeGen,
+    Antimalware,
+    Lsa,
+    Windows,
+    WinTcb,
+    WinSystem,
+    App,
+    Unknown(u8),
+}
+
+/// Protection level of a process as stored in `_EPROCESS::Protection` (`_PS_PROTECTION`)
+///
+/// On windows versions prior to 8.1 this will always be unprotected.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub struct Win32Protection(pub u8);
+
+impl Win32Protection {
+    pub fn protection_type(&self) -> Win32ProtectionType {
+        match self.0 & 0b111 {
+            0 => Win32ProtectionType::None,
+            1 => Win32ProtectionType::ProtectedLight,
+            2 => Win32ProtectionType::Protected,
+            t => Win32ProtectionType::Unknown(t),
+        }
+    }
+
+    pub fn audit(&self) -> bool {
+        (self.0 & 0b1000) != 0
+    }
+
+    pub fn signer(&self) -> Win32ProtectionSigner {
+        match self.0 >> 4 {
+            0 => Win32ProtectionSigner::None,
+            1 => Win32ProtectionSigner::Authenticode,
+            2 => Win32ProtectionSigner::CodeGen,
+            3 => Win32ProtectionSigner::Antimalware,
+            4 => Win32ProtectionSigner::Lsa,
+            5 => Win32ProtectionSigner::Windows,
+            6 => Win32ProtectionSigner::WinTcb,
+            7 => Win32ProtectionSigner::WinSystem,
+            8 => Win32ProtectionSigner::App,
+            s => Win32ProtectionSigner::Unknown(s),
+        }
+    }
+
+    /// Returns true if the process is either a protected process (PP) or a protected process light (PPL)
+    pub fn is_protected(&self) -> bool {
+        self.protection_type() != Win32ProtectionType::None
+    }
+
+    /// Returns true if the process is a protected process light (PPL)
+    pub fn is_protected_light(&self) -> bool {
+        self.protection_type() == Win32ProtectionType::ProtectedLight
+    }
+}
+
+/// Mandatory integrity level of a process token
+///
+/// The integrity level is the last sub authority of the mandatory label SID (S-1-16-X)
+/// that is referenced by `_TOKEN::IntegrityLevelIndex`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub enum IntegrityLevel {
+    Untrusted,
+    Low,
+    Medium,
+    MediumPlus,
+    High,
+    System,
+    Protected,
+    Unknown(u32),
+}
+
+impl IntegrityLevel {
+    /// Returns the relative identifier of the mandatory label SID
+    pub fn rid(&self) -> u32 {
+        match self {
+            IntegrityLevel::Untrusted => 0x0000,
+            IntegrityLevel::Low => 0x1000,
+            IntegrityLevel::Medium => 0x2000,
+            IntegrityLevel::MediumPlus => 0x2100,
+            IntegrityLevel::High => 0x3000,
+            IntegrityLevel::System => 0x4000,
+            IntegrityLevel::Protected => 0x5000,
+            IntegrityLevel::Unknown(rid) => *rid,
+        }
+    }
+}
+
+impl From<u32> for IntegrityLevel {
+    fn from(rid: u32) -> Self {
+        match rid {
+            0x0000 => IntegrityLevel::Untrusted,
+            0x1000 => IntegrityLevel::Low,
+            0x2000 => IntegrityLevel::Medium,
+            0x2100 => IntegrityLevel::MediumPlus,
+            0x3000 => IntegrityLevel::High,
+            0x4000 => IntegrityLevel::System,
+            0x5000 => IntegrityLevel::Protected,
+            rid => IntegrityLevel::Unknown(rid),
+        }
+    }
+}
+
+/// Full path of the image file of a process
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub enum Win32ImagePath {
+    /// Path in the NT device form (e.g. `\Device\HarddiskVolume3\Windows\System32\lsass.exe`)
+    Device(String),
+    /// Path in the DOS form (e.g. `C:\Windows\System32\lsass.exe`)
+    Dos(String),
+}
+
+impl Win32ImagePath {
+    /// Returns the path regardless of its form
+    pub fn as_str(&self) -> &str {
+        match self {
+            Win32ImagePath::Device(path) => path,
+            Win32ImagePath::Dos(path) => path,
+        }
+    }
+}
+
+/// Thread specific information that is stored in the `_TEB` of a thread.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub struct Win32TebInfo {
+    pub address: Address,
+    /// Upper bound of the stack, the stack grows down from here
+    pub stack_base: Address,
+    /// Lower bound of the currently committed stack
+    pub stack_limit: Address,
+    /// The `TLS_MINIMUM_AVAILABLE` slots that are stored directly in the TEB
+    pub tls_slots: Vec<Address>,
+    /// The value returned by `GetLastError`
+    pub last_error: u32,
+}
+
+impl Win32TebInfo {
+    /// Reads the TEB at the given address, `arch` determines the layout of the TEB.
+    ///
+    /// The TLS slots and the last error are zero in case they are paged out.
+    pub fn with_teb<V: VirtualMemory>(
+        mem: &mut V,
+        teb: Address,
+        arch: ArchitectureObj,
+    ) -> Result<Self> {
+        let offsets = Win32ArchOffsets::from(arch);
+
+        let teb_data = StructReader::new(teb)
+            .field_addr(arch, offsets.teb_stack_base)
+            .field_addr(arch, offsets.teb_stack_limit)
+            .field(offsets.teb_last_error, size_of::<u32>())
+            .field(
+                offsets.teb_tls_slots,
+                TLS_MINIMUM_AVAILABLE * arch.size_addr(),
+            )
+            .read(mem)
+            .data_part()?;
+
+        let stack_base = teb_data.get_addr(arch, offsets.teb_stack_base)?;
+        let stack_limit = teb_data.get_addr(arch, offsets.teb_stack_limit)?;
+        trace!("stack_base={:x} stack_limit={:x}", stack_base, stack_limit);
+
+        let last_error = teb_data.get(offsets.teb_last_error).unwrap_or_default();
+        let tls_slots = (0..TLS_MINIMUM_AVAILABLE)
+            .map(|i| {
+                teb_data
+                    .get_addr(arch, offsets.teb_tls_slots + i * arch.size_addr())
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        Ok(Self {
+            address: teb,
+            stack_base,
+            stack_limit,
+            tls_slots,
+            last_error,
+        })
+    }
+}
+
+/// A segment of a NT heap (`_HEAP_SEGMENT`).
+///
+/// The first segment of every heap is the `_HEAP` itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub struct Win32HeapSegment {
+    pub address: Address,
+    /// Start of the memory reserved for this segment
+    pub base_address: Address,
+    /// Size of the memory reserved for this segment
+    pub size: usize,
+    /// The first heap entry (`_HEAP_ENTRY`) of this segment
+    pub first_entry: Address,
+    /// The end of the heap entries of this segment
+    pub last_valid_entry: Address,
+}
+
+/// The decoded `_RTL_USER_PROCESS_PARAMETERS` of a process.
+///
+/// Strings that are not set in the target are empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize))]
+pub struct ProcessParameters {
+    pub address: Address,
+    pub flags: u32,
+    pub console_handle: u64,
+    pub standard_input: u64,
+    pub standard_output: u64,
+    pub standard_error: u64,
+    pub current_directory: String,
+    pub current_directory_handle: u64,
+    pub dll_path: String,
+    pub image_path_name: String,
+    pub command_line: String,
+    /// Entries of the environment block in the form of `NAME=value`
+    pub environment: Vec<String>,
+    pub window_title: String,
+    pub desktop_info: String,
+    pub shell_info: String,
+}
+
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize))]
@@ -69,25 +330,54 @@ impl Win32ModuleListInfo {
         self.module_base
     }
 
+    /// Walks the module list and returns the addresses of all entries.
+    ///
+    /// The list may contain at most `MAX_ITER_COUNT` (65536) entries.
+    /// See `module_entry_list_with_limit` for specifying a custom limit.
     pub fn module_entry_list<V: VirtualMemory>(
         &self,
         mem: &mut V,
         arch: ArchitectureObj,
+    ) -> Result<Vec<Address>> {
+        self.module_entry_list_with_limit(mem, arch, MAX_ITER_COUNT)
+    }
+
+    /// Walks the module list and returns the addresses of at most `max_count` entries.
+    ///
+    /// The walk starts at the first module and stops at the list head
+    /// (the entry that links back to the first module), which is not returned.
+    ///
+    /// In case the list does not terminate within `max_count` entries
+    /// an `Error::ListCorruption` is returned. This guards against malformed or looping lists.
+    pub fn module_entry_list_with_limit<V: VirtualMemory>(
+        &self,
+        mem: &mut V,
+        arch: ArchitectureObj,
+        max_count: usize,
     ) -> Result<Vec<Address>> {
         let mut list = Vec::new();
 
-        let list_start = self.module_base;
-        let mut list_entry = list_start;
-        for _ in 0..MAX_ITER_COUNT {
-            list.push(list_entry);
-            list_entry = mem.virt_read_addr_arch(arch, list_entry)?;
+        // InLoadOrderLinks is the first member of _LDR_DATA_TABLE_ENTRY
+        let entries = ListEntryIter::with_first_entry(
+            mem,
+            arch,
+            self.module_base,
+            0,
+            arch.size_addr(),
+            max_count,
+        );
+        for entry in entries {
+            let entry = entry.map_err(|err| match err {
+                Error::ListCorruption(_) => {
+                    Error::ListCorruption("module list exceeds the maximum number of entries")
+                }
+                err => err,
+            })?;
             // Break on misaligned entry. On NT 4.0 list end is misaligned, maybe it's a flag?
-            if list_entry.is_null()
-                || (list_entry.as_u64() & 0b111) != 0
-                || list_entry == self.module_base
-            {
+            if (entry.as_u64() & 0b111) != 0 {
                 break;
             }
+            list.push(entry);
         }
 
         Ok(list)
@@ -116,6 +406,12 @@ impl Win32ModuleListInfo {
         let name = mem.virt_read_unicode_string(arch, entry + self.offsets.ldr_data_base_name)?;
         trace!("name={}", name);
 
+        let physical_base = mem
+            .virt_page_info(base)
+            .ok()
+            .map(|page| page.page_base + (base.as_usize() & (page.page_size - 1)));
+        trace!("physical_base={:?}", physical_base);
+
         Ok(Win32ModuleInfo {
             peb_entry: entry,
             parent_eprocess,
@@ -123,6 +419,7 @@ impl Win32ModuleListInfo {
             size,
             path,
             name,
+            physical_base,
         })
     }
 }
@@ -141,6 +438,14 @@ pub struct Win32ProcessInfo {
     pub ethread: Address,
     pub wow64: Address,
 
+    // security
+    /// Protection level of the process.
+    /// This is only available when the offsets have been generated from a pdb, otherwise it is unprotected.
+    pub protection: Win32Protection,
+    /// Integrity level of the process token.
+    /// This is only available when the offsets have been generated from a pdb, otherwise it is `None`.
+    pub integrity_level: Option<IntegrityLevel>,
+
     // teb
     pub teb: Option<Address>,
     pub teb_wow64: Option<Address>,
@@ -153,6 +458,11 @@ pub struct Win32ProcessInfo {
     pub module_info_native: Win32ModuleListInfo,
     pub module_info_wow64: Option<Win32ModuleListInfo>,
 
+    // memory usage
+    /// Memory usage of the process.
+    /// This is only available when the offsets have been generated from a pdb, otherwise all values are zero.
+    pub memory_info: ProcessMemoryInfo,
+
     // architecture
     pub sys_arch: ArchitectureObj,
     pub proc_arch: ArchitectureObj,
@@ -224,6 +534,10 @@ impl OsProcessInfo for Win32ProcessInfo {
     fn proc_arch(&self) -> ArchitectureObj {
         self.proc_arch
     }
+
+    fn memory_info(&self) -> ProcessMemoryInfo {
+        self.memory_info
+    }
 }
 
 pub struct Win32Process<T> {
@@ -345,6 +659,239 @@ impl<T: VirtualMemory> Win32Process<T> {
         info.module_entry_list(&mut self.virt_mem, arch)
     }
 
+    /// Reads the stack bounds, TLS slots and last error of a thread from its TEB.
+    ///
+    /// The TEB is interpreted with the layout of the system architecture,
+    /// except for the WoW64 TEB of the first thread (`Win32ProcessInfo::teb_wow64`).
+    /// Use `thread_teb_info_wow64` to read the WoW64 TEB of other threads.
+    pub fn thread_teb_info(&mut self, teb: Address) -> Result<Win32TebInfo> {
+        let arch = if Some(teb) == self.proc_info.teb_wow64 {
+            self.proc_info.proc_arch
+        } else {
+            self.proc_info.sys_arch
+        };
+        Win32TebInfo::with_teb(&mut self.virt_mem, teb, arch)
+    }
+
+    /// Reads the 32-bit TEB that belongs to the given native TEB of a WoW64 thread.
+    pub fn thread_teb_info_wow64(&mut self, teb: Address) -> Result<Win32TebInfo> {
+        if self.proc_info.wow64.is_null() {
+            return Err(Error::Other("process is not a WoW64 process"));
+        }
+        Win32TebInfo::with_teb(
+            &mut self.virt_mem,
+            teb + TEB_WOW64_OFFSET,
+            self.proc_info.proc_arch,
+        )
+    }
+
+    /// Returns the addresses of all heaps (`_HEAP`) of the process.
+    ///
+    /// The heaps are read from `_PEB::ProcessHeaps`, the first entry is the default process heap.
+    /// For WoW64 processes the heaps of the 32-bit PEB are returned.
+    pub fn heap_list(&mut self) -> Result<Vec<Address>> {
+        let arch = self.proc_info.proc_arch;
+        let offsets = Win32ArchOffsets::from(arch);
+        let peb = self.proc_info.peb();
+
+        let peb_data = StructReader::new(peb)
+            .field(offsets.peb_number_of_heaps, size_of::<u32>())
+            .field_addr(arch, offsets.peb_process_heaps)
+            .read(&mut self.virt_mem)
+            .data_part()?;
+        let number_of_heaps: u32 = peb_data.get(offsets.peb_number_of_heaps)?;
+        let process_heaps = peb_data.get_addr(arch, offsets.peb_process_heaps)?;
+        trace!(
+            "number_of_heaps={} process_heaps={:x}",
+            number_of_heaps,
+            process_heaps
+        );
+
+        if number_of_heaps > MAX_HEAP_COUNT {
+            return Err(Error::Other("number of heaps exceeds the maximum"));
+        }
+
+        // all entries of _PEB::ProcessHeaps are read with a single batch
+        let heap_data = (0..number_of_heaps as usize)
+            .fold(StructReader::new(process_heaps), |reader, i| {
+                reader.field_addr(arch, i * arch.size_addr())
+            })
+            .read(&mut self.virt_mem)
+            .data_part()?;
+
+        let mut heaps = Vec::with_capacity(number_of_heaps as usize);
+        for i in 0..number_of_heaps as usize {
+            let heap = heap_data.get_addr(arch, i * arch.size_addr())?;
+            if !heap.is_null() {
+                heaps.push(heap);
+            }
+        }
+        Ok(heaps)
+    }
+
+    /// Reads the `_RTL_USER_PROCESS_PARAMETERS` that are referenced by the PEB of the process.
+    ///
+    /// For WoW64 processes the parameters of the 32-bit PEB are returned.
+    /// The parameters of a process that is still being created do not contain absolute pointers yet,
+    /// in this case an error is returned.
+    pub fn process_parameters(&mut self) -> Result<ProcessParameters> {
+        let arch = self.proc_info.proc_arch;
+        let offsets = Win32ArchOffsets::from(arch);
+
+        let address = self
+            .virt_mem
+            .virt_read_addr_arch(arch, self.proc_info.peb() + offsets.peb_process_parameters)?;
+        if address.is_null() {
+            return Err(Error::Other("process parameters are not available"));
+        }
+        trace!("process_parameters={:x}", address);
+
+        let params = StructReader::new(address)
+            .field(offsets.params_flags, size_of::<u32>())
+            .field_addr(arch, offsets.params_console_handle)
+            .field_addr(arch, offsets.params_standard_input)
+            .field_addr(arch, offsets.params_standard_output)
+            .field_addr(arch, offsets.params_standard_error)
+            .field_addr(arch, offsets.params_cur_dir_handle)
+            .field_addr(arch, offsets.params_environment)
+            .field_addr(arch, offsets.params_environment_size)
+            .read(&mut self.virt_mem)
+            .data_part()?;
+
+        let flags: u32 = params.get(offsets.params_flags)?;
+        if flags & RTL_USER_PROC_PARAMS_NORMALIZED == 0 {
+            return Err(Error::Other("process parameters are not normalized"));
+        }
+
+        let environment = params.get_addr(arch, offsets.params_environment)?;
+        let environment_size = params
+            .get_addr(arch, offsets.params_environment_size)?
+            .as_usize();
+        let environment = if environment.is_null() {
+            Vec::new()
+        } else {
+            self.read_environment(environment, environment_size)?
+        };
+
+        let virt_mem = &mut self.virt_mem;
+        let mut read_string = |offs: usize| {
+            virt_mem
+                .virt_read_unicode_string(arch, address + offs)
+                .unwrap_or_default()
+        };
+
+        Ok(ProcessParameters {
+            address,
+            flags,
+            console_handle: params
+                .get_addr(arch, offsets.params_console_handle)?
+                .as_u64(),
+            standard_input: params
+                .get_addr(arch, offsets.params_standard_input)?
+                .as_u64(),
+            standard_output: params
+                .get_addr(arch, offsets.params_standard_output)?
+                .as_u64(),
+            standard_error: params
+                .get_addr(arch, offsets.params_standard_error)?
+                .as_u64(),
+            current_directory: read_string(offsets.params_cur_dir),
+            current_directory_handle: params
+                .get_addr(arch, offsets.params_cur_dir_handle)?
+                .as_u64(),
+            dll_path: read_string(offsets.params_dll_path),
+            image_path_name: read_string(offsets.params_image_path_name),
+            command_line: read_string(offsets.params_command_line),
+            environment,
+            window_title: read_string(offsets.params_window_title),
+            desktop_info: read_string(offsets.params_desktop_info),
+            shell_info: read_string(offsets.params_shell_info),
+        })
+    }
+
+    /// Reads the `NAME=value` entries of an environment block.
+    ///
+    /// `EnvironmentSize` only exists since windows vista. If it is not set (or out of bounds)
+    /// the block is read page by page until the terminating empty entry is found.
+    /// Blocks without a terminator are cut off after `EnvironmentSize` (or `MAX_ENVIRONMENT_SIZE`) bytes.
+    fn read_environment(&mut self, address: Address, size: usize) -> Result<Vec<String>> {
+        let limit = if size > 0 && size <= MAX_ENVIRONMENT_SIZE {
+            size
+        } else {
+            MAX_ENVIRONMENT_SIZE
+        };
+
+        let mut block = Vec::new();
+        let mut chunk_start = address;
+        while chunk_start < address + limit {
+            let chunk_end = std::cmp::min(
+                (chunk_start + size::kb(4)).as_page_aligned(size::kb(4)),
+                address + limit,
+            );
+            let mut chunk = vec![0u8; chunk_end - chunk_start];
+            self.virt_mem.virt_read_raw_into(chunk_start, &mut chunk)?;
+
+            block.extend(
+                chunk
+                    .chunks_exact(2)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]])),
+            );
+            if block.windows(2).any(|w| w == [0, 0]) {
+                break;
+            }
+            chunk_start = chunk_end;
+        }
+
+        Ok(block
+            .split(|&c| c == 0)
+            .take_while(|entry| !entry.is_empty())
+            .map(String::from_utf16_lossy)
+            .collect())
+    }
+
+    /// Walks the segment list of the given heap (see `heap_list()`).
+    ///
+    /// The layout of `_HEAP` and `_HEAP_SEGMENT` is the one of windows 7 and newer.
+    /// For WoW64 processes the 32-bit layout is used.
+    pub fn heap_segments(&mut self, heap: Address) -> Result<Vec<Win32HeapSegment>> {
+        let arch = self.proc_info.proc_arch;
+        let offsets = Win32ArchOffsets::from(arch);
+
+        let segments = walk_list_entry(
+            &mut self.virt_mem,
+            arch,
+            heap + offsets.heap_segment_list,
+            offsets.heap_segment_list_entry,
+            arch.size_addr(),
+            MAX_ITER_COUNT,
+        )
+        .collect::<Result<Vec<_>>>()?;
+
+        segments
+            .into_iter()
+            .map(|segment| {
+                let segment_data = StructReader::new(segment)
+                    .field_addr(arch, offsets.heap_segment_base_address)
+                    .field(offsets.heap_segment_number_of_pages, size_of::<u32>())
+                    .field_addr(arch, offsets.heap_segment_first_entry)
+                    .field_addr(arch, offsets.heap_segment_last_valid_entry)
+                    .read(&mut self.virt_mem)
+                    .data_part()?;
+                let number_of_pages: u32 =
+                    segment_data.get(offsets.heap_segment_number_of_pages)?;
+
+                Ok(Win32HeapSegment {
+                    address: segment,
+                    base_address: segment_data.get_addr(arch, offsets.heap_segment_base_address)?,
+                    size: number_of_pages as usize * size::kb(4),
+                    first_entry: segment_data.get_addr(arch, offsets.heap_segment_first_entry)?,
+                    last_valid_entry: segment_data
+                        .get_addr(arch, offsets.heap_segment_last_valid_entry)?,
+                })
+            })
+            .collect()
+    }
+
     pub fn module_list(&mut self) -> Result<Vec<Win32ModuleInfo>> {
         let mut vec = Vec::new();
         self.module_list_extend(&mut vec)?;
@@ -385,6 +932,90 @@ impl<T: VirtualMemory> Win32Process<T> {
             .find(|module| module.name() == name)
             .ok_or_else(|| Error::ModuleInfo)
     }
+
+    /// Returns the ASLR slide of the given module.
+    ///
+    /// The slide is the difference between the actual base address of the module
+    /// and the preferred `ImageBase` in its PE optional header.
+    /// Adding the slide to an address taken from the on-disk image
+    /// yields the corresponding address in the process memory.
+    pub fn module_slide(&mut self, module: &Win32ModuleInfo) -> Result<i64> {
+        let header_buf = self.module_header(module)?;
+        let pe = PeView::from_bytes(&header_buf).map_err(Error::PE)?;
+        let image_base = match pe.optional_header() {
+            pelite::Wrap::T32(opt32) => opt32.ImageBase as u64,
+            pelite::Wrap::T64(opt64) => opt64.ImageBase,
+        };
+        trace!("base={:x} image_base={:x}", module.base, image_base);
+
+        Ok(module.base.as_u64().wrapping_sub(image_base) as i64)
+    }
+
+    /// Returns the section table of the given module.
+    ///
+    /// The sections are parsed from the PE headers of the module in the process memory.
+    /// Their addresses are relative to the module base, see `Win32SectionInfo::address`.
+    pub fn module_section_list(
+        &mut self,
+        module: &Win32ModuleInfo,
+    ) -> Result<Vec<Win32SectionInfo>> {
+        let header_buf = self.module_header(module)?;
+        let pe = PeView::from_bytes(&header_buf).map_err(Error::PE)?;
+
+        Ok(pe
+            .section_headers()
+            .iter()
+            .map(|section| Win32SectionInfo {
+                name: section
+                    .name()
+                    .map(String::from)
+                    .unwrap_or_else(|name| String::from_utf8_lossy(name).into_owned()),
+                virtual_address: section.VirtualAddress as usize,
+                virtual_size: section.VirtualSize as usize,
+                characteristics: section.Characteristics,
+            })
+            .inspect(|section| {
+                trace!(
+                    "{} {:x} {:x} {:x}",
+                    section.name,
+                    section.virtual_address,
+                    section.virtual_size,
+                    section.characteristics
+                )
+            })
+            .collect())
+    }
+
+    /// Reads the first page of the module which contains its PE headers.
+    fn module_header(&mut self, module: &Win32ModuleInfo) -> Result<Vec<u8>> {
+        let mut header_buf = vec![0; size::kb(4)];
+        self.virt_mem
+            .virt_read_raw_into(module.base, &mut header_buf)?;
+        Ok(header_buf)
+    }
+
+    /// Returns all regions of the user mode address space that are backed by physical memory.
+    ///
+    /// Adjacent pages are merged into a single `(start, length)` region.
+    /// The regions are built from the page tables of the process and are sorted by their start address,
+    /// which makes them suitable for dumping the entire address space of the process.
+    pub fn readable_regions(&mut self) -> Result<Vec<(Address, usize)>> {
+        let user_end = user_address_end(self.proc_info.sys_arch)?;
+        Ok(self
+            .virt_mem
+            .virt_page_map_range(0, Address::null(), user_end))
+    }
+}
+
+/// Returns the end of the user mode address space on a system of the given architecture.
+///
+/// Large address aware and 4gt setups on 32-bit systems are not taken into account.
+pub(crate) fn user_address_end(sys_arch: ArchitectureObj) -> Result<Address> {
+    match sys_arch.bits() {
+        64 => Ok(Address::from(0x8000_0000_0000_u64)),
+        32 => Ok(Address::from(0x8000_0000_u64)),
+        bits => Err(Error::InvalidArchitecture { bits }),
+    }
 }
 
 impl<T> fmt::Debug for Win32Process<T> {
@@ -392,3 +1023,385 @@ impl<T> fmt::Debug for Win32Process<T> {
         write!(f, "{:?}", self.proc_info)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::win32::{DummyWin32Builder, DummyWin32Process, MODULE_BASE};
+    use memflow::mem::{dummy::DummyMemory, DirectTranslate};
+
+    fn build_kernel() -> Kernel<DummyMemory, DirectTranslate> {
+        DummyWin32Builder::new()
+            .process(
+                DummyWin32Process::new(1000, "explorer.exe")
+                    .module("explorer.exe", size::mb(4))
+                    .module("ntdll.dll", size::mb(2))
+                    .module("kernel32.dll", size::kb(768)),
+            )
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn module_list() {
+        let mut kernel = build_kernel();
+        let proc_info = kernel.process_info_pid(1000).unwrap();
+
+        let mut process = Win32Process::with_kernel_ref(&mut kernel, proc_info);
+        let modules = process.module_list().unwrap();
+        assert_eq!(modules.len(), 3);
+        assert_eq!(modules[1].name(), "ntdll.dll");
+        assert_eq!(modules[1].path, "C:\\Windows\\System32\\ntdll.dll");
+        assert_eq!(modules[1].size(), size::mb(2));
+
+        let main_module = process.main_module_info().unwrap();
+        assert_eq!(main_module.name(), "explorer.exe");
+    }
+
+    #[test]
+    fn module_list_corruption() {
+        let mut kernel = build_kernel();
+        let proc_info = kernel.process_info_pid(1000).unwrap();
+        let module_info = proc_info.module_info();
+        let arch = proc_info.proc_arch;
+
+        let mut process = Win32Process::with_kernel_ref(&mut kernel, proc_info);
+        let entries = process.module_entry_list().unwrap();
+        assert_eq!(entries.len(), 3);
+
+        // let the last module point back to the second one
+        process
+            .virt_mem
+            .virt_write(entries[2], &entries[1].as_u64())
+            .unwrap();
+
+        let result = module_info.module_entry_list_with_limit(&mut process.virt_mem, arch, 16);
+        assert_eq!(
+            result,
+            Err(Error::ListCorruption(
+                "module list exceeds the maximum number of entries"
+            ))
+        );
+    }
+
+    #[test]
+    fn module_dedup() {
+        let process = |pid| {
+            DummyWin32Process::new(pid, "explorer.exe")
+                .module("explorer.exe", size::mb(4))
+                .module("ntdll.dll", size::mb(2))
+                .module("kernel32.dll", size::kb(768))
+        };
+        let mut kernel = DummyWin32Builder::new()
+            .process(process(1000))
+            .process(process(1004))
+            .build()
+            .unwrap();
+
+        // explorer.exe and ntdll.dll are present, kernel32.dll is paged out
+        let dtb = kernel.kernel_info.start_block.dtb;
+        kernel
+            .phys_mem
+            .map_const_base(dtb, Address::from(MODULE_BASE), size::kb(4), &[]);
+        kernel.phys_mem.map_const_base(
+            dtb,
+            Address::from(MODULE_BASE) + size::mb(16),
+            size::kb(4),
+            &[],
+        );
+
+        let mut modules = std::collections::HashSet::new();
+        for &pid in [1000, 1004].iter() {
+            let proc_info = kernel.process_info_pid(pid).unwrap();
+            let mut process = Win32Process::with_kernel_ref(&mut kernel, proc_info);
+            let list = process.module_list().unwrap();
+            assert!(list[0].physical_base.is_some());
+            assert_eq!(list[2].physical_base, None);
+            modules.extend(list);
+        }
+
+        // both processes share the physical pages of the present modules
+        assert_eq!(modules.len(), 4);
+        assert_eq!(
+            modules
+                .iter()
+                .filter(|module| module.name == "kernel32.dll")
+                .count(),
+            2
+        );
+    }
+
+    /// Builds the headers of a x64 PE image with the given `(name, rva, size, characteristics)` sections.
+    fn pe_header(image_base: u64, sections: &[(&str, u32, u32, u32)]) -> Vec<u8> {
+        let mut header = vec![0u8; size::kb(4)];
+        let mut write = |offset: usize, bytes: &[u8]| {
+            header[offset..offset + bytes.len()].copy_from_slice(bytes)
+        };
+
+        let image_size = sections
+            .iter()
+            .map(|&(_, rva, size, _)| (rva + size + 0xfff) & !0xfff)
+            .max()
+            .unwrap_or(0x1000);
+
+        write(0, b"MZ");
+        write(0x3c, &0x40u32.to_le_bytes()); // e_lfanew
+        write(0x40, b"PE\0\0");
+        write(0x44, &0x8664u16.to_le_bytes()); // Machine
+        write(0x46, &(sections.len() as u16).to_le_bytes()); // NumberOfSections
+        write(0x54, &0xf0u16.to_le_bytes()); // SizeOfOptionalHeader
+        write(0x56, &0x2022u16.to_le_bytes()); // Characteristics
+        write(0x58, &0x20bu16.to_le_bytes()); // Magic
+        write(0x70, &image_base.to_le_bytes()); // ImageBase
+        write(0x78, &0x1000u32.to_le_bytes()); // SectionAlignment
+        write(0x7c, &0x200u32.to_le_bytes()); // FileAlignment
+        write(0x90, &image_size.to_le_bytes()); // SizeOfImage
+        write(0x94, &0x1000u32.to_le_bytes()); // SizeOfHeaders
+        write(0x9c, &3u16.to_le_bytes()); // Subsystem
+        write(0xc4, &16u32.to_le_bytes()); // NumberOfRvaAndSizes
+
+        for (i, &(name, rva, size, characteristics)) in sections.iter().enumerate() {
+            let section = 0x148 + i * 0x28;
+            write(section, name.as_bytes());
+            write(section + 0x8, &size.to_le_bytes()); // VirtualSize
+            write(section + 0xc, &rva.to_le_bytes()); // VirtualAddress
+            write(section + 0x10, &size.to_le_bytes()); // SizeOfRawData
+            write(section + 0x14, &rva.to_le_bytes()); // PointerToRawData
+            write(section + 0x24, &characteristics.to_le_bytes()); // Characteristics
+        }
+
+        header
+    }
+
+    #[test]
+    fn module_slide() {
+        let mut kernel = build_kernel();
+        let proc_info = kernel.process_info_pid(1000).unwrap();
+
+        // explorer.exe is loaded below and ntdll.dll above its preferred base,
+        // the header of kernel32.dll is not mapped
+        let dtb = kernel.kernel_info.start_block.dtb;
+        let explorer_base = Address::from(MODULE_BASE);
+        let ntdll_base = Address::from(MODULE_BASE) + size::mb(16);
+        kernel.phys_mem.map_const_base(
+            dtb,
+            explorer_base,
+            size::kb(4),
+            &pe_header(MODULE_BASE + 0x1_0000, &[]),
+        );
+        kernel.phys_mem.map_const_base(
+            dtb,
+            ntdll_base,
+            size::kb(4),
+            &pe_header(0x1_8000_0000, &[]),
+        );
+
+        let mut process = Win32Process::with_kernel_ref(&mut kernel, proc_info);
+
+        let module = process.module_info("explorer.exe").unwrap();
+        assert_eq!(module.base, explorer_base);
+        assert_eq!(process.module_slide(&module).unwrap(), -0x1_0000);
+
+        let module = process.module_info("ntdll.dll").unwrap();
+        assert_eq!(module.base, ntdll_base);
+        assert_eq!(
+            process.module_slide(&module).unwrap(),
+            (ntdll_base.as_u64() - 0x1_8000_0000) as i64
+        );
+
+        let module = process.module_info("kernel32.dll").unwrap();
+        assert!(process.module_slide(&module).is_err());
+    }
+
+    #[test]
+    fn module_section_list() {
+        let mut kernel = build_kernel();
+        let proc_info = kernel.process_info_pid(1000).unwrap();
+
+        let dtb = kernel.kernel_info.start_block.dtb;
+        kernel.phys_mem.map_const_base(
+            dtb,
+            Address::from(MODULE_BASE),
+            size::kb(4),
+            &pe_header(
+                MODULE_BASE,
+                &[
+                    (".text", 0x1000, 0x2345, 0x6000_0020),
+                    (".rdata", 0x4000, 0x1000, 0x4000_0040),
+                    (".data", 0x5000, 0x800, 0xc000_0040),
+                ],
+            ),
+        );
+
+        let mut process = Win32Process::with_kernel_ref(&mut kernel, proc_info);
+        let module = process.module_info("explorer.exe").unwrap();
+        let sections = process.module_section_list(&module).unwrap();
+
+        assert_eq!(
+            sections
+                .iter()
+                .map(|section| section.name.as_str())
+                .collect::<Vec<_>>(),
+            vec![".text", ".rdata", ".data"]
+        );
+
+        assert_eq!(sections[0].address(&module), module.base + 0x1000);
+        assert_eq!(sections[0].virtual_size, 0x2345);
+        assert!(sections[0].is_executable());
+        assert!(sections[0].is_readable());
+        assert!(!sections[0].is_writable());
+
+        assert_eq!(sections[1].address(&module), module.base + 0x4000);
+        assert!(!sections[1].is_executable());
+        assert!(!sections[1].is_writable());
+
+        assert_eq!(sections[2].address(&module), module.base + 0x5000);
+        assert_eq!(sections[2].virtual_size, 0x800);
+        assert!(!sections[2].is_executable());
+        assert!(sections[2].is_writable());
+    }
+
+    #[test]
+    fn thread_teb_info() {
+        let mut kernel = build_kernel();
+        let proc_info = kernel.process_info_pid(1000).unwrap();
+        let teb = proc_info.teb.unwrap();
+
+        let mut process = Win32Process::with_kernel_ref(&mut kernel, proc_info);
+        process
+            .virt_mem
+            .virt_write(teb + 0x8, &0x7000_0000_u64)
+            .unwrap();
+        process
+            .virt_mem
+            .virt_write(teb + 0x10, &0x6ffe_0000_u64)
+            .unwrap();
+        process.virt_mem.virt_write(teb + 0x68, &5u32).unwrap();
+        process
+            .virt_mem
+            .virt_write(teb + 0x1480 + 3 * 8, &0x1234_5678_u64)
+            .unwrap();
+
+        let teb_info = process.thread_teb_info(teb).unwrap();
+        assert_eq!(teb_info.stack_base, Address::from(0x7000_0000));
+        assert_eq!(teb_info.stack_limit, Address::from(0x6ffe_0000));
+        assert_eq!(teb_info.last_error, 5);
+        assert_eq!(teb_info.tls_slots.len(), TLS_MINIMUM_AVAILABLE);
+        assert_eq!(teb_info.tls_slots[3], Address::from(0x1234_5678));
+        assert!(teb_info.tls_slots[4].is_null());
+
+        assert!(process.thread_teb_info_wow64(teb).is_err());
+    }
+
+    #[test]
+    fn heap_list() {
+        let mut kernel = build_kernel();
+        let proc_info = kernel.process_info_pid(1000).unwrap();
+        let peb = proc_info.peb();
+
+        let dtb = kernel.kernel_info.start_block.dtb;
+        let heap_area = Address::from(0x2000_0000_u64);
+        kernel
+            .phys_mem
+            .map_const_base(dtb, heap_area, size::kb(16), &[]);
+
+        let heap_a = heap_area + 0x1000;
+        let heap_b = heap_area + 0x2000;
+        let segment = heap_area + 0x3000;
+
+        let mut process = Win32Process::with_kernel_ref(&mut kernel, proc_info);
+        let mut write_addr = |addr: Address, value: Address| {
+            process.virt_mem.virt_write(addr, &value.as_u64()).unwrap()
+        };
+
+        // _PEB::ProcessHeaps contains a null entry that is skipped
+        write_addr(heap_area, heap_a);
+        write_addr(heap_area + 8, Address::NULL);
+        write_addr(heap_area + 0x10, heap_b);
+        write_addr(peb + 0xf0, heap_area);
+
+        // _HEAP::SegmentList of heap a: heap_a -> segment
+        let (head, first, second) = (heap_a + 0x120, heap_a + 0x18, segment + 0x18);
+        for &(entry, flink, blink) in [
+            (head, first, second),
+            (first, second, head),
+            (second, head, first),
+        ]
+        .iter()
+        {
+            write_addr(entry, flink);
+            write_addr(entry + 8, blink);
+        }
+
+        // _HEAP::SegmentList of heap b: heap_b
+        write_addr(heap_b + 0x120, heap_b + 0x18);
+        write_addr(heap_b + 0x128, heap_b + 0x18);
+        write_addr(heap_b + 0x18, heap_b + 0x120);
+        write_addr(heap_b + 0x20, heap_b + 0x120);
+
+        write_addr(segment + 0x30, Address::from(0x3000_0000_u64));
+        write_addr(segment + 0x40, Address::from(0x3000_0040_u64));
+        write_addr(segment + 0x48, Address::from(0x3010_0000_u64));
+        process
+            .virt_mem
+            .virt_write(segment + 0x38, &0x100u32)
+            .unwrap();
+        process.virt_mem.virt_write(peb + 0xe8, &3u32).unwrap();
+
+        assert_eq!(process.heap_list().unwrap(), vec![heap_a, heap_b]);
+
+        let segments = process.heap_segments(heap_a).unwrap();
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].address, heap_a);
+        assert_eq!(
+            segments[1],
+            Win32HeapSegment {
+                address: segment,
+                base_address: Address::from(0x3000_0000_u64),
+                size: size::mb(1),
+                first_entry: Address::from(0x3000_0040_u64),
+                last_valid_entry: Address::from(0x3010_0000_u64),
+            }
+        );
+        assert_eq!(process.heap_segments(heap_b).unwrap().len(), 1);
+
+        process
+            .virt_mem
+            .virt_write(peb + 0xe8, &0x10000u32)
+            .unwrap();
+        assert!(process.heap_list().is_err());
+    }
+
+    #[test]
+    fn readable_regions() {
+        let mut kernel = build_kernel();
+        let dtb = kernel.kernel_info.start_block.dtb;
+        kernel
+            .phys_mem
+            .map_const_base(dtb, Address::from(MODULE_BASE), size::mb(4), &[]);
+        kernel.phys_mem.map_const_base(
+            dtb,
+            Address::from(MODULE_BASE) + size::mb(4),
+            size::kb(64),
+            &[],
+        );
+        kernel.phys_mem.map_const_base(
+            dtb,
+            Address::from(MODULE_BASE) + size::mb(16),
+            size::kb(4),
+            &[],
+        );
+
+        let proc_info = kernel.process_info_pid(1000).unwrap();
+        let mut process = Win32Process::with_kernel_ref(&mut kernel, proc_info);
+
+        // kernel mappings are not part of the result
+        assert_eq!(
+            process.readable_regions().unwrap(),
+            vec![
+                (Address::from(MODULE_BASE), size::mb(4) + size::kb(64)),
+                (Address::from(MODULE_BASE) + size::mb(16), size::kb(4)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,306 @@
+/*!
+A procfs-like filesystem view of the processes of a `Kernel`.
+
+The `Win32ProcFs` resolves paths of the following layout:
+- `/` contains one directory per process, named after its process id.
+- `/<pid>/maps` lists all readable regions of the process in a format similar to `/proc/<pid>/maps`.
+  Regions that start inside of a module are annotated with the module name.
+- `/<pid>/mem` exposes the virtual address space of the process, the file offset equals the virtual address.
+
+It does not depend on a specific transport so it can serve as the backend of a FUSE or 9p server
+that forwards its `readdir`, `open` and `read` requests. The view is read-only.
+
+Files are opened with `Win32ProcFs::open`, which resolves the process once and snapshots the contents
+of `/<pid>/maps`. Subsequent reads through the returned `Win32ProcFsFile` neither walk the process list
+again nor regenerate the maps, so all chunks of a file are served from the same snapshot.
+
+# Examples
+
+```
+use memflow::mem::{PhysicalMemory, VirtualTranslate};
+use memflow_win32::win32::{Kernel, Win32ProcFs};
+
+fn dump_maps<T: PhysicalMemory, V: VirtualTranslate>(kernel: Kernel<T, V>) {
+    let mut fs = Win32ProcFs::new(kernel);
+    for pid in fs.read_dir("/").unwrap() {
+        let file = fs.open(&format!("/{}/maps", pid)).unwrap();
+        let mut buf = vec![0u8; 0x10000];
+        let len = fs.read_file(&file, 0, &mut buf).unwrap();
+        println!("{}", String::from_utf8_lossy(&buf[..len]));
+    }
+}
+```
+*/
+
+use std::prelude::v1::*;
+
+use super::{Kernel, Win32Process, Win32ProcessInfo};
+use crate::error::{Error, PartialResultExt, Result};
+
+use std::fmt::Write;
+
+use memflow::mem::{PhysicalMemory, VirtualMemory, VirtualTranslate};
+use memflow::process::{OsProcessInfo, OsProcessModuleInfo, PID};
+use memflow::types::Address;
+
+/// A node of the `Win32ProcFs` that a path resolves to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Win32ProcFsNode {
+    /// The root directory that contains all processes
+    Root,
+    /// The directory of a single process
+    Process(PID),
+    /// The list of readable regions of a process
+    Maps(PID),
+    /// The virtual address space of a process
+    Mem(PID),
+}
+
+impl Win32ProcFsNode {
+    /// Parses an absolute path (e.g. `/1234/mem`).
+    pub fn parse(path: &str) -> Result<Self> {
+        let mut components = path.split('/').filter(|c| !c.is_empty());
+
+        let pid = match components.next() {
+            Some(pid) => pid
+                .parse::<PID>()
+                .map_err(|_| Error::Other("invalid process id in path"))?,
+            None => return Ok(Win32ProcFsNode::Root),
+        };
+
+        let node = match components.next() {
+            None => Win32ProcFsNode::Process(pid),
+            Some("maps") => Win32ProcFsNode::Maps(pid),
+            Some("mem") => Win32ProcFsNode::Mem(pid),
+            Some(_) => return Err(Error::Other("file not found")),
+        };
+
+        if components.next().is_some() {
+            return Err(Error::Other("file not found"));
+        }
+        Ok(node)
+    }
+
+    /// Returns true if the node is a directory.
+    pub fn is_dir(self) -> bool {
+        match self {
+            Win32ProcFsNode::Root | Win32ProcFsNode::Process(_) => true,
+            Win32ProcFsNode::Maps(_) | Win32ProcFsNode::Mem(_) => false,
+        }
+    }
+}
+
+/// A file of the `Win32ProcFs` that has been opened with `Win32ProcFs::open`.
+#[derive(Debug, Clone)]
+pub enum Win32ProcFsFile {
+    /// The contents of `/<pid>/maps` at the time the file was opened
+    Maps(Vec<u8>),
+    /// The virtual address space of the process that was resolved when the file was opened
+    Mem(Win32ProcessInfo),
+}
+
+/// Read-only filesystem view of all processes of a `Kernel`.
+pub struct Win32ProcFs<T, V> {
+    kernel: Kernel<T, V>,
+}
+
+impl<T: PhysicalMemory, V: VirtualTranslate> Win32ProcFs<T, V> {
+    pub fn new(kernel: Kernel<T, V>) -> Self {
+        Self { kernel }
+    }
+
+    /// Consumes self and returns the underlying kernel.
+    pub fn destroy(self) -> Kernel<T, V> {
+        self.kernel
+    }
+
+    /// Returns the names of all entries of the directory at the given path.
+    pub fn read_dir(&mut self, path: &str) -> Result<Vec<String>> {
+        match Win32ProcFsNode::parse(path)? {
+            Win32ProcFsNode::Root => Ok(self
+                .kernel
+                .process_info_list()?
+                .iter()
+                .map(|proc_info| proc_info.pid().to_string())
+                .collect()),
+            Win32ProcFsNode::Process(pid) => {
+                self.kernel.process_info_pid(pid)?;
+                Ok(vec!["maps".to_string(), "mem".to_string()])
+            }
+            _ => Err(Error::Other("not a directory")),
+        }
+    }
+
+    /// Opens the file at the given path.
+    ///
+    /// The process is only resolved here, the returned file should be kept around
+    /// for all subsequent reads of the same file.
+    pub fn open(&mut self, path: &str) -> Result<Win32ProcFsFile> {
+        match Win32ProcFsNode::parse(path)? {
+            Win32ProcFsNode::Maps(pid) => {
+                let proc_info = self.kernel.process_info_pid(pid)?;
+                Ok(Win32ProcFsFile::Maps(self.maps(proc_info)?.into_bytes()))
+            }
+            Win32ProcFsNode::Mem(pid) => {
+                Ok(Win32ProcFsFile::Mem(self.kernel.process_info_pid(pid)?))
+            }
+            _ => Err(Error::Other("is a directory")),
+        }
+    }
+
+    /// Reads the opened file starting at `offset` and returns the number of bytes read.
+    ///
+    /// Parts of `/<pid>/mem` that are not readable are zero-filled.
+    pub fn read_file(
+        &mut self,
+        file: &Win32ProcFsFile,
+        offset: u64,
+        buf: &mut [u8],
+    ) -> Result<usize> {
+        match file {
+            Win32ProcFsFile::Maps(maps) => {
+                let start = (offset as usize).min(maps.len());
+                let len = buf.len().min(maps.len() - start);
+                buf[..len].copy_from_slice(&maps[start..start + len]);
+                Ok(len)
+            }
+            Win32ProcFsFile::Mem(proc_info) => {
+                let mut process =
+                    Win32Process::with_kernel_ref(&mut self.kernel, proc_info.clone());
+
+                for b in buf.iter_mut() {
+                    *b = 0;
+                }
+                process
+                    .virt_mem
+                    .virt_read_raw_into(Address::from(offset), buf)
+                    .data_part()?;
+                Ok(buf.len())
+            }
+        }
+    }
+
+    /// Opens the file at the given path and reads it starting at `offset`.
+    ///
+    /// This resolves the process on every call, use `open` and `read_file` when reading a file in chunks.
+    pub fn read(&mut self, path: &str, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        let file = self.open(path)?;
+        self.read_file(&file, offset, buf)
+    }
+
+    /// Generates the contents of `/<pid>/maps`.
+    fn maps(&mut self, proc_info: Win32ProcessInfo) -> Result<String> {
+        let mut process = Win32Process::with_kernel_ref(&mut self.kernel, proc_info);
+
+        let modules = process.module_list().unwrap_or_default();
+        let mut maps = String::new();
+        for (start, size) in process.readable_regions()?.into_iter() {
+            let name = modules
+                .iter()
+                .find(|module| start >= module.base() && start < module.base() + module.size())
+                .map(|module| module.name())
+                .unwrap_or_default();
+            let end = start + size;
+            writeln!(
+                maps,
+                "{:08x}-{:08x} r-- {}",
+                start.as_u64(),
+                end.as_u64(),
+                name
+            )
+            .map_err(|_| Error::Other("unable to format maps"))?;
+        }
+        Ok(maps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::win32::{DummyWin32Builder, DummyWin32Process, MODULE_BASE};
+    use memflow::types::size;
+
+    #[test]
+    fn procfs() {
+        let mut kernel = DummyWin32Builder::new()
+            .process(
+                DummyWin32Process::new(1000, "explorer.exe").module("explorer.exe", size::mb(4)),
+            )
+            .process(DummyWin32Process::new(1004, "notepad.exe"))
+            .build()
+            .unwrap();
+        let dtb = kernel.kernel_info.start_block.dtb;
+        kernel
+            .phys_mem
+            .map_const_base(dtb, Address::from(MODULE_BASE), size::kb(8), &[]);
+        kernel
+            .kernel_virt_mem()
+            .virt_write(Address::from(MODULE_BASE + 0x1000), &0xdead_beef_u32)
+            .unwrap();
+
+        let mut fs = Win32ProcFs::new(kernel);
+        assert_eq!(fs.read_dir("/").unwrap(), vec!["4", "1000", "1004"]);
+        assert_eq!(fs.read_dir("/1000/").unwrap(), vec!["maps", "mem"]);
+        assert!(fs.read_dir("/1000/maps").is_err());
+        assert!(fs.read_dir("/1234").is_err());
+
+        let mut buf = [0u8; 0x100];
+        let len = fs.read("/1000/maps", 0, &mut buf).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&buf[..len]).unwrap(),
+            "7ff700000000-7ff700002000 r-- explorer.exe\n"
+        );
+        let len = fs.read("/1000/maps", 4, &mut buf[..8]).unwrap();
+        assert_eq!(&buf[..len], b"00000000");
+
+        let mut buf = [0u8; 4];
+        assert_eq!(
+            fs.read("/1000/mem", MODULE_BASE + 0x1000, &mut buf)
+                .unwrap(),
+            4
+        );
+        assert_eq!(u32::from_le_bytes(buf), 0xdead_beef);
+
+        // chunks of an opened maps file are served from the snapshot taken on open
+        let maps = fs.open("/1000/maps").unwrap();
+        let dtb = fs.kernel.kernel_info.start_block.dtb;
+        fs.kernel.phys_mem.map_const_base(
+            dtb,
+            Address::from(MODULE_BASE + size::kb(16) as u64),
+            size::kb(4),
+            &[],
+        );
+        let mut buf = [0u8; 0x100];
+        let len = fs.read_file(&maps, 0, &mut buf[..16]).unwrap();
+        let len = len + fs.read_file(&maps, 16, &mut buf[16..]).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&buf[..len]).unwrap(),
+            "7ff700000000-7ff700002000 r-- explorer.exe\n"
+        );
+        let len = fs.read("/1000/maps", 0, &mut buf).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&buf[..len]).unwrap(),
+            "7ff700000000-7ff700002000 r-- explorer.exe\n\
+             7ff700004000-7ff700005000 r-- explorer.exe\n"
+        );
+
+        // an opened mem file does not resolve the process again
+        let mem = fs.open("/1000/mem").unwrap();
+        fs.kernel.offsets.0.eproc_pid = fs.kernel.offsets.0.eproc_link;
+        assert!(fs.open("/1000/mem").is_err());
+        let mut buf = [0u8; 4];
+        assert_eq!(
+            fs.read_file(&mem, MODULE_BASE + 0x1000, &mut buf).unwrap(),
+            4
+        );
+        assert_eq!(u32::from_le_bytes(buf), 0xdead_beef);
+        assert!(fs.open("/1000").is_err());
+
+        assert_eq!(
+            Win32ProcFsNode::parse("/1000/mem").unwrap(),
+            Win32ProcFsNode::Mem(1000)
+        );
+        assert!(Win32ProcFsNode::parse("/explorer.exe").is_err());
+        assert!(Win32ProcFsNode::parse("/1000/mem/x").is_err());
+    }
+}
@@ -0,0 +1,175 @@
+use std::prelude::v1::*;
+
+use crate::error::{Error, Result};
+
+use log::trace;
+use std::collections::BTreeSet;
+
+use memflow::architecture::ArchitectureObj;
+use memflow::mem::VirtualMemory;
+use memflow::types::Address;
+
+/// Maximum depth of a balanced tree.
+///
+/// An AVL tree with a depth of 64 would contain far more nodes than fit into the address space.
+pub const MAX_TREE_DEPTH: usize = 64;
+
+/// Walks a binary tree (e.g. a tree of `_RTL_BALANCED_NODE`s or `_MMADDRESS_NODE`s)
+/// and returns the addresses of all nodes in order.
+///
+/// `left_offset` and `right_offset` are the offsets of the child pointers inside of a node.
+/// For `_RTL_BALANCED_NODE` these are `0` and the pointer size of `arch`.
+///
+/// The tree is traversed with an explicit stack, so malformed trees can not overflow the stack.
+/// An `Error::TreeCorruption` is returned if a node is reachable more than once (e.g. a cycle),
+/// if the tree is deeper than `MAX_TREE_DEPTH` or if it contains more than `max_count` nodes.
+///
+/// # Examples
+///
+/// ```
+/// use memflow::architecture::ArchitectureObj;
+/// use memflow::mem::VirtualMemory;
+/// use memflow::types::Address;
+/// use memflow_win32::error::Result;
+/// use memflow_win32::win32::walk_balanced_tree;
+///
+/// fn vads<V: VirtualMemory>(
+///     virt_mem: &mut V,
+///     arch: ArchitectureObj,
+///     vad_root: Address,
+/// ) -> Result<Vec<Address>> {
+///     walk_balanced_tree(virt_mem, arch, vad_root, 0, arch.size_addr(), 65536)
+/// }
+/// ```
+pub fn walk_balanced_tree<V: VirtualMemory>(
+    reader: &mut V,
+    arch: ArchitectureObj,
+    root: Address,
+    left_offset: usize,
+    right_offset: usize,
+    max_count: usize,
+) -> Result<Vec<Address>> {
+    let mut nodes = Vec::new();
+    let mut visited = BTreeSet::new();
+    let mut stack = Vec::new();
+
+    let mut node = root;
+    let mut depth = 0;
+    loop {
+        // descend to the leftmost node of the current subtree
+        while !node.is_null() {
+            trace!("tree_node={:x} depth={}", node, depth);
+            if depth >= MAX_TREE_DEPTH {
+                return Err(Error::TreeCorruption("tree exceeds the maximum depth"));
+            }
+            if !visited.insert(node) {
+                return Err(Error::TreeCorruption("tree node is referenced twice"));
+            }
+            if visited.len() > max_count {
+                return Err(Error::TreeCorruption(
+                    "tree exceeds the maximum number of nodes",
+                ));
+            }
+
+            stack.push((node, depth));
+            node = reader.virt_read_addr_arch(arch, node + left_offset)?;
+            depth += 1;
+        }
+
+        match stack.pop() {
+            Some((parent, parent_depth)) => {
+                nodes.push(parent);
+                node = reader.virt_read_addr_arch(arch, parent + right_offset)?;
+                depth = parent_depth + 1;
+            }
+            None => break,
+        }
+    }
+
+    Ok(nodes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use memflow::architecture::x86::x64;
+    use memflow::mem::dummy::DummyMemory;
+    use memflow::types::size;
+
+    const NODE_SIZE: usize = 0x18;
+
+    fn write_node<V: VirtualMemory>(mem: &mut V, node: Address, left: Address, right: Address) {
+        mem.virt_write(node, &left.as_u64()).unwrap();
+        mem.virt_write(node + 8, &right.as_u64()).unwrap();
+    }
+
+    #[test]
+    fn walk_tree() {
+        let (mut mem, virt_base) = DummyMemory::new_virt(size::mb(2), size::kb(4), &[]);
+        let node = |i: usize| virt_base + i * NODE_SIZE;
+
+        //       3
+        //     /   \
+        //    1     5
+        //   / \     \
+        //  0   2     6
+        write_node(&mut mem, node(3), node(1), node(5));
+        write_node(&mut mem, node(1), node(0), node(2));
+        write_node(&mut mem, node(5), Address::NULL, node(6));
+        for &i in [0, 2, 6].iter() {
+            write_node(&mut mem, node(i), Address::NULL, Address::NULL);
+        }
+
+        let nodes = walk_balanced_tree(&mut mem, x64::ARCH, node(3), 0, 8, 16).unwrap();
+        assert_eq!(
+            nodes,
+            vec![node(0), node(1), node(2), node(3), node(5), node(6)]
+        );
+
+        assert_eq!(
+            walk_balanced_tree(&mut mem, x64::ARCH, node(3), 0, 8, 4),
+            Err(Error::TreeCorruption(
+                "tree exceeds the maximum number of nodes"
+            ))
+        );
+        assert_eq!(
+            walk_balanced_tree(&mut mem, x64::ARCH, Address::NULL, 0, 8, 16),
+            Ok(Vec::new())
+        );
+
+        // a right child that links back to the root
+        write_node(&mut mem, node(6), Address::NULL, node(3));
+        assert_eq!(
+            walk_balanced_tree(&mut mem, x64::ARCH, node(3), 0, 8, 16),
+            Err(Error::TreeCorruption("tree node is referenced twice"))
+        );
+    }
+
+    #[test]
+    fn walk_tree_depth() {
+        let (mut mem, virt_base) = DummyMemory::new_virt(size::mb(2), size::kb(4), &[]);
+        let node = |i: usize| virt_base + i * NODE_SIZE;
+
+        // a degenerated tree that only consists of right children
+        for i in 0..=MAX_TREE_DEPTH {
+            write_node(&mut mem, node(i), Address::NULL, node(i + 1));
+        }
+        write_node(
+            &mut mem,
+            node(MAX_TREE_DEPTH + 1),
+            Address::NULL,
+            Address::NULL,
+        );
+
+        assert_eq!(
+            walk_balanced_tree(&mut mem, x64::ARCH, node(0), 0, 8, 1024),
+            Err(Error::TreeCorruption("tree exceeds the maximum depth"))
+        );
+        assert_eq!(
+            walk_balanced_tree(&mut mem, x64::ARCH, node(2), 0, 8, 1024)
+                .unwrap()
+                .len(),
+            MAX_TREE_DEPTH
+        );
+    }
+}
@@ -52,8 +52,8 @@ impl<'a, T: VirtualMemory> VirtualReadUnicodeString for T {
         let buffer = match proc_arch.bits() {
             64 => self.virt_read_addr64(addr + 8)?,
             32 => self.virt_read_addr32(addr + 4)?,
-            _ => {
-                return Err(Error::InvalidArchitecture);
+            bits => {
+                return Err(Error::InvalidArchitecture { bits });
             }
         };
         if buffer.is_null() {
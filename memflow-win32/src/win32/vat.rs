@@ -1,6 +1,8 @@
+use std::prelude::v1::*;
+
 use memflow::{
-    architecture::{x86, ArchitectureObj, ScopedVirtualTranslate},
-    error::Error,
+    architecture::{x86, ArchitectureObj, PageWalkStep, ScopedVirtualTranslate},
+    error::{Error, Result},
     iter::SplitAtIndex,
     mem::{PhysicalMemory, VirtualDMA, VirtualMemory, VirtualTranslate},
     types::{Address, PhysicalAddress},
@@ -53,4 +55,13 @@ impl ScopedVirtualTranslate for Win32VirtualTranslate {
     fn arch(&self) -> ArchitectureObj {
         self.sys_arch
     }
+
+    fn virt_page_walk<T: PhysicalMemory + ?Sized>(
+        &self,
+        mem: &mut T,
+        addr: Address,
+    ) -> Result<Vec<PageWalkStep>> {
+        let translator = x86::new_translator(self.dtb, self.sys_arch)?;
+        translator.virt_page_walk(mem, addr)
+    }
 }
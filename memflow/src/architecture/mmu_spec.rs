@@ -1,5 +1,7 @@
 pub(crate) mod translate_data;
 
+use std::prelude::v1::*;
+
 use crate::error::{Error, Result};
 use crate::iter::{PageChunks, SplitAtIndex};
 use crate::mem::{PhysicalMemory, PhysicalReadData};
@@ -63,6 +65,27 @@ pub struct ArchMMUSpec {
     pub large_page_bit: u8,
 }
 
+/// A single step of a page table walk as returned by `ArchMMUSpec::virt_page_walk`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct PageWalkStep {
+    /// Index of the step in the page walk, starting with 0 for the top level page table
+    pub step: usize,
+    /// Physical address of the page table entry
+    pub pte_addr: Address,
+    /// Raw value of the page table entry
+    pub pte: u64,
+    /// Set if the entry is marked as present
+    pub present: bool,
+    /// Set if the entry is marked as writeable
+    pub writeable: bool,
+    /// Set if the entry is marked as non-executable
+    pub nx: bool,
+    /// Size of the page that is mapped by this entry.
+    /// This is `None` if the entry points to the next page table level.
+    pub page_size: Option<usize>,
+}
+
 pub trait MMUTranslationBase {
     fn get_initial_pt(&self, address: Address) -> Address;
 
@@ -279,8 +302,12 @@ impl ArchMMUSpec {
         virt_addr: Address,
         step: usize,
     ) -> PhysicalAddress {
+        // the bits below the page size are not part of the address in large page entries
+        // (e.g. bit 12 selects the PAT entry of 2mb and 1gb pages on x64)
+        let page_size = self.page_size_step(step);
         let phys_addr = Address::from(
-            self.pte_addr_mask(pte_addr, step) | self.virt_addr_to_page_offset(virt_addr, step),
+            (self.pte_addr_mask(pte_addr, step) & !(page_size as u64 - 1))
+                | self.virt_addr_to_page_offset(virt_addr, step),
         );
 
         PhysicalAddress::with_page(
@@ -288,7 +315,7 @@ impl ArchMMUSpec {
             PageType::default()
                 .write(pte_addr.bit_at(self.writeable_bit))
                 .noexec(pte_addr.bit_at(self.nx_bit)),
-            self.page_size_step(step),
+            page_size,
         )
     }
 
@@ -319,6 +346,75 @@ impl ArchMMUSpec {
                 && self.valid_final_page_steps.binary_search(&step).is_ok())
     }
 
+    /// Walks the page tables for a single virtual address and records every visited entry
+    ///
+    /// Unlike `virt_to_phys_iter` this function does not stop at the first error but returns all
+    /// page table entries that have been read up to the point where the translation failed.
+    /// The walk ends at the first entry that is either not present or maps a page.
+    ///
+    /// This is mainly useful for diagnosing failed translations (e.g. an invalid dtb or unmapped pages).
+    ///
+    /// # Arguments
+    ///
+    /// * `mem` - the physical memory the page tables are read from
+    /// * `dtb` - the translation base
+    /// * `virt_addr` - the virtual address to translate
+    pub fn virt_page_walk<T, D>(
+        &self,
+        mem: &mut T,
+        dtb: D,
+        virt_addr: Address,
+    ) -> Result<Vec<PageWalkStep>>
+    where
+        T: PhysicalMemory + ?Sized,
+        D: MMUTranslationBase,
+    {
+        let mut steps = Vec::new();
+        let mut pt_addr = dtb.get_initial_pt(virt_addr);
+
+        for step in 0..self.split_count() - 1 {
+            let pte_addr = self.vtop_step(pt_addr, virt_addr, step);
+
+            let mut buf = [0u8; 8];
+            mem.phys_read_raw_into(
+                PhysicalAddress::with_page(pte_addr, PageType::PAGE_TABLE, self.pt_leaf_size(step)),
+                &mut buf[..self.pte_size],
+            )?;
+            let pte = u64::from_le_bytes(buf);
+            pt_addr = Address::from(pte);
+
+            // the entry read in this step is evaluated by the next step of the walk
+            let present = self.check_entry(pt_addr, step + 1);
+            let page_size = if present && self.is_final_mapping(pt_addr, step + 1) {
+                Some(self.page_size_step_unchecked(step + 1))
+            } else {
+                None
+            };
+
+            vtop_trace!(
+                "page walk step {}: pte_addr={:x} pte={:x}",
+                step,
+                pte_addr,
+                pte
+            );
+            steps.push(PageWalkStep {
+                step,
+                pte_addr,
+                pte,
+                present,
+                writeable: pt_addr.bit_at(self.writeable_bit),
+                nx: pt_addr.bit_at(self.nx_bit),
+                page_size,
+            });
+
+            if !present || page_size.is_some() {
+                break;
+            }
+        }
+
+        Ok(steps)
+    }
+
     /// This function will do a virtual to physical memory translation for the `ArchMMUSpec` in
     /// `MMUTranslationBase` scope, over multiple elements.
     pub(crate) fn virt_to_phys_iter<T, B, D, VI, VO, FO>(
@@ -8,24 +8,30 @@ architecture specific sub-modules.
 Virtual address translations are done using `ScopedVirtualTranslate`
 trait, which is linked to a particular architecture.
 
-Each architecture also has a `ByteOrder` assigned to it.
+Each architecture also has a `Endianess` assigned to it.
 When reading/writing data from/to the target it is necessary
 that memflow know the proper byte order of the target system.
+Primitive types can be converted between the byte order of the target
+and the host via the `Endian` trait.
 */
 
+use std::prelude::v1::*;
+
 pub mod x86;
 
 mod mmu_spec;
 
-pub use mmu_spec::ArchMMUSpec;
+pub use mmu_spec::{ArchMMUSpec, PageWalkStep};
 
 use crate::error::{Error, Result};
 use crate::iter::{FnExtend, SplitAtIndex};
-use crate::mem::PhysicalMemory;
+use crate::mem::{PhysicalMemory, PtrWidth};
 
 use crate::types::{Address, PhysicalAddress};
 pub use bumpalo::{collections::Vec as BumpVec, Bump};
 
+use dataview::Pod;
+
 /// Identifies the byte order of a architecture
 ///
 /// This enum is used when reading/writing to/from the memory of a target system.
@@ -42,6 +48,68 @@ pub enum Endianess {
     BigEndian,
 }
 
+impl Endianess {
+    /// Returns the byte order of the system memflow is currently running on.
+    pub const fn host() -> Self {
+        #[cfg(target_endian = "little")]
+        {
+            Endianess::LittleEndian
+        }
+        #[cfg(target_endian = "big")]
+        {
+            Endianess::BigEndian
+        }
+    }
+}
+
+/// Conversion of primitive types between the byte order of a target and the host.
+///
+/// Arbitrary `Pod` structures can not be converted automatically since their layout is unknown.
+/// Their fields have to be converted individually after reading them.
+///
+/// # Examples
+///
+/// ```
+/// use memflow::architecture::{Endian, Endianess};
+///
+/// let value = 0x1234_u16.to_be().from_endianess(Endianess::BigEndian);
+/// assert_eq!(value, 0x1234);
+/// ```
+pub trait Endian: Pod + Sized {
+    /// Reverses the byte order of the value.
+    fn swap_bytes(self) -> Self;
+
+    /// Converts a value that is stored in the given byte order into the byte order of the host.
+    fn from_endianess(self, endianess: Endianess) -> Self {
+        if endianess == Endianess::host() {
+            self
+        } else {
+            self.swap_bytes()
+        }
+    }
+
+    /// Converts a value from the byte order of the host into the given byte order.
+    fn to_endianess(self, endianess: Endianess) -> Self {
+        // swapping is symmetric
+        self.from_endianess(endianess)
+    }
+}
+
+macro_rules! impl_endian {
+    ($($ty:ty),*) => {
+        $(
+            impl Endian for $ty {
+                #[inline]
+                fn swap_bytes(self) -> Self {
+                    <$ty>::swap_bytes(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_endian!(u8, i8, u16, i16, u32, i32, u64, i64, usize, isize);
+
 /// Translates virtual memory to physical using internal translation base (usually a process' dtb)
 ///
 /// This trait abstracts virtual address translation for a single virtual memory scope.
@@ -90,6 +158,20 @@ pub trait ScopedVirtualTranslate: Clone + Copy + Send {
     fn translation_table_id(&self, address: Address) -> usize;
 
     fn arch(&self) -> ArchitectureObj;
+
+    /// Walks the page tables for the given virtual address and returns all visited page table entries.
+    ///
+    /// This is meant for debugging failed translations. Translators that are not based
+    /// on page tables return an error.
+    fn virt_page_walk<T: PhysicalMemory + ?Sized>(
+        &self,
+        _mem: &mut T,
+        _addr: Address,
+    ) -> Result<Vec<PageWalkStep>> {
+        Err(Error::Other(
+            "page walks are not supported by this translator",
+        ))
+    }
 }
 
 pub trait Architecture: Send + Sync + 'static {
@@ -165,6 +247,58 @@ pub trait Architecture: Send + Sync + 'static {
     ///
     /// ```
     fn address_space_bits(&self) -> u8;
+
+    /// Returns the number of implemented bits of a virtual address for the `Architecture`.
+    ///
+    /// On 64-bit architectures the upper bits of a canonical address are
+    /// sign extended from the highest implemented bit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memflow::architecture::x86::{x64, x64_la57};
+    ///
+    /// assert_eq!(x64::ARCH.virtual_address_bits(), 48);
+    /// assert_eq!(x64_la57::ARCH.virtual_address_bits(), 57);
+    /// ```
+    fn virtual_address_bits(&self) -> u8 {
+        self.bits()
+    }
+
+    /// Returns the native pointer width of the `Architecture`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memflow::architecture::x86::x64;
+    /// use memflow::mem::PtrWidth;
+    ///
+    /// let arch = x64::ARCH;
+    /// assert_eq!(arch.pointer_width().unwrap(), PtrWidth::Ptr64);
+    /// ```
+    fn pointer_width(&self) -> Result<PtrWidth> {
+        match self.bits() {
+            64 => Ok(PtrWidth::Ptr64),
+            32 => Ok(PtrWidth::Ptr32),
+            bits => Err(Error::InvalidArchitecture { bits }),
+        }
+    }
+
+    /// Returns true if a process of this `Architecture` runs in compatibility mode
+    /// (WoW64 on windows) on a system of the given architecture.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memflow::architecture::x86::{x32, x64};
+    ///
+    /// assert!(x32::ARCH.is_wow64_under(x64::ARCH));
+    /// assert!(!x64::ARCH.is_wow64_under(x64::ARCH));
+    /// assert!(!x32::ARCH.is_wow64_under(x32::ARCH));
+    /// ```
+    fn is_wow64_under(&self, sys: ArchitectureObj) -> bool {
+        sys.bits() == 64 && self.bits() == 32
+    }
 }
 
 impl std::fmt::Debug for ArchitectureObj {
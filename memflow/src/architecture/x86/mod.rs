@@ -1,9 +1,12 @@
 pub mod x32;
 pub mod x32_pae;
 pub mod x64;
+pub mod x64_la57;
+
+use std::prelude::v1::*;
 
 use super::{
-    mmu_spec::{translate_data::TranslateVec, ArchMMUSpec, MMUTranslationBase},
+    mmu_spec::{translate_data::TranslateVec, ArchMMUSpec, MMUTranslationBase, PageWalkStep},
     Architecture, ArchitectureObj, Endianess, ScopedVirtualTranslate,
 };
 
@@ -42,6 +45,10 @@ impl Architecture for X86Architecture {
     fn address_space_bits(&self) -> u8 {
         self.mmu.address_space_bits
     }
+
+    fn virtual_address_bits(&self) -> u8 {
+        self.mmu.virtual_address_splits.iter().sum()
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -86,6 +93,14 @@ impl ScopedVirtualTranslate for X86ScopedVirtualTranslate {
     fn arch(&self) -> ArchitectureObj {
         self.arch
     }
+
+    fn virt_page_walk<T: PhysicalMemory + ?Sized>(
+        &self,
+        mem: &mut T,
+        addr: Address,
+    ) -> Result<Vec<PageWalkStep>> {
+        self.arch.mmu.virt_page_walk(mem, self.dtb, addr)
+    }
 }
 
 #[repr(transparent)]
@@ -124,6 +139,8 @@ impl MMUTranslationBase for X86PageTableBase {
 fn underlying_arch(arch: ArchitectureObj) -> Option<&'static X86Architecture> {
     if arch == x64::ARCH {
         Some(&x64::ARCH_SPEC)
+    } else if arch == x64_la57::ARCH {
+        Some(&x64_la57::ARCH_SPEC)
     } else if arch == x32::ARCH {
         Some(&x32::ARCH_SPEC)
     } else if arch == x32_pae::ARCH {
@@ -134,10 +151,63 @@ fn underlying_arch(arch: ArchitectureObj) -> Option<&'static X86Architecture> {
 }
 
 pub fn new_translator(dtb: Address, arch: ArchitectureObj) -> Result<impl ScopedVirtualTranslate> {
-    let arch = underlying_arch(arch).ok_or(Error::InvalidArchitecture)?;
+    let arch = underlying_arch(arch).ok_or(Error::InvalidArchitecture { bits: arch.bits() })?;
     Ok(X86ScopedVirtualTranslate::new(arch, dtb))
 }
 
 pub fn is_x86_arch(arch: ArchitectureObj) -> bool {
     underlying_arch(arch).is_some()
 }
+
+/// Returns the x64 architecture matching the paging mode configured in the given CR4 value.
+///
+/// If the LA57 bit (bit 12) is set the 5-level paging architecture is returned,
+/// otherwise the regular 4-level x64 architecture.
+pub fn x64_arch_from_cr4(cr4: u64) -> ArchitectureObj {
+    if cr4 & (1 << 12) != 0 {
+        x64_la57::ARCH
+    } else {
+        x64::ARCH
+    }
+}
+
+/// Returns true if the given architecture is one of the 64-bit x86 architectures.
+pub fn is_x64_arch(arch: ArchitectureObj) -> bool {
+    arch == x64::ARCH || arch == x64_la57::ARCH
+}
+
+/// Returns the architecture of a process running on a system of the given architecture.
+///
+/// 32-bit processes on a 64-bit system (`wow64 == true`) always use the `x32` architecture,
+/// on 32-bit systems all processes share the architecture of the system.
+pub fn process_arch(sys_arch: ArchitectureObj, wow64: bool) -> Result<ArchitectureObj> {
+    match sys_arch.bits() {
+        64 if wow64 => Ok(x32::ARCH),
+        64 => Ok(sys_arch),
+        32 => Ok(x32::ARCH),
+        bits => Err(Error::InvalidArchitecture { bits }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::PtrWidth;
+
+    #[test]
+    fn process_arch_wow64() {
+        assert_eq!(process_arch(x64::ARCH, false).unwrap(), x64::ARCH);
+        assert_eq!(process_arch(x64_la57::ARCH, false).unwrap(), x64_la57::ARCH);
+        assert_eq!(process_arch(x64::ARCH, true).unwrap(), x32::ARCH);
+        assert_eq!(process_arch(x32_pae::ARCH, false).unwrap(), x32::ARCH);
+
+        assert!(process_arch(x64::ARCH, true)
+            .unwrap()
+            .is_wow64_under(x64::ARCH));
+        assert!(!process_arch(x32_pae::ARCH, false)
+            .unwrap()
+            .is_wow64_under(x32_pae::ARCH));
+        assert_eq!(x32::ARCH.pointer_width().unwrap(), PtrWidth::Ptr32);
+        assert_eq!(x64_la57::ARCH.pointer_width().unwrap(), PtrWidth::Ptr64);
+    }
+}
@@ -30,6 +30,9 @@ pub fn new_translator(dtb: Address) -> impl ScopedVirtualTranslate {
 #[cfg(test)]
 mod tests {
     use crate::architecture::mmu_spec::ArchMMUSpec;
+    use crate::architecture::ScopedVirtualTranslate;
+    use crate::mem::dummy::DummyMemory;
+    use crate::mem::PhysicalMemory;
     use crate::types::{size, Address, PageType};
 
     fn get_mmu_spec() -> ArchMMUSpec {
@@ -204,4 +207,29 @@ mod tests {
         assert_eq!(mmu.is_final_mapping(pte_address, 3), false);
         assert_eq!(mmu.is_final_mapping(pte_address, 4), true);
     }
+
+    #[test]
+    fn x64_translate_1g_page() {
+        let mut mem = DummyMemory::new(size::mb(2));
+        let dtb = Address::from(0x1000);
+        let pdpt = Address::from(0x2000);
+
+        // pml4e 273 -> pdpt, pdpte 5 maps a 1gb page with the PAT bit set
+        let virt_addr =
+            Address::from(0xffff_0000_0000_0000_u64 | (273 << 39) | (5 << 30) | 0x0123_4567);
+        let pml4e = pdpt.as_u64() | 0x3;
+        let pdpte = 0x1_4000_0000_u64 | (1 << 12) | (1 << 7) | 0x3;
+        mem.phys_write((dtb + 273 * 8).into(), &pml4e).unwrap();
+        mem.phys_write((pdpt + 5 * 8).into(), &pdpte).unwrap();
+
+        let translator = super::new_translator(dtb);
+        let phys_addr = translator.virt_to_phys(&mut mem, virt_addr).unwrap();
+        assert_eq!(phys_addr.address(), Address::from(0x1_4123_4567_u64));
+        assert_eq!(phys_addr.page_size(), size::gb(1));
+        assert_eq!(phys_addr.page_base(), Address::from(0x1_4000_0000_u64));
+
+        let walk = translator.virt_page_walk(&mut mem, virt_addr).unwrap();
+        assert_eq!(walk.len(), 2);
+        assert_eq!(walk[1].page_size, Some(size::gb(1)));
+    }
 }
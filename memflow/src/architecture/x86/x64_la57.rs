@@ -0,0 +1,83 @@
+use super::{
+    super::{ArchMMUSpec, ArchitectureObj, Endianess, ScopedVirtualTranslate},
+    X86Architecture, X86ScopedVirtualTranslate,
+};
+
+use crate::types::Address;
+
+/// x64 with 5-level paging enabled (CR4.LA57).
+///
+/// The additional PML5 level extends the virtual address space to 57 bits.
+/// The physical address width is not affected by LA57 and stays at 52 bits.
+pub(super) const ARCH_SPEC: X86Architecture = X86Architecture {
+    bits: 64,
+    endianess: Endianess::LittleEndian,
+    mmu: ArchMMUSpec {
+        virtual_address_splits: &[9, 9, 9, 9, 9, 12],
+        valid_final_page_steps: &[3, 4, 5],
+        address_space_bits: 52,
+        addr_size: 8,
+        pte_size: 8,
+        present_bit: 0,
+        writeable_bit: 1,
+        nx_bit: 63,
+        large_page_bit: 7,
+    },
+};
+
+pub static ARCH: ArchitectureObj = &ARCH_SPEC;
+
+pub fn new_translator(dtb: Address) -> impl ScopedVirtualTranslate {
+    X86ScopedVirtualTranslate::new(&ARCH_SPEC, dtb)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::architecture::mmu_spec::ArchMMUSpec;
+    use crate::types::{size, Address};
+
+    fn get_mmu_spec_la57() -> ArchMMUSpec {
+        super::ARCH_SPEC.mmu
+    }
+
+    #[test]
+    fn x64_la57_split_count() {
+        assert_eq!(get_mmu_spec_la57().split_count(), 6);
+    }
+
+    #[test]
+    fn x64_la57_page_size_level() {
+        let mmu = get_mmu_spec_la57();
+        assert_eq!(mmu.page_size_level(1), size::kb(4));
+        assert_eq!(mmu.page_size_level(2), size::mb(2));
+        assert_eq!(mmu.page_size_level(3), size::gb(1));
+    }
+
+    #[test]
+    fn x64_la57_page_size_step() {
+        let mmu = get_mmu_spec_la57();
+        assert_eq!(mmu.page_size_step(3), size::gb(1));
+        assert_eq!(mmu.page_size_step(4), size::mb(2));
+        assert_eq!(mmu.page_size_step(5), size::kb(4));
+    }
+
+    #[test]
+    fn x64_la57_vtop_step() {
+        let mmu = get_mmu_spec_la57();
+        let indices = [311_usize, 145, 54, 64, 0];
+        let virt_address = indices
+            .iter()
+            .rev()
+            .map(|i| *i as u64)
+            .enumerate()
+            .fold(0, |state, (lvl, idx)| state | (idx << (12 + 9 * lvl)))
+            .into();
+        let pte_address = Address::from(size::kb(4 * 45));
+        for (step, idx) in indices.iter().enumerate() {
+            assert_eq!(
+                mmu.vtop_step(pte_address, virt_address, step),
+                pte_address + (idx * 8)
+            );
+        }
+    }
+}
@@ -7,8 +7,76 @@ use std::prelude::v1::*;
 use crate::error::{Error, Result};
 
 use core::convert::TryFrom;
+use core::fmt;
 use hashbrown::HashMap;
 
+/// Describes a single argument that is accepted by a connector.
+///
+/// Connectors can expose a list of these descriptions to allow tools
+/// to print a usage text and to validate arguments before creating a connector.
+///
+/// # Examples
+///
+/// ```
+/// use memflow::connector::ConnectorArgSpec;
+///
+/// pub static CONNECTOR_ARGS: &[ConnectorArgSpec] = &[
+///     ConnectorArgSpec::required("default", "path to the memory dump"),
+///     ConnectorArgSpec::optional("format", "format of the memory dump", Some("raw")),
+/// ];
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectorArgSpec {
+    /// The name of the argument. The unnamed argument is called `default`.
+    pub name: &'static str,
+    /// A short description of the argument.
+    pub description: &'static str,
+    /// The value that is used by the connector if the argument is not provided.
+    pub default: Option<&'static str>,
+    /// Whether the argument has to be provided.
+    pub required: bool,
+}
+
+impl ConnectorArgSpec {
+    /// Describes an argument that has to be provided.
+    pub const fn required(name: &'static str, description: &'static str) -> Self {
+        Self {
+            name,
+            description,
+            default: None,
+            required: true,
+        }
+    }
+
+    /// Describes an argument that can be omitted.
+    pub const fn optional(
+        name: &'static str,
+        description: &'static str,
+        default: Option<&'static str>,
+    ) -> Self {
+        Self {
+            name,
+            description,
+            default,
+            required: false,
+        }
+    }
+}
+
+impl fmt::Display for ConnectorArgSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if self.required {
+            write!(f, " (required)")?;
+        }
+        write!(f, ": {}", self.description)?;
+        if let Some(default) = self.default {
+            write!(f, " [default: {}]", default)?;
+        }
+        Ok(())
+    }
+}
+
 /// Argument wrapper for connectors
 ///
 /// # Examples
@@ -30,6 +98,23 @@ use hashbrown::HashMap;
 ///     .insert("arg1", "test1")
 ///     .insert("arg2", "test2");
 /// ```
+///
+/// Construct with a validating builder:
+/// ```
+/// use memflow::connector::{ConnectorArgSpec, ConnectorArgs};
+///
+/// let specs = &[
+///     ConnectorArgSpec::required("default", "path to the memory dump"),
+///     ConnectorArgSpec::optional("format", "format of the memory dump", Some("raw")),
+/// ];
+///
+/// let args = ConnectorArgs::builder()
+///     .specs(specs)
+///     .target("./coredump.raw")
+///     .arg("format", "dmp")
+///     .build()
+///     .unwrap();
+/// ```
 #[derive(Debug, Clone)]
 pub struct ConnectorArgs {
     map: HashMap<String, String>,
@@ -43,6 +128,11 @@ impl ConnectorArgs {
         }
     }
 
+    /// Returns a new builder that allows to construct arguments programmatically.
+    pub fn builder<'a>() -> ConnectorArgsBuilder<'a> {
+        ConnectorArgsBuilder::new()
+    }
+
     /// Creates a `ConnectorArgs` struct with a default (unnamed) value.
     pub fn with_default(value: &str) -> Self {
         Self::new().insert("default", value)
@@ -78,6 +168,57 @@ impl ConnectorArgs {
         Ok(Self { map })
     }
 
+    /// Tries to split a url-like target string into the connector name and its arguments.
+    ///
+    /// The target string has the form `connector://default?opt1=val1&opt2=val2`.
+    /// The scheme denotes the name of the connector, the path is placed as the default argument
+    /// and the query contains the named arguments. Both the path and the query are optional.
+    ///
+    /// This function can be used to configure a connector from a single user provided string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memflow::connector::ConnectorArgs;
+    ///
+    /// let (name, args) = ConnectorArgs::from_url("coredump://./dump.raw?format=dmp").unwrap();
+    /// assert_eq!(name, "coredump");
+    /// assert_eq!(args.get_default().unwrap(), "./dump.raw");
+    /// assert_eq!(args.get("format").unwrap(), "dmp");
+    ///
+    /// let (name, args) = ConnectorArgs::from_url("qemu_procfs://win10").unwrap();
+    /// assert_eq!(name, "qemu_procfs");
+    /// assert_eq!(args.get_default().unwrap(), "win10");
+    /// ```
+    pub fn from_url(url: &str) -> Result<(String, Self)> {
+        let idx = url
+            .find("://")
+            .ok_or(Error::Connector("connector url is missing a scheme"))?;
+        let (name, rest) = (&url[..idx], &url[idx + 3..]);
+        if name.is_empty() {
+            return Err(Error::Connector("connector url is missing a scheme"));
+        }
+
+        let (path, query) = match rest.find('?') {
+            Some(idx) => (&rest[..idx], &rest[idx + 1..]),
+            None => (rest, ""),
+        };
+
+        let mut args = Self::new();
+        if !path.is_empty() {
+            args = args.insert("default", path);
+        }
+        for kv in query.split('&').filter(|kv| !kv.is_empty()) {
+            let kvsplit = kv.splitn(2, '=').collect::<Vec<_>>();
+            if kvsplit.len() != 2 || kvsplit[0].is_empty() {
+                return Err(Error::Connector("invalid argument in connector url"));
+            }
+            args = args.insert(kvsplit[0], kvsplit[1]);
+        }
+
+        Ok((name.to_string(), args))
+    }
+
     /// Consumes self, inserts the given key-value pair and returns the self again.
     ///
     /// This function can be used as a builder pattern when programatically
@@ -110,6 +251,34 @@ impl ConnectorArgs {
     pub fn get_default(&self) -> Option<&String> {
         self.get("default")
     }
+
+    /// Validates the arguments against the argument descriptions of a connector.
+    ///
+    /// This function will return an `Error::Connector` if a required argument is missing
+    /// or if an argument is provided that is not part of the descriptions.
+    /// An empty list of descriptions accepts all arguments.
+    pub fn validate(&self, specs: &[ConnectorArgSpec]) -> Result<()> {
+        if specs.is_empty() {
+            return Ok(());
+        }
+
+        if specs
+            .iter()
+            .any(|spec| spec.required && self.get(spec.name).is_none())
+        {
+            return Err(Error::Connector("required connector argument is missing"));
+        }
+
+        if self
+            .map
+            .keys()
+            .any(|key| !specs.iter().any(|spec| spec.name == key))
+        {
+            return Err(Error::Connector("unknown connector argument"));
+        }
+
+        Ok(())
+    }
 }
 
 impl Default for ConnectorArgs {
@@ -118,6 +287,46 @@ impl Default for ConnectorArgs {
     }
 }
 
+/// Builder for `ConnectorArgs`.
+///
+/// In case argument descriptions are provided via `specs()`
+/// the arguments are validated when calling `build()` (see `ConnectorArgs::validate`).
+#[derive(Debug, Clone, Default)]
+pub struct ConnectorArgsBuilder<'a> {
+    args: ConnectorArgs,
+    specs: &'a [ConnectorArgSpec],
+}
+
+impl<'a> ConnectorArgsBuilder<'a> {
+    /// Creates a new builder without any arguments.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the argument descriptions of the connector the arguments are validated against.
+    pub fn specs(mut self, specs: &'a [ConnectorArgSpec]) -> Self {
+        self.specs = specs;
+        self
+    }
+
+    /// Sets the default (unnamed) argument, e.g. the path of a memory dump.
+    pub fn target(self, value: &str) -> Self {
+        self.arg("default", value)
+    }
+
+    /// Sets a named argument, a previously set value for the same key is overwritten.
+    pub fn arg(mut self, key: &str, value: &str) -> Self {
+        self.args = self.args.insert(key, value);
+        self
+    }
+
+    /// Builds the `ConnectorArgs` or returns an `Error::Connector` if the validation fails.
+    pub fn build(self) -> Result<ConnectorArgs> {
+        self.args.validate(self.specs)?;
+        Ok(self.args)
+    }
+}
+
 impl TryFrom<&str> for ConnectorArgs {
     type Error = Error;
 
@@ -165,6 +374,28 @@ mod tests {
         assert_eq!(args.get("opt1").unwrap(), "test1");
     }
 
+    #[test]
+    pub fn from_url() {
+        let (name, args) =
+            ConnectorArgs::from_url("coredump://./dump.raw?format=dmp&writable=true").unwrap();
+        assert_eq!(name, "coredump");
+        let parsed = ConnectorArgs::parse("./dump.raw,format=dmp,writable=true").unwrap();
+        assert_eq!(args.map, parsed.map);
+
+        let (name, args) = ConnectorArgs::from_url("kvm://?pid=1234").unwrap();
+        assert_eq!(name, "kvm");
+        assert_eq!(args.get_default(), None);
+        assert_eq!(args.get("pid").unwrap(), "1234");
+
+        let (name, args) = ConnectorArgs::from_url("dummy://").unwrap();
+        assert_eq!(name, "dummy");
+        assert!(args.map.is_empty());
+
+        assert!(ConnectorArgs::from_url("./dump.raw").is_err());
+        assert!(ConnectorArgs::from_url("://dump.raw").is_err());
+        assert!(ConnectorArgs::from_url("coredump://dump.raw?format").is_err());
+    }
+
     #[test]
     pub fn builder() {
         let args = ConnectorArgs::new()
@@ -174,6 +405,89 @@ mod tests {
         assert_eq!(args.get("arg2").unwrap(), "test2");
     }
 
+    #[test]
+    pub fn typed_builder() {
+        let specs = &[
+            ConnectorArgSpec::required("default", "path to the memory dump"),
+            ConnectorArgSpec::optional("format", "format of the memory dump", Some("raw")),
+            ConnectorArgSpec::optional("writable", "enables writes", Some("false")),
+        ];
+
+        let args = ConnectorArgs::builder()
+            .specs(specs)
+            .target("./coredump.raw")
+            .arg("format", "dmp")
+            .arg("writable", "true")
+            .build()
+            .unwrap();
+        let parsed = ConnectorArgs::parse("./coredump.raw,format=dmp,writable=true").unwrap();
+        assert_eq!(args.map, parsed.map);
+
+        assert_eq!(
+            ConnectorArgs::builder()
+                .specs(specs)
+                .arg("format", "dmp")
+                .build()
+                .unwrap_err(),
+            Error::Connector("required connector argument is missing")
+        );
+        assert_eq!(
+            ConnectorArgs::builder()
+                .specs(specs)
+                .target("./coredump.raw")
+                .arg("size", "4")
+                .build()
+                .unwrap_err(),
+            Error::Connector("unknown connector argument")
+        );
+
+        // without descriptions all arguments are accepted
+        let args = ConnectorArgs::builder().arg("size", "4").build().unwrap();
+        assert_eq!(args.get("size").unwrap(), "4");
+    }
+
+    #[test]
+    pub fn validate() {
+        let specs = &[
+            ConnectorArgSpec::required("default", "path to the memory dump"),
+            ConnectorArgSpec::optional("format", "format of the memory dump", Some("raw")),
+        ];
+
+        assert!(ConnectorArgs::parse("dump.raw,format=elf")
+            .unwrap()
+            .validate(specs)
+            .is_ok());
+        assert_eq!(
+            ConnectorArgs::parse("format=elf").unwrap().validate(specs),
+            Err(Error::Connector("required connector argument is missing"))
+        );
+        assert_eq!(
+            ConnectorArgs::parse("dump.raw,size=4")
+                .unwrap()
+                .validate(specs),
+            Err(Error::Connector("unknown connector argument"))
+        );
+
+        // connectors without descriptions accept everything
+        assert!(ConnectorArgs::parse("size=4")
+            .unwrap()
+            .validate(&[])
+            .is_ok());
+    }
+
+    #[test]
+    pub fn arg_spec_display() {
+        assert_eq!(
+            ConnectorArgSpec::required("default", "path to the memory dump").to_string(),
+            "default (required): path to the memory dump"
+        );
+        assert_eq!(
+            ConnectorArgSpec::optional("format", "format of the memory dump", Some("raw"))
+                .to_string(),
+            "format: format of the memory dump [default: raw]"
+        );
+    }
+
     #[test]
     pub fn parse_empty() {
         let argstr = "opt1=test1,test0";
@@ -0,0 +1,384 @@
+/*!
+Parser for the header of windows full memory dumps.
+
+`parse_coredump_header` only operates on the given byte slice and does not perform any i/o.
+This allows connectors to validate untrusted dump files before mapping them
+and makes the parser suitable as a fuzzing target.
+
+All offsets and sizes that are read from the header are bounds- and overflow-checked,
+a malformed header results in an error instead of a panic.
+
+# Examples
+
+```
+use memflow::connector::coredump::parse_coredump_header;
+use memflow::connector::FileIOMemory;
+
+use std::fs::File;
+use std::io::Read;
+
+fn open(mut file: File) -> memflow::error::Result<()> {
+    let mut header = vec![0u8; 0x2000];
+    file.read_exact(&mut header)
+        .map_err(|_| memflow::error::Error::Connector("unable to read dump header"))?;
+
+    let layout = parse_coredump_header(&header)?;
+    let _connector = FileIOMemory::try_with_reader(file, layout.memory_map())?;
+    Ok(())
+}
+```
+*/
+
+use std::prelude::v1::*;
+
+use crate::architecture::{x86, ArchitectureObj};
+use crate::error::{Error, Result};
+use crate::mem::MemoryMap;
+use crate::types::{size, Address};
+
+use std::convert::TryInto;
+
+const SIGNATURE: &[u8; 4] = b"PAGE";
+const VALID_DUMP_64: &[u8; 4] = b"DU64";
+const VALID_DUMP_32: &[u8; 4] = b"DUMP";
+
+const IMAGE_FILE_MACHINE_I386: u32 = 0x014c;
+const IMAGE_FILE_MACHINE_AMD64: u32 = 0x8664;
+
+/// The dump type of a complete memory dump
+const DUMP_TYPE_FULL: u32 = 1;
+
+/// Offsets into `_DUMP_HEADER64`
+mod header64 {
+    pub const SIZE: usize = 0x2000;
+    pub const DIRECTORY_TABLE_BASE: usize = 0x10;
+    pub const MACHINE_IMAGE_TYPE: usize = 0x30;
+    pub const NUMBER_PROCESSORS: usize = 0x34;
+    pub const PHYSICAL_MEMORY_BLOCK: usize = 0x88;
+    pub const PHYSICAL_MEMORY_BLOCK_SIZE: usize = 0x2c0;
+    pub const DUMP_TYPE: usize = 0xf98;
+}
+
+/// Offsets into `_DUMP_HEADER32`
+mod header32 {
+    pub const SIZE: usize = 0x1000;
+    pub const DIRECTORY_TABLE_BASE: usize = 0x10;
+    pub const MACHINE_IMAGE_TYPE: usize = 0x20;
+    pub const NUMBER_PROCESSORS: usize = 0x24;
+    pub const PAE_ENABLED: usize = 0x5c;
+    pub const PHYSICAL_MEMORY_BLOCK: usize = 0x64;
+    pub const PHYSICAL_MEMORY_BLOCK_SIZE: usize = 0x2bc;
+    pub const DUMP_TYPE: usize = 0xf88;
+}
+
+/// A contiguous range of physical memory that is stored in the dump file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoredumpRun {
+    /// The physical address of the run
+    pub base: Address,
+    /// The size of the run in bytes
+    pub size: usize,
+    /// The offset of the run in the dump file
+    pub file_offset: Address,
+}
+
+/// The physical memory layout and metadata of a dump file.
+#[derive(Debug, Clone)]
+pub struct CoredumpLayout {
+    /// The architecture of the dumped system
+    pub arch: ArchitectureObj,
+    /// The directory table base of the system process
+    pub dtb: Address,
+    /// The number of processors of the dumped system
+    pub num_processors: u32,
+    /// The size of the dump header, the first run starts directly after it
+    pub header_size: usize,
+    /// All physical memory runs in the order they are stored in the file
+    pub runs: Vec<CoredumpRun>,
+}
+
+impl CoredumpLayout {
+    /// Returns the minimum size of a dump file containing all runs.
+    ///
+    /// Connectors should compare this against the actual file size before mapping the file.
+    pub fn file_size(&self) -> usize {
+        self.runs
+            .last()
+            .map(|run| run.file_offset.as_usize() + run.size)
+            .unwrap_or(self.header_size)
+    }
+
+    /// Creates a memory map which maps the physical runs to their offsets in the dump file.
+    ///
+    /// The resulting map can be used with `FileIOMemory` or `MappedPhysicalMemory`.
+    pub fn memory_map(&self) -> MemoryMap<(Address, usize)> {
+        let mut mem_map = MemoryMap::new();
+        for run in self.runs.iter() {
+            mem_map.push_remap(run.base, run.size, run.file_offset);
+        }
+        mem_map
+    }
+}
+
+/// Parses the header of a windows full memory dump (`.dmp`).
+///
+/// Both 32-bit (`PAGEDUMP`) and 64-bit (`PAGEDU64`) headers are supported.
+/// Only complete memory dumps can be parsed, other dump types do not contain the entire physical memory.
+///
+/// The slice has to contain at least the entire header (0x1000 bytes for 32-bit, 0x2000 bytes for 64-bit dumps).
+pub fn parse_coredump_header(bytes: &[u8]) -> Result<CoredumpLayout> {
+    if bytes.len() < 8 || &bytes[0..4] != SIGNATURE {
+        return Err(Error::Connector("invalid dump signature"));
+    }
+
+    match &bytes[4..8] {
+        sig if sig == VALID_DUMP_64 => parse_header64(bytes),
+        sig if sig == VALID_DUMP_32 => parse_header32(bytes),
+        _ => Err(Error::Connector("invalid dump signature")),
+    }
+}
+
+fn parse_header64(bytes: &[u8]) -> Result<CoredumpLayout> {
+    use header64::*;
+
+    if bytes.len() < SIZE {
+        return Err(Error::Connector("dump header is truncated"));
+    }
+
+    if read_u32(bytes, MACHINE_IMAGE_TYPE)? != IMAGE_FILE_MACHINE_AMD64 {
+        return Err(Error::Connector("unsupported machine type in dump header"));
+    }
+    if read_u32(bytes, DUMP_TYPE)? != DUMP_TYPE_FULL {
+        return Err(Error::Connector("only full memory dumps are supported"));
+    }
+
+    // _PHYSICAL_MEMORY_DESCRIPTOR64 { NumberOfRuns: u32, NumberOfPages: u64, Run: [_PHYSICAL_MEMORY_RUN64] }
+    let block = &bytes[PHYSICAL_MEMORY_BLOCK..PHYSICAL_MEMORY_BLOCK + PHYSICAL_MEMORY_BLOCK_SIZE];
+    let num_runs = read_u32(block, 0)? as usize;
+    if num_runs > (PHYSICAL_MEMORY_BLOCK_SIZE - 0x10) / 0x10 {
+        return Err(Error::Connector(
+            "too many physical memory runs in dump header",
+        ));
+    }
+    let runs = (0..num_runs)
+        .map(|i| {
+            let offs = 0x10 + i * 0x10;
+            Ok((read_u64(block, offs)?, read_u64(block, offs + 8)?))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(CoredumpLayout {
+        arch: x86::x64::ARCH,
+        dtb: Address::from(read_u64(bytes, DIRECTORY_TABLE_BASE)?),
+        num_processors: read_u32(bytes, NUMBER_PROCESSORS)?,
+        header_size: SIZE,
+        runs: build_runs(runs, SIZE, read_u64(block, 8)?)?,
+    })
+}
+
+fn parse_header32(bytes: &[u8]) -> Result<CoredumpLayout> {
+    use header32::*;
+
+    if bytes.len() < SIZE {
+        return Err(Error::Connector("dump header is truncated"));
+    }
+
+    if read_u32(bytes, MACHINE_IMAGE_TYPE)? != IMAGE_FILE_MACHINE_I386 {
+        return Err(Error::Connector("unsupported machine type in dump header"));
+    }
+    if read_u32(bytes, DUMP_TYPE)? != DUMP_TYPE_FULL {
+        return Err(Error::Connector("only full memory dumps are supported"));
+    }
+
+    // _PHYSICAL_MEMORY_DESCRIPTOR32 { NumberOfRuns: u32, NumberOfPages: u32, Run: [_PHYSICAL_MEMORY_RUN32] }
+    let block = &bytes[PHYSICAL_MEMORY_BLOCK..PHYSICAL_MEMORY_BLOCK + PHYSICAL_MEMORY_BLOCK_SIZE];
+    let num_runs = read_u32(block, 0)? as usize;
+    if num_runs > (PHYSICAL_MEMORY_BLOCK_SIZE - 0x8) / 0x8 {
+        return Err(Error::Connector(
+            "too many physical memory runs in dump header",
+        ));
+    }
+    let runs = (0..num_runs)
+        .map(|i| {
+            let offs = 0x8 + i * 0x8;
+            Ok((
+                u64::from(read_u32(block, offs)?),
+                u64::from(read_u32(block, offs + 4)?),
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let arch = if bytes[PAE_ENABLED] != 0 {
+        x86::x32_pae::ARCH
+    } else {
+        x86::x32::ARCH
+    };
+
+    Ok(CoredumpLayout {
+        arch,
+        dtb: Address::from(read_u32(bytes, DIRECTORY_TABLE_BASE)?),
+        num_processors: read_u32(bytes, NUMBER_PROCESSORS)?,
+        header_size: SIZE,
+        runs: build_runs(runs, SIZE, u64::from(read_u32(block, 4)?))?,
+    })
+}
+
+/// Converts the (base page, page count) pairs of the header into runs
+/// and validates them against the total number of pages.
+fn build_runs(
+    runs: Vec<(u64, u64)>,
+    header_size: usize,
+    num_pages: u64,
+) -> Result<Vec<CoredumpRun>> {
+    let page_size = size::kb(4) as u64;
+
+    let mut file_offset = header_size as u64;
+    let mut total_pages = 0u64;
+    let mut out = Vec::with_capacity(runs.len());
+    for (base_page, page_count) in runs.into_iter() {
+        let invalid = || Error::Connector("invalid physical memory run in dump header");
+
+        let base = base_page.checked_mul(page_size).ok_or_else(invalid)?;
+        let size = page_count.checked_mul(page_size).ok_or_else(invalid)?;
+        base.checked_add(size).ok_or_else(invalid)?;
+        let next_offset = file_offset.checked_add(size).ok_or_else(invalid)?;
+        total_pages = total_pages.checked_add(page_count).ok_or_else(invalid)?;
+
+        // the entire run has to be addressable in the file on this platform
+        let _: usize = next_offset.try_into().map_err(|_| invalid())?;
+
+        if size > 0 {
+            out.push(CoredumpRun {
+                base: Address::from(base),
+                size: size as usize,
+                file_offset: Address::from(file_offset),
+            });
+        }
+        file_offset = next_offset;
+    }
+
+    if total_pages != num_pages {
+        return Err(Error::Connector(
+            "number of pages in dump header does not match its runs",
+        ));
+    }
+
+    Ok(out)
+}
+
+fn read_u32(bytes: &[u8], offs: usize) -> Result<u32> {
+    bytes
+        .get(offs..offs + 4)
+        .and_then(|b| b.try_into().ok())
+        .map(u32::from_le_bytes)
+        .ok_or(Error::Connector("dump header is truncated"))
+}
+
+fn read_u64(bytes: &[u8], offs: usize) -> Result<u64> {
+    bytes
+        .get(offs..offs + 8)
+        .and_then(|b| b.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or(Error::Connector("dump header is truncated"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header64(runs: &[(u64, u64)]) -> Vec<u8> {
+        let mut buf = vec![0u8; header64::SIZE];
+        buf[0..8].copy_from_slice(b"PAGEDU64");
+        buf[0x10..0x18].copy_from_slice(&0x1aa000u64.to_le_bytes());
+        buf[0x30..0x34].copy_from_slice(&IMAGE_FILE_MACHINE_AMD64.to_le_bytes());
+        buf[0x34..0x38].copy_from_slice(&4u32.to_le_bytes());
+        buf[0xf98..0xf9c].copy_from_slice(&DUMP_TYPE_FULL.to_le_bytes());
+
+        let block = header64::PHYSICAL_MEMORY_BLOCK;
+        let num_pages = runs.iter().map(|(_, count)| count).sum::<u64>();
+        buf[block..block + 4].copy_from_slice(&(runs.len() as u32).to_le_bytes());
+        buf[block + 8..block + 0x10].copy_from_slice(&num_pages.to_le_bytes());
+        for (i, (base, count)) in runs.iter().enumerate() {
+            let offs = block + 0x10 + i * 0x10;
+            buf[offs..offs + 8].copy_from_slice(&base.to_le_bytes());
+            buf[offs + 8..offs + 0x10].copy_from_slice(&count.to_le_bytes());
+        }
+        buf
+    }
+
+    #[test]
+    fn parse_header64() {
+        let layout = parse_coredump_header(&header64(&[(0x1, 0x9e), (0x100, 0x200)])).unwrap();
+        assert_eq!(layout.arch, x86::x64::ARCH);
+        assert_eq!(layout.dtb, Address::from(0x1aa000));
+        assert_eq!(layout.num_processors, 4);
+        assert_eq!(
+            layout.runs,
+            vec![
+                CoredumpRun {
+                    base: Address::from(0x1000),
+                    size: 0x9e000,
+                    file_offset: Address::from(0x2000),
+                },
+                CoredumpRun {
+                    base: Address::from(0x100000),
+                    size: 0x200000,
+                    file_offset: Address::from(0xa0000),
+                },
+            ]
+        );
+        assert_eq!(layout.file_size(), 0x2a0000);
+
+        assert_eq!(
+            layout.memory_map().coverage(),
+            vec![
+                (Address::from(0x1000), 0x9e000),
+                (Address::from(0x100000), 0x200000)
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_header32() {
+        let mut buf = vec![0u8; header32::SIZE];
+        buf[0..8].copy_from_slice(b"PAGEDUMP");
+        buf[0x10..0x14].copy_from_slice(&0x39000u32.to_le_bytes());
+        buf[0x20..0x24].copy_from_slice(&IMAGE_FILE_MACHINE_I386.to_le_bytes());
+        buf[0x5c] = 1;
+        buf[0xf88..0xf8c].copy_from_slice(&DUMP_TYPE_FULL.to_le_bytes());
+        buf[0x64..0x68].copy_from_slice(&1u32.to_le_bytes());
+        buf[0x68..0x6c].copy_from_slice(&0x10u32.to_le_bytes());
+        buf[0x70..0x74].copy_from_slice(&0x10u32.to_le_bytes());
+
+        let layout = parse_coredump_header(&buf).unwrap();
+        assert_eq!(layout.arch, x86::x32_pae::ARCH);
+        assert_eq!(layout.dtb, Address::from(0x39000));
+        assert_eq!(layout.runs.len(), 1);
+        assert_eq!(layout.runs[0].file_offset, Address::from(0x1000));
+        assert_eq!(layout.file_size(), 0x11000);
+    }
+
+    #[test]
+    fn malformed_headers() {
+        assert!(parse_coredump_header(&[]).is_err());
+        assert!(parse_coredump_header(b"PAGEDU64").is_err());
+
+        let header = header64(&[(0x1, 0x10)]);
+        assert!(parse_coredump_header(&header[..header.len() - 1]).is_err());
+
+        // the number of runs exceeds the physical memory block
+        let mut header = header64(&[]);
+        header[header64::PHYSICAL_MEMORY_BLOCK..header64::PHYSICAL_MEMORY_BLOCK + 4]
+            .copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(parse_coredump_header(&header).is_err());
+
+        // page counts that overflow the address space
+        assert!(parse_coredump_header(&header64(&[(u64::MAX / 0x1000, 0x10)])).is_err());
+        assert!(parse_coredump_header(&header64(&[(0x1, u64::MAX / 0x800)])).is_err());
+
+        // mismatching number of pages
+        let mut header = header64(&[(0x1, 0x10)]);
+        header[header64::PHYSICAL_MEMORY_BLOCK + 8] = 0x11;
+        assert!(parse_coredump_header(&header).is_err());
+    }
+}
@@ -0,0 +1,177 @@
+/*!
+Access to the cpu state of the target.
+
+Some connectors do not only provide access to the physical memory of the target
+but also to the state of its cpus (e.g. hypervisor based connectors or coredumps that embed a register context).
+Those connectors can implement the `CpuState` trait and return it via `PhysicalMemory::cpu_state()`.
+
+This allows tools to retrieve the currently active page table base (`cr3`) of every cpu
+directly instead of scanning physical memory for it.
+
+# Examples
+
+```
+use memflow::connector::CpuState;
+use memflow::mem::PhysicalMemory;
+
+fn print_dtbs<T: PhysicalMemory>(mem: &mut T) {
+    if let Some(cpu_state) = mem.cpu_state() {
+        for cpu_id in 0..cpu_state.cpu_count() {
+            if let Ok(registers) = cpu_state.read_registers(cpu_id) {
+                println!("cpu {}: dtb={:x}", cpu_id, registers.dtb());
+            }
+        }
+    } else {
+        println!("connector does not provide cpu state access");
+    }
+}
+# use memflow::mem::dummy::DummyMemory;
+# use memflow::types::size;
+# print_dtbs(&mut DummyMemory::new(size::mb(4)));
+```
+*/
+
+use crate::error::Result;
+use crate::types::Address;
+
+/// Register values of a single cpu.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct RegisterSet {
+    /// Page table base register
+    pub cr3: Address,
+    /// Instruction pointer
+    pub rip: Address,
+    /// Stack pointer
+    pub rsp: Address,
+}
+
+impl RegisterSet {
+    /// Returns the physical address of the page table base with all flag bits
+    /// (e.g. the pcid) of `cr3` stripped.
+    pub fn dtb(&self) -> Address {
+        Address::from(self.cr3.as_u64() & !0xfff)
+    }
+}
+
+/// The `CpuState` trait is implemented by connectors which are able to
+/// retrieve the register state of the cpus of the target.
+pub trait CpuState
+where
+    Self: Send,
+{
+    /// Returns the number of cpus of the target.
+    fn cpu_count(&self) -> usize;
+
+    /// Reads the current register values of the cpu with the given id.
+    ///
+    /// The id has to be smaller than `cpu_count()`, otherwise an `Error::Bounds` is returned.
+    fn read_registers(&mut self, cpu_id: usize) -> Result<RegisterSet>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use crate::mem::dummy::DummyMemory;
+    use crate::mem::{
+        PhysicalMemory, PhysicalMemoryCapabilities, PhysicalMemoryMetadata, PhysicalReadData,
+        PhysicalWriteData, SharedPhysicalMemory,
+    };
+    use crate::types::size;
+
+    struct RegisterContext(Vec<RegisterSet>);
+
+    impl CpuState for RegisterContext {
+        fn cpu_count(&self) -> usize {
+            self.0.len()
+        }
+
+        fn read_registers(&mut self, cpu_id: usize) -> Result<RegisterSet> {
+            self.0.get(cpu_id).copied().ok_or(Error::Bounds)
+        }
+    }
+
+    struct Coredump {
+        mem: DummyMemory,
+        context: RegisterContext,
+    }
+
+    impl PhysicalMemory for Coredump {
+        fn phys_read_raw_list(&mut self, data: &mut [PhysicalReadData]) -> Result<()> {
+            self.mem.phys_read_raw_list(data)
+        }
+
+        fn phys_write_raw_list(&mut self, data: &[PhysicalWriteData]) -> Result<()> {
+            self.mem.phys_write_raw_list(data)
+        }
+
+        fn metadata(&self) -> PhysicalMemoryMetadata {
+            let mut metadata = self.mem.metadata();
+            metadata
+                .capabilities
+                .insert(PhysicalMemoryCapabilities::CPU_STATE);
+            metadata
+        }
+
+        fn cpu_state(&mut self) -> Option<&mut dyn CpuState> {
+            Some(&mut self.context)
+        }
+    }
+
+    fn read_dtb<T: PhysicalMemory>(mem: &mut T, cpu_id: usize) -> Option<Result<Address>> {
+        mem.cpu_state()
+            .map(|cpu_state| cpu_state.read_registers(cpu_id).map(|regs| regs.dtb()))
+    }
+
+    #[test]
+    fn register_context() {
+        let mut mem = Coredump {
+            mem: DummyMemory::new(size::mb(1)),
+            context: RegisterContext(vec![
+                RegisterSet {
+                    cr3: Address::from(0x1aa000_u64 | 0x2),
+                    rip: Address::from(0xffff_f800_1234_0000_u64),
+                    rsp: Address::from(0xffff_f800_5678_0000_u64),
+                },
+                RegisterSet {
+                    cr3: Address::from(0x1bb000_u64),
+                    ..Default::default()
+                },
+            ]),
+        };
+
+        assert_eq!(read_dtb(&mut mem, 0), Some(Ok(Address::from(0x1aa000_u64))));
+        assert_eq!(
+            read_dtb(&mut &mut mem, 1),
+            Some(Ok(Address::from(0x1bb000_u64)))
+        );
+        assert_eq!(read_dtb(&mut mem, 2), Some(Err(Error::Bounds)));
+
+        let mut boxed: Box<dyn PhysicalMemory> = Box::new(mem);
+        assert_eq!(
+            read_dtb(&mut boxed, 1),
+            Some(Ok(Address::from(0x1bb000_u64)))
+        );
+
+        assert_eq!(read_dtb(&mut DummyMemory::new(size::mb(1)), 0), None);
+    }
+
+    #[test]
+    fn cpu_state_capability() {
+        let mem = Coredump {
+            mem: DummyMemory::new(size::mb(1)),
+            context: RegisterContext(vec![RegisterSet::default()]),
+        };
+        assert!(mem.metadata().capabilities.supports_cpu_state());
+        assert!(!DummyMemory::new(size::mb(1))
+            .metadata()
+            .capabilities
+            .supports_cpu_state());
+
+        // the shared wrapper does not hand out the cpu state of the memory behind its lock
+        let mut shared = SharedPhysicalMemory::new(mem);
+        assert!(!shared.metadata().capabilities.supports_cpu_state());
+        assert!(read_dtb(&mut shared, 0).is_none());
+    }
+}
@@ -5,7 +5,8 @@ Basic connector which works on file i/o operations (`Seek`, `Read`, `Write`).
 use crate::error::{Error, Result};
 use crate::iter::FnExtend;
 use crate::mem::{
-    MemoryMap, PhysicalMemory, PhysicalMemoryMetadata, PhysicalReadData, PhysicalWriteData,
+    MemoryMap, PhysicalMemory, PhysicalMemoryCapabilities, PhysicalMemoryMetadata,
+    PhysicalReadData, PhysicalWriteData,
 };
 use crate::types::Address;
 
@@ -37,6 +38,13 @@ impl<T: Seek + Read + Write + Send> FileIOMemory<T> {
     pub fn try_with_reader(reader: T, mem_map: MemoryMap<(Address, usize)>) -> Result<Self> {
         Ok(Self { reader, mem_map })
     }
+
+    /// Returns all physical address ranges that are backed by the underlying file.
+    ///
+    /// See `MemoryMap::coverage` for details.
+    pub fn coverage(&self) -> Vec<(Address, usize)> {
+        self.mem_map.coverage()
+    }
 }
 
 impl<T: Seek + Read + Write + Send> PhysicalMemory for FileIOMemory<T> {
@@ -83,6 +91,7 @@ impl<T: Seek + Read + Write + Send> PhysicalMemory for FileIOMemory<T> {
                 .map(|map| map.base().as_usize() + map.output().1)
                 .unwrap(),
             readonly: false,
+            capabilities: PhysicalMemoryCapabilities::WRITE,
         }
     }
 }
@@ -5,7 +5,7 @@ Connector inventory interface.
 use crate::error::{Error, Result};
 use crate::mem::{CloneablePhysicalMemory, PhysicalMemoryBox};
 
-use super::ConnectorArgs;
+use super::{ConnectorArgSpec, ConnectorArgs};
 
 use std::fs::read_dir;
 use std::path::{Path, PathBuf};
@@ -16,7 +16,7 @@ use log::{debug, error, info, warn};
 use libloading::Library;
 
 /// Exported memflow connector version
-pub const MEMFLOW_CONNECTOR_VERSION: i32 = 5;
+pub const MEMFLOW_CONNECTOR_VERSION: i32 = 6;
 
 /// Type of a single connector instance
 pub type ConnectorType = PhysicalMemoryBox;
@@ -33,6 +33,11 @@ pub struct ConnectorDescriptor {
     /// This name will be used when loading a connector from a connector inventory.
     pub name: &'static str,
 
+    /// Descriptions of all arguments that are accepted by the connector.
+    ///
+    /// An empty list disables the validation of the arguments.
+    pub args: &'static [ConnectorArgSpec],
+
     /// The factory function for the connector.
     /// Calling this function will produce new connector instances.
     pub factory: extern "C" fn(args: &ConnectorArgs) -> Result<ConnectorType>,
@@ -206,10 +211,57 @@ impl ConnectorInventory {
             .collect::<Vec<_>>()
     }
 
+    /// Returns the descriptions of all arguments that are accepted by the connector with the given name.
+    ///
+    /// In case no connector could be found this will throw an `Error::Connector`.
+    pub fn connector_args(&self, name: &str) -> Result<&[ConnectorArgSpec]> {
+        self.connectors
+            .iter()
+            .find(|c| c.name == name)
+            .map(Connector::args)
+            .ok_or(Error::Connector("connector not found"))
+    }
+
+    /// Returns a usage text for the connector with the given name.
+    ///
+    /// The text lists all arguments that are accepted by the connector and
+    /// can be printed by command line tools.
+    ///
+    /// In case no connector could be found this will throw an `Error::Connector`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use memflow::connector::ConnectorInventory;
+    ///
+    /// let inventory = unsafe {
+    ///     ConnectorInventory::scan_path("./")
+    /// }.unwrap();
+    /// println!("{}", inventory.connector_help("coredump").unwrap());
+    /// ```
+    pub fn connector_help(&self, name: &str) -> Result<String> {
+        let args = self.connector_args(name)?;
+        if args.is_empty() {
+            return Ok(format!(
+                "connector '{}' does not describe its arguments",
+                name
+            ));
+        }
+
+        Ok(std::iter::once(format!(
+            "connector '{}' accepts the following arguments:",
+            name
+        ))
+        .chain(args.iter().map(|arg| format!("    {}", arg)))
+        .collect::<Vec<_>>()
+        .join("\n"))
+    }
+
     /// Tries to create a new connector instance for the connector with the given name.
     /// The connector will be initialized with the args provided to this call.
     ///
     /// In case no connector could be found this will throw an `Error::Connector`.
+    /// The same error is returned if the args do not match the arguments described by the connector.
     ///
     /// # Safety
     ///
@@ -316,6 +368,7 @@ impl ConnectorInventory {
 pub struct Connector {
     _library: Arc<Library>,
     name: String,
+    args: &'static [ConnectorArgSpec],
     factory: extern "C" fn(args: &ConnectorArgs) -> Result<ConnectorType>,
 }
 
@@ -355,10 +408,24 @@ impl Connector {
         Ok(Self {
             _library: Arc::new(library),
             name: desc.name.to_string(),
+            args: desc.args,
             factory: desc.factory,
         })
     }
 
+    /// Returns the name of the connector.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the descriptions of all arguments that are accepted by this connector.
+    ///
+    /// The descriptions are owned by the connector library, therefore they are
+    /// only borrowed for as long as this connector is alive.
+    pub fn args(&self) -> &[ConnectorArgSpec] {
+        self.args
+    }
+
     /// Creates a new connector instance from this library.
     /// The connector is initialized with the arguments provided to this function.
     ///
@@ -371,6 +438,19 @@ impl Connector {
     ///
     /// It is adviced to use a proc macro for defining a connector.
     pub unsafe fn create(&self, args: &ConnectorArgs) -> Result<ConnectorInstance> {
+        if let Err(err) = args.validate(self.args) {
+            error!(
+                "invalid arguments for connector '{}'. {}",
+                self.name,
+                self.args
+                    .iter()
+                    .map(|arg| arg.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            return Err(err);
+        }
+
         let connector_res = (self.factory)(args);
 
         if let Err(err) = connector_res {
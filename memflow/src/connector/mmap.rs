@@ -2,10 +2,13 @@
 Basic connector which works on mapped memory.
 */
 
+use std::prelude::v1::*;
+
 use crate::error::{Error, Result};
-use crate::iter::FnExtend;
+use crate::iter::{FnExtend, SplitAtIndexNoMutation};
 use crate::mem::{
-    MemoryMap, PhysicalMemory, PhysicalMemoryMetadata, PhysicalReadData, PhysicalWriteData,
+    MemoryMap, PhysicalMemory, PhysicalMemoryCapabilities, PhysicalMemoryMetadata,
+    PhysicalReadData, PhysicalWriteData,
 };
 use crate::types::Address;
 
@@ -83,6 +86,48 @@ impl<T: AsRef<[u8]>, F: AsRef<MemoryMap<T>>> MappedPhysicalMemory<T, F> {
     }
 }
 
+impl<T: SplitAtIndexNoMutation, F: AsRef<MemoryMap<T>>> MappedPhysicalMemory<T, F> {
+    /// Returns all physical address ranges that are backed by the underlying mappings.
+    ///
+    /// See `MemoryMap::coverage` for details.
+    pub fn coverage(&self) -> Vec<(Address, usize)> {
+        self.info.as_ref().coverage()
+    }
+}
+
+impl<'a, F: AsRef<MemoryMap<&'a [u8]>>> MappedPhysicalMemory<&'a [u8], F> {
+    /// Borrows `len` bytes at the physical address `addr` directly from the underlying mapping.
+    ///
+    /// Unlike `phys_read_raw_list` no data is copied, the returned slice points into the mapping
+    /// (e.g. a memory mapped dump file) and is valid as long as the connector is borrowed.
+    ///
+    /// The requested range has to be covered by a single mapping,
+    /// ranges that are unmapped or cross a mapping boundary result in an `Error::OutOfBounds`.
+    /// In this case `max` contains the end of the mapping that contains `addr`,
+    /// or `addr` itself if it is not mapped.
+    pub fn phys_read_borrow(&self, addr: Address, len: usize) -> Result<&[u8]> {
+        if len == 0 {
+            return Ok(&[]);
+        }
+
+        // the mapping is looked up the same way as in `phys_read_raw_list`,
+        // a rejected range in front of the first chunk ends up in `fails`
+        let mut fails: Vec<(Address, usize)> = Vec::new();
+        let first = self.info.as_ref().map(addr, len, &mut fails).next();
+
+        let start = addr.as_usize();
+        let max = match first {
+            Some((buf, chunk_len)) if chunk_len == len => return Ok(buf),
+            Some((_, chunk_len)) if fails.is_empty() => start + chunk_len,
+            _ => start,
+        };
+        Err(Error::OutOfBounds {
+            requested: start.saturating_add(len),
+            max,
+        })
+    }
+}
+
 impl<'a, F: AsRef<MemoryMap<&'a mut [u8]>> + Send> PhysicalMemory
     for MappedPhysicalMemory<&'a mut [u8], F>
 {
@@ -122,6 +167,7 @@ impl<'a, F: AsRef<MemoryMap<&'a mut [u8]>> + Send> PhysicalMemory
                 .map(|map| map.base().as_usize() + map.output().len())
                 .unwrap(),
             readonly: false,
+            capabilities: PhysicalMemoryCapabilities::WRITE,
         }
     }
 }
@@ -155,6 +201,86 @@ impl<'a, F: AsRef<MemoryMap<&'a [u8]>> + Send> PhysicalMemory
                 .map(|map| map.base().as_usize() + map.output().len())
                 .unwrap(),
             readonly: true,
+            capabilities: PhysicalMemoryCapabilities::NONE,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_borrow() {
+        let low = (0..0x100).map(|i| i as u8).collect::<Vec<_>>();
+        let high = vec![0xffu8; 0x100];
+        let mut map = MemoryMap::new();
+        map.push(Address::from(0x1000), &low[..]);
+        map.push(Address::from(0x1100), &high[..]);
+        map.push(Address::from(0x3000), &low[..]);
+        let mut mem = MappedPhysicalMemory::with_info(map);
+
+        let buf = mem.phys_read_borrow(Address::from(0x1010), 0x10).unwrap();
+        assert_eq!(buf, &low[0x10..0x20]);
+        assert_eq!(buf.as_ptr(), low[0x10..].as_ptr());
+        assert_eq!(
+            mem.phys_read_borrow(Address::from(0x30f0), 0x10).unwrap(),
+            &low[0xf0..]
+        );
+
+        // the borrowed data matches the copying read path
+        let mut out = vec![0u8; 0x10];
+        mem.phys_read_raw_into(Address::from(0x1010).into(), &mut out)
+            .unwrap();
+        assert_eq!(&out[..], &low[0x10..0x20]);
+
+        // ranges across mappings and unmapped ranges can not be borrowed
+        assert_eq!(
+            mem.phys_read_borrow(Address::from(0x10f0), 0x20),
+            Err(Error::OutOfBounds {
+                requested: 0x1110,
+                max: 0x1100
+            })
+        );
+        assert_eq!(
+            mem.phys_read_borrow(Address::from(0x2000), 0x10),
+            Err(Error::OutOfBounds {
+                requested: 0x2010,
+                max: 0x2000
+            })
+        );
+        assert_eq!(
+            mem.phys_read_borrow(Address::from(0x30f0), usize::MAX),
+            Err(Error::OutOfBounds {
+                requested: usize::MAX,
+                max: 0x3100
+            })
+        );
+    }
+
+    #[test]
+    fn read_borrow_many_mappings() {
+        // enough mappings for the lookup to use a binary search
+        let bufs = (0..0x40u8).map(|i| vec![i; 0x100]).collect::<Vec<_>>();
+        let mut map = MemoryMap::new();
+        for (i, buf) in bufs.iter().enumerate() {
+            map.push(Address::from(i as u64 * 0x1000), &buf[..]);
+        }
+        let mem = MappedPhysicalMemory::with_info(map);
+
+        for (i, buf) in bufs.iter().enumerate() {
+            let addr = Address::from(i as u64 * 0x1000 + 0x80);
+            assert_eq!(
+                mem.phys_read_borrow(addr, 0x80).unwrap().as_ptr(),
+                buf[0x80..].as_ptr()
+            );
+        }
+        assert_eq!(
+            mem.phys_read_borrow(Address::from(0x3f080), 0x100),
+            Err(Error::OutOfBounds {
+                requested: 0x3f180,
+                max: 0x3f100
+            })
+        );
+    }
+}
@@ -11,7 +11,15 @@ The inventory system is feature gated behind the `inventory` feature.
 
 pub mod args;
 #[doc(hidden)]
-pub use args::ConnectorArgs;
+pub use args::{ConnectorArgSpec, ConnectorArgs, ConnectorArgsBuilder};
+
+pub mod coredump;
+#[doc(hidden)]
+pub use coredump::{parse_coredump_header, CoredumpLayout, CoredumpRun};
+
+pub mod cpu_state;
+#[doc(hidden)]
+pub use cpu_state::{CpuState, RegisterSet};
 
 #[cfg(feature = "inventory")]
 pub mod inventory;
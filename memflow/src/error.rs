@@ -22,6 +22,10 @@ pub enum Error {
     ///
     /// Catch-all for bounds check errors.
     Bounds,
+    /// Out of bounds physical memory access.
+    ///
+    /// The requested address lies beyond the size reported by `PhysicalMemory::metadata()`.
+    OutOfBounds { requested: usize, max: usize },
     /// IO error
     ///
     /// Catch-all for io related errors.
@@ -29,7 +33,8 @@ pub enum Error {
     /// Invalid Architecture error.
     ///
     /// The architecture provided is not a valid argument for the given function.
-    InvalidArchitecture,
+    /// `bits` contains the pointer width of the offending architecture.
+    InvalidArchitecture { bits: u8 },
     /// Connector error
     ///
     /// Catch-all for connector related errors
@@ -80,8 +85,9 @@ impl Error {
             Error::Other(e) => ("other error", Some(e)),
             Error::Partial => ("partial error", None),
             Error::Bounds => ("out of bounds", None),
+            Error::OutOfBounds { .. } => ("physical memory access out of bounds", None),
             Error::IO(e) => ("io error", Some(e)),
-            Error::InvalidArchitecture => ("invalid architecture", None),
+            Error::InvalidArchitecture { .. } => ("invalid architecture", None),
             Error::Connector(e) => ("connector error", Some(e)),
             Error::PhysicalMemory(e) => ("physical memory error", Some(e)),
             Error::VirtualTranslate => ("virtual address translation failed", None),
@@ -100,7 +106,11 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let (desc, value) = self.to_str_pair();
 
-        if let Some(value) = value {
+        if let Error::OutOfBounds { requested, max } = self {
+            write!(f, "{}: {:x} (max {:x})", desc, requested, max)
+        } else if let Error::InvalidArchitecture { bits } = self {
+            write!(f, "{}: {}-bit", desc, bits)
+        } else if let Some(value) = value {
             write!(f, "{}: {}", desc, value)
         } else {
             f.write_str(desc)
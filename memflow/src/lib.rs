@@ -20,6 +20,20 @@ For non-rust libraries, it is possible to use the [FFI](https://github.com/memfl
 to interface with the library.
 
 You will almost always import this module when working with memflow.
+
+# no_std support
+
+The physical and virtual memory read path (`PhysicalMemory`, `VirtualMemory`, the
+virtual address translation and the architecture definitions) only depends on `core` and `alloc`.
+Disabling the default features builds memflow as a `no_std` crate:
+
+```toml
+memflow = { version = "0.1", default-features = false }
+```
+
+Everything that requires an operating system (loading connectors via `inventory`,
+file backed connectors, memory map files, `TimeoutPhysicalMemory` and the shared wrappers)
+is only available with the `std` feature. Enabling any of the corresponding features also enables `std`.
 */
 
 #![cfg_attr(not(feature = "std"), no_std)]
@@ -0,0 +1,305 @@
+/*!
+Alignment wrapper for connector objects that implement the `PhysicalMemory` trait.
+
+Some memory backends (e.g. certain DMA devices or memory mapped register windows) only support
+physical accesses that are aligned to a specific word size and will either fail or return garbage otherwise.
+
+The `AlignedPhysicalMemory` extends every read to the enclosing aligned range
+and only copies out the requested bytes afterwards.
+Unaligned writes are converted into a read-modify-write of the enclosing aligned range.
+
+# Examples
+
+```
+use memflow::mem::{AlignedPhysicalMemory, PhysicalMemory};
+
+fn read<T: PhysicalMemory>(connector: T) {
+    let mut mem = AlignedPhysicalMemory::new(connector, 8).unwrap();
+
+    // results in an 8 byte read at 0x1000
+    let _value: u16 = mem.phys_read(0x1003.into()).unwrap();
+}
+# use memflow::mem::dummy::DummyMemory;
+# use memflow::types::size;
+# read(DummyMemory::new(size::mb(4)));
+```
+*/
+
+use std::prelude::v1::*;
+
+use crate::connector::CpuState;
+use crate::error::{Error, Result};
+use crate::mem::phys_mem::{
+    PhysicalMemory, PhysicalMemoryMetadata, PhysicalReadData, PhysicalWriteData,
+};
+use crate::types::{Address, PhysicalAddress};
+
+/// Wrapper that transparently aligns all accesses to the underlying memory.
+///
+/// Since this wrapper implements `PhysicalMemory` it can be used as a replacement
+/// in all structs and functions that require a `PhysicalMemory` object.
+#[derive(Clone)]
+pub struct AlignedPhysicalMemory<T> {
+    mem: T,
+    alignment: usize,
+}
+
+impl<T: PhysicalMemory> AlignedPhysicalMemory<T> {
+    /// Creates a new wrapper that aligns all accesses to `alignment` bytes.
+    ///
+    /// The alignment has to be a power of two.
+    pub fn new(mem: T, alignment: usize) -> Result<Self> {
+        if alignment == 0 || !alignment.is_power_of_two() {
+            return Err(Error::Other("alignment must be a power of two"));
+        }
+
+        Ok(Self { mem, alignment })
+    }
+
+    /// Returns the alignment of all accesses to the underlying memory.
+    pub fn alignment(&self) -> usize {
+        self.alignment
+    }
+
+    /// Consumes the wrapper and returns the underlying memory.
+    pub fn destroy(self) -> T {
+        self.mem
+    }
+
+    /// Returns the enclosing aligned range of the given access.
+    fn aligned_range(&self, addr: PhysicalAddress, len: usize) -> (PhysicalAddress, usize) {
+        let start = addr.address().as_page_aligned(self.alignment);
+        let end = (addr.address() + (len + self.alignment - 1)).as_page_aligned(self.alignment);
+        (
+            PhysicalAddress::with_page(start, addr.page_type(), addr.page_size()),
+            end - start,
+        )
+    }
+
+    fn is_aligned(&self, addr: PhysicalAddress, len: usize) -> bool {
+        (addr.as_usize() | len) & (self.alignment - 1) == 0
+    }
+
+    /// Writes all entries with a single read-modify-write cycle.
+    ///
+    /// The aligned ranges of the entries must not overlap.
+    fn write_batch(&mut self, data: &[PhysicalWriteData]) -> Result<()> {
+        let ranges = data
+            .iter()
+            .map(|PhysicalWriteData(addr, data)| self.aligned_range(*addr, data.len()))
+            .collect::<Vec<_>>();
+
+        let mut bufs = ranges
+            .iter()
+            .map(|&(_, len)| vec![0u8; len])
+            .collect::<Vec<_>>();
+
+        // only unaligned entries have to be read first
+        {
+            let mut list = data
+                .iter()
+                .zip(ranges.iter())
+                .zip(bufs.iter_mut())
+                .filter(|((PhysicalWriteData(addr, data), _), _)| {
+                    !self.is_aligned(*addr, data.len())
+                })
+                .map(|((_, &(addr, _)), buf)| PhysicalReadData(addr, &mut buf[..]))
+                .collect::<Vec<_>>();
+            if !list.is_empty() {
+                self.mem.phys_read_raw_list(&mut list)?;
+            }
+        }
+
+        for ((PhysicalWriteData(addr, data), (start, _)), buf) in
+            data.iter().zip(ranges.iter()).zip(bufs.iter_mut())
+        {
+            let offset = addr.address() - start.address();
+            buf[offset..offset + data.len()].copy_from_slice(data);
+        }
+
+        let list = ranges
+            .iter()
+            .zip(bufs.iter())
+            .map(|(&(addr, _), buf)| PhysicalWriteData(addr, &buf[..]))
+            .collect::<Vec<_>>();
+        self.mem.phys_write_raw_list(&list)
+    }
+}
+
+impl<T: PhysicalMemory> PhysicalMemory for AlignedPhysicalMemory<T> {
+    fn phys_read_raw_list(&mut self, data: &mut [PhysicalReadData]) -> Result<()> {
+        if data
+            .iter()
+            .all(|PhysicalReadData(addr, out)| self.is_aligned(*addr, out.len()))
+        {
+            return self.mem.phys_read_raw_list(data);
+        }
+
+        let ranges = data
+            .iter()
+            .map(|PhysicalReadData(addr, out)| self.aligned_range(*addr, out.len()))
+            .collect::<Vec<_>>();
+
+        // pre-fill the aligned buffers with the current output
+        // so that parts which can not be read are left untouched
+        let mut bufs = data
+            .iter()
+            .zip(ranges.iter())
+            .map(|(PhysicalReadData(addr, out), &(start, len))| {
+                let mut buf = vec![0u8; len];
+                let offset = addr.address() - start.address();
+                buf[offset..offset + out.len()].copy_from_slice(out);
+                buf
+            })
+            .collect::<Vec<_>>();
+
+        {
+            let mut list = ranges
+                .iter()
+                .zip(bufs.iter_mut())
+                .map(|(&(addr, _), buf)| PhysicalReadData(addr, &mut buf[..]))
+                .collect::<Vec<_>>();
+            self.mem.phys_read_raw_list(&mut list)?;
+        }
+
+        for ((PhysicalReadData(addr, out), (start, _)), buf) in
+            data.iter_mut().zip(ranges.iter()).zip(bufs.iter())
+        {
+            let offset = addr.address() - start.address();
+            out.copy_from_slice(&buf[offset..offset + out.len()]);
+        }
+
+        Ok(())
+    }
+
+    fn phys_write_raw_list(&mut self, data: &[PhysicalWriteData]) -> Result<()> {
+        if data
+            .iter()
+            .all(|PhysicalWriteData(addr, data)| self.is_aligned(*addr, data.len()))
+        {
+            return self.mem.phys_write_raw_list(data);
+        }
+
+        // entries whose aligned ranges overlap have to be written in separate batches,
+        // otherwise the read-modify-write of a later entry would revert an earlier one
+        let mut start = 0;
+        let mut pending: Vec<(Address, Address)> = Vec::new();
+        for (i, PhysicalWriteData(addr, buf)) in data.iter().enumerate() {
+            let (aligned, len) = self.aligned_range(*addr, buf.len());
+            let range = (aligned.address(), aligned.address() + len);
+            if pending
+                .iter()
+                .any(|&(other_start, other_end)| range.0 < other_end && other_start < range.1)
+            {
+                self.write_batch(&data[start..i])?;
+                start = i;
+                pending.clear();
+            }
+            pending.push(range);
+        }
+
+        self.write_batch(&data[start..])
+    }
+
+    /// `cpu_state()` and `prefetch()` are forwarded,
+    /// so the capabilities of the underlying memory apply as they are.
+    fn metadata(&self) -> PhysicalMemoryMetadata {
+        self.mem.metadata()
+    }
+
+    fn cpu_state(&mut self) -> Option<&mut dyn CpuState> {
+        self.mem.cpu_state()
+    }
+
+    fn prefetch(&mut self, ranges: &[(PhysicalAddress, usize)]) -> Result<()> {
+        let ranges = ranges
+            .iter()
+            .map(|&(addr, len)| self.aligned_range(addr, len))
+            .collect::<Vec<_>>();
+        self.mem.prefetch(&ranges)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::conformance::assert_phys_mem_conformance;
+    use crate::mem::dummy::DummyMemory;
+    use crate::types::size;
+
+    /// Memory that rejects all accesses which are not aligned to 8 bytes
+    struct StrictMemory(DummyMemory);
+
+    impl PhysicalMemory for StrictMemory {
+        fn phys_read_raw_list(&mut self, data: &mut [PhysicalReadData]) -> Result<()> {
+            if data
+                .iter()
+                .any(|PhysicalReadData(addr, out)| (addr.as_usize() | out.len()) & 7 != 0)
+            {
+                return Err(Error::Connector("unaligned read"));
+            }
+            self.0.phys_read_raw_list(data)
+        }
+
+        fn phys_write_raw_list(&mut self, data: &[PhysicalWriteData]) -> Result<()> {
+            if data
+                .iter()
+                .any(|PhysicalWriteData(addr, data)| (addr.as_usize() | data.len()) & 7 != 0)
+            {
+                return Err(Error::Connector("unaligned write"));
+            }
+            self.0.phys_write_raw_list(data)
+        }
+
+        fn metadata(&self) -> PhysicalMemoryMetadata {
+            self.0.metadata()
+        }
+    }
+
+    fn build_mem() -> AlignedPhysicalMemory<StrictMemory> {
+        let mut mem = DummyMemory::new(size::mb(1));
+        let buf = (0..size::kb(4)).map(|i| i as u8).collect::<Vec<_>>();
+        mem.phys_write_raw(0x0.into(), &buf).unwrap();
+        AlignedPhysicalMemory::new(StrictMemory(mem), 8).unwrap()
+    }
+
+    #[test]
+    fn unaligned_read() {
+        let mut mem = build_mem();
+        assert_eq!(
+            mem.phys_read_raw(0x3.into(), 7).unwrap(),
+            vec![0x3, 0x4, 0x5, 0x6, 0x7, 0x8, 0x9]
+        );
+        assert_eq!(mem.phys_read::<u16>(0x10f.into()).unwrap(), 0x100f);
+    }
+
+    #[test]
+    fn unaligned_write() {
+        let mut mem = build_mem();
+        mem.phys_write_raw_list(&[
+            PhysicalWriteData(0x5.into(), &[0xaa; 2]),
+            PhysicalWriteData(0x7.into(), &[0xbb; 2]),
+            PhysicalWriteData(0x20.into(), &[0xcc; 8]),
+        ])
+        .unwrap();
+        assert_eq!(
+            mem.phys_read_raw(0x4.into(), 6).unwrap(),
+            vec![0x4, 0xaa, 0xaa, 0xbb, 0xbb, 0x9]
+        );
+        assert_eq!(
+            mem.phys_read::<u64>(0x20.into()).unwrap(),
+            0xcccc_cccc_cccc_cccc
+        );
+    }
+
+    #[test]
+    fn invalid_alignment() {
+        assert!(AlignedPhysicalMemory::new(DummyMemory::new(size::mb(1)), 6).is_err());
+    }
+
+    #[test]
+    fn conformance() {
+        let mut mem = build_mem();
+        assert_phys_mem_conformance(&mut mem);
+    }
+}
@@ -0,0 +1,311 @@
+/*!
+Throughput and latency measurements for objects implementing the `PhysicalMemory` trait.
+
+The `benchmark_phys_mem` function issues sequential and randomized read workloads
+at different batch sizes against a memory backend and reports the throughput,
+the latency of the individual `phys_read_raw_list` calls and how much batching speeds up the reads.
+
+This allows connector authors and users to objectively compare backends
+and to pick suitable cache settings for a specific connector.
+The randomized workload is seeded, so consecutive runs issue the exact same reads.
+
+# Examples
+
+```
+use memflow::mem::benchmark::{benchmark_phys_mem, BenchmarkConfig};
+use memflow::mem::dummy::DummyMemory;
+use memflow::types::size;
+
+let mut mem = DummyMemory::new(size::mb(16));
+
+let config = BenchmarkConfig {
+    batch_sizes: vec![1, 16, 256],
+    iterations: 64,
+    ..Default::default()
+};
+let report = benchmark_phys_mem(&mut mem, &config).unwrap();
+println!("{}", report);
+```
+*/
+
+use std::prelude::v1::*;
+
+use crate::error::{Error, Result};
+use crate::mem::phys_mem::{PhysicalMemory, PhysicalReadData};
+use crate::types::{size, Address};
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// The access pattern of a benchmark run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessPattern {
+    /// Consecutive reads starting at address 0
+    Sequential,
+    /// Reads at random aligned addresses across the entire physical address space
+    Random,
+}
+
+impl fmt::Display for AccessPattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AccessPattern::Sequential => write!(f, "sequential"),
+            AccessPattern::Random => write!(f, "random"),
+        }
+    }
+}
+
+/// Configuration of a benchmark.
+#[derive(Debug, Clone)]
+pub struct BenchmarkConfig {
+    /// The number of reads that are issued in a single `phys_read_raw_list` call.
+    /// Every batch size is benchmarked separately.
+    pub batch_sizes: Vec<usize>,
+    /// The size of a single read in bytes
+    pub read_size: usize,
+    /// The number of `phys_read_raw_list` calls per batch size and access pattern
+    pub iterations: usize,
+    /// The access patterns that are benchmarked
+    pub patterns: Vec<AccessPattern>,
+    /// Seed for the random access pattern
+    pub seed: u64,
+}
+
+impl Default for BenchmarkConfig {
+    fn default() -> Self {
+        Self {
+            batch_sizes: vec![1, 4, 16, 64, 256],
+            read_size: size::kb(4),
+            iterations: 256,
+            patterns: vec![AccessPattern::Sequential, AccessPattern::Random],
+            seed: 0x6d65_6d66_6c6f_77,
+        }
+    }
+}
+
+/// Result of a single benchmark run with a specific batch size and access pattern.
+#[derive(Debug, Clone)]
+pub struct BenchmarkResult {
+    pub pattern: AccessPattern,
+    pub batch_size: usize,
+    pub read_size: usize,
+    /// Total number of bytes that have been read
+    pub total_bytes: usize,
+    /// Total time spent in `phys_read_raw_list`
+    pub total_time: Duration,
+    /// Median latency of a single `phys_read_raw_list` call
+    pub latency_p50: Duration,
+    /// 99th percentile latency of a single `phys_read_raw_list` call
+    pub latency_p99: Duration,
+    /// Speedup of a single read compared to the smallest benchmarked batch size.
+    ///
+    /// A value of 4.0 means that a single read in this batch size is 4 times faster
+    /// than a single read in the smallest batch size.
+    pub batch_efficiency: f64,
+}
+
+impl BenchmarkResult {
+    /// Returns the throughput in megabytes per second.
+    pub fn throughput(&self) -> f64 {
+        let secs = self.total_time.as_secs_f64();
+        if secs > 0.0 {
+            self.total_bytes as f64 / size::mb(1) as f64 / secs
+        } else {
+            f64::INFINITY
+        }
+    }
+
+    /// Returns the average time spent on a single read of `read_size` bytes.
+    fn time_per_read(&self) -> f64 {
+        self.total_time.as_secs_f64() / (self.total_bytes / self.read_size) as f64
+    }
+}
+
+/// Contains the results of all benchmark runs.
+#[derive(Debug, Clone)]
+pub struct BenchmarkReport {
+    pub results: Vec<BenchmarkResult>,
+}
+
+impl fmt::Display for BenchmarkReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<10} {:>6} {:>8} {:>12} {:>12} {:>12} {:>10}",
+            "pattern", "batch", "size", "MB/s", "p50", "p99", "efficiency"
+        )?;
+        for result in self.results.iter() {
+            writeln!(
+                f,
+                "{:<10} {:>6} {:>8x} {:>12.2} {:>12?} {:>12?} {:>10.2}",
+                result.pattern.to_string(),
+                result.batch_size,
+                result.read_size,
+                result.throughput(),
+                result.latency_p50,
+                result.latency_p99,
+                result.batch_efficiency
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Benchmarks the read performance of the given memory.
+///
+/// All combinations of the access patterns and batch sizes in the config are benchmarked.
+/// The reads are distributed across the size reported by `metadata()`.
+///
+/// The benchmark is aborted with the first error returned by the memory.
+pub fn benchmark_phys_mem<T: PhysicalMemory + ?Sized>(
+    mem: &mut T,
+    config: &BenchmarkConfig,
+) -> Result<BenchmarkReport> {
+    let mem_size = mem.metadata().size;
+    if config.read_size == 0 || config.read_size > mem_size {
+        return Err(Error::Other(
+            "benchmark read size must fit into the physical memory",
+        ));
+    }
+    if config.batch_sizes.iter().any(|&batch_size| batch_size == 0) {
+        return Err(Error::Other("benchmark batch sizes must not be zero"));
+    }
+
+    // all reads are aligned to the read size
+    let slot_count = mem_size / config.read_size;
+
+    let mut results = Vec::new();
+    for &pattern in config.patterns.iter() {
+        let mut rng = XorShift(config.seed | 1);
+        let mut next_slot = 0;
+        let first_result = results.len();
+
+        for &batch_size in config.batch_sizes.iter() {
+            let mut bufs = vec![vec![0u8; config.read_size]; batch_size];
+            let mut latencies = Vec::with_capacity(config.iterations);
+
+            for _ in 0..config.iterations {
+                let mut list = bufs
+                    .iter_mut()
+                    .map(|buf| {
+                        let slot = match pattern {
+                            AccessPattern::Sequential => {
+                                let slot = next_slot;
+                                next_slot = (next_slot + 1) % slot_count;
+                                slot
+                            }
+                            AccessPattern::Random => rng.next_u64() as usize % slot_count,
+                        };
+                        PhysicalReadData(
+                            Address::from(slot * config.read_size).into(),
+                            &mut buf[..],
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                let start = Instant::now();
+                mem.phys_read_raw_list(&mut list)?;
+                latencies.push(start.elapsed());
+            }
+
+            let total_time = latencies.iter().sum::<Duration>();
+            latencies.sort();
+
+            results.push(BenchmarkResult {
+                pattern,
+                batch_size,
+                read_size: config.read_size,
+                total_bytes: config.read_size * batch_size * config.iterations,
+                total_time,
+                latency_p50: percentile(&latencies, 50),
+                latency_p99: percentile(&latencies, 99),
+                batch_efficiency: 1.0,
+            });
+        }
+
+        // compare against the smallest batch size of this pattern
+        let baseline = results[first_result..]
+            .iter()
+            .min_by_key(|result| result.batch_size)
+            .map(BenchmarkResult::time_per_read);
+        if let Some(baseline) = baseline {
+            for result in results[first_result..].iter_mut() {
+                let time_per_read = result.time_per_read();
+                if time_per_read > 0.0 {
+                    result.batch_efficiency = baseline / time_per_read;
+                }
+            }
+        }
+    }
+
+    Ok(BenchmarkReport { results })
+}
+
+/// Returns the given percentile of a sorted list of durations.
+fn percentile(sorted: &[Duration], percentile: usize) -> Duration {
+    if sorted.is_empty() {
+        return Duration::default();
+    }
+    let idx = (sorted.len() * percentile / 100).min(sorted.len() - 1);
+    sorted[idx]
+}
+
+/// Minimal xorshift generator so the random workload is reproducible
+/// without depending on the `rand` crate.
+struct XorShift(u64);
+
+impl XorShift {
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::dummy::DummyMemory;
+
+    #[test]
+    fn benchmark_report() {
+        let mut mem = DummyMemory::new(size::mb(4));
+        let config = BenchmarkConfig {
+            batch_sizes: vec![4, 1],
+            iterations: 16,
+            ..Default::default()
+        };
+
+        let report = benchmark_phys_mem(&mut mem, &config).unwrap();
+        assert_eq!(report.results.len(), 4);
+
+        let result = &report.results[0];
+        assert_eq!(result.pattern, AccessPattern::Sequential);
+        assert_eq!(result.batch_size, 4);
+        assert_eq!(result.total_bytes, size::kb(4) * 4 * 16);
+        assert!(result.latency_p50 <= result.latency_p99);
+
+        // the efficiency is relative to the smallest batch size
+        assert_eq!(report.results[1].batch_efficiency, 1.0);
+        assert_eq!(report.results[3].pattern, AccessPattern::Random);
+
+        assert_eq!(report.to_string().lines().count(), 5);
+    }
+
+    #[test]
+    fn invalid_config() {
+        let mut mem = DummyMemory::new(size::mb(1));
+        let config = BenchmarkConfig {
+            read_size: size::mb(2),
+            ..Default::default()
+        };
+        assert!(benchmark_phys_mem(&mut mem, &config).is_err());
+
+        let config = BenchmarkConfig {
+            batch_sizes: vec![0],
+            ..Default::default()
+        };
+        assert!(benchmark_phys_mem(&mut mem, &config).is_err());
+    }
+}
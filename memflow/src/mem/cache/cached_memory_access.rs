@@ -8,6 +8,8 @@ and not issue out a new read. In case the cache is not valid anymore it will do
 
 The cache time is determined by the customizable cache validator.
 The cache validator has to implement the [`CacheValidator`](../trait.CacheValidator.html) trait.
+The validator is only consulted for volatile memory (see `PhysicalMemoryCapabilities::VOLATILE`),
+pages of memory that can not change on its own (e.g. a memory dump) stay valid until they are evicted.
 
 To make it easier and quicker to construct and work with caches this module also contains a cache builder.
 
@@ -29,19 +31,87 @@ fn build<T: PhysicalMemory>(mem: T) {
 ```
 */
 
+use std::prelude::v1::*;
+
 use super::{
     page_cache::PageCache, page_cache::PageValidity, CacheValidator, DefaultCacheValidator,
 };
 use crate::architecture::ArchitectureObj;
+use crate::connector::CpuState;
 use crate::error::Result;
 use crate::iter::PageChunks;
 use crate::mem::phys_mem::{
-    PhysicalMemory, PhysicalMemoryMetadata, PhysicalReadData, PhysicalWriteData,
+    PhysicalMemory, PhysicalMemoryCapabilities, PhysicalMemoryMetadata, PhysicalReadData,
+    PhysicalWriteData,
 };
-use crate::types::{size, PageType};
+use crate::types::{size, PageType, PhysicalAddress};
 
 use bumpalo::Bump;
 
+/// Maximum number of bytes that are read at once when prefetching into the cache
+const PREFETCH_CHUNK_SIZE: usize = size::mb(2);
+
+/// Maximum size of a read that is considered to be a pointer or list entry by `CacheStrategy::PointerChase`
+const POINTER_CHASE_MAX_READ: usize = 0x10;
+
+/// Maximum number of reads per node that `CacheStrategy::PointerChase` can detect (e.g. flink and blink)
+const POINTER_CHASE_MAX_PERIOD: usize = 2;
+
+/// The strategy the cache uses to decide which pages are read ahead of time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheStrategy {
+    /// Only pages that are actually accessed are read into the cache.
+    Page,
+    /// Detects small reads that repeat at a constant stride, as they occur when
+    /// walking a linked list whose nodes have been allocated next to each other
+    /// (e.g. the EPROCESS or module list). Once a stride has been detected the page
+    /// of the next predicted node is read ahead of time instead of relying on the locality of the current page.
+    ///
+    /// Pages of types that are not cached are passed to `PhysicalMemory::prefetch` of the underlying memory instead.
+    PointerChase,
+}
+
+impl Default for CacheStrategy {
+    fn default() -> Self {
+        CacheStrategy::Page
+    }
+}
+
+/// Keeps track of the last small reads and predicts the address of the next one.
+#[derive(Debug, Clone, Default)]
+struct StrideDetector {
+    history: [u64; 2 * POINTER_CHASE_MAX_PERIOD],
+    len: usize,
+}
+
+impl StrideDetector {
+    /// Records a read and returns the address that is likely to be read next.
+    ///
+    /// A prediction is only made if the stride to the read `period` reads ago
+    /// matches the stride between the two previous reads of the same period.
+    fn record(&mut self, addr: u64) -> Option<u64> {
+        let history = &self.history[..self.len];
+        let prediction = (1..=POINTER_CHASE_MAX_PERIOD)
+            .filter(|&period| history.len() >= 2 * period)
+            .map(|period| {
+                let prev = history[history.len() - period];
+                let prev2 = history[history.len() - 2 * period];
+                (addr.wrapping_sub(prev), prev.wrapping_sub(prev2))
+            })
+            .find(|&(stride, prev_stride)| stride == prev_stride && stride != 0)
+            .map(|(stride, _)| addr.wrapping_add(stride));
+
+        if self.len == self.history.len() {
+            self.history.rotate_left(1);
+            self.len -= 1;
+        }
+        self.history[self.len] = addr;
+        self.len += 1;
+
+        prediction
+    }
+}
+
 /// The cache object that can use as a drop-in replacement for any Connector.
 ///
 /// Since this cache implements `PhysicalMemory` it can be used as a replacement
@@ -50,6 +120,9 @@ pub struct CachedMemoryAccess<'a, T, Q> {
     mem: T,
     cache: PageCache<'a, Q>,
     arena: Bump,
+    strategy: CacheStrategy,
+    strides: StrideDetector,
+    volatile: bool,
 }
 
 impl<'a, T, Q> Clone for CachedMemoryAccess<'a, T, Q>
@@ -62,6 +135,9 @@ where
             mem: self.mem.clone(),
             cache: self.cache.clone(),
             arena: Bump::new(),
+            strategy: self.strategy,
+            strides: StrideDetector::default(),
+            volatile: self.volatile,
         }
     }
 }
@@ -74,10 +150,39 @@ impl<'a, T: PhysicalMemory, Q: CacheValidator> CachedMemoryAccess<'a, T, Q> {
     /// For general usage it is advised to just use the [builder](struct.CachedMemoryAccessBuilder.html)
     /// to construct the cache.
     pub fn new(mem: T, cache: PageCache<'a, Q>) -> Self {
+        Self::with_strategy(mem, cache, CacheStrategy::default())
+    }
+
+    /// Constructs a new cache based on the given `PageCache` that reads ahead according to the given strategy.
+    pub fn with_strategy(mem: T, cache: PageCache<'a, Q>, strategy: CacheStrategy) -> Self {
+        let volatile = mem.metadata().capabilities.is_volatile();
         Self {
             mem,
             cache,
             arena: Bump::new(),
+            strategy,
+            strides: StrideDetector::default(),
+            volatile,
+        }
+    }
+
+    /// Returns the strategy the cache uses to read ahead.
+    pub fn strategy(&self) -> CacheStrategy {
+        self.strategy
+    }
+
+    /// Reads the page of the predicted address ahead of time.
+    fn read_ahead(&mut self, addr: PhysicalAddress, len: usize) -> Result<()> {
+        if self.cache.is_cached_page_type(addr.page_type()) {
+            let mut buf = [0u8; POINTER_CHASE_MAX_READ];
+            self.cache.cached_read(
+                &mut self.mem,
+                &mut [PhysicalReadData(addr, &mut buf[..len])],
+                &self.arena,
+                !self.volatile,
+            )
+        } else {
+            self.mem.prefetch(&[(addr, len)])
         }
     }
 
@@ -129,7 +234,34 @@ impl<'a, T: PhysicalMemory, Q: CacheValidator> PhysicalMemory for CachedMemoryAc
     fn phys_read_raw_list(&mut self, data: &mut [PhysicalReadData]) -> Result<()> {
         self.cache.validator.update_validity();
         self.arena.reset();
-        self.cache.cached_read(&mut self.mem, data, &self.arena)
+
+        let prediction = match (self.strategy, &data) {
+            (CacheStrategy::PointerChase, [PhysicalReadData(addr, out)])
+                if out.len() <= POINTER_CHASE_MAX_READ =>
+            {
+                let (addr, len) = (*addr, out.len());
+                self.strides
+                    .record(addr.as_u64())
+                    .map(|next| {
+                        PhysicalAddress::with_page(next.into(), addr.page_type(), addr.page_size())
+                    })
+                    .filter(|next| {
+                        next.address().as_page_aligned(self.cache.page_size())
+                            != addr.address().as_page_aligned(self.cache.page_size())
+                    })
+                    .map(|next| (next, len))
+            }
+            _ => None,
+        };
+
+        self.cache
+            .cached_read(&mut self.mem, data, &self.arena, !self.volatile)?;
+
+        if let Some((next, len)) = prediction {
+            // reading ahead is only a hint, a failure must not affect the actual read
+            self.read_ahead(next, len).ok();
+        }
+        Ok(())
     }
 
     fn phys_write_raw_list(&mut self, data: &[PhysicalWriteData]) -> Result<()> {
@@ -137,11 +269,12 @@ impl<'a, T: PhysicalMemory, Q: CacheValidator> PhysicalMemory for CachedMemoryAc
 
         let cache = &mut self.cache;
         let mem = &mut self.mem;
+        let skip_validator = !self.volatile;
 
         data.iter().for_each(move |PhysicalWriteData(addr, data)| {
             if cache.is_cached_page_type(addr.page_type()) {
                 for (paddr, data_chunk) in data.page_chunks(addr.address(), cache.page_size()) {
-                    let mut cached_page = cache.cached_page_mut(paddr, false);
+                    let mut cached_page = cache.cached_page_mut(paddr, skip_validator);
                     if let PageValidity::Valid(buf) = &mut cached_page.validity {
                         // write-back into still valid cache pages
                         let start = paddr - cached_page.address;
@@ -157,7 +290,52 @@ impl<'a, T: PhysicalMemory, Q: CacheValidator> PhysicalMemory for CachedMemoryAc
     }
 
     fn metadata(&self) -> PhysicalMemoryMetadata {
-        self.mem.metadata()
+        let mut metadata = self.mem.metadata();
+        metadata
+            .capabilities
+            .insert(PhysicalMemoryCapabilities::PREFETCH);
+        metadata
+    }
+
+    fn cpu_state(&mut self) -> Option<&mut dyn CpuState> {
+        self.mem.cpu_state()
+    }
+
+    /// Reads all ranges that contain cacheable pages into the cache.
+    ///
+    /// Ranges of page types that are not cached are forwarded to the underlying memory.
+    fn prefetch(&mut self, ranges: &[(PhysicalAddress, usize)]) -> Result<()> {
+        let (cached, uncached): (Vec<_>, Vec<_>) = ranges
+            .iter()
+            .copied()
+            .partition(|(addr, _)| self.cache.is_cached_page_type(addr.page_type()));
+
+        let max_len = cached.iter().map(|(_, len)| *len).max().unwrap_or_default();
+        let mut buf = vec![0u8; max_len.min(PREFETCH_CHUNK_SIZE)];
+        for (addr, len) in cached.into_iter() {
+            for offs in (0..len).step_by(PREFETCH_CHUNK_SIZE) {
+                let chunk_len = (len - offs).min(PREFETCH_CHUNK_SIZE);
+                let chunk_addr = if addr.has_page() {
+                    PhysicalAddress::with_page(
+                        addr.address() + offs,
+                        addr.page_type(),
+                        addr.page_size(),
+                    )
+                } else {
+                    (addr.address() + offs).into()
+                };
+                // ranges beyond the end of the memory are not an error for prefetching
+                self.phys_read_raw_list(&mut [PhysicalReadData(
+                    chunk_addr,
+                    &mut buf[..chunk_len],
+                )])?;
+            }
+        }
+
+        if !uncached.is_empty() {
+            self.mem.prefetch(&uncached)?;
+        }
+        Ok(())
     }
 }
 
@@ -168,6 +346,7 @@ pub struct CachedMemoryAccessBuilder<T, Q> {
     page_size: Option<usize>,
     cache_size: usize,
     page_type_mask: PageType,
+    strategy: CacheStrategy,
 }
 
 impl<T: PhysicalMemory> CachedMemoryAccessBuilder<T, DefaultCacheValidator> {
@@ -248,6 +427,7 @@ impl<T: PhysicalMemory> CachedMemoryAccessBuilder<T, DefaultCacheValidator> {
             page_size: None,
             cache_size: size::mb(2),
             page_type_mask: PageType::PAGE_TABLE | PageType::READ_ONLY,
+            strategy: CacheStrategy::default(),
         }
     }
 }
@@ -255,7 +435,7 @@ impl<T: PhysicalMemory> CachedMemoryAccessBuilder<T, DefaultCacheValidator> {
 impl<T: PhysicalMemory, Q: CacheValidator> CachedMemoryAccessBuilder<T, Q> {
     /// Builds the `CachedMemoryAccess` object or returns an error if the page size is not set.
     pub fn build<'a>(self) -> Result<CachedMemoryAccess<'a, T, Q>> {
-        Ok(CachedMemoryAccess::new(
+        Ok(CachedMemoryAccess::with_strategy(
             self.mem,
             PageCache::with_page_size(
                 self.page_size.ok_or("page_size must be initialized")?,
@@ -263,6 +443,7 @@ impl<T: PhysicalMemory, Q: CacheValidator> CachedMemoryAccessBuilder<T, Q> {
                 self.page_type_mask,
                 self.validator,
             ),
+            self.strategy,
         ))
     }
 
@@ -300,6 +481,7 @@ impl<T: PhysicalMemory, Q: CacheValidator> CachedMemoryAccessBuilder<T, Q> {
             page_size: self.page_size,
             cache_size: self.cache_size,
             page_type_mask: self.page_type_mask,
+            strategy: self.strategy,
         }
     }
 
@@ -427,4 +609,94 @@ impl<T: PhysicalMemory, Q: CacheValidator> CachedMemoryAccessBuilder<T, Q> {
         self.page_type_mask = page_type_mask;
         self
     }
+
+    /// Sets the strategy the cache uses to read pages ahead of time.
+    ///
+    /// The default setting is `CacheStrategy::Page`.
+    ///
+    /// # Examples:
+    ///
+    /// ```
+    /// use memflow::types::PageType;
+    /// use memflow::architecture::x86::x64;
+    /// use memflow::mem::{PhysicalMemory, CachedMemoryAccess, CacheStrategy};
+    ///
+    /// fn build<T: PhysicalMemory>(mem: T) {
+    ///     let cache = CachedMemoryAccess::builder(mem)
+    ///         .arch(x64::ARCH)
+    ///         .page_type_mask(PageType::PAGE_TABLE | PageType::READ_ONLY | PageType::WRITEABLE)
+    ///         .strategy(CacheStrategy::PointerChase)
+    ///         .build()
+    ///         .unwrap();
+    /// }
+    /// # use memflow::mem::dummy::DummyMemory;
+    /// # use memflow::types::size;
+    /// # let mut mem = DummyMemory::new(size::mb(4));
+    /// # build(mem);
+    /// ```
+    pub fn strategy(mut self, strategy: CacheStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::dummy::DummyMemory;
+    use crate::mem::{AccessKind, MemoryAccess, TracingMemory};
+    use crate::types::Address;
+
+    fn walk_nodes(strategy: CacheStrategy) -> Vec<MemoryAccess> {
+        let mut cache =
+            CachedMemoryAccess::builder(TracingMemory::new(DummyMemory::new(size::mb(1))))
+                .page_size(size::kb(4))
+                .page_type_mask(PageType::UNKNOWN)
+                .strategy(strategy)
+                .build()
+                .unwrap();
+
+        // flink and blink of the first three nodes
+        for i in 0..3u64 {
+            let node = 0x10000 + i * 0x2000;
+            cache.phys_read::<u64>(node.into()).unwrap();
+            cache.phys_read::<u64>((node + 8).into()).unwrap();
+        }
+
+        cache.destroy().take_trace()
+    }
+
+    #[test]
+    fn stride_detector() {
+        let mut strides = StrideDetector::default();
+        assert_eq!(strides.record(0x1000), None);
+        assert_eq!(strides.record(0x1008), None);
+        assert_eq!(strides.record(0x3000), None);
+        assert_eq!(strides.record(0x3008), None);
+        assert_eq!(strides.record(0x5000), Some(0x7000));
+        assert_eq!(strides.record(0x5008), Some(0x7008));
+        assert_eq!(strides.record(0x9000), None);
+
+        let mut strides = StrideDetector::default();
+        strides.record(0x1000);
+        strides.record(0x1100);
+        assert_eq!(strides.record(0x1200), Some(0x1300));
+    }
+
+    #[test]
+    fn pointer_chase() {
+        let next_page = |trace: &[MemoryAccess]| {
+            trace.iter().any(|access| {
+                *access
+                    == MemoryAccess {
+                        kind: AccessKind::Read,
+                        addr: Address::from(0x16000),
+                        len: size::kb(4),
+                    }
+            })
+        };
+
+        assert!(!next_page(&walk_nodes(CacheStrategy::Page)));
+        assert!(next_page(&walk_nodes(CacheStrategy::PointerChase)));
+    }
 }
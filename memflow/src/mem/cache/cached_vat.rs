@@ -108,6 +108,36 @@ impl<V: VirtualTranslate, Q: CacheValidator> CachedVirtualTranslate<V, Q> {
     }
 }
 
+impl<V, Q: CacheValidator> CachedVirtualTranslate<V, Q> {
+    /// Drops all cached translations.
+    ///
+    /// This should be used when the page tables of a live target changed in an unknown way.
+    pub fn flush(&mut self) {
+        self.tlb.flush();
+    }
+
+    /// Drops all cached translations of the page tables with the given directory table base.
+    ///
+    /// This only affects the address space of a single process,
+    /// cached translations of other processes stay valid.
+    /// The dtb is matched against the translation table id of the x86 translators.
+    ///
+    /// Returns the number of entries that have been invalidated.
+    pub fn invalidate_dtb(&mut self, dtb: Address) -> usize {
+        self.tlb
+            .invalidate_table(dtb.as_u64().overflowing_shr(12).0 as usize)
+    }
+
+    /// Returns how often the translations of the given directory table base have been invalidated.
+    ///
+    /// Cached translations are tagged with the generation of their dtb,
+    /// translations of a previous generation are never served again.
+    pub fn dtb_generation(&self, dtb: Address) -> u32 {
+        self.tlb
+            .generation(dtb.as_u64().overflowing_shr(12).0 as usize)
+    }
+}
+
 impl<V: VirtualTranslate> CachedVirtualTranslate<V, DefaultCacheValidator> {
     pub fn builder(vat: V) -> CachedVirtualTranslateBuilder<V, DefaultCacheValidator> {
         CachedVirtualTranslateBuilder::new(vat)
@@ -282,7 +312,7 @@ mod tests {
     use crate::mem::cache::cached_vat::CachedVirtualTranslate;
     use crate::mem::cache::timed_validator::TimedCacheValidator;
     use crate::mem::{dummy::DummyMemory, DirectTranslate, PhysicalMemory};
-    use crate::mem::{VirtualDMA, VirtualMemory};
+    use crate::mem::{VirtualDMA, VirtualMemory, VirtualTranslate};
     use crate::types::{size, Address};
     use coarsetime::Duration;
 
@@ -353,4 +383,120 @@ mod tests {
             .unwrap();
         assert!(read_into == buffer);
     }
+
+    #[test]
+    fn cached_entries_keep_page_size() {
+        let mut mem = DummyMemory::new(size::mb(64));
+        let (dtb, virt_base) = mem.alloc_dtb(size::mb(16), &[]);
+        let translator = x86::x64::new_translator(dtb);
+
+        let mut direct = DirectTranslate::new();
+        let mut cached = CachedVirtualTranslate::builder(DirectTranslate::new())
+            .arch(x86::x64::ARCH)
+            .validator(TimedCacheValidator::new(Duration::from_secs(100)))
+            .build()
+            .unwrap();
+
+        for i in (0..size::mb(16)).step_by(size::kb(4)) {
+            let addr = virt_base + i;
+            let expected = direct.virt_to_phys(&mut mem, &translator, addr).unwrap();
+
+            // the first translation fills the tlb, the second one is served from it
+            let miss = cached.virt_to_phys(&mut mem, &translator, addr).unwrap();
+            let hit = cached.virt_to_phys(&mut mem, &translator, addr).unwrap();
+
+            assert_eq!(miss.address(), expected.address());
+            assert_eq!(hit.address(), expected.address());
+            assert_eq!(miss.page_size(), expected.page_size());
+            assert_eq!(hit.page_size(), expected.page_size());
+        }
+
+        assert!(cached.hitc > 0);
+    }
+
+    #[test]
+    fn flush_and_invalidate_dtb() {
+        let mut mem = DummyMemory::new(size::mb(32));
+        // the bases map to different tlb slots
+        let virt_base1 = Address::from(0x1000_0000);
+        let virt_base2 = Address::from(0x2000_1000);
+        let dtb1 = mem.alloc_dtb_const_base(virt_base1, size::mb(2), &[]);
+        let dtb2 = mem.alloc_dtb_const_base(virt_base2, size::mb(2), &[]);
+        let translator1 = x86::x64::new_translator(dtb1);
+        let translator2 = x86::x64::new_translator(dtb2);
+
+        let mut cached = CachedVirtualTranslate::builder(DirectTranslate::new())
+            .arch(x86::x64::ARCH)
+            .validator(TimedCacheValidator::new(Duration::from_secs(100)))
+            .build()
+            .unwrap();
+
+        let warm = |cached: &mut CachedVirtualTranslate<DirectTranslate, TimedCacheValidator>,
+                    mem: &mut DummyMemory| {
+            cached.virt_to_phys(mem, &translator1, virt_base1).unwrap();
+            cached.virt_to_phys(mem, &translator2, virt_base2).unwrap();
+        };
+
+        warm(&mut cached, &mut mem);
+        let misc = cached.misc;
+        warm(&mut cached, &mut mem);
+        assert_eq!(cached.misc, misc);
+
+        // only the translations of the first address space are dropped
+        assert_eq!(cached.invalidate_dtb(dtb1), 1);
+        assert_eq!(cached.invalidate_dtb(dtb1), 0);
+        warm(&mut cached, &mut mem);
+        assert_eq!(cached.misc, misc + 1);
+
+        cached.flush();
+        warm(&mut cached, &mut mem);
+        assert_eq!(cached.misc, misc + 3);
+    }
+
+    #[test]
+    fn invalidate_dtb_generation() {
+        let mut mem = DummyMemory::new(size::mb(32));
+        let virt_base = Address::from(0x1000_0000);
+        let dtb1 = mem.alloc_dtb_const_base(virt_base, size::mb(2), &[]);
+        let dtb2 = mem.alloc_dtb_const_base(virt_base, size::mb(2), &[]);
+        let translator1 = x86::x64::new_translator(dtb1);
+        let translator2 = x86::x64::new_translator(dtb2);
+
+        let mut cached = CachedVirtualTranslate::builder(DirectTranslate::new())
+            .arch(x86::x64::ARCH)
+            .validator(TimedCacheValidator::new(Duration::from_secs(100)))
+            .build()
+            .unwrap();
+
+        let old = cached
+            .virt_to_phys(&mut mem, &translator1, virt_base)
+            .unwrap();
+        let new = DirectTranslate::new()
+            .virt_to_phys(&mut mem, &translator2, virt_base)
+            .unwrap();
+        assert_ne!(old.address(), new.address());
+
+        // the page tables behind dtb1 are replaced with the ones of dtb2
+        let pml4 = mem.phys_read_raw(dtb2.into(), size::kb(4)).unwrap();
+        mem.phys_write_raw(dtb1.into(), &pml4).unwrap();
+        assert_eq!(
+            cached
+                .virt_to_phys(&mut mem, &translator1, virt_base)
+                .unwrap(),
+            old
+        );
+
+        // translations of the previous generation are not served anymore
+        assert_eq!(cached.dtb_generation(dtb1), 0);
+        assert_eq!(cached.invalidate_dtb(dtb1), 1);
+        assert_eq!(cached.dtb_generation(dtb1), 1);
+        assert_eq!(cached.dtb_generation(dtb2), 0);
+        assert_eq!(
+            cached
+                .virt_to_phys(&mut mem, &translator1, virt_base)
+                .unwrap()
+                .address(),
+            new.address()
+        );
+    }
 }
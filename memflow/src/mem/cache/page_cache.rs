@@ -178,11 +178,15 @@ impl<'a, T: CacheValidator> PageCache<'a, T> {
             })
     }
 
+    /// Reads the given data through the cache.
+    ///
+    /// If `skip_validator` is set cached pages are used regardless of the validator.
     pub fn cached_read<F: PhysicalMemory>(
         &mut self,
         mem: &mut F,
         data: &mut [PhysicalReadData],
         arena: &Bump,
+        skip_validator: bool,
     ) -> Result<()> {
         let page_size = self.page_size;
 
@@ -207,7 +211,7 @@ impl<'a, T: CacheValidator> PageCache<'a, T> {
                                 chunk,
                             );
 
-                            let cached_page = self.cached_page_mut(prd.0.address(), false);
+                            let cached_page = self.cached_page_mut(prd.0.address(), skip_validator);
 
                             match cached_page.validity {
                                 PageValidity::Valid(buf) => {
@@ -263,7 +267,7 @@ impl<'a, T: CacheValidator> PageCache<'a, T> {
                     }
 
                     while let Some(PhysicalReadData(addr, out)) = clist.pop() {
-                        let cached_page = self.cached_page_mut(addr.address(), false);
+                        let cached_page = self.cached_page_mut(addr.address(), skip_validator);
                         let aligned_addr = cached_page.address.as_page_aligned(self.page_size);
 
                         let start = addr.address() - aligned_addr;
@@ -337,6 +341,9 @@ mod tests {
     use super::*;
     use crate::architecture::x86;
     use crate::mem::{dummy::DummyMemory, CachedMemoryAccess, TimedCacheValidator};
+    use crate::mem::{
+        CountCacheValidator, PhysicalMemoryCapabilities, PhysicalMemoryMetadata, PhysicalWriteData,
+    };
     use crate::mem::{VirtualDMA, VirtualMemory};
     use crate::types::{size, Address, PhysicalAddress};
 
@@ -468,6 +475,97 @@ mod tests {
         }
     }
 
+    /// Memory that reports itself as belonging to a running system
+    struct LiveMemory(DummyMemory);
+
+    impl PhysicalMemory for LiveMemory {
+        fn phys_read_raw_list(&mut self, data: &mut [PhysicalReadData]) -> Result<()> {
+            self.0.phys_read_raw_list(data)
+        }
+
+        fn phys_write_raw_list(&mut self, data: &[PhysicalWriteData]) -> Result<()> {
+            self.0.phys_write_raw_list(data)
+        }
+
+        fn metadata(&self) -> PhysicalMemoryMetadata {
+            let mut metadata = self.0.metadata();
+            metadata
+                .capabilities
+                .insert(PhysicalMemoryCapabilities::VOLATILE);
+            metadata
+        }
+    }
+
+    #[test]
+    fn cache_volatile_memory() {
+        let mut dummy_mem = DummyMemory::new(size::mb(4));
+        dummy_mem.phys_write(0x1000.into(), &0x1234_u64).unwrap();
+        let mut live_mem = LiveMemory(dummy_mem.clone());
+        let dummy_ptr = &mut dummy_mem as *mut DummyMemory;
+        let live_ptr = &mut live_mem as *mut LiveMemory;
+
+        let build_cache = || {
+            PageCache::new(
+                x86::x64::ARCH,
+                size::mb(2),
+                PageType::UNKNOWN,
+                CountCacheValidator::new(2),
+            )
+        };
+        let mut static_cache = CachedMemoryAccess::new(&mut dummy_mem, build_cache());
+        let mut live_cache = CachedMemoryAccess::new(&mut live_mem, build_cache());
+        assert!(!static_cache.metadata().capabilities.is_volatile());
+        assert!(live_cache.metadata().capabilities.is_volatile());
+        assert!(live_cache.metadata().capabilities.supports_prefetch());
+
+        assert_eq!(
+            static_cache.phys_read::<u64>(0x1000.into()).unwrap(),
+            0x1234
+        );
+        assert_eq!(live_cache.phys_read::<u64>(0x1000.into()).unwrap(), 0x1234);
+
+        // modify the memory from other channels
+        unsafe { dummy_ptr.as_mut().unwrap() }
+            .phys_write(0x1000.into(), &0x5678_u64)
+            .unwrap();
+        unsafe { live_ptr.as_mut().unwrap() }
+            .phys_write(0x1000.into(), &0x5678_u64)
+            .unwrap();
+
+        // only the cache of the volatile memory lets its pages expire
+        for _ in 0..3 {
+            static_cache.phys_read::<u64>(0x1000.into()).unwrap();
+            live_cache.phys_read::<u64>(0x1000.into()).unwrap();
+        }
+        assert_eq!(
+            static_cache.phys_read::<u64>(0x1000.into()).unwrap(),
+            0x1234
+        );
+        assert_eq!(live_cache.phys_read::<u64>(0x1000.into()).unwrap(), 0x5678);
+    }
+
+    #[test]
+    fn cache_prefetch() {
+        let mut dummy_mem = DummyMemory::new(size::mb(4));
+        let mem_ptr = &mut dummy_mem as *mut DummyMemory;
+        dummy_mem.phys_write(0x1000.into(), &0x1234_u64).unwrap();
+
+        let cache = PageCache::new(
+            x86::x64::ARCH,
+            size::mb(2),
+            PageType::UNKNOWN,
+            TimedCacheValidator::new(Duration::from_secs(100)),
+        );
+        let mut mem_cache = CachedMemoryAccess::new(&mut dummy_mem, cache);
+        mem_cache.prefetch(&[(0x1000.into(), size::kb(8))]).unwrap();
+
+        // modifying the memory from other channels should not affect the prefetched pages
+        unsafe { mem_ptr.as_mut().unwrap() }
+            .phys_write(0x1000.into(), &0x5678_u64)
+            .unwrap();
+        assert_eq!(mem_cache.phys_read::<u64>(0x1000.into()).unwrap(), 0x1234);
+    }
+
     #[test]
     fn cache_invalidity_cached() {
         let mut dummy_mem = DummyMemory::new(size::mb(64));
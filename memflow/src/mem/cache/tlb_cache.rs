@@ -5,6 +5,8 @@ use crate::architecture::{ArchitectureObj, ScopedVirtualTranslate};
 use crate::error::{Error, Result};
 use crate::types::{Address, PhysicalAddress};
 
+use hashbrown::HashMap;
+
 #[derive(Clone, Copy)]
 pub struct TLBEntry {
     pub pt_index: usize,
@@ -25,6 +27,7 @@ impl TLBEntry {
 #[derive(Clone, Copy)]
 pub struct CachedEntry {
     pt_index: usize,
+    generation: u32,
     virt_page: Address,
     phys_page: PhysicalAddress,
 }
@@ -32,6 +35,7 @@ pub struct CachedEntry {
 impl CachedEntry {
     const INVALID: CachedEntry = CachedEntry {
         pt_index: !0,
+        generation: 0,
         virt_page: Address::INVALID,
         phys_page: PhysicalAddress::INVALID,
     };
@@ -40,6 +44,11 @@ impl CachedEntry {
 #[derive(Clone)]
 pub struct TLBCache<T> {
     entries: Box<[CachedEntry]>,
+    /// Current generation of every translation table that has been invalidated at least once.
+    ///
+    /// Entries are tagged with the generation of their translation table at the time they were cached.
+    /// Entries of a previous generation are ignored, even if they are still stored in the cache.
+    generations: HashMap<usize, u32>,
     pub validator: T,
 }
 
@@ -49,10 +58,55 @@ impl<T: CacheValidator> TLBCache<T> {
 
         Self {
             entries: vec![CachedEntry::INVALID; size].into_boxed_slice(),
+            generations: HashMap::new(),
             validator,
         }
     }
 
+    /// Invalidates all entries of the cache.
+    pub fn flush(&mut self) {
+        for (idx, entry) in self.entries.iter_mut().enumerate() {
+            *entry = CachedEntry::INVALID;
+            self.validator.invalidate_slot(idx);
+        }
+        // no entry of any generation is left
+        self.generations.clear();
+    }
+
+    /// Invalidates all entries that were created with the given translation table id.
+    ///
+    /// This advances the generation of the translation table, all entries of the previous
+    /// generation will be ignored from now on.
+    /// Returns the number of entries that have been invalidated.
+    pub fn invalidate_table(&mut self, pt_index: usize) -> usize {
+        let generation = self.generation(pt_index);
+        let count = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(idx, entry)| {
+                entry.pt_index == pt_index
+                    && entry.generation == generation
+                    && self.validator.is_slot_valid(*idx)
+            })
+            .count();
+        // the generation wraps after 2^32 invalidations of the same table,
+        // an entry would have to survive all of them to be served again
+        self.generations
+            .insert(pt_index, generation.wrapping_add(1));
+        count
+    }
+
+    /// Returns the current generation of the given translation table id.
+    #[inline]
+    pub fn generation(&self, pt_index: usize) -> u32 {
+        if self.generations.is_empty() {
+            0
+        } else {
+            self.generations.get(&pt_index).copied().unwrap_or(0)
+        }
+    }
+
     #[inline]
     fn get_cache_index(&self, page_addr: Address, page_size: usize) -> usize {
         ((page_addr.as_u64() / (page_size as u64)) % (self.entries.len() as u64)) as usize
@@ -76,6 +130,7 @@ impl<T: CacheValidator> TLBCache<T> {
         let idx = self.get_cache_index(page_address, page_size);
         let entry = self.entries[idx];
         if entry.pt_index == pt_index
+            && entry.generation == self.generation(pt_index)
             && entry.virt_page == page_address
             && self.validator.is_slot_valid(idx)
         {
@@ -83,12 +138,13 @@ impl<T: CacheValidator> TLBCache<T> {
                 Some(Ok(TLBEntry {
                     pt_index,
                     virt_addr: addr,
-                    // TODO: this should be aware of huge pages
+                    // entries are stored at base page granularity, but the
+                    // page size of the underlying (possibly huge) mapping is retained
                     phys_addr: PhysicalAddress::with_page(
                         entry.phys_page.address().as_page_aligned(page_size)
                             + (addr - page_address),
                         entry.phys_page.page_type(),
-                        page_size,
+                        entry.phys_page.page_size(),
                     ),
                 }))
             } else {
@@ -112,6 +168,7 @@ impl<T: CacheValidator> TLBCache<T> {
         let idx = self.get_cache_index(in_addr.as_page_aligned(page_size), page_size);
         self.entries[idx] = CachedEntry {
             pt_index,
+            generation: self.generation(pt_index),
             virt_page: in_addr.as_page_aligned(page_size),
             phys_page: out_page,
         };
@@ -127,6 +184,7 @@ impl<T: CacheValidator> TLBCache<T> {
         arch: ArchitectureObj,
     ) {
         let pt_index = translator.translation_table_id(in_addr);
+        let generation = self.generation(pt_index);
         let page_size = arch.page_size();
         let page_addr = in_addr.as_page_aligned(page_size);
         let end_addr = (in_addr + invalid_len + 1).as_page_aligned(page_size);
@@ -138,12 +196,15 @@ impl<T: CacheValidator> TLBCache<T> {
             let cur_page = Address::from(i);
             let idx = self.get_cache_index(cur_page, page_size);
 
+            let stale = self.entries[idx].generation != self.generation(self.entries[idx].pt_index);
             let entry = &mut self.entries[idx];
             if entry.pt_index == !0
                 || !entry.phys_page.is_valid()
                 || !self.validator.is_slot_valid(idx)
+                || stale
             {
                 entry.pt_index = pt_index;
+                entry.generation = generation;
                 entry.virt_page = cur_page;
                 entry.phys_page = PhysicalAddress::INVALID;
                 self.validator.validate_slot(idx);
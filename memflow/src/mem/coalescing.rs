@@ -0,0 +1,365 @@
+/*!
+Read and write coalescing wrapper for connector objects that implement the `PhysicalMemory` trait.
+
+Many callers issue lots of small reads or writes of adjacent memory (e.g. reading a struct field by field).
+Most connectors have a considerable overhead per request and serve a single larger read
+far more efficiently than many small ones.
+
+The `CoalescingPhysicalMemory` sorts all entries of a batched read by their address,
+merges adjacent and overlapping entries into a single read of up to `ideal_batch_size` bytes
+and scatters the results back into the original output buffers afterwards.
+
+Batched writes are merged the same way. Overlapping writes are applied in the order
+they appear in the batch, so just like with the underlying memory the last write wins.
+
+# Examples
+
+```
+use memflow::mem::{CoalescingPhysicalMemory, PhysicalMemory, PhysicalReadData};
+use memflow::types::size;
+
+fn read<T: PhysicalMemory>(connector: T) {
+    let mut mem = CoalescingPhysicalMemory::new(connector, size::kb(4)).unwrap();
+
+    // results in a single 16 byte read at 0x1000
+    let mut a = [0u8; 8];
+    let mut b = [0u8; 8];
+    mem.phys_read_raw_list(&mut [
+        PhysicalReadData(0x1008.into(), &mut b),
+        PhysicalReadData(0x1000.into(), &mut a),
+    ])
+    .unwrap();
+}
+# use memflow::mem::dummy::DummyMemory;
+# read(DummyMemory::new(size::mb(4)));
+```
+*/
+
+use std::prelude::v1::*;
+
+use crate::connector::CpuState;
+use crate::error::{Error, Result};
+use crate::mem::phys_mem::{
+    PhysicalMemory, PhysicalMemoryMetadata, PhysicalReadData, PhysicalWriteData,
+};
+use crate::types::{Address, PhysicalAddress};
+
+/// A merged read or write that covers one or more entries of the original batch.
+struct Run {
+    start: PhysicalAddress,
+    end: Address,
+    /// Indices of the covered entries in the original batch
+    entries: Vec<usize>,
+}
+
+/// Wrapper that merges adjacent reads and writes before forwarding them to the underlying memory.
+///
+/// Since this wrapper implements `PhysicalMemory` it can be used as a replacement
+/// in all structs and functions that require a `PhysicalMemory` object.
+#[derive(Clone)]
+pub struct CoalescingPhysicalMemory<T> {
+    mem: T,
+    ideal_batch_size: usize,
+}
+
+impl<T: PhysicalMemory> CoalescingPhysicalMemory<T> {
+    /// Creates a new wrapper that merges adjacent reads and writes into requests of up to `ideal_batch_size` bytes.
+    ///
+    /// Single requests that are already larger than `ideal_batch_size` are forwarded as they are.
+    pub fn new(mem: T, ideal_batch_size: usize) -> Result<Self> {
+        if ideal_batch_size == 0 {
+            return Err(Error::Other("ideal_batch_size must not be zero"));
+        }
+
+        Ok(Self {
+            mem,
+            ideal_batch_size,
+        })
+    }
+
+    /// Returns the maximum size of a merged read or write.
+    pub fn ideal_batch_size(&self) -> usize {
+        self.ideal_batch_size
+    }
+
+    /// Consumes the wrapper and returns the underlying memory.
+    pub fn destroy(self) -> T {
+        self.mem
+    }
+}
+
+/// Groups the `(address, length)` entries of a batch into runs of adjacent or overlapping requests.
+///
+/// Only entries of the same page type are merged. Adjacent entries are only merged up to
+/// `ideal_batch_size` bytes, overlapping entries are always merged so that the resulting runs are disjoint.
+fn coalesce(ranges: &[(PhysicalAddress, usize)], ideal_batch_size: usize) -> Vec<Run> {
+    let mut order = (0..ranges.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| ranges[i].0.address());
+
+    let mut runs: Vec<Run> = Vec::new();
+    for i in order.into_iter() {
+        let (addr, len) = ranges[i];
+        let end = addr.address() + len;
+
+        if let Some(run) = runs.last_mut() {
+            let merged_end = std::cmp::max(run.end, end);
+            if addr.page_type() == run.start.page_type()
+                && (addr.address() < run.end
+                    || (addr.address() == run.end
+                        && merged_end - run.start.address() <= ideal_batch_size))
+            {
+                run.end = merged_end;
+                run.entries.push(i);
+                continue;
+            }
+        }
+
+        runs.push(Run {
+            start: addr,
+            end,
+            entries: vec![i],
+        });
+    }
+
+    runs
+}
+
+/// Reads the batch from `mem` after merging adjacent entries into reads of up to `ideal_batch_size` bytes.
+///
+/// This is the read path of `CoalescingPhysicalMemory`, it is also used by `VirtualDMA`
+/// to merge physically contiguous pages of a virtual read.
+pub(crate) fn coalesced_read_raw_list<T: PhysicalMemory + ?Sized>(
+    mem: &mut T,
+    data: &mut [PhysicalReadData],
+    ideal_batch_size: usize,
+) -> Result<()> {
+    let ranges = data
+        .iter()
+        .map(|PhysicalReadData(addr, out)| (*addr, out.len()))
+        .collect::<Vec<_>>();
+    let runs = coalesce(&ranges, ideal_batch_size);
+    if runs.len() == data.len() {
+        // nothing could be merged
+        return mem.phys_read_raw_list(data);
+    }
+
+    let mut bufs = runs
+        .iter()
+        .map(|run| vec![0u8; run.end - run.start.address()])
+        .collect::<Vec<_>>();
+
+    {
+        let mut list = runs
+            .iter()
+            .zip(bufs.iter_mut())
+            .map(|(run, buf)| PhysicalReadData(run.start, &mut buf[..]))
+            .collect::<Vec<_>>();
+        mem.phys_read_raw_list(&mut list)?;
+    }
+
+    for (run, buf) in runs.iter().zip(bufs.iter()) {
+        for &i in run.entries.iter() {
+            let PhysicalReadData(addr, out) = &mut data[i];
+            let offset = addr.address() - run.start.address();
+            out.copy_from_slice(&buf[offset..offset + out.len()]);
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the batch to `mem` after merging adjacent entries into writes of up to `ideal_batch_size` bytes.
+///
+/// The entries of a merged write are copied into its buffer in the order of the original batch,
+/// overlapping entries therefore retain last-writer-wins semantics.
+pub(crate) fn coalesced_write_raw_list<T: PhysicalMemory + ?Sized>(
+    mem: &mut T,
+    data: &[PhysicalWriteData],
+    ideal_batch_size: usize,
+) -> Result<()> {
+    let ranges = data
+        .iter()
+        .map(|PhysicalWriteData(addr, buf)| (*addr, buf.len()))
+        .collect::<Vec<_>>();
+    let mut runs = coalesce(&ranges, ideal_batch_size);
+    if runs.len() == data.len() {
+        // nothing could be merged
+        return mem.phys_write_raw_list(data);
+    }
+
+    let bufs = runs
+        .iter_mut()
+        .map(|run| {
+            run.entries.sort_unstable();
+            let mut buf = vec![0u8; run.end - run.start.address()];
+            for &i in run.entries.iter() {
+                let PhysicalWriteData(addr, src) = &data[i];
+                let offset = addr.address() - run.start.address();
+                buf[offset..offset + src.len()].copy_from_slice(src);
+            }
+            buf
+        })
+        .collect::<Vec<_>>();
+
+    let list = runs
+        .iter()
+        .zip(bufs.iter())
+        .map(|(run, buf)| PhysicalWriteData(run.start, &buf[..]))
+        .collect::<Vec<_>>();
+    mem.phys_write_raw_list(&list)
+}
+
+impl<T: PhysicalMemory> PhysicalMemory for CoalescingPhysicalMemory<T> {
+    fn phys_read_raw_list(&mut self, data: &mut [PhysicalReadData]) -> Result<()> {
+        coalesced_read_raw_list(&mut self.mem, data, self.ideal_batch_size)
+    }
+
+    fn phys_write_raw_list(&mut self, data: &[PhysicalWriteData]) -> Result<()> {
+        coalesced_write_raw_list(&mut self.mem, data, self.ideal_batch_size)
+    }
+
+    fn metadata(&self) -> PhysicalMemoryMetadata {
+        self.mem.metadata()
+    }
+
+    fn cpu_state(&mut self) -> Option<&mut dyn CpuState> {
+        self.mem.cpu_state()
+    }
+
+    fn prefetch(&mut self, ranges: &[(PhysicalAddress, usize)]) -> Result<()> {
+        self.mem.prefetch(ranges)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::conformance::assert_phys_mem_conformance;
+    use crate::mem::dummy::DummyMemory;
+    use crate::mem::{AccessKind, MemoryAccess, TracingMemory};
+    use crate::types::size;
+
+    fn build_mem(ideal_batch_size: usize) -> CoalescingPhysicalMemory<TracingMemory<DummyMemory>> {
+        let mut mem = DummyMemory::new(size::mb(1));
+        let buf = (0..size::kb(8)).map(|i| i as u8).collect::<Vec<_>>();
+        mem.phys_write_raw(0x0.into(), &buf).unwrap();
+        CoalescingPhysicalMemory::new(TracingMemory::new(mem), ideal_batch_size).unwrap()
+    }
+
+    fn read(addr: u64, len: usize) -> MemoryAccess {
+        MemoryAccess {
+            kind: AccessKind::Read,
+            addr: addr.into(),
+            len,
+        }
+    }
+
+    fn write(addr: u64, len: usize) -> MemoryAccess {
+        MemoryAccess {
+            kind: AccessKind::Write,
+            addr: addr.into(),
+            len,
+        }
+    }
+
+    #[test]
+    fn coalesce_reads() {
+        let mut mem = build_mem(0x20);
+
+        let mut a = [0u8; 4];
+        let mut b = [0u8; 8];
+        let mut c = [0u8; 4];
+        let mut d = [0u8; 4];
+        mem.phys_read_raw_list(&mut [
+            PhysicalReadData(0x108.into(), &mut b),
+            PhysicalReadData(0x100.into(), &mut a),
+            PhysicalReadData(0x10c.into(), &mut c),
+            PhysicalReadData(0x200.into(), &mut d),
+        ])
+        .unwrap();
+
+        assert_eq!(a, [0x0, 0x1, 0x2, 0x3]);
+        assert_eq!(b, [0x8, 0x9, 0xa, 0xb, 0xc, 0xd, 0xe, 0xf]);
+        assert_eq!(c, [0xc, 0xd, 0xe, 0xf]);
+        assert_eq!(d, [0x0, 0x1, 0x2, 0x3]);
+
+        // 0x100 and 0x108 are not adjacent, 0x10c overlaps 0x108
+        assert_eq!(
+            mem.destroy().take_trace(),
+            vec![read(0x100, 4), read(0x108, 8), read(0x200, 4)]
+        );
+    }
+
+    #[test]
+    fn ideal_batch_size() {
+        let mut mem = build_mem(0x10);
+
+        let mut bufs = vec![[0u8; 8]; 4];
+        let mut list = bufs
+            .iter_mut()
+            .enumerate()
+            .map(|(i, buf)| PhysicalReadData((0x1000 + i as u64 * 8).into(), &mut buf[..]))
+            .collect::<Vec<_>>();
+        mem.phys_read_raw_list(&mut list).unwrap();
+
+        assert_eq!(bufs[3], [0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f]);
+        assert_eq!(
+            mem.destroy().take_trace(),
+            vec![read(0x1000, 0x10), read(0x1010, 0x10)]
+        );
+    }
+
+    #[test]
+    fn coalesce_writes() {
+        let mut mem = build_mem(0x20);
+
+        mem.phys_write_raw_list(&[
+            PhysicalWriteData(0x108.into(), &[0xb; 8]),
+            PhysicalWriteData(0x100.into(), &[0xa; 8]),
+            PhysicalWriteData(0x104.into(), &[0xc; 2]),
+            PhysicalWriteData(0x200.into(), &[0xd; 4]),
+        ])
+        .unwrap();
+
+        let mut buf = [0u8; 0x10];
+        mem.phys_read_raw_into(0x100.into(), &mut buf).unwrap();
+        assert_eq!(
+            buf,
+            [0xa, 0xa, 0xa, 0xa, 0xc, 0xc, 0xa, 0xa, 0xb, 0xb, 0xb, 0xb, 0xb, 0xb, 0xb, 0xb]
+        );
+
+        let trace = mem.destroy().take_trace();
+        assert_eq!(trace[..2], [write(0x100, 0x10), write(0x200, 4)]);
+    }
+
+    #[test]
+    fn overlapping_writes() {
+        let mut mem = build_mem(0x10);
+
+        // overlapping writes are merged even if they exceed the ideal batch size
+        mem.phys_write_raw_list(&[
+            PhysicalWriteData(0x1008.into(), &[0x2; 0x10]),
+            PhysicalWriteData(0x1000.into(), &[0x1; 0x10]),
+        ])
+        .unwrap();
+
+        let mut buf = [0u8; 0x18];
+        mem.phys_read_raw_into(0x1000.into(), &mut buf).unwrap();
+        assert_eq!(buf[..0x10], [0x1; 0x10]);
+        assert_eq!(buf[0x10..], [0x2; 0x8]);
+
+        let trace = mem.destroy().take_trace();
+        assert_eq!(trace[0], write(0x1000, 0x18));
+    }
+
+    #[test]
+    fn invalid_batch_size() {
+        assert!(CoalescingPhysicalMemory::new(DummyMemory::new(size::mb(1)), 0).is_err());
+    }
+
+    #[test]
+    fn conformance() {
+        let mut mem = build_mem(size::kb(4));
+        assert_phys_mem_conformance(&mut mem);
+    }
+}
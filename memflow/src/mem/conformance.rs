@@ -0,0 +1,326 @@
+/*!
+Conformance checks for implementations of the `PhysicalMemory` trait.
+
+Connector authors can run `assert_phys_mem_conformance` against their backend
+to verify that it upholds the contract documented on
+[`PhysicalMemory::phys_read_raw_list`](../phys_mem/trait.PhysicalMemory.html#tymethod.phys_read_raw_list).
+
+The checks expect the contents of the memory to be static while they are running,
+so they should be executed against a test fixture or a halted target.
+If the memory is not marked as read-only, the checks will temporarily write to it
+and restore the original contents afterwards.
+
+# Examples
+
+```
+use memflow::mem::conformance::assert_phys_mem_conformance;
+use memflow::mem::dummy::DummyMemory;
+use memflow::types::size;
+
+let mut mem = DummyMemory::new(size::mb(4));
+assert_phys_mem_conformance(&mut mem);
+```
+*/
+
+use std::prelude::v1::*;
+
+use crate::mem::{PhysicalMemory, PhysicalReadData, PhysicalWriteData};
+use crate::types::{size, Address};
+
+/// List of probe reads relative to a base address.
+///
+/// The probes do not overlap and cover aligned, unaligned, empty and page crossing reads.
+const PROBES: [(usize, usize); 5] = [
+    (0x0, 8),
+    (0x13, 5),
+    (0x100, 0x800),
+    (0xff8, 0x10),
+    (0x1800, 0),
+];
+
+/// Verifies that the given memory object follows the contract of the `PhysicalMemory` trait.
+///
+/// # Panics
+///
+/// Panics with a description of the violated rule if the memory object does not conform.
+pub fn assert_phys_mem_conformance<T: PhysicalMemory + ?Sized>(mem: &mut T) {
+    let metadata = mem.metadata();
+    assert!(metadata.size > 0, "physical memory reports a size of 0");
+
+    assert!(
+        mem.phys_read_raw_list(&mut []).is_ok(),
+        "reading an empty list has to succeed"
+    );
+
+    let probes = probe_list(metadata.size);
+
+    // reference reads, every probe is read on its own
+    let single = probes
+        .iter()
+        .map(|&(addr, len)| {
+            let mut buf = vec![0u8; len];
+            assert!(
+                mem.phys_read_raw_into(addr.into(), &mut buf).is_ok(),
+                "reading {} bytes at {:x} failed",
+                len,
+                addr
+            );
+            buf
+        })
+        .collect::<Vec<_>>();
+
+    // a batch has to yield the same results as individual reads, regardless of ordering
+    assert_eq!(
+        read_list(mem, &probes, 0x0),
+        single,
+        "batched reads differ from individual reads"
+    );
+
+    let mut reversed = probes.clone();
+    reversed.reverse();
+    let mut reversed_result = read_list(mem, &reversed, 0x0);
+    reversed_result.reverse();
+    assert_eq!(
+        reversed_result, single,
+        "reversed batched reads differ from individual reads"
+    );
+
+    let mut duplicated = probes.clone();
+    duplicated.extend(probes.iter().copied());
+    assert_eq!(
+        read_list(mem, &duplicated, 0x0),
+        single
+            .iter()
+            .chain(single.iter())
+            .cloned()
+            .collect::<Vec<_>>(),
+        "reads with duplicate entries differ from individual reads"
+    );
+
+    // reads outside of the physical address space are not an error and do not affect other entries
+    let out_of_bounds = probes
+        .iter()
+        .copied()
+        .chain(
+            [
+                (Address::from(metadata.size), 8),
+                (Address::from(metadata.size.saturating_sub(4)), 8),
+            ]
+            .iter()
+            .copied(),
+        )
+        .collect::<Vec<_>>();
+    let mut bufs = out_of_bounds
+        .iter()
+        .map(|&(_, len)| vec![0u8; len])
+        .collect::<Vec<_>>();
+    {
+        let mut list = out_of_bounds
+            .iter()
+            .zip(bufs.iter_mut())
+            .map(|(&(addr, _), buf)| PhysicalReadData(addr.into(), &mut buf[..]))
+            .collect::<Vec<_>>();
+        assert!(
+            mem.phys_read_raw_list(&mut list).is_ok(),
+            "partially out of bounds reads have to succeed"
+        );
+    }
+    assert_eq!(
+        &bufs[..probes.len()],
+        &single[..],
+        "out of bounds entries affected other entries in the same batch"
+    );
+
+    assert_eq!(
+        metadata.readonly,
+        !metadata.capabilities.supports_write(),
+        "readonly has to match the WRITE capability"
+    );
+    if metadata.readonly {
+        return;
+    }
+
+    // only probes that are backed by memory can be written to.
+    // unbacked parts are left untouched and thus still contain the initial buffer contents.
+    let filled = read_list(mem, &probes, 0xff);
+    let backed = probes
+        .iter()
+        .zip(single.iter())
+        .zip(filled.iter())
+        .filter(|((probe, original), filled)| probe.1 > 0 && original == filled)
+        .map(|((probe, original), _)| (*probe, original.clone()))
+        .collect::<Vec<_>>();
+
+    let patterns = backed
+        .iter()
+        .enumerate()
+        .map(|(i, (_, original))| {
+            original
+                .iter()
+                .enumerate()
+                .map(|(j, b)| !*b ^ ((i + j) as u8 & 0x7f))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let list = backed
+        .iter()
+        .zip(patterns.iter())
+        .map(|(&((addr, _), _), data)| PhysicalWriteData(addr.into(), &data[..]))
+        .collect::<Vec<_>>();
+    assert!(
+        mem.phys_write_raw_list(&list).is_ok(),
+        "batched writes failed"
+    );
+
+    for (((addr, len), _), data) in backed.iter().zip(patterns.iter()) {
+        let mut buf = vec![0u8; *len];
+        assert!(mem.phys_read_raw_into((*addr).into(), &mut buf).is_ok());
+        assert_eq!(
+            &buf, data,
+            "batched write of {} bytes at {:x} could not be read back",
+            len, addr
+        );
+    }
+
+    // restore the original contents
+    for ((addr, _), original) in backed.iter() {
+        assert!(
+            mem.phys_write_raw((*addr).into(), original).is_ok(),
+            "writing {} bytes at {:x} failed",
+            original.len(),
+            addr
+        );
+    }
+
+    let backed_probes = backed.iter().map(|(probe, _)| *probe).collect::<Vec<_>>();
+    assert_eq!(
+        read_list(mem, &backed_probes, 0x0),
+        backed
+            .iter()
+            .map(|(_, original)| original.clone())
+            .collect::<Vec<_>>(),
+        "individual writes could not be read back"
+    );
+}
+
+/// Places the probes at the start, in the middle and at the end of the physical address space.
+fn probe_list(mem_size: usize) -> Vec<(Address, usize)> {
+    let mut bases: Vec<usize> = Vec::new();
+    for &base in [
+        0,
+        (mem_size / 2) & !(size::kb(4) - 1),
+        mem_size.saturating_sub(size::kb(8)) & !(size::kb(4) - 1),
+    ]
+    .iter()
+    {
+        // probes of different bases must not overlap
+        if bases
+            .last()
+            .map(|&last| base >= last + size::kb(8))
+            .unwrap_or(true)
+        {
+            bases.push(base);
+        }
+    }
+
+    bases
+        .into_iter()
+        .flat_map(|base| {
+            PROBES
+                .iter()
+                .map(move |&(offset, len)| (base + offset, len))
+        })
+        .filter(|&(addr, len)| addr + len <= mem_size)
+        .map(|(addr, len)| (Address::from(addr), len))
+        .collect()
+}
+
+/// Reads all probes in a single batch with output buffers that are initialized to `fill`.
+fn read_list<T: PhysicalMemory + ?Sized>(
+    mem: &mut T,
+    probes: &[(Address, usize)],
+    fill: u8,
+) -> Vec<Vec<u8>> {
+    let mut bufs = probes
+        .iter()
+        .map(|&(_, len)| vec![fill; len])
+        .collect::<Vec<_>>();
+
+    let mut list = probes
+        .iter()
+        .zip(bufs.iter_mut())
+        .map(|(&(addr, _), buf)| PhysicalReadData(addr.into(), &mut buf[..]))
+        .collect::<Vec<_>>();
+    assert!(
+        mem.phys_read_raw_list(&mut list).is_ok(),
+        "batched read of {} entries failed",
+        probes.len()
+    );
+
+    bufs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connector::FileIOMemory;
+    use crate::error::Result;
+    use crate::mem::dummy::DummyMemory;
+    use crate::mem::{MemoryMap, PhysicalMemoryMetadata};
+
+    use std::io::Cursor;
+
+    #[test]
+    fn dummy_memory() {
+        let mut mem = DummyMemory::new(size::mb(1));
+        mem.phys_write(0x1000.into(), &0xdead_beef_u32).unwrap();
+        assert_phys_mem_conformance(&mut mem);
+        assert_eq!(
+            mem.phys_read::<u32>(0x1000.into()).unwrap(),
+            0xdead_beef_u32
+        );
+    }
+
+    #[test]
+    fn sparse_file() {
+        let mut map = MemoryMap::new();
+        map.push_remap(0x0.into(), size::kb(64), 0x0.into());
+        map.push_remap(size::kb(768).into(), size::kb(256), size::kb(64).into());
+
+        let buf = (0..size::kb(320)).map(|i| i as u8).collect::<Vec<_>>();
+        let mut mem = FileIOMemory::try_with_reader(Cursor::new(buf), map).unwrap();
+        assert_phys_mem_conformance(&mut mem);
+    }
+
+    /// Fills every entry of a batch with the data of the first entry
+    struct BrokenBatching(DummyMemory);
+
+    impl PhysicalMemory for BrokenBatching {
+        fn phys_read_raw_list(&mut self, data: &mut [PhysicalReadData]) -> Result<()> {
+            if let Some(PhysicalReadData(first, _)) = data.first() {
+                let first = *first;
+                for PhysicalReadData(_, buf) in data.iter_mut() {
+                    self.0.phys_read_raw_into(first, buf)?;
+                }
+            }
+            Ok(())
+        }
+
+        fn phys_write_raw_list(&mut self, data: &[PhysicalWriteData]) -> Result<()> {
+            self.0.phys_write_raw_list(data)
+        }
+
+        fn metadata(&self) -> PhysicalMemoryMetadata {
+            self.0.metadata()
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "batched reads differ from individual reads")]
+    fn broken_batching() {
+        let mut mem = DummyMemory::new(size::mb(1));
+        mem.phys_write(0x13.into(), &0xdead_beef_u32).unwrap();
+        assert_phys_mem_conformance(&mut BrokenBatching(mem));
+    }
+}
@@ -377,19 +377,37 @@ impl DummyMemory {
         map_size: usize,
         test_buf: &[u8],
     ) -> Address {
-        let mut cur_len = 0;
-
         let dtb = self.alloc_pt_page();
 
-        let mut pml4 = unsafe {
+        let pml4 = unsafe {
             &mut *(self
                 .buf
                 .as_ptr()
                 .add(dtb.addr.as_usize())
-                .cast::<PageTable>() as *mut _)
+                .cast::<PageTable>() as *mut PageTable)
         };
         *pml4 = PageTable::new();
 
+        self.map_const_base(dtb.addr, virt_base, map_size, test_buf);
+
+        dtb.addr
+    }
+
+    /// Maps `map_size` bytes at `virt_base` into the existing page tables at `dtb`.
+    ///
+    /// The newly allocated pages are initialized with the contents of `test_buf`.
+    pub fn map_const_base(
+        &mut self,
+        dtb: Address,
+        virt_base: Address,
+        map_size: usize,
+        test_buf: &[u8],
+    ) {
+        let mut cur_len = 0;
+
+        let mut pml4 =
+            unsafe { &mut *(self.buf.as_ptr().add(dtb.as_usize()).cast::<PageTable>() as *mut _) };
+
         let mut pt_mapper =
             unsafe { OffsetPageTable::new(&mut pml4, VirtAddr::from_ptr(self.buf.as_ptr())) };
 
@@ -452,7 +470,5 @@ impl DummyMemory {
             }
             cur_len += page_info.size.to_size();
         }
-
-        dtb.addr
     }
 }
@@ -0,0 +1,235 @@
+/*!
+Acquisition of the physical address space of a target into a raw image.
+
+`dump_physical` streams all given physical regions into a file. The file offset of every byte
+equals its physical address, gaps between the regions (e.g. MMIO holes) are never read from the target
+and are left as holes in the file. Most file systems store these holes sparsely, they read back as zeros.
+
+The returned `MemoryMap` describes which parts of the file contain data.
+Together with the file it can be opened with the `FileIOMemory` connector.
+
+# Examples
+
+```
+use memflow::connector::FileIOMemory;
+use memflow::mem::{dump, PhysicalMemory};
+use memflow::types::{size, Address};
+
+use std::fs::OpenOptions;
+
+fn archive<T: PhysicalMemory>(mem: &mut T, path: &str) {
+    // skip the legacy vga/bios area
+    let regions = [
+        (Address::NULL, size::kb(640)),
+        (Address::from(size::mb(1)), mem.metadata().size - size::mb(1)),
+    ];
+    let map = dump::dump_physical(mem, path, &regions, |done, total| {
+        println!("{}/{} bytes", done, total);
+    })
+    .unwrap();
+
+    let file = OpenOptions::new().read(true).write(true).open(path).unwrap();
+    let _connector = FileIOMemory::try_with_reader(file, map).unwrap();
+}
+# use memflow::mem::dummy::DummyMemory;
+# let path = std::env::temp_dir().join("memflow_dump_doctest.raw");
+# archive(&mut DummyMemory::new(size::mb(4)), path.to_str().unwrap());
+# std::fs::remove_file(path).unwrap();
+```
+*/
+
+use std::prelude::v1::*;
+
+use crate::error::{Error, Result};
+use crate::mem::{MemoryMap, PhysicalMemory};
+use crate::types::Address;
+
+use std::fs::File;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Number of bytes that are read from the target at once
+pub const DUMP_BATCH_SIZE: usize = 0x10_0000;
+
+/// Dumps the given physical regions of `mem` into a raw image at `path`.
+///
+/// An existing file will be truncated. If `regions` is empty the whole range
+/// reported by `PhysicalMemory::metadata()` is dumped.
+///
+/// `progress` is called after every chunk with the number of bytes dumped so far
+/// and the total number of bytes. A failing read aborts the dump.
+///
+/// Returns the memory map that describes the layout of the image.
+pub fn dump_physical<T, P, F>(
+    mem: &mut T,
+    path: P,
+    regions: &[(Address, usize)],
+    progress: F,
+) -> Result<MemoryMap<(Address, usize)>>
+where
+    T: PhysicalMemory + ?Sized,
+    P: AsRef<Path>,
+    F: FnMut(usize, usize),
+{
+    let mut file = File::create(path).map_err(|_| Error::IO("unable to create dump file"))?;
+    let map = dump_physical_into(mem, &mut file, regions, DUMP_BATCH_SIZE, progress)?;
+    file.sync_all()
+        .map_err(|_| Error::IO("unable to write dump file"))?;
+    Ok(map)
+}
+
+/// Dumps the given physical regions of `mem` into `writer`, reading `batch_size` bytes at once.
+///
+/// The writer is expected to be empty and positioned at offset 0.
+/// Gaps between the regions are skipped by seeking, see `dump_physical()` for details.
+pub fn dump_physical_into<T, W, F>(
+    mem: &mut T,
+    writer: &mut W,
+    regions: &[(Address, usize)],
+    batch_size: usize,
+    mut progress: F,
+) -> Result<MemoryMap<(Address, usize)>>
+where
+    T: PhysicalMemory + ?Sized,
+    W: Write + Seek,
+    F: FnMut(usize, usize),
+{
+    if batch_size == 0 {
+        return Err(Error::Other("batch_size must not be zero"));
+    }
+
+    let regions = normalize_regions(regions, mem.metadata().size);
+    let total = regions.iter().map(|&(_, size)| size).sum::<usize>();
+
+    let mut map = MemoryMap::new();
+    let mut buf = vec![0u8; batch_size];
+    let mut done = 0;
+    for &(base, size) in regions.iter() {
+        writer
+            .seek(SeekFrom::Start(base.as_u64()))
+            .map_err(|_| Error::IO("unable to seek in dump file"))?;
+
+        let mut offset = 0;
+        while offset < size {
+            let len = std::cmp::min(batch_size, size - offset);
+            let chunk = &mut buf[..len];
+            mem.phys_read_raw_into((base + offset).into(), chunk)?;
+            writer
+                .write_all(chunk)
+                .map_err(|_| Error::IO("unable to write dump file"))?;
+
+            offset += len;
+            done += len;
+            progress(done, total);
+        }
+
+        map.push_remap(base, size, base);
+    }
+
+    writer
+        .flush()
+        .map_err(|_| Error::IO("unable to write dump file"))?;
+    Ok(map)
+}
+
+/// Sorts and merges the regions and clips them to `mem_size`.
+fn normalize_regions(regions: &[(Address, usize)], mem_size: usize) -> Vec<(Address, usize)> {
+    let mut ranges = if regions.is_empty() {
+        vec![(0, mem_size)]
+    } else {
+        regions
+            .iter()
+            .map(|&(base, size)| (base.as_usize(), base.as_usize().saturating_add(size)))
+            .collect::<Vec<_>>()
+    };
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in ranges.into_iter() {
+        let end = std::cmp::min(end, mem_size);
+        if start >= end {
+            continue;
+        }
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = std::cmp::max(last.1, end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    merged
+        .into_iter()
+        .map(|(start, end)| (Address::from(start), end - start))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connector::FileIOMemory;
+    use crate::mem::dummy::DummyMemory;
+    use crate::types::size;
+
+    use std::io::Cursor;
+
+    #[test]
+    fn dump_regions() {
+        let mut mem = DummyMemory::new(size::kb(128));
+        let buf = (0..size::kb(128))
+            .map(|i| (i / 7) as u8)
+            .collect::<Vec<_>>();
+        mem.phys_write_raw(Address::NULL.into(), &buf).unwrap();
+
+        let regions = [
+            (Address::from(0x10000), size::kb(8)),
+            (Address::NULL, size::kb(4)),
+            (Address::from(0x11000), size::kb(4)),
+            (Address::from(0x1f000), size::kb(64)),
+        ];
+
+        let mut progress = Vec::new();
+        let mut image = Cursor::new(Vec::new());
+        let map = dump_physical_into(&mut mem, &mut image, &regions, 0x1800, |done, total| {
+            progress.push((done, total))
+        })
+        .unwrap();
+
+        // overlapping regions are merged and the last region is clipped
+        assert_eq!(
+            map.coverage(),
+            vec![
+                (Address::NULL, size::kb(4)),
+                (Address::from(0x10000), size::kb(8)),
+                (Address::from(0x1f000), size::kb(4)),
+            ]
+        );
+        assert_eq!(progress.last(), Some(&(size::kb(16), size::kb(16))));
+        assert_eq!(progress.len(), 5);
+
+        let image = image.into_inner();
+        assert_eq!(image.len(), 0x20000);
+        assert_eq!(&image[..0x1000], &buf[..0x1000]);
+        assert!(image[0x1000..0x10000].iter().all(|&b| b == 0));
+        assert_eq!(&image[0x10000..0x12000], &buf[0x10000..0x12000]);
+
+        // the image can be opened with the file i/o connector
+        let mut loaded = FileIOMemory::try_with_reader(Cursor::new(image), map).unwrap();
+        let mut out = vec![0u8; 0x100];
+        loaded
+            .phys_read_raw_into(Address::from(0x11f00).into(), &mut out)
+            .unwrap();
+        assert_eq!(&out[..], &buf[0x11f00..0x12000]);
+    }
+
+    #[test]
+    fn dump_all() {
+        let mut mem = DummyMemory::new(size::kb(64));
+        let mut image = Cursor::new(Vec::new());
+        let map = dump_physical_into(&mut mem, &mut image, &[], size::kb(16), |_, _| {}).unwrap();
+        assert_eq!(map.coverage(), vec![(Address::NULL, size::kb(64))]);
+        assert_eq!(image.into_inner().len(), size::kb(64));
+
+        assert!(
+            dump_physical_into(&mut mem, &mut Cursor::new(Vec::new()), &[], 0, |_, _| {}).is_err()
+        );
+    }
+}
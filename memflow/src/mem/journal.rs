@@ -0,0 +1,252 @@
+/*!
+Undo support for writes to objects implementing the `VirtualMemory` trait.
+
+The `JournaledMemory` reads the original contents of every region before it is written
+and keeps them in a journal. Writes can then be reverted one by one in reverse order
+or all at once, which allows trying out patches on a live target and restoring the
+original state afterwards.
+
+Reads are forwarded to the underlying memory unchanged.
+
+# Examples
+
+```
+use memflow::mem::{JournaledMemory, VirtualMemory};
+use memflow::types::Address;
+
+fn patch<T: VirtualMemory>(virt_mem: T, addr: Address) {
+    let mut mem = JournaledMemory::new(virt_mem);
+
+    let original = mem.virt_read::<u32>(addr).unwrap();
+    mem.virt_write(addr, &0x9090_9090u32).unwrap();
+    assert_eq!(mem.virt_read::<u32>(addr).unwrap(), 0x9090_9090);
+
+    // observe the effect of the patch, then revert it
+    assert!(mem.undo_last().unwrap());
+    assert_eq!(mem.virt_read::<u32>(addr).unwrap(), original);
+}
+# use memflow::mem::dummy::DummyMemory;
+# use memflow::types::size;
+# let (mem, virt_base) = DummyMemory::new_virt(size::mb(4), size::mb(2), &[]);
+# patch(mem, virt_base);
+```
+*/
+
+use std::prelude::v1::*;
+
+use crate::architecture::Endianess;
+use crate::error::{Error, PartialError, PartialResult, Result};
+use crate::mem::virt_mem::{VirtualMemory, VirtualReadData, VirtualWriteData};
+use crate::types::{Address, Page, PhysicalAddress};
+
+/// The original contents of all regions touched by a single batched write.
+struct JournalEntry {
+    regions: Vec<(Address, Vec<u8>)>,
+}
+
+/// Wrapper that records the original contents of all written regions so that writes can be undone.
+///
+/// Since this wrapper implements `VirtualMemory` it can be used as a replacement
+/// in all structs and functions that require a `VirtualMemory` object.
+pub struct JournaledMemory<T> {
+    mem: T,
+    journal: Vec<JournalEntry>,
+}
+
+impl<T: VirtualMemory> JournaledMemory<T> {
+    /// Creates a new wrapper with an empty journal.
+    pub fn new(mem: T) -> Self {
+        Self {
+            mem,
+            journal: Vec::new(),
+        }
+    }
+
+    /// Returns the number of writes that can be undone.
+    pub fn len(&self) -> usize {
+        self.journal.len()
+    }
+
+    /// Returns true if there are no writes that can be undone.
+    pub fn is_empty(&self) -> bool {
+        self.journal.is_empty()
+    }
+
+    /// Clears the journal and keeps all writes in place.
+    pub fn clear(&mut self) {
+        self.journal.clear();
+    }
+
+    /// Consumes the wrapper and returns the underlying memory.
+    ///
+    /// Writes that have not been undone are kept in place.
+    pub fn destroy(self) -> T {
+        self.mem
+    }
+
+    /// Restores the original contents of the regions touched by the last write.
+    ///
+    /// Returns `false` if the journal is empty.
+    /// In case the original contents can not be written back the entry remains in the journal.
+    pub fn undo_last(&mut self) -> PartialResult<bool> {
+        let entry = match self.journal.pop() {
+            Some(entry) => entry,
+            None => return Ok(false),
+        };
+
+        // restore in reverse order in case regions of the same batch overlap
+        let data = entry
+            .regions
+            .iter()
+            .rev()
+            .map(|(addr, buf)| VirtualWriteData(*addr, buf.as_slice()))
+            .collect::<Vec<_>>();
+        match self.mem.virt_write_raw_list(&data) {
+            Ok(_) => Ok(true),
+            Err(err) => {
+                self.journal.push(entry);
+                Err(match err {
+                    PartialError::Error(err) => PartialError::Error(err),
+                    _ => PartialError::PartialVirtualWrite,
+                })
+            }
+        }
+    }
+
+    /// Restores the original contents of all journaled writes, starting with the most recent one.
+    ///
+    /// Returns the number of writes that have been undone.
+    /// In case a write can not be undone the remaining entries are kept in the journal.
+    pub fn undo_all(&mut self) -> PartialResult<usize> {
+        let mut count = 0;
+        while !self.journal.is_empty() {
+            match self.undo_last() {
+                Ok(_) => count += 1,
+                Err(PartialError::Error(err)) => return Err(PartialError::Error(err)),
+                Err(_) => return Err(PartialError::PartialVirtualWrite),
+            }
+        }
+        Ok(count)
+    }
+}
+
+impl<T: VirtualMemory> VirtualMemory for JournaledMemory<T> {
+    fn virt_read_raw_list(&mut self, data: &mut [VirtualReadData]) -> PartialResult<()> {
+        self.mem.virt_read_raw_list(data)
+    }
+
+    /// Reads the original contents of all regions before forwarding the write.
+    ///
+    /// The write is rejected if the original contents can not be read completely.
+    fn virt_write_raw_list(&mut self, data: &[VirtualWriteData]) -> PartialResult<()> {
+        let mut regions = data
+            .iter()
+            .map(|VirtualWriteData(addr, buf)| (*addr, vec![0u8; buf.len()]))
+            .collect::<Vec<_>>();
+        {
+            let mut reads = regions
+                .iter_mut()
+                .map(|(addr, buf)| VirtualReadData(*addr, buf.as_mut_slice()))
+                .collect::<Vec<_>>();
+            self.mem.virt_read_raw_list(&mut reads).map_err(|_| {
+                PartialError::Error(Error::VirtualMemory(
+                    "unable to read the original contents of a journaled write",
+                ))
+            })?;
+        }
+
+        let ret = self.mem.virt_write_raw_list(data);
+        match ret {
+            // nothing has been written, there is nothing to undo
+            Err(PartialError::Error(_)) => {}
+            _ => self.journal.push(JournalEntry { regions }),
+        }
+        ret
+    }
+
+    fn virt_page_info(&mut self, addr: Address) -> Result<Page> {
+        self.mem.virt_page_info(addr)
+    }
+
+    fn virt_to_phys(&mut self, addr: Address) -> Result<PhysicalAddress> {
+        self.mem.virt_to_phys(addr)
+    }
+
+    fn virt_translation_map_range(
+        &mut self,
+        start: Address,
+        end: Address,
+    ) -> Vec<(Address, usize, PhysicalAddress)> {
+        self.mem.virt_translation_map_range(start, end)
+    }
+
+    fn virt_page_map_range(
+        &mut self,
+        gap_size: usize,
+        start: Address,
+        end: Address,
+    ) -> Vec<(Address, usize)> {
+        self.mem.virt_page_map_range(gap_size, start, end)
+    }
+
+    fn endianess(&self) -> Endianess {
+        self.mem.endianess()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::architecture::x86::x64;
+    use crate::error::PartialResultExt;
+    use crate::mem::dummy::DummyMemory;
+    use crate::mem::VirtualDMA;
+    use crate::types::size;
+
+    #[test]
+    fn undo_writes() {
+        let buf = (0..0x100).map(|i| i as u8).collect::<Vec<_>>();
+        let (mem, dtb, virt_base) = DummyMemory::new_and_dtb(size::mb(4), size::mb(2), &buf);
+        let virt_mem = VirtualDMA::new(mem, x64::ARCH, x64::new_translator(dtb));
+        let mut mem = JournaledMemory::new(virt_mem);
+
+        mem.virt_write(virt_base + 0x10, &0xdead_beef_u32).unwrap();
+        mem.virt_write_raw_list(&[
+            VirtualWriteData(virt_base + 0x20, &[0xaa; 8]),
+            VirtualWriteData(virt_base + 0x24, &[0xbb; 8]),
+        ])
+        .unwrap();
+        mem.virt_write(virt_base + 0x12, &0u16).unwrap();
+        assert_eq!(mem.len(), 3);
+
+        assert_eq!(mem.undo_last(), Ok(true));
+        assert_eq!(
+            mem.virt_read::<u32>(virt_base + 0x10).data().unwrap(),
+            0xdead_beef
+        );
+
+        // overlapping regions of a single batch are restored in reverse order
+        assert_eq!(mem.undo_last(), Ok(true));
+        assert_eq!(
+            mem.virt_read_raw(virt_base + 0x20, 0x10).data().unwrap(),
+            &buf[0x20..0x30]
+        );
+
+        mem.virt_write(virt_base + 0x30, &[0u8; 0x10]).unwrap();
+        assert_eq!(mem.undo_all(), Ok(2));
+        assert!(mem.is_empty());
+        assert_eq!(mem.undo_last(), Ok(false));
+        assert_eq!(mem.virt_read_raw(virt_base, 0x100).data().unwrap(), buf);
+    }
+
+    #[test]
+    fn reject_unreadable() {
+        let (mem, dtb, virt_base) = DummyMemory::new_and_dtb(size::mb(4), size::mb(2), &[]);
+        let virt_mem = VirtualDMA::new(mem, x64::ARCH, x64::new_translator(dtb));
+        let mut mem = JournaledMemory::new(virt_mem);
+
+        // the page after the mapped region is not present
+        assert!(mem.virt_write(virt_base + size::mb(2) - 4, &0u64).is_err());
+        assert!(mem.is_empty());
+    }
+}
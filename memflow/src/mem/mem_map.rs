@@ -91,6 +91,39 @@ impl<M: SplitAtIndexNoMutation> MemoryMap<M> {
         self.mappings.iter()
     }
 
+    /// Returns all address ranges that are backed by this memory map.
+    ///
+    /// Adjacent mappings are merged into a single `(start, length)` run.
+    /// The runs are sorted by their start address and the gaps between them are not backed by any mapping.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memflow::mem::MemoryMap;
+    /// use memflow::types::Address;
+    ///
+    /// let mut map = MemoryMap::new();
+    /// map.push_remap(0x1000.into(), 0x1000, 0.into());
+    /// map.push_remap(0x2000.into(), 0x1000, 0x4000.into());
+    /// map.push_remap(0x5000.into(), 0x1000, 0x2000.into());
+    ///
+    /// assert_eq!(
+    ///     map.coverage(),
+    ///     vec![(Address::from(0x1000), 0x2000), (Address::from(0x5000), 0x1000)]
+    /// );
+    /// ```
+    pub fn coverage(&self) -> Vec<(Address, usize)> {
+        let mut runs: Vec<(Address, usize)> = Vec::new();
+        for mapping in self.mappings.iter() {
+            let size = mapping.output.borrow().length();
+            match runs.last_mut() {
+                Some((base, len)) if *base + *len == mapping.base => *len += size,
+                _ => runs.push((mapping.base, size)),
+            }
+        }
+        runs
+    }
+
     /// Maps a linear address range to a hardware address range.
     ///
     /// Output element lengths will both match, so there is no need to do additonal clipping
@@ -502,6 +535,24 @@ mod tests {
         assert_eq!(map.map(0x4000.into(), 1, &mut void).next(), None);
     }
 
+    #[test]
+    fn test_coverage() {
+        let mut map = MemoryMap::new();
+        assert!(map.coverage().is_empty());
+
+        map.push_remap(0x3000.into(), 0x1000, 0x2000.into());
+        map.push_remap(0x1000.into(), 0x1000, 0.into());
+        map.push_remap(0x4000.into(), 0x2000, 0x8000.into());
+
+        assert_eq!(
+            map.coverage(),
+            vec![
+                (Address::from(0x1000), 0x1000),
+                (Address::from(0x3000), 0x3000)
+            ]
+        );
+    }
+
     #[test]
     fn test_mapping_out_of_bounds() {
         let mut map = MemoryMap::new();
@@ -9,30 +9,79 @@ and are themselves a memory backend.
 TODO: more documentation
 */
 
+pub mod aligned;
+#[cfg(feature = "std")]
+pub mod benchmark;
 pub mod cache;
+pub mod coalescing;
+pub mod conformance;
+#[cfg(feature = "std")]
+pub mod dump;
+pub mod journal;
 pub mod mem_map;
+pub mod overlay;
 pub mod phys_mem;
 pub mod phys_mem_batcher;
+pub mod reconnect;
+pub mod signature;
+pub mod snapshot;
+pub mod sparse;
+pub mod tracing;
 pub mod virt_mem;
 pub mod virt_mem_batcher;
 pub mod virt_translate;
 
+#[cfg(feature = "std")]
+pub mod shared;
+#[cfg(feature = "std")]
+pub mod timeout;
+
 #[cfg(any(feature = "dummy_mem", test))]
 pub mod dummy;
 
+#[doc(hidden)]
+pub use aligned::AlignedPhysicalMemory;
 #[doc(hidden)]
 pub use cache::*; // TODO: specify pub declarations
 #[doc(hidden)]
+pub use coalescing::CoalescingPhysicalMemory;
+#[doc(hidden)]
+pub use conformance::assert_phys_mem_conformance;
+#[doc(hidden)]
+pub use journal::JournaledMemory;
+#[doc(hidden)]
 pub use mem_map::MemoryMap;
 #[doc(hidden)]
+pub use overlay::{OverlayPhysicalMemory, WRITABLE_ARG_SPEC};
+#[doc(hidden)]
 pub use phys_mem::{
-    CloneablePhysicalMemory, PhysicalMemory, PhysicalMemoryBox, PhysicalMemoryMetadata,
-    PhysicalReadData, PhysicalReadIterator, PhysicalWriteData, PhysicalWriteIterator,
+    CloneablePhysicalMemory, PhysicalMemory, PhysicalMemoryBox, PhysicalMemoryCapabilities,
+    PhysicalMemoryMetadata, PhysicalReadData, PhysicalReadIterator, PhysicalWriteData,
+    PhysicalWriteIterator,
 };
 #[doc(hidden)]
 pub use phys_mem_batcher::PhysicalMemoryBatcher;
 #[doc(hidden)]
-pub use virt_mem::{VirtualDMA, VirtualMemory, VirtualReadData, VirtualWriteData};
+pub use reconnect::ReconnectingPhysicalMemory;
+#[doc(hidden)]
+#[cfg(feature = "std")]
+pub use shared::{SharedPhysicalMemory, SharedVirtualTranslate};
+#[doc(hidden)]
+pub use signature::Signature;
+#[doc(hidden)]
+pub use snapshot::Snapshot;
+#[doc(hidden)]
+pub use sparse::SparseMemory;
+#[doc(hidden)]
+#[cfg(feature = "std")]
+pub use timeout::{TimeoutPhysicalMemory, TimeoutPhysicalMemoryBuilder};
+#[doc(hidden)]
+pub use tracing::{AccessKind, MemoryAccess, TracingMemory};
+#[doc(hidden)]
+pub use virt_mem::{
+    PtrWidth, StrEncoding, StructData, StructReader, VirtualDMA, VirtualMemory, VirtualReadData,
+    VirtualWriteData,
+};
 #[doc(hidden)]
 pub use virt_mem_batcher::VirtualMemoryBatcher;
 #[doc(hidden)]
@@ -0,0 +1,362 @@
+/*!
+Copy-on-write overlay for objects implementing the `PhysicalMemory` trait.
+
+The `OverlayPhysicalMemory` wraps a (usually read-only) memory backend, like a coredump,
+and redirects all writes into a sparse set of in-memory pages.
+Reads are served from the overlay pages first and fall back to the underlying backend.
+
+This allows patching memory and re-reading it without ever modifying the underlying backend.
+The modified pages can be inspected via `pages()` and are dropped again with `discard()`.
+
+Connectors that are read-only by nature (e.g. file based dumps) can use `with_args()`
+to offer an opt-in write mode via the `writable=true` connector argument (see `WRITABLE_ARG_SPEC`).
+Without it the overlay rejects all writes and reports itself as read-only.
+
+# Examples
+
+```
+use memflow::mem::{OverlayPhysicalMemory, PhysicalMemory};
+
+fn patch<T: PhysicalMemory>(dump: T) {
+    let mut mem = OverlayPhysicalMemory::new(dump);
+
+    mem.phys_write(0x1000.into(), &0xdead_beef_u32).unwrap();
+    assert_eq!(mem.phys_read::<u32>(0x1000.into()).unwrap(), 0xdead_beef);
+
+    // the underlying memory is left untouched
+    let mut dump = mem.destroy();
+    assert_ne!(dump.phys_read::<u32>(0x1000.into()).unwrap(), 0xdead_beef);
+}
+# use memflow::mem::dummy::DummyMemory;
+# use memflow::types::size;
+# patch(DummyMemory::new(size::mb(4)));
+```
+*/
+
+use std::prelude::v1::*;
+
+use crate::connector::{ConnectorArgSpec, ConnectorArgs, CpuState};
+use crate::error::{Error, Result};
+use crate::iter::PageChunks;
+use crate::mem::phys_mem::{
+    PhysicalMemory, PhysicalMemoryCapabilities, PhysicalMemoryMetadata, PhysicalReadData,
+    PhysicalWriteData,
+};
+use crate::types::{size, Address, PhysicalAddress};
+
+use std::collections::BTreeMap;
+
+/// Describes the `writable` argument that is evaluated by `OverlayPhysicalMemory::with_args`.
+///
+/// Connectors can add this to their list of accepted arguments.
+pub const WRITABLE_ARG_SPEC: ConnectorArgSpec = ConnectorArgSpec::optional(
+    "writable",
+    "redirect writes into an in-memory copy-on-write overlay",
+    Some("false"),
+);
+
+/// Wrapper that keeps all writes in a page granular overlay instead of writing them to the underlying memory.
+///
+/// Since this wrapper implements `PhysicalMemory` it can be used as a replacement
+/// in all structs and functions that require a `PhysicalMemory` object.
+pub struct OverlayPhysicalMemory<T> {
+    mem: T,
+    page_size: usize,
+    pages: BTreeMap<Address, Box<[u8]>>,
+    writable: bool,
+}
+
+impl<T: PhysicalMemory> OverlayPhysicalMemory<T> {
+    /// Creates a new overlay with a page size of 4kb.
+    pub fn new(mem: T) -> Self {
+        Self {
+            mem,
+            page_size: size::kb(4),
+            pages: BTreeMap::new(),
+            writable: true,
+        }
+    }
+
+    /// Creates a new overlay that is configured by the `writable` connector argument.
+    ///
+    /// Unless `writable=true` is provided the overlay rejects all writes
+    /// and `metadata()` reports the memory as read-only.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memflow::connector::ConnectorArgs;
+    /// use memflow::mem::{OverlayPhysicalMemory, PhysicalMemory};
+    ///
+    /// fn open<T: PhysicalMemory>(dump: T, args: &ConnectorArgs) -> OverlayPhysicalMemory<T> {
+    ///     OverlayPhysicalMemory::with_args(dump, args).unwrap()
+    /// }
+    /// # use memflow::mem::dummy::DummyMemory;
+    /// # use memflow::types::size;
+    /// let mem = open(DummyMemory::new(size::mb(4)), &ConnectorArgs::parse("writable=true").unwrap());
+    /// assert_eq!(mem.metadata().readonly, false);
+    /// ```
+    pub fn with_args(mem: T, args: &ConnectorArgs) -> Result<Self> {
+        let writable = match args.get("writable").map(String::as_str) {
+            None | Some("false") | Some("0") => false,
+            Some("true") | Some("1") => true,
+            Some(_) => {
+                return Err(Error::Connector(
+                    "the writable argument must either be true or false",
+                ))
+            }
+        };
+
+        let mut overlay = Self::new(mem);
+        overlay.set_writable(writable);
+        Ok(overlay)
+    }
+
+    /// Creates a new overlay with the given page size.
+    ///
+    /// The page size has to be a power of two.
+    pub fn with_page_size(mem: T, page_size: usize) -> Result<Self> {
+        if page_size == 0 || !page_size.is_power_of_two() {
+            return Err(Error::Other("page size must be a power of two"));
+        }
+
+        Ok(Self {
+            mem,
+            page_size,
+            pages: BTreeMap::new(),
+            writable: true,
+        })
+    }
+
+    /// Enables or disables writes to the overlay.
+    ///
+    /// Disabling writes keeps all modifications that have been done so far.
+    pub fn set_writable(&mut self, writable: bool) {
+        self.writable = writable;
+    }
+
+    /// Returns true if the overlay accepts writes.
+    pub fn is_writable(&self) -> bool {
+        self.writable
+    }
+
+    /// Returns the page size of the overlay.
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    /// Returns an iterator over all modified pages and their current contents, sorted by address.
+    pub fn pages(&self) -> impl Iterator<Item = (Address, &[u8])> + '_ {
+        self.pages.iter().map(|(addr, buf)| (*addr, &buf[..]))
+    }
+
+    /// Drops all modifications, subsequent reads will return the contents of the underlying memory again.
+    pub fn discard(&mut self) {
+        self.pages.clear();
+    }
+
+    /// Consumes the overlay and returns the underlying memory. All modifications are lost.
+    pub fn destroy(self) -> T {
+        self.mem
+    }
+}
+
+impl<T: PhysicalMemory> PhysicalMemory for OverlayPhysicalMemory<T> {
+    fn phys_read_raw_list(&mut self, data: &mut [PhysicalReadData]) -> Result<()> {
+        self.mem.phys_read_raw_list(data)?;
+
+        if self.pages.is_empty() {
+            return Ok(());
+        }
+
+        let page_size = self.page_size;
+        for PhysicalReadData(addr, out) in data.iter_mut() {
+            for (chunk_addr, chunk) in (&mut out[..]).page_chunks(addr.address(), page_size) {
+                let page_addr = chunk_addr.as_page_aligned(page_size);
+                if let Some(page) = self.pages.get(&page_addr) {
+                    let start = chunk_addr - page_addr;
+                    chunk.copy_from_slice(&page[start..start + chunk.len()]);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn phys_write_raw_list(&mut self, data: &[PhysicalWriteData]) -> Result<()> {
+        if !self.writable {
+            return Err(Error::Connector("memory is read-only"));
+        }
+
+        let page_size = self.page_size;
+        let mem_size = self.mem.metadata().size;
+
+        // copy all pages that are written to for the first time
+        let mut new_pages = data
+            .iter()
+            .flat_map(|PhysicalWriteData(addr, data)| {
+                data.page_chunks(addr.address(), page_size)
+                    .map(move |(chunk_addr, _)| chunk_addr.as_page_aligned(page_size))
+            })
+            .filter(|page_addr| page_addr.as_usize() < mem_size)
+            .filter(|page_addr| !self.pages.contains_key(page_addr))
+            .collect::<Vec<_>>();
+        new_pages.sort();
+        new_pages.dedup();
+
+        if !new_pages.is_empty() {
+            let mut bufs = vec![vec![0u8; page_size].into_boxed_slice(); new_pages.len()];
+            {
+                let mut list = new_pages
+                    .iter()
+                    .zip(bufs.iter_mut())
+                    .map(|(&addr, buf)| PhysicalReadData(addr.into(), &mut buf[..]))
+                    .collect::<Vec<_>>();
+                self.mem.phys_read_raw_list(&mut list)?;
+            }
+            self.pages
+                .extend(new_pages.into_iter().zip(bufs.into_iter()));
+        }
+
+        for PhysicalWriteData(addr, data) in data.iter() {
+            for (chunk_addr, chunk) in data.page_chunks(addr.address(), page_size) {
+                let page_addr = chunk_addr.as_page_aligned(page_size);
+                // parts outside of the physical address space are skipped
+                if let Some(page) = self.pages.get_mut(&page_addr) {
+                    let start = chunk_addr - page_addr;
+                    page[start..start + chunk.len()].copy_from_slice(chunk);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn metadata(&self) -> PhysicalMemoryMetadata {
+        let metadata = self.mem.metadata();
+        let mut capabilities = metadata.capabilities;
+        capabilities.set(PhysicalMemoryCapabilities::WRITE, self.writable);
+        PhysicalMemoryMetadata {
+            size: metadata.size,
+            readonly: !self.writable,
+            capabilities,
+        }
+    }
+
+    fn cpu_state(&mut self) -> Option<&mut dyn CpuState> {
+        self.mem.cpu_state()
+    }
+
+    fn prefetch(&mut self, ranges: &[(PhysicalAddress, usize)]) -> Result<()> {
+        self.mem.prefetch(ranges)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::conformance::assert_phys_mem_conformance;
+    use crate::mem::dummy::DummyMemory;
+
+    /// Memory that rejects all writes
+    struct ReadOnlyMemory(DummyMemory);
+
+    impl PhysicalMemory for ReadOnlyMemory {
+        fn phys_read_raw_list(&mut self, data: &mut [PhysicalReadData]) -> Result<()> {
+            self.0.phys_read_raw_list(data)
+        }
+
+        fn phys_write_raw_list(&mut self, _data: &[PhysicalWriteData]) -> Result<()> {
+            Err(Error::Connector("memory is read-only"))
+        }
+
+        fn metadata(&self) -> PhysicalMemoryMetadata {
+            PhysicalMemoryMetadata {
+                size: self.0.metadata().size,
+                readonly: true,
+                capabilities: PhysicalMemoryCapabilities::NONE,
+            }
+        }
+    }
+
+    fn build_overlay() -> OverlayPhysicalMemory<ReadOnlyMemory> {
+        let mut mem = DummyMemory::new(size::mb(1));
+        let buf = (0..size::kb(16)).map(|i| i as u8).collect::<Vec<_>>();
+        mem.phys_write_raw(0x0.into(), &buf).unwrap();
+        OverlayPhysicalMemory::new(ReadOnlyMemory(mem))
+    }
+
+    #[test]
+    fn write_overlay() {
+        let mut mem = build_overlay();
+        assert_eq!(mem.metadata().readonly, false);
+        assert!(mem.metadata().capabilities.supports_write());
+
+        // write across a page boundary
+        mem.phys_write_raw(0xffe.into(), &[0xaa; 4]).unwrap();
+        assert_eq!(
+            mem.phys_read_raw(0xffc.into(), 8).unwrap(),
+            vec![0xfc, 0xfd, 0xaa, 0xaa, 0xaa, 0xaa, 0x02, 0x03]
+        );
+        assert_eq!(
+            mem.pages().map(|(addr, _)| addr).collect::<Vec<_>>(),
+            vec![Address::from(0x0), Address::from(0x1000)]
+        );
+
+        // the underlying memory is not modified
+        let mut base = mem.destroy();
+        assert_eq!(
+            base.phys_read_raw(0xffc.into(), 8).unwrap(),
+            vec![0xfc, 0xfd, 0xfe, 0xff, 0x00, 0x01, 0x02, 0x03]
+        );
+    }
+
+    #[test]
+    fn discard() {
+        let mut mem = build_overlay();
+        mem.phys_write(0x2000.into(), &0xdead_beef_u32).unwrap();
+        assert_eq!(mem.phys_read::<u32>(0x2000.into()).unwrap(), 0xdead_beef);
+
+        mem.discard();
+        assert_eq!(mem.pages().count(), 0);
+        assert_eq!(mem.phys_read::<u32>(0x2000.into()).unwrap(), 0x0302_0100);
+    }
+
+    #[test]
+    fn writable_arg() {
+        let args = ConnectorArgs::parse("dump.raw").unwrap();
+        let mut mem = OverlayPhysicalMemory::with_args(build_overlay().destroy(), &args).unwrap();
+        assert_eq!(mem.metadata().readonly, true);
+        assert!(mem.phys_write(0x2000.into(), &0xdead_beef_u32).is_err());
+        assert_eq!(mem.pages().count(), 0);
+
+        let args = ConnectorArgs::parse("dump.raw,writable=true").unwrap();
+        let mut mem = OverlayPhysicalMemory::with_args(mem.destroy(), &args).unwrap();
+        assert_eq!(mem.metadata().readonly, false);
+        mem.phys_write(0x2000.into(), &0xdead_beef_u32).unwrap();
+        assert_eq!(mem.phys_read::<u32>(0x2000.into()).unwrap(), 0xdead_beef);
+
+        let args = ConnectorArgs::parse("writable=maybe").unwrap();
+        assert!(OverlayPhysicalMemory::with_args(mem.destroy(), &args).is_err());
+    }
+
+    #[test]
+    fn out_of_bounds() {
+        let mut mem = build_overlay();
+        assert!(mem
+            .phys_write_raw(Address::from(size::mb(1)).into(), &[0xaa; 8])
+            .is_err());
+        mem.phys_write_raw_list(&[PhysicalWriteData(
+            Address::from(size::mb(1)).into(),
+            &[0xaa; 8],
+        )])
+        .unwrap();
+        assert_eq!(mem.pages().count(), 0);
+    }
+
+    #[test]
+    fn conformance() {
+        let mut mem = build_overlay();
+        assert_phys_mem_conformance(&mut mem);
+    }
+}
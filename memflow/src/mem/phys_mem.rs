@@ -1,16 +1,18 @@
 use std::prelude::v1::*;
 
 use super::PhysicalMemoryBatcher;
-use crate::error::Result;
-use crate::types::PhysicalAddress;
+use crate::architecture::Endianess;
+use crate::connector::CpuState;
+use crate::error::{Error, Result};
+use crate::types::{ByteSwap, PhysicalAddress};
 
 use std::mem::MaybeUninit;
 
 use dataview::Pod;
 
-// TODO:
-// - check endianess here and return an error
-// - better would be to convert endianess with word alignment from addr
+// Note:
+// - physical memory is not aware of the architecture of the target,
+//   the `phys_*_swapped` helpers therefore require the byte order to be passed in explicitly.
 
 /// The `PhysicalMemory` trait is implemented by memory backends
 /// and provides a generic way to read and write from/to physical memory.
@@ -31,7 +33,8 @@ use dataview::Pod;
 ///     PhysicalMemory,
 ///     PhysicalReadData,
 ///     PhysicalWriteData,
-///     PhysicalMemoryMetadata
+///     PhysicalMemoryMetadata,
+///     PhysicalMemoryCapabilities,
 /// };
 ///
 /// use memflow::types::PhysicalAddress;
@@ -46,11 +49,12 @@ use dataview::Pod;
 ///         &mut self,
 ///         data: &mut [PhysicalReadData]
 ///     ) -> Result<()> {
-///         data
-///             .iter_mut()
-///             .for_each(|PhysicalReadData(addr, out)| out
-///                 .copy_from_slice(&self.mem[addr.as_usize()..(addr.as_usize() + out.len())])
-///             );
+///         for PhysicalReadData(addr, out) in data.iter_mut() {
+///             // parts outside of the memory are left untouched
+///             let start = addr.as_usize().min(self.mem.len());
+///             let end = (start + out.len()).min(self.mem.len());
+///             out[..end - start].copy_from_slice(&self.mem[start..end]);
+///         }
 ///         Ok(())
 ///     }
 ///
@@ -58,18 +62,19 @@ use dataview::Pod;
 ///         &mut self,
 ///         data: &[PhysicalWriteData]
 ///     ) -> Result<()> {
-///         data
-///             .iter()
-///             .for_each(|PhysicalWriteData(addr, data)| self
-///                 .mem[addr.as_usize()..(addr.as_usize() + data.len())].copy_from_slice(data)
-///             );
+///         for PhysicalWriteData(addr, data) in data.iter() {
+///             let start = addr.as_usize().min(self.mem.len());
+///             let end = (start + data.len()).min(self.mem.len());
+///             self.mem[start..end].copy_from_slice(&data[..end - start]);
+///         }
 ///         Ok(())
 ///     }
 ///
 ///     fn metadata(&self) -> PhysicalMemoryMetadata {
 ///         PhysicalMemoryMetadata {
 ///             size: self.mem.len(),
-///             readonly: false
+///             readonly: false,
+///             capabilities: PhysicalMemoryCapabilities::WRITE,
 ///         }
 ///     }
 /// }
@@ -94,7 +99,30 @@ pub trait PhysicalMemory
 where
     Self: Send,
 {
+    /// Reads all entries of the given list from physical memory.
+    ///
+    /// Implementations have to uphold the following contract so that wrappers
+    /// (like caches, retry or timeout layers) can rely on it:
+    ///
+    /// - All reads are completed when this function returns.
+    ///   The output buffers are not accessed anymore afterwards.
+    /// - Every entry is read independently. The contents of an output buffer
+    ///   only depend on its own address, not on the order or the number of entries in the list.
+    ///   Implementations are free to reorder, split or merge the underlying requests.
+    /// - Parts of an entry that can not be read (e.g. because they are not backed by the memory map)
+    ///   are left untouched, all other parts are still read.
+    ///   This partial success is not an error and still returns `Ok`.
+    /// - An `Err` is only returned in case the backend itself failed (e.g. a broken connection).
+    ///   The contents of all output buffers are unspecified in that case.
+    ///
+    /// The [`assert_phys_mem_conformance`](../conformance/fn.assert_phys_mem_conformance.html)
+    /// function can be used to verify these rules for a given implementation.
     fn phys_read_raw_list(&mut self, data: &mut [PhysicalReadData]) -> Result<()>;
+
+    /// Writes all entries of the given list to physical memory.
+    ///
+    /// The same rules as for `phys_read_raw_list` apply. Entries are written in list order,
+    /// parts of an entry that are not backed by the memory map are skipped without returning an error.
     fn phys_write_raw_list(&mut self, data: &[PhysicalWriteData]) -> Result<()>;
 
     /// Retrieve metadata about the physical memory
@@ -113,11 +141,42 @@ where
     ///
     /// assert_eq!(metadata.size, size::mb(16));
     /// assert_eq!(metadata.readonly, false);
+    /// assert!(metadata.capabilities.supports_write());
+    /// assert!(!metadata.capabilities.is_volatile());
     /// ```
     fn metadata(&self) -> PhysicalMemoryMetadata;
 
+    /// Returns access to the cpu state of the target if the connector supports it.
+    ///
+    /// By default connectors do not provide access to the cpu state and `None` is returned.
+    /// See the [`cpu_state`](../../connector/cpu_state/index.html) module for more information.
+    fn cpu_state(&mut self) -> Option<&mut dyn CpuState> {
+        None
+    }
+
+    /// Hints that the given ranges will be read in the near future.
+    ///
+    /// Connectors that buffer reads internally (e.g. DMA devices) and caches can override this
+    /// to start filling their buffers ahead of time. Subsequent reads of these ranges can then
+    /// be served with a lower latency.
+    ///
+    /// This is only a hint and does not have to be honored. By default it is a no-op.
+    fn prefetch(&mut self, _ranges: &[(PhysicalAddress, usize)]) -> Result<()> {
+        Ok(())
+    }
+
     // read helpers
+
+    /// Reads a single buffer from physical memory.
+    ///
+    /// Unlike `phys_read_raw_list` this function checks the read against the size reported by `metadata()`.
+    /// Reads that exceed the end of the physical memory return an `Error::OutOfBounds`
+    /// and leave `out` untouched. A reported size of 0 is treated as unknown and disables the check.
+    ///
+    /// `metadata()` is queried once per call. Wrappers whose metadata is expensive to retrieve
+    /// (e.g. because it requires a lock) should forward this function to the underlying memory.
     fn phys_read_raw_into(&mut self, addr: PhysicalAddress, out: &mut [u8]) -> Result<()> {
+        check_bounds(self.metadata(), addr, out.len())?;
         self.phys_read_raw_list(&mut [PhysicalReadData(addr, out)])
     }
 
@@ -134,22 +193,57 @@ where
         Ok(buf)
     }
 
-    /// # Safety
+    /// Reads a structure from physical memory.
     ///
-    /// this function will overwrite the contents of 'obj' so we can just allocate an unitialized memory section.
-    /// this function should only be used with [repr(C)] structs.
-    #[allow(clippy::uninit_assumed_init)]
+    /// The structure is zero initialized, parts that can not be read are therefore returned as zeroes.
+    /// This function should only be used with [repr(C)] structs.
     fn phys_read<T: Pod + Sized>(&mut self, addr: PhysicalAddress) -> Result<T>
     where
         Self: Sized,
     {
-        let mut obj: T = unsafe { MaybeUninit::uninit().assume_init() };
+        let mut obj: T = unsafe { MaybeUninit::zeroed().assume_init() };
         self.phys_read_into(addr, &mut obj)?;
         Ok(obj)
     }
 
+    /// Reads a structure and converts all of its fields from the given byte order
+    /// into the byte order of the host.
+    fn phys_read_into_swapped<T: Pod + ByteSwap + ?Sized>(
+        &mut self,
+        addr: PhysicalAddress,
+        out: &mut T,
+        endianess: Endianess,
+    ) -> Result<()>
+    where
+        Self: Sized,
+    {
+        self.phys_read_into(addr, out)?;
+        out.byte_swap_endianess(endianess);
+        Ok(())
+    }
+
+    /// Reads a structure and converts all of its fields from the given byte order
+    /// into the byte order of the host.
+    fn phys_read_swapped<T: Pod + ByteSwap + Sized>(
+        &mut self,
+        addr: PhysicalAddress,
+        endianess: Endianess,
+    ) -> Result<T>
+    where
+        Self: Sized,
+    {
+        let mut obj: T = unsafe { MaybeUninit::zeroed().assume_init() };
+        self.phys_read_into_swapped(addr, &mut obj, endianess)?;
+        Ok(obj)
+    }
+
     // write helpers
+
+    /// Writes a single buffer to physical memory.
+    ///
+    /// The write is checked against the size reported by `metadata()` in the same way as `phys_read_raw_into`.
     fn phys_write_raw(&mut self, addr: PhysicalAddress, data: &[u8]) -> Result<()> {
+        check_bounds(self.metadata(), addr, data.len())?;
         self.phys_write_raw_list(&[PhysicalWriteData(addr, data)])
     }
 
@@ -160,6 +254,21 @@ where
         self.phys_write_raw(addr, data.as_bytes())
     }
 
+    /// Converts all fields of a structure into the given byte order and writes it.
+    fn phys_write_swapped<T: Pod + ByteSwap + Clone>(
+        &mut self,
+        addr: PhysicalAddress,
+        data: &T,
+        endianess: Endianess,
+    ) -> Result<()>
+    where
+        Self: Sized,
+    {
+        let mut data = data.clone();
+        data.byte_swap_endianess(endianess);
+        self.phys_write(addr, &data)
+    }
+
     fn phys_batcher(&mut self) -> PhysicalMemoryBatcher<Self>
     where
         Self: Sized,
@@ -168,6 +277,23 @@ where
     }
 }
 
+/// Checks that an access of `len` bytes at `addr` lies entirely within the physical memory.
+fn check_bounds(metadata: PhysicalMemoryMetadata, addr: PhysicalAddress, len: usize) -> Result<()> {
+    if metadata.size == 0 {
+        return Ok(());
+    }
+
+    let end = addr.as_usize().saturating_add(len);
+    if end > metadata.size {
+        Err(Error::OutOfBounds {
+            requested: end,
+            max: metadata.size,
+        })
+    } else {
+        Ok(())
+    }
+}
+
 // forward impls
 impl<T: PhysicalMemory + ?Sized, P: std::ops::DerefMut<Target = T> + Send> PhysicalMemory for P {
     #[inline]
@@ -184,6 +310,26 @@ impl<T: PhysicalMemory + ?Sized, P: std::ops::DerefMut<Target = T> + Send> Physi
     fn metadata(&self) -> PhysicalMemoryMetadata {
         (**self).metadata()
     }
+
+    #[inline]
+    fn cpu_state(&mut self) -> Option<&mut dyn CpuState> {
+        (**self).cpu_state()
+    }
+
+    #[inline]
+    fn prefetch(&mut self, ranges: &[(PhysicalAddress, usize)]) -> Result<()> {
+        (**self).prefetch(ranges)
+    }
+
+    #[inline]
+    fn phys_read_raw_into(&mut self, addr: PhysicalAddress, out: &mut [u8]) -> Result<()> {
+        (**self).phys_read_raw_into(addr, out)
+    }
+
+    #[inline]
+    fn phys_write_raw(&mut self, addr: PhysicalAddress, data: &[u8]) -> Result<()> {
+        (**self).phys_write_raw(addr, data)
+    }
 }
 
 /// Wrapper trait around physical memory which implements a boxed clone
@@ -222,6 +368,46 @@ impl Clone for PhysicalMemoryBox {
 pub struct PhysicalMemoryMetadata {
     pub size: usize,
     pub readonly: bool,
+    pub capabilities: PhysicalMemoryCapabilities,
+}
+
+bitflags! {
+    /// Describes the features a `PhysicalMemory` object supports using a bitflag.
+    ///
+    /// Wrappers and tools can use these flags to adapt their behavior to the connector,
+    /// e.g. caches do not have to revalidate pages of non-volatile memory.
+    #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+    #[repr(transparent)]
+    pub struct PhysicalMemoryCapabilities: u8 {
+        /// The memory explicitly has no capabilities.
+        const NONE = 0b0000_0000;
+        /// The memory belongs to a running system and may change at any time (as opposed to a dump file).
+        const VOLATILE = 0b0000_0001;
+        /// The memory can be written to.
+        const WRITE = 0b0000_0010;
+        /// `PhysicalMemory::cpu_state()` provides access to the cpu state of the target.
+        const CPU_STATE = 0b0000_0100;
+        /// `PhysicalMemory::prefetch()` is implemented and not just a no-op.
+        const PREFETCH = 0b0000_1000;
+    }
+}
+
+impl PhysicalMemoryCapabilities {
+    pub fn is_volatile(self) -> bool {
+        self.contains(PhysicalMemoryCapabilities::VOLATILE)
+    }
+
+    pub fn supports_write(self) -> bool {
+        self.contains(PhysicalMemoryCapabilities::WRITE)
+    }
+
+    pub fn supports_cpu_state(self) -> bool {
+        self.contains(PhysicalMemoryCapabilities::CPU_STATE)
+    }
+
+    pub fn supports_prefetch(self) -> bool {
+        self.contains(PhysicalMemoryCapabilities::PREFETCH)
+    }
 }
 
 // iterator helpers
@@ -247,3 +433,92 @@ impl<'a> From<PhysicalWriteData<'a>> for (PhysicalAddress, &'a [u8]) {
         (a, b)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::dummy::DummyMemory;
+    use crate::types::size;
+
+    #[test]
+    fn out_of_bounds_helpers() {
+        let mut mem = DummyMemory::new(size::mb(1));
+        let end = size::mb(1);
+
+        assert_eq!(
+            mem.phys_read::<u32>(end.into()),
+            Err(Error::OutOfBounds {
+                requested: end + 4,
+                max: end
+            })
+        );
+        assert_eq!(
+            mem.phys_write((end + 0x1000).into(), &0u32),
+            Err(Error::OutOfBounds {
+                requested: end + 0x1004,
+                max: end
+            })
+        );
+
+        // partially out of bounds accesses are rejected as a whole
+        mem.phys_write((end - 4).into(), &[0xaa_u8; 4]).unwrap();
+        assert_eq!(
+            mem.phys_write((end - 4).into(), &[0xbb_u8; 8]),
+            Err(Error::OutOfBounds {
+                requested: end + 4,
+                max: end
+            })
+        );
+        let mut buf = [0x55_u8; 8];
+        assert!(mem.phys_read_raw_into((end - 4).into(), &mut buf).is_err());
+        assert_eq!(buf, [0x55; 8]);
+        assert_eq!(mem.phys_read::<u32>((end - 4).into()), Ok(0xaaaa_aaaa));
+
+        assert_eq!(
+            Error::OutOfBounds {
+                requested: end,
+                max: end
+            }
+            .to_string(),
+            "physical memory access out of bounds: 100000 (max 100000)"
+        );
+    }
+
+    /// Memory that serves reads past the size reported by its metadata
+    struct MirroredMemory(DummyMemory);
+
+    impl PhysicalMemory for MirroredMemory {
+        fn phys_read_raw_list(&mut self, data: &mut [PhysicalReadData]) -> Result<()> {
+            self.0.phys_read_raw_list(data)
+        }
+
+        fn phys_write_raw_list(&mut self, data: &[PhysicalWriteData]) -> Result<()> {
+            self.0.phys_write_raw_list(data)
+        }
+
+        fn metadata(&self) -> PhysicalMemoryMetadata {
+            self.0.metadata()
+        }
+
+        fn phys_read_raw_into(&mut self, addr: PhysicalAddress, out: &mut [u8]) -> Result<()> {
+            let size = self.0.metadata().size;
+            let addr = PhysicalAddress::from(addr.as_u64() % size as u64);
+            self.0.phys_read_raw_into(addr, out)
+        }
+    }
+
+    #[test]
+    fn forward_helpers() {
+        fn read<T: PhysicalMemory>(mem: &mut T, addr: usize) -> Result<u32> {
+            mem.phys_read(addr.into())
+        }
+
+        let mut mem = MirroredMemory(DummyMemory::new(size::mb(1)));
+        mem.phys_write(0x1000.into(), &0x1234_u32).unwrap();
+        assert_eq!(read(&mut mem, size::mb(1) + 0x1000), Ok(0x1234));
+        assert_eq!(read(&mut &mut mem, size::mb(1) + 0x1000), Ok(0x1234));
+
+        let mut boxed: Box<dyn PhysicalMemory> = Box::new(mem);
+        assert_eq!(read(&mut boxed, size::mb(1) + 0x1000), Ok(0x1234));
+    }
+}
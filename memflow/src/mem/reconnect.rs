@@ -0,0 +1,209 @@
+/*!
+Reconnecting wrapper for connector objects that implement the `PhysicalMemory` trait.
+
+Hardware based connectors can lose their link to the target in the middle of a session
+(e.g. a DMA device that is attached via USB). Without intervention every
+subsequent read will then fail until the connector has been re-created.
+
+The `ReconnectingPhysicalMemory` detects fatal connector errors, re-creates the
+underlying connector through a user-supplied closure and retries the failed operation once.
+
+An error is considered fatal if it is either an `Error::Connector` or an `Error::IO` error.
+
+# Examples
+
+```
+use memflow::mem::{PhysicalMemory, ReconnectingPhysicalMemory};
+use memflow::error::Result;
+
+fn build<T: PhysicalMemory, F: FnMut() -> Result<T> + Send>(create_connector: F) {
+    let mut mem = ReconnectingPhysicalMemory::with_reconnect(create_connector).unwrap();
+
+    let _value: u64 = mem.phys_read(0x1000.into()).unwrap();
+}
+# use memflow::mem::dummy::DummyMemory;
+# use memflow::types::size;
+# build(|| Ok(DummyMemory::new(size::mb(4))));
+```
+*/
+
+use crate::connector::CpuState;
+use crate::error::{Error, Result};
+use crate::mem::phys_mem::{
+    PhysicalMemory, PhysicalMemoryMetadata, PhysicalReadData, PhysicalWriteData,
+};
+use crate::types::PhysicalAddress;
+
+use log::warn;
+
+/// Wrapper that transparently re-creates the underlying connector after a fatal error.
+///
+/// Since this wrapper implements `PhysicalMemory` it can be used as a replacement
+/// in all structs and functions that require a `PhysicalMemory` object.
+pub struct ReconnectingPhysicalMemory<T, F> {
+    mem: T,
+    reconnect: F,
+    metadata: PhysicalMemoryMetadata,
+    reconnect_count: usize,
+}
+
+impl<T: PhysicalMemory, F: FnMut() -> Result<T> + Send> ReconnectingPhysicalMemory<T, F> {
+    /// Constructs a new wrapper around an already connected memory object.
+    ///
+    /// The `reconnect` closure is only invoked once a fatal error has been encountered.
+    pub fn new(mem: T, reconnect: F) -> Self {
+        let metadata = mem.metadata();
+        Self {
+            mem,
+            reconnect,
+            metadata,
+            reconnect_count: 0,
+        }
+    }
+
+    /// Constructs a new wrapper and uses the `reconnect` closure to establish the initial connection.
+    pub fn with_reconnect(mut reconnect: F) -> Result<Self> {
+        let mem = reconnect()?;
+        Ok(Self::new(mem, reconnect))
+    }
+
+    /// Returns the number of times the underlying connector has been re-created.
+    pub fn reconnect_count(&self) -> usize {
+        self.reconnect_count
+    }
+
+    /// Re-creates the underlying connector and re-queries its metadata.
+    pub fn reconnect(&mut self) -> Result<()> {
+        self.mem = (self.reconnect)()?;
+        self.metadata = self.mem.metadata();
+        self.reconnect_count += 1;
+        Ok(())
+    }
+
+    /// Consumes this wrapper and returns the containing memory object.
+    pub fn destroy(self) -> T {
+        self.mem
+    }
+
+    fn is_fatal(error: Error) -> bool {
+        matches!(error, Error::Connector(_) | Error::IO(_))
+    }
+}
+
+impl<T: PhysicalMemory, F: FnMut() -> Result<T> + Send> PhysicalMemory
+    for ReconnectingPhysicalMemory<T, F>
+{
+    fn phys_read_raw_list(&mut self, data: &mut [PhysicalReadData]) -> Result<()> {
+        match self.mem.phys_read_raw_list(data) {
+            Err(err) if Self::is_fatal(err) => {
+                warn!("physical read failed ({}), trying to reconnect", err);
+                self.reconnect()?;
+                self.mem.phys_read_raw_list(data)
+            }
+            result => result,
+        }
+    }
+
+    fn phys_write_raw_list(&mut self, data: &[PhysicalWriteData]) -> Result<()> {
+        match self.mem.phys_write_raw_list(data) {
+            Err(err) if Self::is_fatal(err) => {
+                warn!("physical write failed ({}), trying to reconnect", err);
+                self.reconnect()?;
+                self.mem.phys_write_raw_list(data)
+            }
+            result => result,
+        }
+    }
+
+    /// Returns the metadata of the current connector.
+    ///
+    /// `cpu_state()` and `prefetch()` are forwarded, so its capabilities apply as they are.
+    /// The metadata is re-queried whenever the connector has been re-created.
+    fn metadata(&self) -> PhysicalMemoryMetadata {
+        self.metadata
+    }
+
+    fn cpu_state(&mut self) -> Option<&mut dyn CpuState> {
+        self.mem.cpu_state()
+    }
+
+    fn prefetch(&mut self, ranges: &[(PhysicalAddress, usize)]) -> Result<()> {
+        self.mem.prefetch(ranges)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::dummy::DummyMemory;
+    use crate::types::{size, PhysicalAddress};
+
+    /// Memory object that fails all accesses after a given amount of successful accesses.
+    struct FlakyMemory {
+        mem: DummyMemory,
+        remaining: usize,
+    }
+
+    impl FlakyMemory {
+        fn check(&mut self) -> Result<()> {
+            if self.remaining == 0 {
+                Err(Error::Connector("link lost"))
+            } else {
+                self.remaining -= 1;
+                Ok(())
+            }
+        }
+    }
+
+    impl PhysicalMemory for FlakyMemory {
+        fn phys_read_raw_list(&mut self, data: &mut [PhysicalReadData]) -> Result<()> {
+            self.check()?;
+            self.mem.phys_read_raw_list(data)
+        }
+
+        fn phys_write_raw_list(&mut self, data: &[PhysicalWriteData]) -> Result<()> {
+            self.check()?;
+            self.mem.phys_write_raw_list(data)
+        }
+
+        fn metadata(&self) -> PhysicalMemoryMetadata {
+            self.mem.metadata()
+        }
+    }
+
+    #[test]
+    fn reconnect_on_fatal_error() {
+        let dummy = DummyMemory::new(size::mb(4));
+        let mut mem = ReconnectingPhysicalMemory::with_reconnect(move || {
+            Ok(FlakyMemory {
+                mem: dummy.clone(),
+                remaining: 2,
+            })
+        })
+        .unwrap();
+
+        mem.phys_write(PhysicalAddress::from(0x1000), &0xdead_beef_u64)
+            .unwrap();
+        for _ in 0..4 {
+            let value: u64 = mem.phys_read(PhysicalAddress::from(0x1000)).unwrap();
+            assert_eq!(value, 0xdead_beef);
+        }
+
+        assert_eq!(mem.reconnect_count(), 2);
+    }
+
+    #[test]
+    fn reconnect_failure() {
+        let mut mem = ReconnectingPhysicalMemory::new(
+            FlakyMemory {
+                mem: DummyMemory::new(size::mb(1)),
+                remaining: 0,
+            },
+            || Err(Error::Connector("unable to connect")),
+        );
+
+        let result: Result<u64> = mem.phys_read(PhysicalAddress::from(0x1000));
+        assert_eq!(result.err(), Some(Error::Connector("unable to connect")));
+        assert_eq!(mem.reconnect_count(), 0);
+    }
+}
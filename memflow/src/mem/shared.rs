@@ -0,0 +1,260 @@
+/*!
+Thread-safe sharing of objects implementing the `PhysicalMemory` and `VirtualTranslate` traits.
+
+Cloning a memory backend or a cache usually creates an independent copy of it.
+For caches this means that every clone starts out with a snapshot of the cache at the time of cloning
+and all entries added afterwards are only visible to the clone that created them.
+
+The `SharedPhysicalMemory` and `SharedVirtualTranslate` wrappers move the wrapped object behind an `Arc<Mutex<_>>`.
+Cloning the wrapper only clones the reference, so all clones operate on the same underlying object.
+This allows multiple threads to share a single warmed up page or translation cache.
+All accesses are serialized by the mutex.
+
+# Examples
+
+```
+use memflow::mem::{PhysicalMemory, SharedPhysicalMemory};
+
+fn share<T: PhysicalMemory + 'static>(connector: T) {
+    let mut mem = SharedPhysicalMemory::new(connector);
+
+    let mut thread_mem = mem.clone();
+    std::thread::spawn(move || {
+        thread_mem.phys_write(0x1000.into(), &0xdead_beef_u32).unwrap();
+    })
+    .join()
+    .unwrap();
+
+    assert_eq!(mem.phys_read::<u32>(0x1000.into()).unwrap(), 0xdead_beef);
+}
+# use memflow::mem::dummy::DummyMemory;
+# use memflow::types::size;
+# share(DummyMemory::new(size::mb(4)));
+```
+*/
+
+use std::prelude::v1::*;
+
+use crate::architecture::ScopedVirtualTranslate;
+use crate::error::{Error, Result};
+use crate::iter::SplitAtIndex;
+use crate::mem::phys_mem::{
+    PhysicalMemory, PhysicalMemoryCapabilities, PhysicalMemoryMetadata, PhysicalReadData,
+    PhysicalWriteData,
+};
+use crate::mem::virt_translate::VirtualTranslate;
+use crate::types::{Address, PhysicalAddress};
+
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// Wrapper that shares the underlying memory between all of its clones.
+///
+/// Since this wrapper implements `PhysicalMemory` it can be used as a replacement
+/// in all structs and functions that require a `PhysicalMemory` object.
+///
+/// Access to the cpu state of the underlying memory is not possible through this wrapper
+/// because it can not be borrowed beyond the lifetime of the lock.
+pub struct SharedPhysicalMemory<T> {
+    mem: Arc<Mutex<T>>,
+}
+
+impl<T> Clone for SharedPhysicalMemory<T> {
+    fn clone(&self) -> Self {
+        Self {
+            mem: self.mem.clone(),
+        }
+    }
+}
+
+impl<T: PhysicalMemory> SharedPhysicalMemory<T> {
+    /// Moves the given memory behind a shared reference.
+    pub fn new(mem: T) -> Self {
+        Self {
+            mem: Arc::new(Mutex::new(mem)),
+        }
+    }
+
+    /// Locks the underlying memory and returns a guard to it.
+    ///
+    /// All other clones of this wrapper are blocked as long as the guard is alive.
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        self.mem.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the number of clones that currently share the underlying memory.
+    pub fn handle_count(&self) -> usize {
+        Arc::strong_count(&self.mem)
+    }
+
+    /// Consumes the wrapper and returns the underlying memory.
+    ///
+    /// In case there are still other clones of this wrapper alive the wrapper is returned as an error.
+    pub fn try_destroy(self) -> std::result::Result<T, Self> {
+        Arc::try_unwrap(self.mem)
+            .map(|mem| mem.into_inner().unwrap_or_else(PoisonError::into_inner))
+            .map_err(|mem| Self { mem })
+    }
+}
+
+impl<T: PhysicalMemory> PhysicalMemory for SharedPhysicalMemory<T> {
+    fn phys_read_raw_list(&mut self, data: &mut [PhysicalReadData]) -> Result<()> {
+        self.lock().phys_read_raw_list(data)
+    }
+
+    fn phys_write_raw_list(&mut self, data: &[PhysicalWriteData]) -> Result<()> {
+        self.lock().phys_write_raw_list(data)
+    }
+
+    fn metadata(&self) -> PhysicalMemoryMetadata {
+        // the cpu state of the underlying memory can not be handed out past the lock
+        let mut metadata = self.lock().metadata();
+        metadata
+            .capabilities
+            .remove(PhysicalMemoryCapabilities::CPU_STATE);
+        metadata
+    }
+
+    fn prefetch(&mut self, ranges: &[(PhysicalAddress, usize)]) -> Result<()> {
+        self.lock().prefetch(ranges)
+    }
+
+    // bounds check and access happen under the same lock
+    fn phys_read_raw_into(&mut self, addr: PhysicalAddress, out: &mut [u8]) -> Result<()> {
+        self.lock().phys_read_raw_into(addr, out)
+    }
+
+    fn phys_write_raw(&mut self, addr: PhysicalAddress, data: &[u8]) -> Result<()> {
+        self.lock().phys_write_raw(addr, data)
+    }
+}
+
+/// Wrapper that shares the underlying translator (e.g. a `CachedVirtualTranslate`) between all of its clones.
+///
+/// Since this wrapper implements `VirtualTranslate` it can be used as a replacement
+/// in all structs and functions that require a `VirtualTranslate` object.
+pub struct SharedVirtualTranslate<V> {
+    vat: Arc<Mutex<V>>,
+}
+
+impl<V> Clone for SharedVirtualTranslate<V> {
+    fn clone(&self) -> Self {
+        Self {
+            vat: self.vat.clone(),
+        }
+    }
+}
+
+impl<V: VirtualTranslate> SharedVirtualTranslate<V> {
+    /// Moves the given translator behind a shared reference.
+    pub fn new(vat: V) -> Self {
+        Self {
+            vat: Arc::new(Mutex::new(vat)),
+        }
+    }
+
+    /// Locks the underlying translator and returns a guard to it.
+    ///
+    /// All other clones of this wrapper are blocked as long as the guard is alive.
+    pub fn lock(&self) -> MutexGuard<'_, V> {
+        self.vat.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the number of clones that currently share the underlying translator.
+    pub fn handle_count(&self) -> usize {
+        Arc::strong_count(&self.vat)
+    }
+
+    /// Consumes the wrapper and returns the underlying translator.
+    ///
+    /// In case there are still other clones of this wrapper alive the wrapper is returned as an error.
+    pub fn try_destroy(self) -> std::result::Result<V, Self> {
+        Arc::try_unwrap(self.vat)
+            .map(|vat| vat.into_inner().unwrap_or_else(PoisonError::into_inner))
+            .map_err(|vat| Self { vat })
+    }
+}
+
+impl<V: VirtualTranslate> VirtualTranslate for SharedVirtualTranslate<V> {
+    fn virt_to_phys_iter<T, B, D, VI, VO, FO>(
+        &mut self,
+        phys_mem: &mut T,
+        translator: &D,
+        addrs: VI,
+        out: &mut VO,
+        out_fail: &mut FO,
+    ) where
+        T: PhysicalMemory + ?Sized,
+        B: SplitAtIndex,
+        D: ScopedVirtualTranslate,
+        VI: Iterator<Item = (Address, B)>,
+        VO: Extend<(PhysicalAddress, B)>,
+        FO: Extend<(Error, Address, B)>,
+    {
+        self.lock()
+            .virt_to_phys_iter(phys_mem, translator, addrs, out, out_fail)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::architecture::x86::x64;
+    use crate::mem::cache::CachedVirtualTranslate;
+    use crate::mem::conformance::assert_phys_mem_conformance;
+    use crate::mem::dummy::DummyMemory;
+    use crate::mem::{DirectTranslate, VirtualDMA, VirtualMemory};
+    use crate::types::size;
+
+    #[test]
+    fn shared_writes() {
+        let mut mem = SharedPhysicalMemory::new(DummyMemory::new(size::mb(1)));
+        let mut other = mem.clone();
+        assert_eq!(mem.handle_count(), 2);
+
+        other.phys_write(0x2000.into(), &0xdead_beef_u32).unwrap();
+        assert_eq!(mem.phys_read::<u32>(0x2000.into()).unwrap(), 0xdead_beef);
+
+        let mem = mem.try_destroy().unwrap_err();
+        std::mem::drop(other);
+        assert!(mem.try_destroy().is_ok());
+    }
+
+    #[test]
+    fn shared_vat_cache() {
+        let mut mem = DummyMemory::new(size::mb(16));
+        let (dtb, virt_base) = mem.alloc_dtb(size::mb(2), &[]);
+        let translator = x64::new_translator(dtb);
+
+        let vat = SharedVirtualTranslate::new(
+            CachedVirtualTranslate::builder(DirectTranslate::new())
+                .arch(x64::ARCH)
+                .build()
+                .unwrap(),
+        );
+        let mut vat_a = vat.clone();
+        let mut vat_b = vat.clone();
+
+        // warm up the cache through the first clone
+        vat_a
+            .virt_to_phys(&mut mem, &translator, virt_base)
+            .unwrap();
+        let hits = vat.lock().hitc;
+
+        // the second clone hits the entry that was cached by the first one
+        vat_b
+            .virt_to_phys(&mut mem, &translator, virt_base)
+            .unwrap();
+        assert_eq!(vat.lock().hitc, hits + 1);
+
+        let mut virt_mem = VirtualDMA::with_vat(&mut mem, x64::ARCH, translator, vat_b);
+        virt_mem.virt_write(virt_base, &0x1234_u32).unwrap();
+        assert_eq!(virt_mem.virt_read::<u32>(virt_base).unwrap(), 0x1234);
+    }
+
+    #[test]
+    fn conformance() {
+        let mut mem = SharedPhysicalMemory::new(DummyMemory::new(size::mb(1)));
+        assert_phys_mem_conformance(&mut mem.clone());
+        assert_phys_mem_conformance(&mut mem);
+    }
+}
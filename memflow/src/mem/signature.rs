@@ -0,0 +1,417 @@
+/*!
+Byte signature scanning.
+
+A `Signature` is a byte pattern that may contain wildcards, usually written in the
+common `48 8B 05 ? ? ? ? 48 89 81` notation.
+
+Scanning is done in two stages: a fast filter searches for the first fixed byte of the
+signature (the anchor) and only the positions at which the anchor was found are compared
+against the entire signature. The filter processes an entire machine word at a time.
+With the `simd_scan` feature enabled the filter uses SSE2 on x86_64 targets
+and AVX2 if it is available on the host at runtime (requires `std`).
+
+# Examples
+
+```
+use memflow::mem::signature::Signature;
+
+let sig = Signature::parse("48 8B 05 ? ? ? ? 48 89").unwrap();
+
+let buf = [0x90, 0x48, 0x8b, 0x05, 0x11, 0x22, 0x33, 0x44, 0x48, 0x89, 0x90];
+assert_eq!(sig.find(&buf), Some(1));
+```
+*/
+
+use std::prelude::v1::*;
+
+use crate::error::{Error, PartialResultExt, Result};
+use crate::mem::VirtualMemory;
+use crate::types::{size, Address};
+
+use std::str::FromStr;
+
+/// Size of the chunks that are read from virtual memory by `Signature::scan_virt`.
+pub const SCAN_CHUNK_SIZE: usize = size::mb(2);
+
+/// A byte pattern with optional wildcards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    bytes: Vec<u8>,
+    mask: Vec<bool>,
+    anchor: Option<usize>,
+}
+
+impl Signature {
+    /// Creates a new signature from the given bytes and mask.
+    ///
+    /// Bytes with a mask of `false` are treated as wildcards.
+    pub fn new(bytes: Vec<u8>, mask: Vec<bool>) -> Result<Self> {
+        if bytes.is_empty() {
+            return Err(Error::Other("signature must not be empty"));
+        }
+        if bytes.len() != mask.len() {
+            return Err(Error::Other(
+                "signature mask does not match the signature length",
+            ));
+        }
+
+        let anchor = mask.iter().position(|&fixed| fixed);
+        Ok(Self {
+            bytes,
+            mask,
+            anchor,
+        })
+    }
+
+    /// Parses a signature of space separated hex bytes.
+    ///
+    /// Wildcards are denoted by `?` or `??`.
+    pub fn parse(pattern: &str) -> Result<Self> {
+        let (bytes, mask): (Vec<u8>, Vec<bool>) = pattern
+            .split_whitespace()
+            .map(|byte| match byte {
+                "?" | "??" => Ok((0, false)),
+                _ if byte.len() == 2 => u8::from_str_radix(byte, 16)
+                    .map(|byte| (byte, true))
+                    .map_err(|_| Error::Other("signature contains an invalid byte")),
+                _ => Err(Error::Other("signature contains an invalid byte")),
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .unzip();
+
+        Self::new(bytes, mask)
+    }
+
+    /// Returns the length of the signature in bytes.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Checks if the signature matches the beginning of the given buffer.
+    pub fn matches(&self, buf: &[u8]) -> bool {
+        buf.len() >= self.len()
+            && self
+                .bytes
+                .iter()
+                .zip(self.mask.iter())
+                .zip(buf.iter())
+                .all(|((byte, &fixed), buf_byte)| !fixed || byte == buf_byte)
+    }
+
+    /// Returns the offset of the first match in the given buffer.
+    pub fn find(&self, buf: &[u8]) -> Option<usize> {
+        self.find_from(buf, 0)
+    }
+
+    /// Returns an iterator over the offsets of all matches in the given buffer.
+    ///
+    /// Overlapping matches are returned as well.
+    pub fn find_iter<'a>(&'a self, buf: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        let mut start = 0;
+        std::iter::from_fn(move || {
+            let offset = self.find_from(buf, start)?;
+            start = offset + 1;
+            Some(offset)
+        })
+    }
+
+    /// Scans the given range of virtual memory and returns the addresses of all matches.
+    ///
+    /// The memory is read in chunks of `SCAN_CHUNK_SIZE` bytes, matches that cross
+    /// the boundary of two chunks are found as well.
+    /// Parts of the range that can not be read are zero-filled.
+    pub fn scan_virt<T: VirtualMemory + ?Sized>(
+        &self,
+        virt_mem: &mut T,
+        start: Address,
+        size: usize,
+    ) -> Result<Vec<Address>> {
+        self.scan_virt_chunked(virt_mem, start, size, SCAN_CHUNK_SIZE)
+    }
+
+    fn scan_virt_chunked<T: VirtualMemory + ?Sized>(
+        &self,
+        virt_mem: &mut T,
+        start: Address,
+        size: usize,
+        chunk_size: usize,
+    ) -> Result<Vec<Address>> {
+        let mut matches = Vec::new();
+        if size < self.len() {
+            return Ok(matches);
+        }
+
+        // the last `len - 1` bytes of each chunk are carried over to the next one
+        let overlap = self.len() - 1;
+        let mut buf = vec![0u8; overlap + std::cmp::max(chunk_size, 1)];
+
+        let mut buf_addr = start;
+        let mut buf_len = 0;
+        let mut offset = 0;
+        while offset < size {
+            let read_len = std::cmp::min(buf.len() - buf_len, size - offset);
+            let out = &mut buf[buf_len..buf_len + read_len];
+            for b in out.iter_mut() {
+                *b = 0;
+            }
+            virt_mem
+                .virt_read_raw_into(start + offset, out)
+                .data_part()?;
+            buf_len += read_len;
+            offset += read_len;
+
+            matches.extend(
+                self.find_iter(&buf[..buf_len])
+                    .map(|match_offset| buf_addr + match_offset),
+            );
+
+            let keep = std::cmp::min(overlap, buf_len);
+            buf.copy_within(buf_len - keep..buf_len, 0);
+            buf_addr += buf_len - keep;
+            buf_len = keep;
+        }
+
+        Ok(matches)
+    }
+
+    fn find_from(&self, buf: &[u8], start: usize) -> Option<usize> {
+        let len = self.len();
+        if buf.len() < len || start > buf.len() - len {
+            return None;
+        }
+        let last = buf.len() - len;
+
+        let anchor = match self.anchor {
+            Some(anchor) => anchor,
+            // a signature that only consists of wildcards matches everywhere
+            None => return Some(start),
+        };
+        let anchor_byte = self.bytes[anchor];
+
+        let mut pos = start;
+        while pos <= last {
+            let haystack = &buf[pos + anchor..=last + anchor];
+            pos += find_byte(haystack, anchor_byte)?;
+            if self.matches(&buf[pos..]) {
+                return Some(pos);
+            }
+            pos += 1;
+        }
+
+        None
+    }
+}
+
+impl FromStr for Signature {
+    type Err = Error;
+
+    fn from_str(pattern: &str) -> Result<Self> {
+        Self::parse(pattern)
+    }
+}
+
+/// Returns the offset of the first occurrence of `byte` in the haystack.
+fn find_byte(haystack: &[u8], byte: u8) -> Option<usize> {
+    #[cfg(all(feature = "simd_scan", feature = "std", target_arch = "x86_64"))]
+    {
+        if is_x86_feature_detected!("avx2") {
+            // safe because the avx2 support has been checked above
+            return unsafe { simd::find_byte_avx2(haystack, byte) };
+        }
+    }
+
+    #[cfg(all(feature = "simd_scan", target_arch = "x86_64"))]
+    {
+        // sse2 is always available on x86_64
+        unsafe { simd::find_byte_sse2(haystack, byte) }
+    }
+
+    #[cfg(not(all(feature = "simd_scan", target_arch = "x86_64")))]
+    {
+        find_byte_swar(haystack, byte)
+    }
+}
+
+/// Scalar fallback that compares an entire machine word at a time.
+fn find_byte_swar(haystack: &[u8], byte: u8) -> Option<usize> {
+    const WORD_SIZE: usize = std::mem::size_of::<usize>();
+    const LO: usize = usize::MAX / 0xff;
+    const HI: usize = LO << 7;
+
+    let repeated = LO * byte as usize;
+
+    let mut chunks = haystack.chunks_exact(WORD_SIZE);
+    for (i, chunk) in (&mut chunks).enumerate() {
+        let mut word = [0u8; WORD_SIZE];
+        word.copy_from_slice(chunk);
+        let x = usize::from_ne_bytes(word) ^ repeated;
+
+        // a byte of x is zero if the word contains the searched byte at that position
+        if x.wrapping_sub(LO) & !x & HI != 0 {
+            let offset = i * WORD_SIZE;
+            return chunk
+                .iter()
+                .position(|&b| b == byte)
+                .map(|pos| offset + pos);
+        }
+    }
+
+    let offset = haystack.len() - chunks.remainder().len();
+    chunks
+        .remainder()
+        .iter()
+        .position(|&b| b == byte)
+        .map(|pos| offset + pos)
+}
+
+#[cfg(all(feature = "simd_scan", target_arch = "x86_64"))]
+mod simd {
+    use core::arch::x86_64::*;
+
+    /// # Safety
+    ///
+    /// The host has to support sse2.
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn find_byte_sse2(haystack: &[u8], byte: u8) -> Option<usize> {
+        const LANES: usize = 16;
+
+        let needle = _mm_set1_epi8(byte as i8);
+        let mut offset = 0;
+        while offset + LANES <= haystack.len() {
+            let data = _mm_loadu_si128(haystack.as_ptr().add(offset) as *const __m128i);
+            let mask = _mm_movemask_epi8(_mm_cmpeq_epi8(data, needle));
+            if mask != 0 {
+                return Some(offset + mask.trailing_zeros() as usize);
+            }
+            offset += LANES;
+        }
+
+        super::find_byte_swar(&haystack[offset..], byte).map(|pos| offset + pos)
+    }
+
+    /// # Safety
+    ///
+    /// The host has to support avx2.
+    #[cfg(feature = "std")]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn find_byte_avx2(haystack: &[u8], byte: u8) -> Option<usize> {
+        const LANES: usize = 32;
+
+        let needle = _mm256_set1_epi8(byte as i8);
+        let mut offset = 0;
+        while offset + LANES <= haystack.len() {
+            let data = _mm256_loadu_si256(haystack.as_ptr().add(offset) as *const __m256i);
+            let mask = _mm256_movemask_epi8(_mm256_cmpeq_epi8(data, needle));
+            if mask != 0 {
+                return Some(offset + mask.trailing_zeros() as usize);
+            }
+            offset += LANES;
+        }
+
+        find_byte_sse2(&haystack[offset..], byte).map(|pos| offset + pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::dummy::DummyMemory;
+
+    fn test_buf() -> Vec<u8> {
+        // deterministic pseudo random data
+        let mut state = 0x1234_5678_u32;
+        (0..size::kb(64))
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse() {
+        let sig = Signature::parse("48 8b 05 ? ?? ff").unwrap();
+        assert_eq!(sig.len(), 6);
+        assert!(sig.matches(&[0x48, 0x8b, 0x05, 0x00, 0x11, 0xff]));
+        assert!(!sig.matches(&[0x48, 0x8b, 0x06, 0x00, 0x11, 0xff]));
+        assert_eq!("48 8B".parse::<Signature>().unwrap().len(), 2);
+
+        assert!(Signature::parse("").is_err());
+        assert!(Signature::parse("48 8g").is_err());
+        assert!(Signature::parse("488b").is_err());
+    }
+
+    #[test]
+    fn find() {
+        let sig = Signature::parse("? aa ? aa").unwrap();
+        let buf = [0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0x00, 0xaa];
+        assert_eq!(sig.find(&buf), Some(0));
+        assert_eq!(sig.find_iter(&buf).collect::<Vec<_>>(), vec![0, 1, 3]);
+        assert_eq!(sig.find(&buf[..3]), None);
+
+        let sig = Signature::parse("? ?").unwrap();
+        assert_eq!(sig.find_iter(&buf).count(), buf.len() - 1);
+    }
+
+    #[test]
+    fn find_byte_impls() {
+        let buf = test_buf();
+        for start in 0..64 {
+            for byte in [0x00, 0x42, 0xff].iter().copied() {
+                let haystack = &buf[start..];
+                let expected = haystack.iter().position(|&b| b == byte);
+                assert_eq!(find_byte_swar(haystack, byte), expected);
+                assert_eq!(find_byte(haystack, byte), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn find_matches_naive() {
+        let buf = test_buf();
+        let sig = Signature::new(
+            buf[0x1234..0x123a].to_vec(),
+            vec![true, false, true, true, false, true],
+        )
+        .unwrap();
+        let expected = (0..=buf.len() - sig.len())
+            .filter(|&i| sig.matches(&buf[i..]))
+            .collect::<Vec<_>>();
+        assert!(expected.contains(&0x1234));
+        assert_eq!(sig.find_iter(&buf).collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn scan_virt_chunk_boundaries() {
+        let mut buf = vec![0u8; size::kb(16)];
+        let pattern = [0xde, 0xad, 0xbe, 0xef, 0x13, 0x37];
+        for &offset in [0x0, 0xffe, 0x1ffd, size::kb(16) - pattern.len()].iter() {
+            buf[offset..offset + pattern.len()].copy_from_slice(&pattern);
+        }
+
+        let (mut virt_mem, virt_base) = DummyMemory::new_virt(size::mb(4), size::mb(2), &buf);
+        let sig = Signature::parse("de ad ? ef 13 37").unwrap();
+
+        let expected = vec![
+            virt_base,
+            virt_base + 0xffe,
+            virt_base + 0x1ffd,
+            virt_base + (size::kb(16) - pattern.len()),
+        ];
+        for &chunk_size in [0x1000, 0x1001, 0x3, size::kb(16)].iter() {
+            assert_eq!(
+                sig.scan_virt_chunked(&mut virt_mem, virt_base, buf.len(), chunk_size)
+                    .unwrap(),
+                expected
+            );
+        }
+        assert_eq!(
+            sig.scan_virt(&mut virt_mem, virt_base, buf.len()).unwrap(),
+            expected
+        );
+    }
+}
@@ -0,0 +1,188 @@
+/*!
+Page based change detection for objects implementing the `PhysicalMemory` trait.
+
+A `Snapshot` stores a hash for every physical page in a given set of memory ranges.
+Calling `diff()` at a later point in time re-reads the same pages and returns the addresses
+of all pages whose contents changed in the meantime.
+
+All reads are batched into a single `phys_read_raw_list` call per chunk of pages.
+
+# Examples
+
+```
+use memflow::mem::{PhysicalMemory, Snapshot};
+use memflow::types::size;
+
+fn changed_pages<T: PhysicalMemory>(mem: &mut T) {
+    let snapshot = Snapshot::new(mem, size::kb(4)).unwrap();
+
+    // ... let the target run for a while ...
+
+    for addr in snapshot.diff(mem).unwrap() {
+        println!("page {:x} changed", addr);
+    }
+}
+# use memflow::mem::dummy::DummyMemory;
+# changed_pages(&mut DummyMemory::new(size::mb(4)));
+```
+*/
+
+use std::prelude::v1::*;
+
+use crate::error::{Error, Result};
+use crate::mem::{MemoryMap, PhysicalMemory, PhysicalReadData};
+use crate::types::Address;
+
+/// Number of pages that are read in a single batch
+const PAGES_PER_BATCH: usize = 64;
+
+/// A set of physical page hashes captured at a specific point in time.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    page_size: usize,
+    pages: Vec<(Address, u64)>,
+}
+
+impl Snapshot {
+    /// Captures all pages of the given memory object.
+    ///
+    /// The range is determined by the size reported in the `PhysicalMemoryMetadata`.
+    pub fn new<T: PhysicalMemory + ?Sized>(mem: &mut T, page_size: usize) -> Result<Self> {
+        let mut map = MemoryMap::new();
+        map.push_remap(Address::NULL, mem.metadata().size, Address::NULL);
+        Self::with_map(mem, &map, page_size)
+    }
+
+    /// Captures all pages that are covered by the given memory map.
+    ///
+    /// This is useful to exclude MMIO regions and other gaps of the physical address space.
+    /// Only the linear base addresses of the mappings are taken into account.
+    pub fn with_map<T: PhysicalMemory + ?Sized>(
+        mem: &mut T,
+        map: &MemoryMap<(Address, usize)>,
+        page_size: usize,
+    ) -> Result<Self> {
+        if page_size == 0 || !page_size.is_power_of_two() {
+            return Err(Error::Other("page size must be a power of two"));
+        }
+
+        let mut addrs = Vec::new();
+        for mapping in map.iter() {
+            let start = mapping.base().as_page_aligned(page_size);
+            let end = mapping.base() + mapping.output().1;
+            let mut addr = start;
+            while addr < end {
+                addrs.push(addr);
+                addr += page_size;
+            }
+        }
+
+        let hashes = hash_pages(mem, &addrs, page_size)?;
+        Ok(Self {
+            page_size,
+            pages: addrs.into_iter().zip(hashes.into_iter()).collect(),
+        })
+    }
+
+    /// Returns the page size used for this snapshot.
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    /// Returns the addresses and hashes of all captured pages.
+    pub fn pages(&self) -> &[(Address, u64)] {
+        &self.pages
+    }
+
+    /// Re-reads all captured pages and returns the addresses of the pages that changed.
+    pub fn diff<T: PhysicalMemory + ?Sized>(&self, mem: &mut T) -> Result<Vec<Address>> {
+        let addrs = self.pages.iter().map(|(addr, _)| *addr).collect::<Vec<_>>();
+        let hashes = hash_pages(mem, &addrs, self.page_size)?;
+
+        Ok(self
+            .pages
+            .iter()
+            .zip(hashes.into_iter())
+            .filter(|((_, old), new)| *old != *new)
+            .map(|((addr, _), _)| *addr)
+            .collect())
+    }
+}
+
+fn hash_pages<T: PhysicalMemory + ?Sized>(
+    mem: &mut T,
+    addrs: &[Address],
+    page_size: usize,
+) -> Result<Vec<u64>> {
+    let mut hashes = Vec::with_capacity(addrs.len());
+    let mut buf = vec![0u8; page_size * PAGES_PER_BATCH];
+
+    for chunk in addrs.chunks(PAGES_PER_BATCH) {
+        let mut list = chunk
+            .iter()
+            .zip(buf.chunks_mut(page_size))
+            .map(|(&addr, out)| PhysicalReadData(addr.into(), out))
+            .collect::<Vec<_>>();
+        mem.phys_read_raw_list(&mut list)?;
+
+        hashes.extend(list.iter().map(|PhysicalReadData(_, data)| fnv1a(data)));
+    }
+
+    Ok(hashes)
+}
+
+/// 64-bit FNV-1a hash
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::dummy::DummyMemory;
+    use crate::types::size;
+
+    #[test]
+    fn diff_full() {
+        let mut mem = DummyMemory::new(size::mb(1));
+        let snapshot = Snapshot::new(&mut mem, size::kb(4)).unwrap();
+        assert_eq!(snapshot.pages().len(), size::mb(1) / size::kb(4));
+        assert!(snapshot.diff(&mut mem).unwrap().is_empty());
+
+        mem.phys_write(0x2008.into(), &0xdead_beef_u32).unwrap();
+        mem.phys_write(0x5ff0.into(), &0xdead_beef_u32).unwrap();
+        assert_eq!(
+            snapshot.diff(&mut mem).unwrap(),
+            vec![Address::from(0x2000), Address::from(0x5000)]
+        );
+    }
+
+    #[test]
+    fn diff_mapped() {
+        let mut mem = DummyMemory::new(size::mb(1));
+
+        let mut map = MemoryMap::new();
+        map.push_remap(0x1000.into(), 0x2000, 0x1000.into());
+        map.push_remap(0x8000.into(), 0x1000, 0x8000.into());
+
+        let snapshot = Snapshot::with_map(&mut mem, &map, size::kb(4)).unwrap();
+        assert_eq!(snapshot.pages().len(), 3);
+
+        mem.phys_write(0x0.into(), &0xdead_beef_u32).unwrap();
+        mem.phys_write(0x2000.into(), &0xdead_beef_u32).unwrap();
+        mem.phys_write(0x9000.into(), &0xdead_beef_u32).unwrap();
+        assert_eq!(
+            snapshot.diff(&mut mem).unwrap(),
+            vec![Address::from(0x2000)]
+        );
+    }
+
+    #[test]
+    fn invalid_page_size() {
+        let mut mem = DummyMemory::new(size::mb(1));
+        assert!(Snapshot::new(&mut mem, 0).is_err());
+        assert!(Snapshot::new(&mut mem, 0x1001).is_err());
+    }
+}
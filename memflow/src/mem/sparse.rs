@@ -0,0 +1,260 @@
+/*!
+Sparse in-memory backend implementing the `PhysicalMemory` trait.
+
+The `SparseMemory` only holds a set of explicitly defined regions instead of a full physical address space.
+This makes it easy to hand-craft deterministic fixtures for tests of higher level logic
+(e.g. page table walks or struct readers) with specific bytes at specific addresses.
+
+Reads and writes of addresses outside of the defined regions are left untouched
+as described by the contract of
+[`PhysicalMemory::phys_read_raw_list`](../phys_mem/trait.PhysicalMemory.html#tymethod.phys_read_raw_list).
+
+# Examples
+
+```
+use memflow::mem::{PhysicalMemory, SparseMemory};
+use memflow::types::Address;
+
+let mut mem = SparseMemory::from(vec![
+    (Address::from(0x1000), vec![0u8; 0x1000]),
+    (Address::from(0x8000), 0xdead_beef_u32.to_le_bytes().to_vec()),
+]);
+
+assert_eq!(mem.phys_read::<u32>(0x8000.into()).unwrap(), 0xdead_beef);
+
+mem.phys_write(0x1010.into(), &0x1234_u64).unwrap();
+assert_eq!(mem.phys_read::<u64>(0x1010.into()).unwrap(), 0x1234);
+```
+*/
+
+use std::prelude::v1::*;
+
+use crate::error::Result;
+use crate::mem::phys_mem::{
+    PhysicalMemory, PhysicalMemoryCapabilities, PhysicalMemoryMetadata, PhysicalReadData,
+    PhysicalWriteData,
+};
+use crate::types::Address;
+
+use std::cmp::{max, min};
+use std::collections::BTreeMap;
+use std::iter::FromIterator;
+
+/// Physical memory that consists of a sorted set of independent regions.
+///
+/// Overlapping or adjacent regions are merged when they are inserted,
+/// so every byte is stored exactly once.
+#[derive(Debug, Clone, Default)]
+pub struct SparseMemory {
+    regions: BTreeMap<Address, Vec<u8>>,
+}
+
+impl SparseMemory {
+    /// Creates a new memory object without any regions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts a region with the given contents at the given address.
+    ///
+    /// Bytes of existing regions that overlap with the new region are overwritten.
+    pub fn insert(&mut self, addr: Address, data: Vec<u8>) {
+        if data.is_empty() {
+            return;
+        }
+
+        let end = addr + data.len();
+
+        // regions are disjoint and not adjacent, so their end addresses are sorted as well
+        let merged = self
+            .regions
+            .range(..=end)
+            .rev()
+            .take_while(|(&base, region)| base + region.len() >= addr)
+            .map(|(&base, _)| base)
+            .collect::<Vec<_>>();
+
+        if merged.is_empty() {
+            self.regions.insert(addr, data);
+            return;
+        }
+
+        let mut start = addr;
+        let mut merged_end = end;
+        for base in merged.iter() {
+            start = min(start, *base);
+            merged_end = max(merged_end, *base + self.regions[base].len());
+        }
+
+        let mut buf = vec![0u8; merged_end - start];
+        for base in merged.iter() {
+            let region = self.regions.remove(base).unwrap();
+            let offs = *base - start;
+            buf[offs..offs + region.len()].copy_from_slice(&region);
+        }
+        let offs = addr - start;
+        buf[offs..offs + data.len()].copy_from_slice(&data);
+
+        self.regions.insert(start, buf);
+    }
+
+    /// Returns the base addresses and contents of all regions in ascending order.
+    pub fn regions(&self) -> impl Iterator<Item = (Address, &[u8])> + '_ {
+        self.regions
+            .iter()
+            .map(|(addr, region)| (*addr, &region[..]))
+    }
+}
+
+impl From<Vec<(Address, Vec<u8>)>> for SparseMemory {
+    fn from(regions: Vec<(Address, Vec<u8>)>) -> Self {
+        regions.into_iter().collect()
+    }
+}
+
+impl FromIterator<(Address, Vec<u8>)> for SparseMemory {
+    fn from_iter<I: IntoIterator<Item = (Address, Vec<u8>)>>(iter: I) -> Self {
+        let mut mem = Self::new();
+        for (addr, data) in iter {
+            mem.insert(addr, data);
+        }
+        mem
+    }
+}
+
+impl PhysicalMemory for SparseMemory {
+    fn phys_read_raw_list(&mut self, data: &mut [PhysicalReadData]) -> Result<()> {
+        for PhysicalReadData(addr, out) in data.iter_mut() {
+            let start = addr.address();
+            let end = start + out.len();
+
+            // parts that are not covered by any region are left untouched
+            for (&base, region) in self
+                .regions
+                .range(..end)
+                .rev()
+                .take_while(|(&base, region)| base + region.len() > start)
+            {
+                let from = max(base, start);
+                let to = min(base + region.len(), end);
+                out[from - start..to - start].copy_from_slice(&region[from - base..to - base]);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn phys_write_raw_list(&mut self, data: &[PhysicalWriteData]) -> Result<()> {
+        for PhysicalWriteData(addr, data) in data.iter() {
+            let start = addr.address();
+            let end = start + data.len();
+
+            for (&base, region) in self
+                .regions
+                .range_mut(..end)
+                .rev()
+                .take_while(|(&base, region)| base + region.len() > start)
+            {
+                let from = max(base, start);
+                let to = min(base + region.len(), end);
+                region[from - base..to - base].copy_from_slice(&data[from - start..to - start]);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn metadata(&self) -> PhysicalMemoryMetadata {
+        PhysicalMemoryMetadata {
+            size: self
+                .regions
+                .iter()
+                .next_back()
+                .map(|(base, region)| (*base + region.len()).as_usize())
+                .unwrap_or_default(),
+            readonly: false,
+            capabilities: PhysicalMemoryCapabilities::WRITE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::architecture::x86::x64;
+    use crate::mem::conformance::assert_phys_mem_conformance;
+    use crate::mem::{VirtualDMA, VirtualMemory};
+
+    #[test]
+    fn merge_regions() {
+        let mut mem = SparseMemory::new();
+        mem.insert(0x1000.into(), vec![1u8; 0x10]);
+        mem.insert(0x1020.into(), vec![2u8; 0x10]);
+        assert_eq!(mem.regions().count(), 2);
+
+        // bridges the gap and overwrites the end of the first region
+        mem.insert(0x1008.into(), vec![3u8; 0x18]);
+        let regions = mem.regions().collect::<Vec<_>>();
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].0, Address::from(0x1000));
+        assert_eq!(regions[0].1.len(), 0x30);
+        assert_eq!(regions[0].1[0x7], 1);
+        assert_eq!(regions[0].1[0x8], 3);
+        assert_eq!(regions[0].1[0x20], 2);
+
+        assert_eq!(mem.metadata().size, 0x1030);
+    }
+
+    #[test]
+    fn unbacked_reads() {
+        let mut mem = SparseMemory::from(vec![
+            (Address::from(0x10), vec![0xaa; 4]),
+            (Address::from(0x18), vec![0xbb; 4]),
+        ]);
+
+        let mut buf = [0u8; 0x10];
+        mem.phys_read_raw_into(0xc.into(), &mut buf).unwrap();
+        assert_eq!(
+            buf,
+            [0, 0, 0, 0, 0xaa, 0xaa, 0xaa, 0xaa, 0, 0, 0, 0, 0xbb, 0xbb, 0xbb, 0xbb]
+        );
+
+        // writes to unbacked parts are skipped
+        mem.phys_write_raw(0x12.into(), &[0xcc; 8]).unwrap();
+        assert_eq!(mem.regions().count(), 2);
+        assert_eq!(mem.phys_read::<u32>(0x10.into()).unwrap(), 0xcccc_aaaa);
+        assert_eq!(mem.phys_read::<u32>(0x18.into()).unwrap(), 0xbbbb_cccc);
+    }
+
+    #[test]
+    fn page_table_fixture() {
+        let mut mem = (1..=5)
+            .map(|i| (Address::from(i * 0x1000), vec![0u8; 0x1000]))
+            .collect::<SparseMemory>();
+
+        // map 0x1000_0000 via pml4 (0x1000) -> pdpt (0x2000) -> pd (0x3000) -> pt (0x4000) to 0x5000
+        mem.phys_write(0x1000.into(), &0x2007_u64).unwrap();
+        mem.phys_write(0x2000.into(), &0x3007_u64).unwrap();
+        mem.phys_write((0x3000 + 0x80 * 8).into(), &0x4007_u64)
+            .unwrap();
+        mem.phys_write(0x4000.into(), &0x5007_u64).unwrap();
+        mem.phys_write(0x5010.into(), &0xdead_beef_u32).unwrap();
+
+        let translator = x64::new_translator(0x1000.into());
+        let mut virt_mem = VirtualDMA::new(&mut mem, x64::ARCH, translator);
+        assert_eq!(
+            virt_mem.virt_read::<u32>(0x1000_0010.into()).unwrap(),
+            0xdead_beef
+        );
+        assert!(virt_mem.virt_read::<u32>(0x2000_0000.into()).is_err());
+    }
+
+    #[test]
+    fn conformance() {
+        let mut mem = SparseMemory::from(vec![
+            (Address::from(0x0), vec![0x11; 0x1000]),
+            (Address::from(0x3000), vec![0x22; 0x3000]),
+        ]);
+        assert_phys_mem_conformance(&mut mem);
+    }
+}
@@ -0,0 +1,345 @@
+/*!
+Timeout wrapper for connector objects that implement the `PhysicalMemory` trait.
+
+Some memory backends (e.g. misbehaving DMA devices) can block indefinitely on a single request.
+The `TimeoutPhysicalMemory` enforces a wall-clock deadline on each call to
+`phys_read_raw_list` and `phys_write_raw_list`.
+
+Since most connectors can not be interrupted in the middle of a request the underlying connector
+is moved to a single worker thread that executes all requests in order.
+In case the deadline is exceeded the call returns an error while the request is left
+to finish (or hang) on the worker thread, its result is discarded.
+The connector is not reset after a timeout, so a write that timed out might still be applied.
+Requests that time out before the worker thread picked them up are dropped without being executed.
+All subsequent calls are queued behind the pending request and are subject to the same deadline.
+
+# Examples
+
+```
+use std::time::Duration;
+use memflow::mem::{PhysicalMemory, TimeoutPhysicalMemory};
+
+fn build<T: PhysicalMemory + 'static>(mem: T) {
+    let mut mem = TimeoutPhysicalMemory::builder(mem)
+        .timeout(Duration::from_millis(500))
+        .build()
+        .unwrap();
+
+    let _value: u64 = mem.phys_read(0x1000.into()).unwrap();
+}
+# use memflow::mem::dummy::DummyMemory;
+# use memflow::types::size;
+# build(DummyMemory::new(size::mb(4)));
+```
+*/
+
+use std::prelude::v1::*;
+
+use crate::error::{Error, Result};
+use crate::mem::phys_mem::{
+    PhysicalMemory, PhysicalMemoryCapabilities, PhysicalMemoryMetadata, PhysicalReadData,
+    PhysicalWriteData,
+};
+use crate::types::PhysicalAddress;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// A request that is executed on the worker thread.
+struct Request<T> {
+    func: Box<dyn FnOnce(&mut T) + Send>,
+    /// Set by the caller once it stopped waiting for the result
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Wrapper that aborts physical memory accesses that take longer than the configured timeout.
+///
+/// Since this wrapper implements `PhysicalMemory` it can be used as a replacement
+/// in all structs and functions that require a `PhysicalMemory` object.
+pub struct TimeoutPhysicalMemory<T> {
+    requests: mpsc::Sender<Request<T>>,
+    destroyed: mpsc::Receiver<T>,
+    metadata: PhysicalMemoryMetadata,
+    timeout: Duration,
+}
+
+impl<T: PhysicalMemory + 'static> TimeoutPhysicalMemory<T> {
+    /// Constructs a new timeout wrapper around the given memory object.
+    ///
+    /// This spawns the worker thread that owns the memory object until the wrapper is dropped.
+    pub fn new(mut mem: T, timeout: Duration) -> Self {
+        // the memory object is owned by the worker thread, its cpu state can not be handed out
+        let mut metadata = mem.metadata();
+        metadata
+            .capabilities
+            .remove(PhysicalMemoryCapabilities::CPU_STATE);
+        let (requests, rx) = mpsc::channel::<Request<T>>();
+        let (tx, destroyed) = mpsc::channel();
+
+        thread::spawn(move || {
+            for request in rx.iter() {
+                if !request.cancelled.load(Ordering::SeqCst) {
+                    (request.func)(&mut mem);
+                }
+            }
+            // hand the memory object back to `destroy()`
+            tx.send(mem).ok();
+        });
+
+        Self {
+            requests,
+            destroyed,
+            metadata,
+            timeout,
+        }
+    }
+
+    /// Returns a new builder for this wrapper with default settings.
+    pub fn builder(mem: T) -> TimeoutPhysicalMemoryBuilder<T> {
+        TimeoutPhysicalMemoryBuilder::new(mem)
+    }
+
+    /// Returns the currently configured timeout.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Consumes this wrapper and returns the containing memory object.
+    ///
+    /// This waits for the worker thread to finish all pending requests.
+    /// If a previously timed out request is still running after the configured timeout
+    /// the memory object can not be retrieved and `None` is returned.
+    pub fn destroy(self) -> Option<T> {
+        let Self {
+            requests,
+            destroyed,
+            timeout,
+            ..
+        } = self;
+        std::mem::drop(requests);
+        destroyed.recv_timeout(timeout).ok()
+    }
+
+    fn run_with_timeout<R, F>(&self, func: F) -> Result<R>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut T) -> R + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+
+        self.requests
+            .send(Request {
+                func: Box::new(move |mem: &mut T| {
+                    tx.send(func(mem)).ok();
+                }),
+                cancelled: cancelled.clone(),
+            })
+            .map_err(|_| Error::Connector("physical memory worker thread is not running"))?;
+
+        rx.recv_timeout(self.timeout).map_err(|err| match err {
+            RecvTimeoutError::Timeout => {
+                cancelled.store(true, Ordering::SeqCst);
+                Error::Connector("physical memory access timed out")
+            }
+            RecvTimeoutError::Disconnected => {
+                Error::Connector("physical memory access failed on the worker thread")
+            }
+        })
+    }
+}
+
+impl<T: PhysicalMemory + 'static> PhysicalMemory for TimeoutPhysicalMemory<T> {
+    fn phys_read_raw_list(&mut self, data: &mut [PhysicalReadData]) -> Result<()> {
+        let mut requests = data
+            .iter()
+            .map(|PhysicalReadData(addr, out)| (*addr, vec![0u8; out.len()]))
+            .collect::<Vec<_>>();
+
+        let (result, requests) = self.run_with_timeout(move |mem| {
+            let result = {
+                let mut list = requests
+                    .iter_mut()
+                    .map(|(addr, buf)| PhysicalReadData(*addr, buf.as_mut_slice()))
+                    .collect::<Vec<_>>();
+                mem.phys_read_raw_list(&mut list)
+            };
+            (result, requests)
+        })?;
+        result?;
+
+        data.iter_mut()
+            .zip(requests.into_iter())
+            .for_each(|(PhysicalReadData(_, out), (_, buf))| out.copy_from_slice(&buf));
+
+        Ok(())
+    }
+
+    fn phys_write_raw_list(&mut self, data: &[PhysicalWriteData]) -> Result<()> {
+        let requests = data
+            .iter()
+            .map(|PhysicalWriteData(addr, buf)| (*addr, buf.to_vec()))
+            .collect::<Vec<_>>();
+
+        self.run_with_timeout(move |mem| {
+            let list = requests
+                .iter()
+                .map(|(addr, buf)| PhysicalWriteData(*addr, buf.as_slice()))
+                .collect::<Vec<_>>();
+            mem.phys_write_raw_list(&list)
+        })?
+    }
+
+    fn metadata(&self) -> PhysicalMemoryMetadata {
+        self.metadata
+    }
+
+    fn prefetch(&mut self, ranges: &[(PhysicalAddress, usize)]) -> Result<()> {
+        let ranges = ranges.to_vec();
+        self.run_with_timeout(move |mem| mem.prefetch(&ranges))?
+    }
+}
+
+/// Builder for the `TimeoutPhysicalMemory` wrapper.
+pub struct TimeoutPhysicalMemoryBuilder<T> {
+    mem: T,
+    timeout: Duration,
+}
+
+impl<T: PhysicalMemory + 'static> TimeoutPhysicalMemoryBuilder<T> {
+    /// Creates a new `TimeoutPhysicalMemory` builder.
+    ///
+    /// Without further adjustments this function creates a wrapper with a timeout of 1 second.
+    pub fn new(mem: T) -> Self {
+        Self {
+            mem,
+            timeout: Duration::from_secs(1),
+        }
+    }
+
+    /// Builds the `TimeoutPhysicalMemory` object or returns an error if the timeout is zero.
+    pub fn build(self) -> Result<TimeoutPhysicalMemory<T>> {
+        if self.timeout == Duration::from_secs(0) {
+            return Err(Error::Other("timeout must be greater than zero"));
+        }
+
+        Ok(TimeoutPhysicalMemory::new(self.mem, self.timeout))
+    }
+
+    /// Sets the deadline for every single call to the underlying memory object.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::dummy::DummyMemory;
+    use crate::types::{size, PhysicalAddress};
+    use std::sync::atomic::AtomicUsize;
+
+    struct SlowMemory {
+        delay: Duration,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl SlowMemory {
+        fn new(delay: Duration) -> Self {
+            Self {
+                delay,
+                calls: Arc::new(AtomicUsize::new(0)),
+            }
+        }
+    }
+
+    impl PhysicalMemory for SlowMemory {
+        fn phys_read_raw_list(&mut self, _data: &mut [PhysicalReadData]) -> Result<()> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            thread::sleep(self.delay);
+            Ok(())
+        }
+
+        fn phys_write_raw_list(&mut self, _data: &[PhysicalWriteData]) -> Result<()> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            thread::sleep(self.delay);
+            Ok(())
+        }
+
+        fn metadata(&self) -> PhysicalMemoryMetadata {
+            PhysicalMemoryMetadata {
+                size: size::mb(1),
+                readonly: false,
+                capabilities: PhysicalMemoryCapabilities::WRITE,
+            }
+        }
+    }
+
+    #[test]
+    fn read_write_passthrough() {
+        let mut mem = TimeoutPhysicalMemory::builder(DummyMemory::new(size::mb(4)))
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap();
+
+        mem.phys_write(PhysicalAddress::from(0x1000), &0xdead_beef_u64)
+            .unwrap();
+        let value: u64 = mem.phys_read(PhysicalAddress::from(0x1000)).unwrap();
+        assert_eq!(value, 0xdead_beef);
+        assert_eq!(mem.metadata().size, size::mb(4));
+
+        assert!(mem.destroy().is_some());
+    }
+
+    #[test]
+    fn read_timeout() {
+        let mut mem = TimeoutPhysicalMemory::builder(SlowMemory::new(Duration::from_millis(500)))
+            .timeout(Duration::from_millis(10))
+            .build()
+            .unwrap();
+
+        let result: Result<u64> = mem.phys_read(PhysicalAddress::from(0x1000));
+        assert_eq!(
+            result.err(),
+            Some(Error::Connector("physical memory access timed out"))
+        );
+    }
+
+    #[test]
+    fn queued_requests_are_dropped() {
+        let slow_mem = SlowMemory::new(Duration::from_millis(200));
+        let calls = slow_mem.calls.clone();
+        let mut mem = TimeoutPhysicalMemory::builder(slow_mem)
+            .timeout(Duration::from_millis(10))
+            .build()
+            .unwrap();
+
+        // the first write is still running while the second one times out in the queue
+        assert!(mem
+            .phys_write(PhysicalAddress::from(0x1000), &1u64)
+            .is_err());
+        assert!(mem
+            .phys_write(PhysicalAddress::from(0x1000), &2u64)
+            .is_err());
+
+        thread::sleep(Duration::from_millis(400));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // the worker thread is idle again and hands back the connector
+        assert!(mem.destroy().is_some());
+    }
+
+    #[test]
+    fn zero_timeout() {
+        assert!(
+            TimeoutPhysicalMemory::builder(DummyMemory::new(size::mb(1)))
+                .timeout(Duration::from_secs(0))
+                .build()
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,294 @@
+/*!
+Access tracing for objects implementing the `PhysicalMemory` or `VirtualMemory` trait.
+
+The `TracingMemory` forwards all reads and writes to the wrapped memory and records
+the address and length of every accessed region, either into an internal buffer
+or by handing it to a user provided callback.
+
+This is mostly useful for debugging, e.g. to find out which struct offsets
+a specific function reads from a base address or to verify that an optimized
+code path accesses the same regions as the original one.
+
+# Examples
+
+Tracing all reads of a kernel by wrapping its connector:
+
+```
+use memflow::mem::{AccessKind, PhysicalMemory, TracingMemory};
+
+fn trace<T: PhysicalMemory>(connector: T) {
+    let mut mem = TracingMemory::new(connector);
+
+    mem.phys_read::<u64>(0x1000.into()).unwrap();
+    mem.phys_read::<u32>(0x1010.into()).unwrap();
+
+    for access in mem.trace().iter().filter(|a| a.kind == AccessKind::Read) {
+        println!("{:x} {:x}", access.addr, access.len);
+    }
+}
+# use memflow::mem::dummy::DummyMemory;
+# use memflow::types::size;
+# trace(DummyMemory::new(size::mb(4)));
+```
+*/
+
+use std::prelude::v1::*;
+
+use crate::architecture::Endianess;
+use crate::connector::CpuState;
+use crate::error::{PartialResult, Result};
+use crate::mem::phys_mem::{
+    PhysicalMemory, PhysicalMemoryMetadata, PhysicalReadData, PhysicalWriteData,
+};
+use crate::mem::virt_mem::{VirtualMemory, VirtualReadData, VirtualWriteData};
+use crate::types::{Address, Page, PhysicalAddress};
+
+/// The type of a recorded memory access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+/// A single recorded memory access.
+///
+/// Every entry of a batched read or write is recorded separately
+/// in the same order as it was passed to the wrapped memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryAccess {
+    pub kind: AccessKind,
+    /// The physical or virtual address of the access, depending on the wrapped memory
+    pub addr: Address,
+    pub len: usize,
+}
+
+type TraceCallback = Box<dyn FnMut(&MemoryAccess) + Send>;
+
+/// Wrapper that records all reads and writes of the underlying memory.
+///
+/// Since this wrapper implements `PhysicalMemory` and `VirtualMemory` it can be used as a replacement
+/// in all structs and functions that require one of those objects.
+pub struct TracingMemory<T> {
+    mem: T,
+    trace: Vec<MemoryAccess>,
+    callback: Option<TraceCallback>,
+}
+
+impl<T> TracingMemory<T> {
+    /// Creates a new wrapper that records all accesses into an internal buffer.
+    pub fn new(mem: T) -> Self {
+        Self {
+            mem,
+            trace: Vec::new(),
+            callback: None,
+        }
+    }
+
+    /// Creates a new wrapper that hands every access to the given callback.
+    ///
+    /// Accesses are not stored in the internal buffer in this mode.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memflow::mem::{PhysicalMemory, TracingMemory};
+    ///
+    /// fn log<T: PhysicalMemory>(connector: T) -> TracingMemory<T> {
+    ///     TracingMemory::with_callback(connector, |access| {
+    ///         println!("{:?} {:x} {:x}", access.kind, access.addr, access.len)
+    ///     })
+    /// }
+    /// # use memflow::mem::dummy::DummyMemory;
+    /// # use memflow::types::size;
+    /// # log(DummyMemory::new(size::mb(4))).phys_read::<u64>(0x1000.into()).unwrap();
+    /// ```
+    pub fn with_callback<F: FnMut(&MemoryAccess) + Send + 'static>(mem: T, callback: F) -> Self {
+        Self {
+            mem,
+            trace: Vec::new(),
+            callback: Some(Box::new(callback)),
+        }
+    }
+
+    /// Returns all accesses that have been recorded so far.
+    pub fn trace(&self) -> &[MemoryAccess] {
+        &self.trace
+    }
+
+    /// Returns all accesses that have been recorded so far and clears the internal buffer.
+    pub fn take_trace(&mut self) -> Vec<MemoryAccess> {
+        std::mem::take(&mut self.trace)
+    }
+
+    /// Clears the internal buffer.
+    pub fn clear(&mut self) {
+        self.trace.clear();
+    }
+
+    /// Consumes the wrapper and returns the underlying memory.
+    pub fn destroy(self) -> T {
+        self.mem
+    }
+
+    fn record<I: Iterator<Item = (Address, usize)>>(&mut self, kind: AccessKind, accesses: I) {
+        let accesses = accesses.map(|(addr, len)| MemoryAccess { kind, addr, len });
+        match &mut self.callback {
+            Some(callback) => accesses.for_each(|access| callback(&access)),
+            None => self.trace.extend(accesses),
+        }
+    }
+}
+
+impl<T: PhysicalMemory> PhysicalMemory for TracingMemory<T> {
+    fn phys_read_raw_list(&mut self, data: &mut [PhysicalReadData]) -> Result<()> {
+        self.record(
+            AccessKind::Read,
+            data.iter()
+                .map(|PhysicalReadData(addr, buf)| (addr.address(), buf.len())),
+        );
+        self.mem.phys_read_raw_list(data)
+    }
+
+    fn phys_write_raw_list(&mut self, data: &[PhysicalWriteData]) -> Result<()> {
+        self.record(
+            AccessKind::Write,
+            data.iter()
+                .map(|PhysicalWriteData(addr, buf)| (addr.address(), buf.len())),
+        );
+        self.mem.phys_write_raw_list(data)
+    }
+
+    fn metadata(&self) -> PhysicalMemoryMetadata {
+        self.mem.metadata()
+    }
+
+    fn cpu_state(&mut self) -> Option<&mut dyn CpuState> {
+        self.mem.cpu_state()
+    }
+
+    fn prefetch(&mut self, ranges: &[(PhysicalAddress, usize)]) -> Result<()> {
+        self.mem.prefetch(ranges)
+    }
+}
+
+impl<T: VirtualMemory> VirtualMemory for TracingMemory<T> {
+    fn virt_read_raw_list(&mut self, data: &mut [VirtualReadData]) -> PartialResult<()> {
+        self.record(
+            AccessKind::Read,
+            data.iter()
+                .map(|VirtualReadData(addr, buf)| (*addr, buf.len())),
+        );
+        self.mem.virt_read_raw_list(data)
+    }
+
+    fn virt_write_raw_list(&mut self, data: &[VirtualWriteData]) -> PartialResult<()> {
+        self.record(
+            AccessKind::Write,
+            data.iter()
+                .map(|VirtualWriteData(addr, buf)| (*addr, buf.len())),
+        );
+        self.mem.virt_write_raw_list(data)
+    }
+
+    fn virt_page_info(&mut self, addr: Address) -> Result<Page> {
+        self.mem.virt_page_info(addr)
+    }
+
+    fn virt_to_phys(&mut self, addr: Address) -> Result<PhysicalAddress> {
+        self.mem.virt_to_phys(addr)
+    }
+
+    fn virt_translation_map_range(
+        &mut self,
+        start: Address,
+        end: Address,
+    ) -> Vec<(Address, usize, PhysicalAddress)> {
+        self.mem.virt_translation_map_range(start, end)
+    }
+
+    fn virt_page_map_range(
+        &mut self,
+        gap_size: usize,
+        start: Address,
+        end: Address,
+    ) -> Vec<(Address, usize)> {
+        self.mem.virt_page_map_range(gap_size, start, end)
+    }
+
+    fn endianess(&self) -> Endianess {
+        self.mem.endianess()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::architecture::x86::x64;
+    use crate::error::PartialResultExt;
+    use crate::mem::dummy::DummyMemory;
+    use crate::mem::VirtualDMA;
+    use crate::types::size;
+
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn trace_phys() {
+        let mut mem = TracingMemory::new(DummyMemory::new(size::mb(1)));
+
+        mem.phys_write(0x1000.into(), &0x1234_u64).unwrap();
+        let mut a = [0u8; 8];
+        let mut b = [0u8; 4];
+        mem.phys_read_raw_list(&mut [
+            PhysicalReadData(0x1000.into(), &mut a),
+            PhysicalReadData(0x2004.into(), &mut b),
+        ])
+        .unwrap();
+        assert_eq!(u64::from_le_bytes(a), 0x1234);
+
+        assert_eq!(
+            mem.take_trace(),
+            vec![
+                MemoryAccess {
+                    kind: AccessKind::Write,
+                    addr: 0x1000.into(),
+                    len: 8
+                },
+                MemoryAccess {
+                    kind: AccessKind::Read,
+                    addr: 0x1000.into(),
+                    len: 8
+                },
+                MemoryAccess {
+                    kind: AccessKind::Read,
+                    addr: 0x2004.into(),
+                    len: 4
+                },
+            ]
+        );
+        assert!(mem.trace().is_empty());
+    }
+
+    #[test]
+    fn trace_virt_callback() {
+        let (mem, dtb, virt_base) = DummyMemory::new_and_dtb(size::mb(4), size::mb(2), &[]);
+        let virt_mem = VirtualDMA::new(mem, x64::ARCH, x64::new_translator(dtb));
+
+        let accesses = Arc::new(Mutex::new(Vec::new()));
+        let sink = accesses.clone();
+        let mut virt_mem = TracingMemory::with_callback(virt_mem, move |access| {
+            sink.lock().unwrap().push(*access)
+        });
+
+        virt_mem.virt_read::<u32>(virt_base + 0x10).data().unwrap();
+        virt_mem.virt_read::<u64>(virt_base + 0x28).data().unwrap();
+
+        let offsets = accesses
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|access| (access.addr - virt_base, access.len))
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, vec![(0x10, 4), (0x28, 8)]);
+        assert!(virt_mem.trace().is_empty());
+    }
+}
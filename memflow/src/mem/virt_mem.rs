@@ -1,17 +1,96 @@
 use std::prelude::v1::*;
 
+pub mod struct_reader;
+pub use struct_reader::{StructData, StructReader};
+
 pub mod virtual_dma;
 pub use virtual_dma::VirtualDMA;
 
 use super::VirtualMemoryBatcher;
-use crate::architecture::ArchitectureObj;
+use crate::architecture::{ArchitectureObj, Endian, Endianess};
 use crate::error::{Error, PartialError, PartialResult, PartialResultExt, Result};
-use crate::types::{Address, Page, PhysicalAddress, Pointer32, Pointer64};
+use crate::types::{Address, ByteSwap, Page, PhysicalAddress, Pointer32, Pointer64};
 
 use std::mem::MaybeUninit;
 
 use dataview::Pod;
 
+/// Encoding of strings in the memory of the target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrEncoding {
+    /// Narrow strings restricted to 7-bit characters, all other bytes are replaced
+    Ascii,
+    /// Narrow strings (`char*`)
+    Utf8,
+    /// Wide strings (`wchar_t*` on windows)
+    Utf16,
+}
+
+impl StrEncoding {
+    /// Returns the size of a single code unit in bytes.
+    pub fn char_size(self) -> usize {
+        match self {
+            StrEncoding::Ascii | StrEncoding::Utf8 => 1,
+            StrEncoding::Utf16 => 2,
+        }
+    }
+
+    /// Decodes `buf` up to the first code unit that matches one of the `terminators`.
+    ///
+    /// For `Utf16` a code unit matches if it equals the zero-extended terminator.
+    /// Invalid characters are replaced by the unicode replacement character.
+    pub(crate) fn decode(self, buf: &[u8], terminators: &[u8], endianess: Endianess) -> String {
+        match self {
+            StrEncoding::Ascii => buf
+                .iter()
+                .take_while(|&&c| !terminators.contains(&c))
+                .map(|&c| if c.is_ascii() { c as char } else { '\u{fffd}' })
+                .collect(),
+            StrEncoding::Utf8 => {
+                let len = buf
+                    .iter()
+                    .position(|c| terminators.contains(c))
+                    .unwrap_or(buf.len());
+                String::from_utf8_lossy(&buf[..len]).to_string()
+            }
+            StrEncoding::Utf16 => {
+                let content = buf
+                    .chunks_exact(2)
+                    .map(|c| u16::from_ne_bytes([c[0], c[1]]).from_endianess(endianess))
+                    .take_while(|&c| !terminators.iter().any(|&t| u16::from(t) == c))
+                    .collect::<Vec<u16>>();
+                String::from_utf16_lossy(&content)
+            }
+        }
+    }
+}
+
+/// Width and extension of a pointer that is read with `VirtualMemory::virt_read_ptr`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PtrWidth {
+    /// 32-bit pointer that is zero-extended (e.g. pointers inside of a WoW64 process)
+    Ptr32,
+    /// 32-bit pointer that is sign-extended (e.g. `__ptr32 __sptr` or 32-bit kernel pointers on x64)
+    Ptr32Signed,
+    /// 64-bit pointer
+    Ptr64,
+}
+
+impl PtrWidth {
+    /// Returns the native pointer width of the given architecture.
+    pub fn from_arch(arch: ArchitectureObj) -> Result<Self> {
+        arch.pointer_width()
+    }
+
+    /// Returns the size of the pointer in memory.
+    pub fn size(self) -> usize {
+        match self {
+            PtrWidth::Ptr32 | PtrWidth::Ptr32Signed => 4,
+            PtrWidth::Ptr64 => 8,
+        }
+    }
+}
+
 /// The `VirtualMemory` trait implements access to virtual memory for a specific process
 /// and provides a generic way to read and write from/to that processes virtual memory.
 ///
@@ -63,6 +142,72 @@ where
         end: Address,
     ) -> Vec<(Address, usize)>;
 
+    /// Returns the byte order of the memory.
+    ///
+    /// The byte order is used by the `virt_read_endian` and `virt_write_endian` helpers
+    /// (and all helpers built on top of them) to convert values into the byte order of the host.
+    /// Defaults to `Endianess::LittleEndian`.
+    fn endianess(&self) -> Endianess {
+        Endianess::LittleEndian
+    }
+
+    /// Translates the given virtual address into its physical address.
+    ///
+    /// The returned `PhysicalAddress` also contains the type and size of the page it belongs to.
+    /// This allows cross-referencing a virtual address with the results of a physical scan
+    /// or reading the memory directly through the underlying `PhysicalMemory`.
+    ///
+    /// The default implementation derives the physical address from `virt_page_info`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memflow::mem::VirtualMemory;
+    /// use memflow::types::Address;
+    ///
+    /// fn print_phys<T: VirtualMemory>(virt_mem: &mut T, addr: Address) {
+    ///     let paddr = virt_mem.virt_to_phys(addr).unwrap();
+    ///     println!("{:x} -> {:x} ({:x} byte page)", addr, paddr.address(), paddr.page_size());
+    /// }
+    /// # use memflow::mem::dummy::DummyMemory;
+    /// # use memflow::types::size;
+    /// # let (mut mem, virt_base) = DummyMemory::new_virt(size::mb(4), size::mb(2), &[]);
+    /// # print_phys(&mut mem, virt_base + 0x123);
+    /// ```
+    fn virt_to_phys(&mut self, addr: Address) -> Result<PhysicalAddress> {
+        let page = self.virt_page_info(addr)?;
+        Ok(PhysicalAddress::with_page(
+            page.page_base + (addr.as_usize() & (page.page_size - 1)),
+            page.page_type,
+            page.page_size,
+        ))
+    }
+
+    /// Reads all entries of the given list and reports which of them could be read.
+    ///
+    /// The returned vector contains one flag per entry in the same order as the list.
+    /// Entries that could not be read (e.g. because they are paged out) are zero-filled.
+    ///
+    /// In case the batched read only partially succeeded the entries are read
+    /// one by one again to determine which of them failed.
+    fn virt_read_raw_list_fallible(&mut self, data: &mut [VirtualReadData]) -> Result<Vec<bool>> {
+        match self.virt_read_raw_list(data) {
+            Ok(_) => Ok(vec![true; data.len()]),
+            Err(PartialError::Error(err)) => Err(err),
+            Err(_) => Ok(data
+                .iter_mut()
+                .map(|VirtualReadData(addr, out)| {
+                    if self.virt_read_raw_into(*addr, out).is_ok() {
+                        true
+                    } else {
+                        out.iter_mut().for_each(|b| *b = 0);
+                        false
+                    }
+                })
+                .collect()),
+        }
+    }
+
     // read helpers
     fn virt_read_raw_into(&mut self, addr: Address, out: &mut [u8]) -> PartialResult<()> {
         self.virt_read_raw_list(&mut [VirtualReadData(addr, out)])
@@ -93,6 +238,120 @@ where
         self.virt_read_into(addr, &mut obj).map_data(|_| obj)
     }
 
+    /// Reads `count` consecutive values of type `T` in a single read.
+    ///
+    /// This is the preferred way of reading arrays (e.g. pointer tables) instead of
+    /// reading the raw bytes and converting them manually.
+    /// In case parts of the array could not be read they are zero-filled and a partial result is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memflow::mem::VirtualMemory;
+    /// use memflow::types::Address;
+    ///
+    /// fn read_pointers<T: VirtualMemory>(virt_mem: &mut T, table: Address) -> Vec<u64> {
+    ///     virt_mem.virt_read_vec::<u64>(table, 16).unwrap()
+    /// }
+    /// ```
+    fn virt_read_vec<T: Pod + Sized>(
+        &mut self,
+        addr: Address,
+        count: usize,
+    ) -> PartialResult<Vec<T>>
+    where
+        Self: Sized,
+    {
+        let mut out: Vec<T> = Vec::with_capacity(count);
+        // all bit patterns, including zero, are valid for pod types
+        unsafe {
+            std::ptr::write_bytes(out.as_mut_ptr(), 0, count);
+            out.set_len(count);
+        }
+        self.virt_read_into(addr, &mut out[..]).map_data(|_| out)
+    }
+
+    /// Reads `count` consecutive values of type `T` and converts them from the byte order of the memory
+    /// (see `endianess()`) into the byte order of the host.
+    fn virt_read_vec_swapped<T: Pod + ByteSwap + Sized>(
+        &mut self,
+        addr: Address,
+        count: usize,
+    ) -> PartialResult<Vec<T>>
+    where
+        Self: Sized,
+    {
+        let endianess = self.endianess();
+        self.virt_read_vec(addr, count).map_data(|mut out: Vec<T>| {
+            out[..].byte_swap_endianess(endianess);
+            out
+        })
+    }
+
+    /// Reads a primitive value and converts it from the byte order of the memory
+    /// (see `endianess()`) into the byte order of the host.
+    ///
+    /// Unlike `virt_read` this function will properly handle targets with a different byte order.
+    fn virt_read_endian<T: Endian>(&mut self, addr: Address) -> PartialResult<T>
+    where
+        Self: Sized,
+    {
+        let endianess = self.endianess();
+        self.virt_read::<T>(addr)
+            .map_data(|v| v.from_endianess(endianess))
+    }
+
+    /// Reads a structure and converts all of its fields from the byte order of the memory
+    /// (see `endianess()`) into the byte order of the host.
+    ///
+    /// The structure has to implement `ByteSwap`, usually via `#[derive(ByteSwap)]`.
+    fn virt_read_into_swapped<T: Pod + ByteSwap + ?Sized>(
+        &mut self,
+        addr: Address,
+        out: &mut T,
+    ) -> PartialResult<()>
+    where
+        Self: Sized,
+    {
+        let endianess = self.endianess();
+        self.virt_read_into(addr, out)
+            .map_data(|_| out.byte_swap_endianess(endianess))
+    }
+
+    /// Reads a structure and converts all of its fields from the byte order of the memory
+    /// (see `endianess()`) into the byte order of the host.
+    ///
+    /// Unlike `virt_read` this function will properly handle targets with a different byte order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memflow::mem::VirtualMemory;
+    /// use memflow::types::Address;
+    /// use memflow_derive::ByteSwap;
+    /// use dataview::Pod;
+    ///
+    /// #[repr(C)]
+    /// #[derive(Clone, ByteSwap, Pod)]
+    /// struct ListEntry {
+    ///     flink: u64,
+    ///     blink: u64,
+    /// }
+    ///
+    /// fn read_entry<T: VirtualMemory>(virt_mem: &mut T, addr: Address) -> ListEntry {
+    ///     virt_mem.virt_read_swapped::<ListEntry>(addr).unwrap()
+    /// }
+    /// ```
+    #[allow(clippy::uninit_assumed_init)]
+    fn virt_read_swapped<T: Pod + ByteSwap + Sized>(&mut self, addr: Address) -> PartialResult<T>
+    where
+        Self: Sized,
+    {
+        let mut obj: T = unsafe { MaybeUninit::uninit().assume_init() };
+        self.virt_read_into_swapped(addr, &mut obj)
+            .map_data(|_| obj)
+    }
+
     // write helpers
     fn virt_write_raw(&mut self, addr: Address, data: &[u8]) -> PartialResult<()> {
         self.virt_write_raw_list(&[VirtualWriteData(addr, data)])
@@ -105,6 +364,29 @@ where
         self.virt_write_raw(addr, data.as_bytes())
     }
 
+    /// Converts a primitive value into the byte order of the memory (see `endianess()`) and writes it.
+    fn virt_write_endian<T: Endian>(&mut self, addr: Address, data: T) -> PartialResult<()>
+    where
+        Self: Sized,
+    {
+        let data = data.to_endianess(self.endianess());
+        self.virt_write(addr, &data)
+    }
+
+    /// Converts all fields of a structure into the byte order of the memory (see `endianess()`) and writes it.
+    fn virt_write_swapped<T: Pod + ByteSwap + Clone>(
+        &mut self,
+        addr: Address,
+        data: &T,
+    ) -> PartialResult<()>
+    where
+        Self: Sized,
+    {
+        let mut data = data.clone();
+        data.byte_swap_endianess(self.endianess());
+        self.virt_write(addr, &data)
+    }
+
     // page map helpers
     fn virt_translation_map(&mut self) -> Vec<(Address, usize, PhysicalAddress)> {
         self.virt_translation_map_range(Address::null(), Address::invalid())
@@ -119,14 +401,14 @@ where
     where
         Self: Sized,
     {
-        self.virt_read::<u32>(addr).map_data(|d| d.into())
+        self.virt_read_endian::<u32>(addr).map_data(|d| d.into())
     }
 
     fn virt_read_addr64(&mut self, addr: Address) -> PartialResult<Address>
     where
         Self: Sized,
     {
-        self.virt_read::<u64>(addr).map_data(|d| d.into())
+        self.virt_read_endian::<u64>(addr).map_data(|d| d.into())
     }
 
     fn virt_read_addr_arch(
@@ -140,7 +422,112 @@ where
         match arch.bits() {
             64 => self.virt_read_addr64(addr),
             32 => self.virt_read_addr32(addr),
-            _ => Err(PartialError::Error(Error::InvalidArchitecture)),
+            bits => Err(PartialError::Error(Error::InvalidArchitecture { bits })),
+        }
+    }
+
+    /// Reads a pointer of the given width and extends it to a full `Address`.
+    ///
+    /// Unlike `virt_read_addr_arch` the width does not depend on an architecture.
+    /// This makes it possible to follow 32-bit pointers (e.g. of a WoW64 process)
+    /// with a 64-bit reader.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memflow::mem::{PtrWidth, VirtualMemory};
+    /// use memflow::types::Address;
+    ///
+    /// fn read_wow64_ptr<T: VirtualMemory>(virt_mem: &mut T, addr: Address) -> Address {
+    ///     virt_mem.virt_read_ptr(addr, PtrWidth::Ptr32).unwrap()
+    /// }
+    /// # use memflow::mem::dummy::DummyMemory;
+    /// # use memflow::types::size;
+    /// # let (mut mem, virt_base) = DummyMemory::new_virt(size::mb(4), size::mb(2), &[0xff; 8]);
+    /// # assert_eq!(read_wow64_ptr(&mut mem, virt_base), Address::from(0xffff_ffff_u64));
+    /// ```
+    fn virt_read_ptr(&mut self, addr: Address, width: PtrWidth) -> PartialResult<Address>
+    where
+        Self: Sized,
+    {
+        match width {
+            PtrWidth::Ptr32 => self.virt_read_addr32(addr),
+            PtrWidth::Ptr32Signed => self
+                .virt_read_endian::<i32>(addr)
+                .map_data(|d| Address::from(i64::from(d) as u64)),
+            PtrWidth::Ptr64 => self.virt_read_addr64(addr),
+        }
+    }
+
+    /// Follows a chain of pointers of the given width and returns the last pointer.
+    ///
+    /// Starting at `base`, each offset is added to the current address and the pointer
+    /// stored at the resulting address is read. An empty chain returns `base`.
+    ///
+    /// Since every pointer is required to continue the walk, a pointer that can only be
+    /// read partially or that is null results in an error.
+    fn virt_read_addr_chain(
+        &mut self,
+        width: PtrWidth,
+        base: Address,
+        offsets: &[usize],
+    ) -> PartialResult<Address>
+    where
+        Self: Sized,
+    {
+        let mut addr = base;
+        for &offset in offsets.iter() {
+            addr = self.virt_read_ptr(addr + offset, width).data()?;
+            if addr.is_null() {
+                return Err(PartialError::Error(Error::Other(
+                    "null pointer in pointer chain",
+                )));
+            }
+        }
+        Ok(addr)
+    }
+
+    /// Follows a chain of pointers of the given width and reads a `T` at the end of it.
+    ///
+    /// All offsets but the last one are dereferenced like in `virt_read_addr_chain`,
+    /// `T` is then read at the last pointer plus the last offset.
+    /// An empty chain reads `T` at `base`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memflow::mem::{PtrWidth, VirtualMemory};
+    /// use memflow::types::Address;
+    ///
+    /// // reads `peb->Ldr->InLoadOrderModuleList.Flink` of a 64-bit process
+    /// fn first_module<T: VirtualMemory>(virt_mem: &mut T, peb: Address) -> Address {
+    ///     virt_mem
+    ///         .virt_read_chain::<u64>(PtrWidth::Ptr64, peb, &[0x18, 0x10])
+    ///         .unwrap()
+    ///         .into()
+    /// }
+    /// # use memflow::mem::dummy::DummyMemory;
+    /// # use memflow::types::size;
+    /// # let (mut mem, virt_base) = DummyMemory::new_virt(size::mb(4), size::mb(2), &[]);
+    /// # mem.virt_write(virt_base + 0x18, &(virt_base + 0x100).as_u64()).unwrap();
+    /// # mem.virt_write(virt_base + 0x110, &0x1234u64).unwrap();
+    /// # assert_eq!(first_module(&mut mem, virt_base), Address::from(0x1234));
+    /// ```
+    fn virt_read_chain<T: Pod + Sized>(
+        &mut self,
+        width: PtrWidth,
+        base: Address,
+        offsets: &[usize],
+    ) -> PartialResult<T>
+    where
+        Self: Sized,
+    {
+        match offsets.split_last() {
+            Some((&last, chain)) => {
+                let addr = self.virt_read_addr_chain(width, base, chain)?;
+                self.virt_read(addr + last)
+            }
+            None => self.virt_read(base),
         }
     }
 
@@ -184,12 +571,145 @@ where
     // TODO: read into slice?
     // TODO: if len is shorter than string -> dynamically double length up to an upper bound
     fn virt_read_cstr(&mut self, addr: Address, len: usize) -> PartialResult<String> {
-        let mut buf = vec![0; len];
+        self.virt_read_str_until(addr, len, &[0], StrEncoding::Utf8)
+    }
+
+    /// Reads a string in the given encoding that ends at one of the given terminators.
+    ///
+    /// At most `max_chars` code units are being read. The string ends either at the first
+    /// code unit that matches one of the `terminators` or after `max_chars` code units.
+    /// The terminator itself is not part of the returned string.
+    /// Wide strings are decoded in the byte order of the target.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memflow::mem::{StrEncoding, VirtualMemory};
+    /// use memflow::types::Address;
+    ///
+    /// fn read_directory<T: VirtualMemory>(virt_mem: &mut T, path: Address) {
+    ///     let dir = virt_mem
+    ///         .virt_read_str_until(path, 260, &[b'\\', 0], StrEncoding::Utf16)
+    ///         .unwrap();
+    ///     assert_eq!(dir, "C:");
+    /// }
+    /// # use memflow::mem::dummy::DummyMemory;
+    /// # use memflow::types::size;
+    /// # let path = "C:\\Windows".encode_utf16().flat_map(|c| c.to_le_bytes().to_vec()).collect::<Vec<u8>>();
+    /// # let (mut mem, virt_base) = DummyMemory::new_virt(size::mb(4), size::mb(2), &path);
+    /// # read_directory(&mut mem, virt_base);
+    /// ```
+    fn virt_read_str_until(
+        &mut self,
+        addr: Address,
+        max_chars: usize,
+        terminators: &[u8],
+        encoding: StrEncoding,
+    ) -> PartialResult<String> {
+        let mut buf = vec![0; max_chars * encoding.char_size()];
+        self.virt_read_raw_into(addr, &mut buf).data_part()?;
+        Ok(encoding.decode(&buf, terminators, self.endianess()))
+    }
+
+    /// Reads a null-terminated UTF-16 string in the byte order of the target.
+    ///
+    /// At most `max_chars` UTF-16 code units are being read. The string ends either
+    /// at the first null terminator or after `max_chars` code units.
+    /// Invalid code units are replaced by the unicode replacement character.
+    fn virt_read_wstr(&mut self, addr: Address, max_chars: usize) -> PartialResult<String> {
+        self.virt_read_str_until(addr, max_chars, &[0], StrEncoding::Utf16)
+    }
+
+    /// Reads a table of consecutive null-terminated strings.
+    ///
+    /// The whole table of `total_bytes` bytes is read at once and split on the null terminators afterwards.
+    /// The table ends either at the first empty string (e.g. the double null terminator of an environment block),
+    /// after `max_strings` strings or at the end of the buffer.
+    /// A string that is not terminated within the buffer is returned truncated.
+    fn virt_read_cstr_array(
+        &mut self,
+        addr: Address,
+        total_bytes: usize,
+        max_strings: usize,
+    ) -> PartialResult<Vec<String>> {
+        let mut buf = vec![0; total_bytes];
         self.virt_read_raw_into(addr, &mut buf).data_part()?;
-        if let Some((n, _)) = buf.iter().enumerate().find(|(_, c)| **c == 0_u8) {
-            buf.truncate(n);
+        Ok(buf
+            .split(|&c| c == 0)
+            .take_while(|s| !s.is_empty())
+            .take(max_strings)
+            .map(|s| String::from_utf8_lossy(s).to_string())
+            .collect())
+    }
+
+    /// Reads a null-terminated array of string pointers (e.g. `argv` or `envp`) and all strings it points to.
+    ///
+    /// The pointers are read in batches of the pointer width of `arch` until either a null pointer
+    /// is encountered or `max_entries` pointers have been read.
+    /// Afterwards all strings are read in a single batch. Each string is read with at most
+    /// `max_len` characters in the given `encoding` and ends at its null terminator.
+    ///
+    /// Pointers that can not be read are treated as the end of the array,
+    /// strings that can not be read are returned empty.
+    fn virt_read_str_ptr_array(
+        &mut self,
+        arch: ArchitectureObj,
+        addr: Address,
+        max_entries: usize,
+        max_len: usize,
+        encoding: StrEncoding,
+    ) -> Result<Vec<String>>
+    where
+        Self: Sized,
+    {
+        const PTR_BATCH: usize = 16;
+
+        let endianess = self.endianess();
+        let ptr_size = match arch.bits() {
+            64 => 8,
+            32 => 4,
+            bits => return Err(Error::InvalidArchitecture { bits }),
+        };
+
+        let mut ptrs = Vec::new();
+        let mut buf = vec![0u8; PTR_BATCH * ptr_size];
+        'outer: while ptrs.len() < max_entries {
+            let count = (max_entries - ptrs.len()).min(PTR_BATCH);
+            let buf = &mut buf[..count * ptr_size];
+            self.virt_read_raw_into(addr + ptrs.len() * ptr_size, buf)
+                .data_part()?;
+
+            for chunk in buf.chunks_exact(ptr_size) {
+                let ptr = if ptr_size == 8 {
+                    let mut raw = [0u8; 8];
+                    raw.copy_from_slice(chunk);
+                    Address::from(u64::from_ne_bytes(raw).from_endianess(endianess))
+                } else {
+                    let mut raw = [0u8; 4];
+                    raw.copy_from_slice(chunk);
+                    Address::from(u32::from_ne_bytes(raw).from_endianess(endianess))
+                };
+                if ptr.is_null() {
+                    break 'outer;
+                }
+                ptrs.push(ptr);
+            }
+        }
+
+        let mut bufs = vec![vec![0u8; max_len * encoding.char_size()]; ptrs.len()];
+        {
+            let mut list = ptrs
+                .iter()
+                .zip(bufs.iter_mut())
+                .map(|(&ptr, buf)| VirtualReadData(ptr, &mut buf[..]))
+                .collect::<Vec<_>>();
+            self.virt_read_raw_list(&mut list).data_part()?;
         }
-        Ok(String::from_utf8_lossy(&buf).to_string())
+
+        Ok(bufs
+            .into_iter()
+            .map(|buf| encoding.decode(&buf, &[0], endianess))
+            .collect())
     }
 
     fn virt_batcher(&mut self) -> VirtualMemoryBatcher<Self>
@@ -217,6 +737,11 @@ impl<T: VirtualMemory + ?Sized, P: std::ops::DerefMut<Target = T> + Send> Virtua
         (**self).virt_page_info(addr)
     }
 
+    #[inline]
+    fn virt_to_phys(&mut self, addr: Address) -> Result<PhysicalAddress> {
+        (**self).virt_to_phys(addr)
+    }
+
     #[inline]
     fn virt_translation_map_range(
         &mut self,
@@ -235,6 +760,11 @@ impl<T: VirtualMemory + ?Sized, P: std::ops::DerefMut<Target = T> + Send> Virtua
     ) -> Vec<(Address, usize)> {
         (**self).virt_page_map_range(gap_size, start, end)
     }
+
+    #[inline]
+    fn endianess(&self) -> Endianess {
+        (**self).endianess()
+    }
 }
 
 // iterator helpers
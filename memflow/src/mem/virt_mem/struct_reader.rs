@@ -0,0 +1,258 @@
+/*!
+Batched reading of multiple fields of a single structure.
+
+Walking operating system structures usually requires reading a lot of individual fields
+from the same base address. Reading them one by one results in a round-trip to the
+underlying memory backend for every single field.
+
+The `StructReader` collects a set of `(offset, size)` field descriptions and reads all of them
+with a single call to `virt_read_raw_list`. The resulting `StructData` contains the decoded buffer
+from which the individual fields can be retrieved afterwards.
+
+# Examples
+
+```
+use memflow::architecture::x86::x64;
+use memflow::mem::{StructReader, VirtualMemory};
+use memflow::types::Address;
+
+fn read<T: VirtualMemory>(virt_mem: &mut T, base: Address) {
+    let data = StructReader::new(base)
+        .field(0x0, 4)
+        .field_addr(x64::ARCH, 0x8)
+        .read(virt_mem)
+        .unwrap();
+
+    let value: u32 = data.get(0x0).unwrap();
+    let ptr = data.get_addr(x64::ARCH, 0x8).unwrap();
+    println!("value: {:x}, ptr: {:x}", value, ptr);
+}
+# use memflow::mem::dummy::DummyMemory;
+# use memflow::types::size;
+# let (mut mem, virt_base) = DummyMemory::new_virt(size::mb(4), size::mb(2), &[0; 16]);
+# read(&mut mem, virt_base);
+```
+*/
+
+use std::prelude::v1::*;
+
+use super::{StrEncoding, VirtualMemory, VirtualReadData};
+use crate::architecture::{ArchitectureObj, Endianess};
+use crate::error::{Error, PartialError, PartialResult, Result};
+use crate::types::Address;
+
+use std::mem::MaybeUninit;
+
+use dataview::Pod;
+
+/// Collects field reads relative to a base address and performs them in a single batch.
+#[derive(Debug, Clone)]
+pub struct StructReader {
+    base: Address,
+    fields: Vec<(usize, usize)>,
+}
+
+impl StructReader {
+    /// Creates a new `StructReader` for the structure located at `base`.
+    pub fn new(base: Address) -> Self {
+        Self {
+            base,
+            fields: Vec::new(),
+        }
+    }
+
+    /// Adds a field with the given offset and size in bytes to the batch.
+    pub fn field(mut self, offset: usize, size: usize) -> Self {
+        self.fields.push((offset, size));
+        self
+    }
+
+    /// Adds a pointer sized field for the given architecture to the batch.
+    pub fn field_addr(self, arch: ArchitectureObj, offset: usize) -> Self {
+        self.field(offset, arch.size_addr())
+    }
+
+    /// Reads all fields with a single call to `virt_read_raw_list`.
+    ///
+    /// In case only some of the fields could be read a `PartialVirtualRead` error
+    /// containing the partially filled `StructData` is returned.
+    /// Fields that could not be read are zero-filled and can be identified via `StructData::is_valid`.
+    pub fn read<V: VirtualMemory>(&self, mem: &mut V) -> PartialResult<StructData> {
+        let mut buf = vec![0u8; self.fields.iter().map(|(_, size)| size).sum()];
+
+        let valid = {
+            let mut list = Vec::with_capacity(self.fields.len());
+            let mut rest = &mut buf[..];
+            for &(offset, size) in self.fields.iter() {
+                let (field, tail) = rest.split_at_mut(size);
+                list.push(VirtualReadData(self.base + offset, field));
+                rest = tail;
+            }
+            mem.virt_read_raw_list_fallible(&mut list)?
+        };
+
+        let mut start = 0;
+        let fields = self
+            .fields
+            .iter()
+            .zip(valid.iter())
+            .map(|(&(offset, size), &valid)| {
+                start += size;
+                (offset, start - size, size, valid)
+            })
+            .collect::<Vec<_>>();
+
+        let partial = fields.iter().any(|&(_, _, _, valid)| !valid);
+        let data = StructData {
+            base: self.base,
+            fields,
+            buf,
+        };
+
+        if partial {
+            Err(PartialError::PartialVirtualRead(data))
+        } else {
+            Ok(data)
+        }
+    }
+}
+
+/// Contains the data of all fields that were read by a `StructReader`.
+#[derive(Debug, Clone)]
+pub struct StructData {
+    base: Address,
+    fields: Vec<(usize, usize, usize, bool)>,
+    buf: Vec<u8>,
+}
+
+impl StructData {
+    /// Returns the base address of the structure.
+    pub fn base(&self) -> Address {
+        self.base
+    }
+
+    /// Returns true if the field at the given offset has been read successfully.
+    pub fn is_valid(&self, offset: usize) -> bool {
+        self.fields
+            .iter()
+            .any(|&(field_offset, _, _, valid)| field_offset == offset && valid)
+    }
+
+    /// Returns the raw bytes of the field at the given offset.
+    ///
+    /// If the field has not been added to the `StructReader` an `Error::Bounds` is returned.
+    /// If the field could not be read an `Error::Partial` is returned.
+    pub fn get_raw(&self, offset: usize) -> Result<&[u8]> {
+        match self
+            .fields
+            .iter()
+            .find(|(field_offset, _, _, _)| *field_offset == offset)
+        {
+            Some(&(_, start, size, true)) => Ok(&self.buf[start..start + size]),
+            Some(_) => Err(Error::Partial),
+            None => Err(Error::Bounds),
+        }
+    }
+
+    /// Decodes the field at the given offset into a `Pod` type.
+    ///
+    /// The field has to be at least as large as the requested type.
+    #[allow(clippy::uninit_assumed_init)]
+    pub fn get<T: Pod + Sized>(&self, offset: usize) -> Result<T> {
+        let raw = self.get_raw(offset)?;
+        let mut obj: T = unsafe { MaybeUninit::uninit().assume_init() };
+        let out = obj.as_bytes_mut();
+        if raw.len() < out.len() {
+            return Err(Error::Bounds);
+        }
+        out.copy_from_slice(&raw[..out.len()]);
+        Ok(obj)
+    }
+
+    /// Decodes the pointer sized field at the given offset for the given architecture.
+    pub fn get_addr(&self, arch: ArchitectureObj, offset: usize) -> Result<Address> {
+        match arch.bits() {
+            64 => self.get::<u64>(offset).map(Address::from),
+            32 => self.get::<u32>(offset).map(Address::from),
+            bits => Err(Error::InvalidArchitecture { bits }),
+        }
+    }
+
+    /// Decodes the field at the given offset as a null-terminated string.
+    pub fn get_cstr(&self, offset: usize) -> Result<String> {
+        let raw = self.get_raw(offset)?;
+        Ok(StrEncoding::Utf8.decode(raw, &[0], Endianess::LittleEndian))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::architecture::x86::{x32, x64};
+    use crate::mem::dummy::DummyMemory;
+    use crate::types::size;
+
+    #[test]
+    fn read_fields() {
+        let mut buf = vec![0u8; 0x100];
+        buf[0x10..0x14].copy_from_slice(&0x1234_u32.to_le_bytes());
+        buf[0x20..0x28].copy_from_slice(&0xffff_f800_1234_5678_u64.to_le_bytes());
+        buf[0x30..0x3b].copy_from_slice(b"notepad.exe");
+
+        let (mut mem, virt_base) = DummyMemory::new_virt(size::mb(4), size::mb(2), &buf);
+
+        let data = StructReader::new(virt_base)
+            .field(0x10, 4)
+            .field_addr(x64::ARCH, 0x20)
+            .field(0x30, 15)
+            .read(&mut mem)
+            .unwrap();
+
+        assert_eq!(data.base(), virt_base);
+        assert_eq!(data.get::<u32>(0x10).unwrap(), 0x1234);
+        assert_eq!(
+            data.get_addr(x64::ARCH, 0x20).unwrap(),
+            Address::from(0xffff_f800_1234_5678_u64)
+        );
+        assert_eq!(
+            data.get_addr(x32::ARCH, 0x20).unwrap(),
+            Address::from(0x1234_5678_u64)
+        );
+        assert_eq!(data.get_cstr(0x30).unwrap(), "notepad.exe");
+    }
+
+    #[test]
+    fn missing_field() {
+        let (mut mem, virt_base) = DummyMemory::new_virt(size::mb(4), size::mb(2), &[0; 16]);
+
+        let data = StructReader::new(virt_base)
+            .field(0x0, 4)
+            .read(&mut mem)
+            .unwrap();
+
+        assert_eq!(data.get::<u32>(0x4), Err(Error::Bounds));
+        assert_eq!(data.get::<u64>(0x0), Err(Error::Bounds));
+    }
+
+    #[test]
+    fn partial_read() {
+        let (mut mem, virt_base) = DummyMemory::new_virt(size::mb(4), size::mb(2), &[0x11; 16]);
+
+        let data = StructReader::new(virt_base)
+            .field(0x0, 4)
+            .field(size::mb(64), 8)
+            .read(&mut mem)
+            .unwrap_err();
+
+        let data = match data {
+            PartialError::PartialVirtualRead(data) => data,
+            _ => panic!("expected a partial read"),
+        };
+
+        assert!(data.is_valid(0x0));
+        assert_eq!(data.get::<u32>(0x0).unwrap(), 0x1111_1111);
+        assert!(!data.is_valid(size::mb(64)));
+        assert_eq!(data.get::<u64>(size::mb(64)), Err(Error::Partial));
+        assert!(!data.is_valid(0x4));
+    }
+}
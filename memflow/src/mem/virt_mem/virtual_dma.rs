@@ -1,10 +1,11 @@
 use std::prelude::v1::*;
 
 use super::{VirtualReadData, VirtualWriteData};
-use crate::architecture::{ArchitectureObj, ScopedVirtualTranslate};
+use crate::architecture::{ArchitectureObj, Endianess, ScopedVirtualTranslate};
 use crate::error::{Error, PartialError, PartialResult, Result};
 use crate::iter::FnExtend;
 use crate::mem::{
+    coalescing::coalesced_read_raw_list,
     virt_translate::{DirectTranslate, VirtualTranslate},
     PhysicalMemory, PhysicalReadData, PhysicalWriteData, VirtualMemory,
 };
@@ -22,6 +23,7 @@ pub struct VirtualDMA<T, V, D> {
     vat: V,
     proc_arch: ArchitectureObj,
     translator: D,
+    read_granularity: usize,
     arena: Bump,
 }
 
@@ -64,6 +66,7 @@ impl<T: PhysicalMemory, D: ScopedVirtualTranslate> VirtualDMA<T, DirectTranslate
             vat: DirectTranslate::new(),
             proc_arch,
             translator,
+            read_granularity: 0,
             arena: Bump::new(),
         }
     }
@@ -105,6 +108,7 @@ impl<T: PhysicalMemory, V: VirtualTranslate, D: ScopedVirtualTranslate> VirtualD
             vat,
             proc_arch,
             translator,
+            read_granularity: 0,
             arena: Bump::new(),
         }
     }
@@ -124,12 +128,34 @@ impl<T: PhysicalMemory, V: VirtualTranslate, D: ScopedVirtualTranslate> VirtualD
         &self.translator
     }
 
+    /// Sets the maximum size of a single physical read.
+    ///
+    /// Translated pages that turn out to be physically contiguous are merged into
+    /// reads of up to `granularity` bytes before they are issued to the physical memory.
+    /// This reduces the number of requests for backends with a high per-request overhead.
+    ///
+    /// A granularity of 0 disables merging, which is the default.
+    pub fn set_read_granularity(&mut self, granularity: usize) {
+        self.read_granularity = granularity;
+    }
+
+    /// Builder-style variant of `set_read_granularity()`.
+    pub fn with_read_granularity(mut self, granularity: usize) -> Self {
+        self.set_read_granularity(granularity);
+        self
+    }
+
+    /// Returns the maximum size of a single physical read, 0 if merging is disabled.
+    pub fn read_granularity(&self) -> usize {
+        self.read_granularity
+    }
+
     /// A wrapper around `virt_read_addr64` and `virt_read_addr32` that will use the pointer size of this context's process.
     pub fn virt_read_addr(&mut self, addr: Address) -> PartialResult<Address> {
         match self.proc_arch.bits() {
             64 => self.virt_read_addr64(addr),
             32 => self.virt_read_addr32(addr),
-            _ => Err(PartialError::Error(Error::InvalidArchitecture)),
+            bits => Err(PartialError::Error(Error::InvalidArchitecture { bits })),
         }
     }
 
@@ -151,6 +177,7 @@ where
             vat: self.vat.clone(),
             proc_arch: self.proc_arch,
             translator: self.translator.clone(),
+            read_granularity: self.read_granularity,
             arena: Bump::new(),
         }
     }
@@ -178,7 +205,11 @@ impl<T: PhysicalMemory, V: VirtualTranslate, D: ScopedVirtualTranslate> VirtualM
             }),
         );
 
-        self.phys_mem.phys_read_raw_list(&mut translation)?;
+        if self.read_granularity > 0 {
+            coalesced_read_raw_list(&mut self.phys_mem, &mut translation, self.read_granularity)?;
+        } else {
+            self.phys_mem.phys_read_raw_list(&mut translation)?;
+        }
         if !partial_read {
             Ok(())
         } else {
@@ -216,6 +247,11 @@ impl<T: PhysicalMemory, V: VirtualTranslate, D: ScopedVirtualTranslate> VirtualM
         Ok(paddr.containing_page())
     }
 
+    fn virt_to_phys(&mut self, addr: Address) -> Result<PhysicalAddress> {
+        self.vat
+            .virt_to_phys(&mut self.phys_mem, &self.translator, addr)
+    }
+
     fn virt_translation_map_range(
         &mut self,
         start: Address,
@@ -276,4 +312,7 @@ impl<T: PhysicalMemory, V: VirtualTranslate, D: ScopedVirtualTranslate> VirtualM
             })
             .collect()
     }
+    fn endianess(&self) -> Endianess {
+        self.proc_arch.endianess()
+    }
 }
@@ -12,7 +12,7 @@ use crate::error::{Error, Result};
 use crate::mem::PhysicalMemory;
 use crate::types::{Address, PhysicalAddress};
 
-use crate::architecture::ScopedVirtualTranslate;
+use crate::architecture::{PageWalkStep, ScopedVirtualTranslate};
 
 pub trait VirtualTranslate
 where
@@ -110,6 +110,57 @@ where
             Err(vec_fail.pop().unwrap().0)
         }
     }
+
+    /// Walks the page tables of the translator for the given virtual address
+    /// and returns all page table entries that have been visited.
+    ///
+    /// The walk bypasses any caches and is meant for debugging failed translations,
+    /// see `ScopedVirtualTranslate::virt_page_walk` for more information.
+    fn virt_page_walk<T: PhysicalMemory + ?Sized, D: ScopedVirtualTranslate>(
+        &mut self,
+        phys_mem: &mut T,
+        translator: &D,
+        vaddr: Address,
+    ) -> Result<Vec<PageWalkStep>> {
+        translator.virt_page_walk(phys_mem, vaddr)
+    }
+
+    /// Translates a batch of virtual addresses at once.
+    ///
+    /// In contrast to calling `virt_to_phys` for every address the page tables are walked
+    /// for the entire batch simultaneously. Page table entries that are shared between multiple
+    /// addresses (e.g. the same PML4 or PDPT entry) are only read once.
+    ///
+    /// The results are returned in the same order as the given addresses.
+    fn virt_to_phys_list<T: PhysicalMemory + ?Sized, D: ScopedVirtualTranslate>(
+        &mut self,
+        phys_mem: &mut T,
+        translator: &D,
+        addrs: &[Address],
+    ) -> Vec<Result<PhysicalAddress>> {
+        // every address is tagged with its index so the results can be put back in order
+        let indices = (0..addrs.len()).collect::<Vec<_>>();
+        let mut out = vec![Err(Error::VirtualTranslate); addrs.len()];
+
+        let mut vec = vec![];
+        let mut vec_fail = vec![];
+        self.virt_to_phys_iter(
+            phys_mem,
+            translator,
+            addrs.iter().copied().zip(indices.chunks(1)),
+            &mut vec,
+            &mut vec_fail,
+        );
+
+        for (paddr, idx) in vec {
+            out[idx[0]] = Ok(paddr);
+        }
+        for (err, _, idx) in vec_fail {
+            out[idx[0]] = Err(err);
+        }
+
+        out
+    }
 }
 
 // forward impls
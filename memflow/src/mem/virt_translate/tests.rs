@@ -1,8 +1,15 @@
 use crate::architecture::x86::x64;
+use crate::architecture::{Architecture, ArchitectureObj, Endianess};
 
+use crate::error::{Error, PartialError, Result};
 use crate::mem::dummy::DummyMemory;
-use crate::mem::{DirectTranslate, VirtualDMA, VirtualMemory, VirtualTranslate};
-use crate::types::size;
+use crate::mem::{
+    DirectTranslate, PhysicalMemory, PhysicalMemoryMetadata, PhysicalReadData, PhysicalWriteData,
+    PtrWidth, StrEncoding, TracingMemory, VirtualDMA, VirtualMemory, VirtualTranslate,
+};
+use crate::types::{size, Address, ByteSwap};
+
+use dataview::Pod;
 
 #[test]
 fn test_vtop() {
@@ -50,6 +57,205 @@ fn test_vtop() {
     }
 }
 
+#[test]
+fn test_virt_to_phys() {
+    let mut dummy_mem = DummyMemory::new(size::mb(32));
+    let virt_size = size::mb(8);
+    let buf = (0..size::kb(8)).map(|i| i as u8).collect::<Vec<_>>();
+    let (dtb, virt_base) = dummy_mem.alloc_dtb(virt_size, &buf);
+    let addr = virt_base + 0x1234;
+
+    let (paddr, page) = {
+        let mut virt_mem = VirtualDMA::new(&mut dummy_mem, x64::ARCH, x64::new_translator(dtb));
+        assert!(virt_mem.virt_to_phys(virt_base + virt_size).is_err());
+        (
+            virt_mem.virt_to_phys(addr).unwrap(),
+            virt_mem.virt_page_info(addr).unwrap(),
+        )
+    };
+
+    assert!(paddr.has_page());
+    assert_eq!(paddr.containing_page(), page);
+    assert_eq!(
+        paddr.address() - page.page_base,
+        addr.as_usize() & (page.page_size - 1)
+    );
+    assert_eq!(dummy_mem.vtop(dtb, addr), Some(paddr.address()));
+
+    let mut out = [0u8; 4];
+    dummy_mem.phys_read_raw_into(paddr, &mut out).unwrap();
+    assert_eq!(out, [0x34, 0x35, 0x36, 0x37]);
+}
+
+/// Memory that counts the number of physical reads
+struct CountingMemory {
+    mem: DummyMemory,
+    reads: usize,
+}
+
+impl PhysicalMemory for CountingMemory {
+    fn phys_read_raw_list(&mut self, data: &mut [PhysicalReadData]) -> Result<()> {
+        self.reads += data.len();
+        self.mem.phys_read_raw_list(data)
+    }
+
+    fn phys_write_raw_list(&mut self, data: &[PhysicalWriteData]) -> Result<()> {
+        self.mem.phys_write_raw_list(data)
+    }
+
+    fn metadata(&self) -> PhysicalMemoryMetadata {
+        self.mem.metadata()
+    }
+}
+
+#[test]
+fn test_vtop_list() {
+    let mut dummy_mem = DummyMemory::new(size::mb(32));
+    let virt_size = size::mb(8);
+    let (dtb, virt_base) = dummy_mem.alloc_dtb(virt_size, &[]);
+    let translator = x64::new_translator(dtb);
+    let mut vat = DirectTranslate::new();
+
+    let mut addrs = (0..virt_size)
+        .step_by(size::kb(128))
+        .map(|i| virt_base + i + 0x10)
+        .rev()
+        .collect::<Vec<_>>();
+    addrs.insert(2, virt_base + virt_size);
+
+    let mut mem = CountingMemory {
+        mem: dummy_mem,
+        reads: 0,
+    };
+
+    let list = vat.virt_to_phys_list(&mut mem, &translator, &addrs);
+    assert_eq!(list.len(), addrs.len());
+    assert_eq!(list[2], Err(Error::VirtualTranslate));
+    for (addr, paddr) in addrs.iter().zip(list.iter()) {
+        assert_eq!(
+            paddr.as_ref().ok().map(|paddr| paddr.address()),
+            mem.mem.vtop(dtb, *addr)
+        );
+    }
+    let batch_reads = mem.reads;
+
+    mem.reads = 0;
+    for addr in addrs.iter() {
+        vat.virt_to_phys(&mut mem, &translator, *addr).ok();
+    }
+
+    // shared upper level page table entries are only read once
+    assert!(batch_reads < mem.reads);
+}
+
+#[test]
+fn test_virt_read_granularity() {
+    let mut dummy_mem = DummyMemory::new(size::kb(256));
+    let dtb = Address::from(0x1000);
+    let pdpt = Address::from(0x2000);
+    let pd = Address::from(0x3000);
+    let pt = Address::from(0x4000);
+
+    // the first 3 pages are physically contiguous, the 4th page is not
+    let virt_base = Address::from(1_u64 << 39);
+    let pages = [0x10000_u64, 0x11000, 0x12000, 0x20000];
+    dummy_mem
+        .phys_write((dtb + 8).into(), &(pdpt.as_u64() | 0x3))
+        .unwrap();
+    dummy_mem
+        .phys_write(pdpt.into(), &(pd.as_u64() | 0x3))
+        .unwrap();
+    dummy_mem
+        .phys_write(pd.into(), &(pt.as_u64() | 0x3))
+        .unwrap();
+    for (i, page) in pages.iter().enumerate() {
+        dummy_mem
+            .phys_write((pt + i * 8).into(), &(page | 0x3))
+            .unwrap();
+        dummy_mem
+            .phys_write_raw(Address::from(*page).into(), &[i as u8 + 1; 0x1000])
+            .unwrap();
+    }
+
+    let mut mem = TracingMemory::new(dummy_mem);
+    let mut page_reads = |granularity: usize| {
+        let mut virt_mem = VirtualDMA::new(&mut mem, x64::ARCH, x64::new_translator(dtb))
+            .with_read_granularity(granularity);
+        assert_eq!(virt_mem.read_granularity(), granularity);
+
+        let buf = virt_mem.virt_read_raw(virt_base, 0x4000).unwrap();
+        for (i, chunk) in buf.chunks(0x1000).enumerate() {
+            assert!(chunk.iter().all(|&b| b == i as u8 + 1));
+        }
+
+        std::mem::drop(virt_mem);
+
+        // skip the page table reads
+        mem.take_trace()
+            .into_iter()
+            .filter(|access| access.len >= 0x1000)
+            .map(|access| (access.addr.as_u64(), access.len))
+            .collect::<Vec<_>>()
+    };
+
+    // merging is disabled by default
+    assert_eq!(page_reads(0).len(), 4);
+    assert_eq!(
+        page_reads(size::kb(64)),
+        vec![(0x10000, 0x3000), (0x20000, 0x1000)]
+    );
+    assert_eq!(
+        page_reads(size::kb(8)),
+        vec![(0x10000, 0x2000), (0x12000, 0x1000), (0x20000, 0x1000)]
+    );
+}
+
+#[test]
+fn test_virt_page_walk() {
+    let mut dummy_mem = DummyMemory::new(size::mb(16));
+    let virt_size = size::mb(2);
+    let (dtb, virt_base) = dummy_mem.alloc_dtb(virt_size, &[]);
+    let translator = x64::new_translator(dtb);
+    let mut vat = DirectTranslate::new();
+
+    let addr = virt_base + 0x1234;
+    let steps = vat
+        .virt_page_walk(&mut dummy_mem, &translator, addr)
+        .unwrap();
+    assert!(steps.iter().all(|step| step.present));
+    assert_eq!(
+        steps.iter().map(|step| step.step).collect::<Vec<_>>(),
+        (0..steps.len()).collect::<Vec<_>>()
+    );
+    assert_eq!(steps[0].pte_addr.as_page_aligned(size::kb(4)), dtb);
+
+    // the dummy memory maps either 4kb or 2mb pages
+    let last = steps.last().unwrap();
+    let page_size = last.page_size.unwrap() as u64;
+    assert_eq!(
+        steps.len(),
+        if page_size == size::kb(4) as u64 {
+            4
+        } else {
+            3
+        }
+    );
+    assert_eq!(
+        Some(Address::from(
+            (last.pte & 0x000f_ffff_ffff_f000 & !(page_size - 1))
+                | (addr.as_u64() & (page_size - 1))
+        )),
+        dummy_mem.vtop(dtb, addr)
+    );
+
+    // the walk stops at the first entry that is not present
+    let steps = vat
+        .virt_page_walk(&mut dummy_mem, &translator, virt_base + virt_size)
+        .unwrap();
+    assert!(!steps.last().unwrap().present);
+    assert!(steps.iter().all(|step| step.page_size.is_none()));
+}
+
 #[test]
 fn test_virt_page_map() {
     let mut dummy_mem = DummyMemory::new(size::mb(16));
@@ -87,6 +293,41 @@ fn test_virt_read_small() {
     assert_eq!(buf, out);
 }
 
+#[test]
+fn test_virt_read_vec() {
+    let mut dummy_mem = DummyMemory::new(size::mb(2));
+    let input = (0..64u64).map(|i| i * 0x1000).collect::<Vec<_>>();
+    let buf = input
+        .iter()
+        .flat_map(|v| v.to_le_bytes().to_vec())
+        .collect::<Vec<_>>();
+    let (dtb, virt_base) = dummy_mem.alloc_dtb(buf.len(), &buf);
+    let translator = x64::new_translator(dtb);
+    let arch = x64::ARCH;
+    let mut virt_mem = VirtualDMA::new(&mut dummy_mem, arch, translator);
+
+    assert_eq!(
+        virt_mem
+            .virt_read_vec::<u64>(virt_base, input.len())
+            .unwrap(),
+        input
+    );
+    assert_eq!(
+        virt_mem.virt_read_vec::<u64>(virt_base + 8, 2).unwrap(),
+        vec![0x1000, 0x2000]
+    );
+    assert_eq!(
+        virt_mem
+            .virt_read_vec_swapped::<u64>(virt_base + 8, 2)
+            .unwrap(),
+        vec![0x1000, 0x2000]
+    );
+    assert!(virt_mem
+        .virt_read_vec::<u32>(virt_base, 0)
+        .unwrap()
+        .is_empty());
+}
+
 #[test]
 fn test_virt_write_small() {
     let mut dummy_mem = DummyMemory::new(size::mb(2));
@@ -302,3 +543,416 @@ fn test_virt_write_big_shifted() {
     assert_eq!(buf.to_vec().len(), input.len());
     assert_eq!(buf.to_vec(), input);
 }
+
+#[test]
+fn test_virt_read_wstr() {
+    let mut dummy_mem = DummyMemory::new(size::mb(2));
+    let mut buf = "memflow.dll"
+        .encode_utf16()
+        .flat_map(|c| c.to_le_bytes().to_vec())
+        .collect::<Vec<u8>>();
+    buf.extend_from_slice(&[0, 0, b'x', 0]);
+    let (dtb, virt_base) = dummy_mem.alloc_dtb(buf.len(), &buf);
+    let translator = x64::new_translator(dtb);
+    let arch = x64::ARCH;
+    let mut virt_mem = VirtualDMA::new(&mut dummy_mem, arch, translator);
+
+    // stops at the null terminator
+    let wstr = virt_mem.virt_read_wstr(virt_base, 64).unwrap();
+    assert_eq!(wstr, "memflow.dll");
+
+    // stops after max_chars
+    let wstr = virt_mem.virt_read_wstr(virt_base, 7).unwrap();
+    assert_eq!(wstr, "memflow");
+}
+
+#[test]
+fn test_virt_read_wstr_big_endian() {
+    let mut dummy_mem = DummyMemory::new(size::mb(2));
+    let buf = "memflow.dll"
+        .encode_utf16()
+        .chain(Some(0))
+        .flat_map(|c| c.to_be_bytes().to_vec())
+        .collect::<Vec<u8>>();
+    let (dtb, virt_base) = dummy_mem.alloc_dtb(buf.len(), &buf);
+    let translator = x64::new_translator(dtb);
+    let arch: ArchitectureObj = &BigEndian64;
+    let mut virt_mem = VirtualDMA::new(&mut dummy_mem, arch, translator);
+
+    // wide strings are decoded in the byte order of the target
+    let wstr = virt_mem.virt_read_wstr(virt_base, 64).unwrap();
+    assert_eq!(wstr, "memflow.dll");
+}
+
+#[test]
+fn test_virt_read_str_until() {
+    let mut dummy_mem = DummyMemory::new(size::mb(2));
+    let mut buf = b"cmd.exe /c dir\0\xff\xfe\0".to_vec();
+    buf.extend(
+        "C:\\Windows"
+            .encode_utf16()
+            .flat_map(|c| c.to_le_bytes().to_vec()),
+    );
+    let (dtb, virt_base) = dummy_mem.alloc_dtb(buf.len(), &buf);
+    let translator = x64::new_translator(dtb);
+    let mut virt_mem = VirtualDMA::new(&mut dummy_mem, x64::ARCH, translator);
+
+    // space delimited tokens
+    let token = virt_mem
+        .virt_read_str_until(virt_base, 64, &[b' ', 0], StrEncoding::Utf8)
+        .unwrap();
+    assert_eq!(token, "cmd.exe");
+    let token = virt_mem
+        .virt_read_str_until(virt_base + 8, 64, &[b' ', 0], StrEncoding::Utf8)
+        .unwrap();
+    assert_eq!(token, "/c");
+
+    // the same as virt_read_cstr with a null terminator
+    assert_eq!(
+        virt_mem
+            .virt_read_str_until(virt_base, 64, &[0], StrEncoding::Utf8)
+            .unwrap(),
+        virt_mem.virt_read_cstr(virt_base, 64).unwrap()
+    );
+
+    // invalid characters are replaced
+    let invalid = virt_mem
+        .virt_read_str_until(virt_base + 15, 4, &[0], StrEncoding::Ascii)
+        .unwrap();
+    assert_eq!(invalid, "\u{fffd}\u{fffd}");
+
+    // wide strings stop at the backslash or after max_chars
+    let dir = virt_mem
+        .virt_read_str_until(virt_base + 18, 64, &[b'\\'], StrEncoding::Utf16)
+        .unwrap();
+    assert_eq!(dir, "C:");
+    let path = virt_mem
+        .virt_read_str_until(virt_base + 18, 10, &[0], StrEncoding::Utf16)
+        .unwrap();
+    assert_eq!(path, "C:\\Windows");
+}
+
+#[test]
+fn test_virt_read_cstr_array() {
+    let mut dummy_mem = DummyMemory::new(size::mb(2));
+    let buf = b"PATH=C:\\Windows\0TEMP=C:\\Temp\0OS=Windows_NT\0\0garbage\0";
+    let (dtb, virt_base) = dummy_mem.alloc_dtb(buf.len(), &buf[..]);
+    let translator = x64::new_translator(dtb);
+    let arch = x64::ARCH;
+    let mut virt_mem = VirtualDMA::new(&mut dummy_mem, arch, translator);
+
+    // stops at the empty string
+    let strings = virt_mem
+        .virt_read_cstr_array(virt_base, buf.len(), 16)
+        .unwrap();
+    assert_eq!(
+        strings,
+        vec!["PATH=C:\\Windows", "TEMP=C:\\Temp", "OS=Windows_NT"]
+    );
+
+    // stops after max_strings
+    let strings = virt_mem
+        .virt_read_cstr_array(virt_base, buf.len(), 2)
+        .unwrap();
+    assert_eq!(strings, vec!["PATH=C:\\Windows", "TEMP=C:\\Temp"]);
+
+    // the last string is truncated at the end of the buffer
+    let strings = virt_mem.virt_read_cstr_array(virt_base, 19, 16).unwrap();
+    assert_eq!(strings, vec!["PATH=C:\\Windows", "TEM"]);
+}
+
+#[test]
+fn test_virt_read_str_ptr_array() {
+    let mut dummy_mem = DummyMemory::new(size::mb(2));
+    let (dtb, virt_base) = dummy_mem.alloc_dtb(size::kb(8), &[]);
+    let translator = x64::new_translator(dtb);
+    let arch = x64::ARCH;
+    let mut virt_mem = VirtualDMA::new(&mut dummy_mem, arch, translator);
+
+    // argv = { "memflow", "--verbose", "\\?\\C:\\", NULL }
+    let argv = [virt_base + 0x100, virt_base + 0x110, virt_base + 0x120];
+    for (i, ptr) in argv.iter().enumerate() {
+        virt_mem
+            .virt_write(virt_base + i * 8, &ptr.as_u64())
+            .unwrap();
+    }
+    virt_mem.virt_write(virt_base + 0x18, &0u64).unwrap();
+    virt_mem.virt_write_raw(argv[0], b"memflow\0").unwrap();
+    virt_mem.virt_write_raw(argv[1], b"--verbose\0").unwrap();
+    virt_mem.virt_write_raw(argv[2], b"\\?\\C:\\\0").unwrap();
+
+    let strings = virt_mem
+        .virt_read_str_ptr_array(arch, virt_base, 64, 32, StrEncoding::Utf8)
+        .unwrap();
+    assert_eq!(strings, vec!["memflow", "--verbose", "\\?\\C:\\"]);
+
+    // stops after max_entries and truncates the strings after max_len characters
+    let strings = virt_mem
+        .virt_read_str_ptr_array(arch, virt_base, 2, 4, StrEncoding::Utf8)
+        .unwrap();
+    assert_eq!(strings, vec!["memf", "--ve"]);
+
+    // wide strings
+    let wide = "C:\\Windows"
+        .encode_utf16()
+        .chain(Some(0))
+        .flat_map(|c| c.to_le_bytes().to_vec())
+        .collect::<Vec<u8>>();
+    virt_mem.virt_write_raw(argv[2], &wide).unwrap();
+    virt_mem
+        .virt_write(virt_base + 0x8, &argv[2].as_u64())
+        .unwrap();
+    virt_mem.virt_write(virt_base + 0x10, &0u64).unwrap();
+    let strings = virt_mem
+        .virt_read_str_ptr_array(arch, virt_base + 0x8, 64, 32, StrEncoding::Utf16)
+        .unwrap();
+    assert_eq!(strings, vec!["C:\\Windows"]);
+}
+
+/// 64-bit architecture with big endian byte order
+struct BigEndian64;
+
+impl Architecture for BigEndian64 {
+    fn bits(&self) -> u8 {
+        64
+    }
+
+    fn endianess(&self) -> Endianess {
+        Endianess::BigEndian
+    }
+
+    fn page_size(&self) -> usize {
+        size::kb(4)
+    }
+
+    fn size_addr(&self) -> usize {
+        8
+    }
+
+    fn address_space_bits(&self) -> u8 {
+        52
+    }
+}
+
+#[test]
+fn test_virt_read_big_endian() {
+    let mut dummy_mem = DummyMemory::new(size::mb(2));
+    let mut buf = vec![0u8; 0x100];
+    buf[0x10..0x14].copy_from_slice(&0x1234_5678_u32.to_be_bytes());
+    buf[0x20..0x28].copy_from_slice(&0x0000_7ff6_1234_0000_u64.to_be_bytes());
+    let (dtb, virt_base) = dummy_mem.alloc_dtb(buf.len(), &buf);
+    let translator = x64::new_translator(dtb);
+    let arch: ArchitectureObj = &BigEndian64;
+    let mut virt_mem = VirtualDMA::new(&mut dummy_mem, arch, translator);
+
+    assert_eq!(virt_mem.endianess(), Endianess::BigEndian);
+    assert_eq!(
+        virt_mem.virt_read_endian::<u32>(virt_base + 0x10).unwrap(),
+        0x1234_5678
+    );
+    assert_eq!(
+        virt_mem
+            .virt_read_addr_arch(arch, virt_base + 0x20)
+            .unwrap(),
+        Address::from(0x0000_7ff6_1234_0000_u64)
+    );
+
+    // values are written in the byte order of the target
+    virt_mem
+        .virt_write_endian(virt_base + 0x30, 0xdead_beef_u32)
+        .unwrap();
+    assert_eq!(
+        virt_mem.virt_read_raw(virt_base + 0x30, 4).unwrap(),
+        vec![0xde, 0xad, 0xbe, 0xef]
+    );
+    assert_eq!(
+        virt_mem.virt_read_endian::<u32>(virt_base + 0x30).unwrap(),
+        0xdead_beef
+    );
+
+    // x64 is little endian
+    std::mem::drop(virt_mem);
+    let mut virt_mem = VirtualDMA::new(&mut dummy_mem, x64::ARCH, translator);
+    assert_eq!(
+        virt_mem.virt_read_endian::<u32>(virt_base + 0x10).unwrap(),
+        0x7856_3412
+    );
+}
+
+#[test]
+fn test_virt_read_ptr() {
+    let mut dummy_mem = DummyMemory::new(size::mb(2));
+    let mut buf = vec![0u8; 0x100];
+    buf[0x10..0x14].copy_from_slice(&0x7ffd_e000_u32.to_le_bytes());
+    buf[0x18..0x1c].copy_from_slice(&0x8123_4000_u32.to_le_bytes());
+    buf[0x20..0x28].copy_from_slice(&0xffff_f803_1234_0000_u64.to_le_bytes());
+    let (dtb, virt_base) = dummy_mem.alloc_dtb(buf.len(), &buf);
+    let translator = x64::new_translator(dtb);
+    let mut virt_mem = VirtualDMA::new(&mut dummy_mem, x64::ARCH, translator);
+
+    assert_eq!(
+        virt_mem
+            .virt_read_ptr(virt_base + 0x10, PtrWidth::Ptr32)
+            .unwrap(),
+        Address::from(0x7ffd_e000_u64)
+    );
+    assert_eq!(
+        virt_mem
+            .virt_read_ptr(virt_base + 0x10, PtrWidth::Ptr32Signed)
+            .unwrap(),
+        Address::from(0x7ffd_e000_u64)
+    );
+    assert_eq!(
+        virt_mem
+            .virt_read_ptr(virt_base + 0x18, PtrWidth::Ptr32)
+            .unwrap(),
+        Address::from(0x8123_4000_u64)
+    );
+    assert_eq!(
+        virt_mem
+            .virt_read_ptr(virt_base + 0x18, PtrWidth::Ptr32Signed)
+            .unwrap(),
+        Address::from(0xffff_ffff_8123_4000_u64)
+    );
+    assert_eq!(
+        virt_mem
+            .virt_read_ptr(virt_base + 0x20, PtrWidth::Ptr64)
+            .unwrap(),
+        Address::from(0xffff_f803_1234_0000_u64)
+    );
+
+    assert_eq!(PtrWidth::from_arch(x64::ARCH).unwrap(), PtrWidth::Ptr64);
+    assert_eq!(PtrWidth::Ptr32Signed.size(), 4);
+}
+
+#[test]
+fn test_virt_read_chain() {
+    let mut dummy_mem = DummyMemory::new(size::mb(2));
+    // the mapping has to be below 4gb so it can be reached through 32-bit pointers
+    let virt_base = Address::from(0x10_0000);
+    let dtb = dummy_mem.alloc_dtb_const_base(virt_base, size::kb(4), &[0; 0x1000]);
+    let translator = x64::new_translator(dtb);
+    let mut virt_mem = VirtualDMA::new(&mut dummy_mem, x64::ARCH, translator);
+
+    // a->b (64-bit) -> c (32-bit) -> value
+    let a = virt_base;
+    let b = virt_base + 0x100;
+    let c = virt_base + 0x200;
+    virt_mem.virt_write(a + 0x8, &b.as_u64()).unwrap();
+    virt_mem.virt_write(b + 0x10, &(c.as_u64() as u32)).unwrap();
+    virt_mem.virt_write(c + 0x18, &0xdead_beef_u32).unwrap();
+
+    assert_eq!(
+        virt_mem
+            .virt_read_addr_chain(PtrWidth::Ptr64, a, &[0x8])
+            .unwrap(),
+        b
+    );
+    assert_eq!(
+        virt_mem
+            .virt_read_addr_chain(PtrWidth::Ptr64, a, &[])
+            .unwrap(),
+        a
+    );
+    assert_eq!(
+        virt_mem
+            .virt_read_chain::<u32>(PtrWidth::Ptr64, a + 0x8, &[0, 0x10])
+            .unwrap(),
+        c.as_u64() as u32
+    );
+    assert_eq!(
+        virt_mem
+            .virt_read_chain::<u32>(PtrWidth::Ptr32, b + 0x10, &[0, 0x18])
+            .unwrap(),
+        0xdead_beef
+    );
+    assert_eq!(
+        virt_mem
+            .virt_read_chain::<u32>(PtrWidth::Ptr64, c, &[0x18])
+            .unwrap(),
+        0xdead_beef
+    );
+
+    // c + 0x20 does not contain a pointer
+    assert_eq!(
+        virt_mem.virt_read_chain::<u32>(PtrWidth::Ptr64, c, &[0x20, 0]),
+        Err(PartialError::Error(Error::Other(
+            "null pointer in pointer chain"
+        )))
+    );
+}
+
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq, Pod)]
+struct SwapTest {
+    a: u32,
+    b: u16,
+    c: u16,
+    d: u64,
+}
+
+impl ByteSwap for SwapTest {
+    fn byte_swap(&mut self) {
+        self.a.byte_swap();
+        self.b.byte_swap();
+        self.c.byte_swap();
+        self.d.byte_swap();
+    }
+}
+
+#[test]
+fn test_virt_read_swapped() {
+    let mut dummy_mem = DummyMemory::new(size::mb(2));
+    let mut buf = vec![0u8; 0x100];
+    buf[0x10..0x14].copy_from_slice(&0x1234_5678_u32.to_be_bytes());
+    buf[0x14..0x16].copy_from_slice(&0xabcd_u16.to_be_bytes());
+    buf[0x16..0x18].copy_from_slice(&0x00ff_u16.to_be_bytes());
+    buf[0x18..0x20].copy_from_slice(&0x0000_7ff6_1234_0000_u64.to_be_bytes());
+    let (dtb, virt_base) = dummy_mem.alloc_dtb(buf.len(), &buf);
+    let translator = x64::new_translator(dtb);
+    let arch: ArchitectureObj = &BigEndian64;
+    let mut virt_mem = VirtualDMA::new(&mut dummy_mem, arch, translator);
+
+    let expected = SwapTest {
+        a: 0x1234_5678,
+        b: 0xabcd,
+        c: 0x00ff,
+        d: 0x0000_7ff6_1234_0000,
+    };
+    assert_eq!(
+        virt_mem
+            .virt_read_swapped::<SwapTest>(virt_base + 0x10)
+            .unwrap(),
+        expected
+    );
+
+    // the structure is written in the byte order of the target
+    virt_mem
+        .virt_write_swapped(virt_base + 0x40, &expected)
+        .unwrap();
+    assert_eq!(
+        virt_mem.virt_read_raw(virt_base + 0x40, 0x10).unwrap(),
+        virt_mem.virt_read_raw(virt_base + 0x10, 0x10).unwrap()
+    );
+
+    // physical memory requires the byte order to be specified explicitly
+    let phys = virt_mem
+        .virt_translation_map()
+        .into_iter()
+        .find(|&(virt, size, _)| virt <= virt_base && virt_base < virt + size)
+        .map(|(virt, _, phys)| phys.address() + (virt_base - virt) + 0x10)
+        .unwrap();
+    std::mem::drop(virt_mem);
+    assert_eq!(
+        dummy_mem
+            .phys_read_swapped::<SwapTest>(phys.into(), Endianess::BigEndian)
+            .unwrap(),
+        expected
+    );
+    assert_ne!(
+        dummy_mem
+            .phys_read_swapped::<SwapTest>(phys.into(), Endianess::LittleEndian)
+            .unwrap(),
+        expected
+    );
+}
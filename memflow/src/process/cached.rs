@@ -0,0 +1,204 @@
+/*!
+Result cache for objects implementing the `OperatingSystem` trait.
+
+Retrieving the process list usually requires walking all kernel process structures.
+Tools that query the same target repeatedly (e.g. a dashboard polling the process list)
+can wrap the operating system in a `CachedOs` to reuse the last result for a configurable amount of time.
+
+The cache can be invalidated explicitly via `CachedOs::refresh()`.
+
+# Examples
+
+```
+use std::time::Duration;
+use memflow::process::{CachedOs, OperatingSystem, OsProcessInfo};
+
+fn poll<O: OperatingSystem>(os: O)
+where
+    O::ProcessInfoType: Clone,
+{
+    let mut os = CachedOs::new(os, Duration::from_secs(1));
+
+    // only the first call walks the process list of the target
+    for _ in 0..10 {
+        if let Ok(list) = os.process_info_list() {
+            for process in list.iter() {
+                println!("{} {}", process.pid(), process.name());
+            }
+        }
+    }
+
+    // force re-reading the process list on the next call
+    os.refresh();
+}
+```
+*/
+
+use std::prelude::v1::*;
+
+use super::{OperatingSystem, OsInfo};
+
+use std::time::{Duration, Instant};
+
+/// Wrapper that caches the process list of the underlying operating system for a fixed amount of time.
+///
+/// Since this wrapper implements `OperatingSystem` it can be used as a replacement
+/// in all structs and functions that require an `OperatingSystem` object.
+pub struct CachedOs<O: OperatingSystem> {
+    os: O,
+    ttl: Duration,
+    process_list: Option<(Instant, Vec<O::ProcessInfoType>)>,
+}
+
+impl<O: OperatingSystem> CachedOs<O>
+where
+    O::ProcessInfoType: Clone,
+{
+    /// Creates a new wrapper that keeps results for `ttl`.
+    ///
+    /// A `ttl` of zero disables caching.
+    pub fn new(os: O, ttl: Duration) -> Self {
+        Self {
+            os,
+            ttl,
+            process_list: None,
+        }
+    }
+
+    /// Returns the time a result is kept in the cache.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Changes the time a result is kept in the cache.
+    pub fn set_ttl(&mut self, ttl: Duration) {
+        self.ttl = ttl;
+    }
+
+    /// Drops all cached results, the next query is forwarded to the underlying operating system.
+    pub fn refresh(&mut self) {
+        self.process_list = None;
+    }
+
+    /// Returns a reference to the underlying operating system.
+    pub fn os(&self) -> &O {
+        &self.os
+    }
+
+    /// Returns a mutable reference to the underlying operating system.
+    ///
+    /// Changes made through this reference are not reflected in cached results until they expire.
+    pub fn os_mut(&mut self) -> &mut O {
+        &mut self.os
+    }
+
+    /// Consumes the wrapper and returns the underlying operating system.
+    pub fn destroy(self) -> O {
+        self.os
+    }
+}
+
+impl<O: OperatingSystem> OperatingSystem for CachedOs<O>
+where
+    O::ProcessInfoType: Clone,
+{
+    type ProcessInfoType = O::ProcessInfoType;
+    type Error = O::Error;
+
+    fn process_info_list(&mut self) -> Result<Vec<Self::ProcessInfoType>, Self::Error> {
+        if let Some((time, list)) = &self.process_list {
+            if time.elapsed() < self.ttl {
+                return Ok(list.clone());
+            }
+        }
+
+        let list = self.os.process_info_list()?;
+        self.process_list = Some((Instant::now(), list.clone()));
+        Ok(list)
+    }
+
+    fn os_info(&mut self) -> Result<OsInfo, Self::Error> {
+        self.os.os_info()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::architecture::{x86::x64, ArchitectureObj};
+    use crate::error::Error;
+    use crate::process::{OsKind, OsProcessInfo, PID};
+    use crate::types::Address;
+
+    #[derive(Clone)]
+    struct TestProcess(PID);
+
+    impl OsProcessInfo for TestProcess {
+        fn address(&self) -> Address {
+            Address::NULL
+        }
+
+        fn pid(&self) -> PID {
+            self.0
+        }
+
+        fn name(&self) -> String {
+            format!("process{}", self.0)
+        }
+
+        fn sys_arch(&self) -> ArchitectureObj {
+            x64::ARCH
+        }
+
+        fn proc_arch(&self) -> ArchitectureObj {
+            x64::ARCH
+        }
+    }
+
+    /// Operating system that spawns a new process on every walk of the process list
+    #[derive(Default)]
+    struct TestOs {
+        walks: u32,
+    }
+
+    impl OperatingSystem for TestOs {
+        type ProcessInfoType = TestProcess;
+        type Error = Error;
+
+        fn process_info_list(&mut self) -> Result<Vec<TestProcess>, Error> {
+            self.walks += 1;
+            Ok((0..self.walks).map(TestProcess).collect())
+        }
+
+        fn os_info(&mut self) -> Result<OsInfo, Error> {
+            Ok(OsInfo {
+                kind: OsKind::Windows,
+                version: None,
+                arch: x64::ARCH,
+            })
+        }
+    }
+
+    #[test]
+    fn cached_process_list() {
+        let mut os = CachedOs::new(TestOs::default(), Duration::from_secs(3600));
+        assert_eq!(os.process_info_list().unwrap().len(), 1);
+        assert_eq!(os.process_info_list().unwrap().len(), 1);
+        assert_eq!(os.process_info_by_pid(0).unwrap().pid(), 0);
+        assert!(os.process_info_by_name("process1").is_err());
+        assert_eq!(os.os().walks, 1);
+
+        os.refresh();
+        assert_eq!(os.process_info_by_name("process1").unwrap().pid(), 1);
+        assert_eq!(os.os().walks, 2);
+    }
+
+    #[test]
+    fn zero_ttl() {
+        let mut os = CachedOs::new(TestOs::default(), Duration::from_secs(0));
+        assert_eq!(os.ttl(), Duration::from_secs(0));
+        os.process_info_list().unwrap();
+        os.process_info_list().unwrap();
+        assert_eq!(os.destroy().walks, 2);
+    }
+}
@@ -2,13 +2,86 @@
 Traits for OS independent process abstractions.
 */
 
+#[cfg(feature = "std")]
+pub mod cached;
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use cached::CachedOs;
+
 use std::prelude::v1::*;
 
 use crate::architecture::ArchitectureObj;
+use crate::error::Error;
 use crate::types::Address;
 
+/// The family of an operating system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum OsKind {
+    Windows,
+    Linux,
+}
+
+/// OS independent information about the target operating system.
+#[derive(Debug, Clone)]
+pub struct OsInfo {
+    /// The family of the operating system
+    pub kind: OsKind,
+    /// Human readable version of the operating system, if it could be determined
+    pub version: Option<String>,
+    /// The architecture of the operating system kernel
+    pub arch: ArchitectureObj,
+}
+
 /// Trait describing a operating system
-pub trait OperatingSystem {}
+///
+/// This allows writing tools that are generic over the underlying OS layer.
+///
+/// # Examples
+///
+/// ```
+/// use memflow::process::{OperatingSystem, OsProcessInfo};
+///
+/// fn print_processes<T: OperatingSystem>(os: &mut T) -> Result<(), T::Error> {
+///     for process in os.process_info_list()?.iter() {
+///         println!("{} {}", process.pid(), process.name());
+///     }
+///     Ok(())
+/// }
+/// ```
+pub trait OperatingSystem {
+    /// The process information type of this operating system.
+    type ProcessInfoType: OsProcessInfo;
+    /// The error type of this operating system.
+    type Error: From<Error>;
+
+    /// Retrieves a list of all processes that can be found on the target system.
+    fn process_info_list(&mut self) -> Result<Vec<Self::ProcessInfoType>, Self::Error>;
+
+    /// Finds a process by its process id.
+    ///
+    /// The default implementation searches the list returned by `process_info_list`.
+    fn process_info_by_pid(&mut self, pid: PID) -> Result<Self::ProcessInfoType, Self::Error> {
+        self.process_info_list()?
+            .into_iter()
+            .find(|process| process.pid() == pid)
+            .ok_or_else(|| Error::Other("unable to find process").into())
+    }
+
+    /// Finds a process by its name.
+    ///
+    /// The default implementation searches the list returned by `process_info_list`
+    /// and compares the names case-sensitively.
+    fn process_info_by_name(&mut self, name: &str) -> Result<Self::ProcessInfoType, Self::Error> {
+        self.process_info_list()?
+            .into_iter()
+            .find(|process| process.name() == name)
+            .ok_or_else(|| Error::Other("unable to find process").into())
+    }
+
+    /// Retrieves information about the operating system itself.
+    fn os_info(&mut self) -> Result<OsInfo, Self::Error>;
+}
 
 /// Type alias for a PID.
 pub type PID = u32;
@@ -44,6 +117,29 @@ pub trait OsProcessInfo {
     ///
     /// On windows this technique is called [`WOW64`](https://docs.microsoft.com/en-us/windows/win32/winprog64/wow64-implementation-details).
     fn proc_arch(&self) -> ArchitectureObj;
+
+    /// Returns the memory usage of the process at the time the information was gathered.
+    ///
+    /// # Remarks
+    ///
+    /// All values are zero if the OS layer does not support retrieving the memory usage.
+    fn memory_info(&self) -> ProcessMemoryInfo {
+        ProcessMemoryInfo::default()
+    }
+}
+
+/// OS independent memory usage statistics of a process.
+///
+/// All sizes are in bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct ProcessMemoryInfo {
+    /// Size of the physical memory that is currently mapped into the process (working set).
+    pub working_set_size: usize,
+    /// Size of the virtual address space that is reserved by the process.
+    pub virtual_size: usize,
+    /// Size of the memory that is committed exclusively by this process (private bytes).
+    pub private_bytes: usize,
 }
 
 // TODO: Range impl for base to size?
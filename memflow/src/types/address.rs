@@ -2,6 +2,8 @@
 Abstraction over a address on the target system.
 */
 
+use crate::architecture::ArchitectureObj;
+
 use core::convert::TryInto;
 use std::default::Default;
 use std::fmt;
@@ -257,6 +259,77 @@ impl Address {
     pub fn extract_bits<T: TryInto<u64>>(self, bits: ops::Range<T>) -> Address {
         (self.0 & Address::bit_mask(bits).as_u64()).into()
     }
+
+    /// Returns a wrapper that formats the address for the given architecture.
+    ///
+    /// The address is zero-padded to the pointer width of the architecture.
+    /// When formatted with the alternate flag (`{:#}`) addresses in the kernel half of the address space
+    /// are marked with `(kernel)` and non-canonical 64-bit addresses with `(non-canonical)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use memflow::architecture::x86::{x32, x64};
+    /// use memflow::types::Address;
+    ///
+    /// let addr = Address::from(0x7ff6_1000);
+    /// assert_eq!(addr.format_for(x32::ARCH).to_string(), "7ff61000");
+    /// assert_eq!(addr.format_for(x64::ARCH).to_string(), "000000007ff61000");
+    ///
+    /// let addr = Address::from(0xffff_f800_0000_0000_u64);
+    /// assert_eq!(format!("{:#}", addr.format_for(x64::ARCH)), "fffff80000000000 (kernel)");
+    /// ```
+    pub fn format_for(self, arch: ArchitectureObj) -> ArchAddress {
+        ArchAddress {
+            address: self,
+            arch,
+        }
+    }
+}
+
+/// Formats an `Address` according to the pointer width of an architecture.
+///
+/// This type is constructed via `Address::format_for`.
+#[derive(Copy, Clone)]
+pub struct ArchAddress {
+    address: Address,
+    arch: ArchitectureObj,
+}
+
+impl fmt::Display for ArchAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self.arch.size_addr() * 2;
+        write!(f, "{:0width$x}", self.address.0, width = width)?;
+
+        if f.alternate() {
+            let addr = self.address.0;
+            match self.arch.bits() {
+                64 => {
+                    // virtual addresses have to be sign extended from the highest implemented bit
+                    let sign_bit = self.arch.virtual_address_bits().min(64) - 1;
+                    let upper = addr >> sign_bit;
+                    if upper == u64::MAX >> sign_bit {
+                        write!(f, " (kernel)")?;
+                    } else if upper != 0 {
+                        write!(f, " (non-canonical)")?;
+                    }
+                }
+                32 => {
+                    if addr >= 0x8000_0000 {
+                        write!(f, " (kernel)")?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for ArchAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
 }
 
 /// Returns a address with a value of zero.
@@ -466,4 +539,62 @@ mod tests {
         assert_eq!(Address::from(10) - Address::from(5), 5usize);
         assert_eq!(Address::from(100) - 5usize, Address::from(95));
     }
+
+    #[test]
+    fn test_format_for() {
+        use crate::architecture::x86::{x32, x32_pae, x64};
+
+        let addr = Address::from(0x1000);
+        assert_eq!(addr.format_for(x32::ARCH).to_string(), "00001000");
+        assert_eq!(addr.format_for(x32_pae::ARCH).to_string(), "00001000");
+        assert_eq!(addr.format_for(x64::ARCH).to_string(), "0000000000001000");
+        assert_eq!(
+            format!("{:#}", addr.format_for(x64::ARCH)),
+            "0000000000001000"
+        );
+
+        let addr = Address::from(0x8000_1000_u64);
+        assert_eq!(
+            format!("{:#}", addr.format_for(x32::ARCH)),
+            "80001000 (kernel)"
+        );
+
+        let addr = Address::from(0x0000_8000_0000_0000_u64);
+        assert_eq!(
+            format!("{:#}", addr.format_for(x64::ARCH)),
+            "0000800000000000 (non-canonical)"
+        );
+    }
+
+    #[test]
+    fn test_format_for_la57() {
+        use crate::architecture::x86::{x64, x64_la57};
+
+        // user mode address that is only canonical with 5-level paging
+        let addr = Address::from(0x00ff_8000_0000_0000_u64);
+        assert_eq!(
+            format!("{:#}", addr.format_for(x64_la57::ARCH)),
+            "00ff800000000000"
+        );
+        assert_eq!(
+            format!("{:#}", addr.format_for(x64::ARCH)),
+            "00ff800000000000 (non-canonical)"
+        );
+
+        let addr = Address::from(0xff01_0000_0000_0000_u64);
+        assert_eq!(
+            format!("{:#}", addr.format_for(x64_la57::ARCH)),
+            "ff01000000000000 (kernel)"
+        );
+        assert_eq!(
+            format!("{:#}", addr.format_for(x64::ARCH)),
+            "ff01000000000000 (non-canonical)"
+        );
+
+        let addr = Address::from(0xfe00_0000_0000_0000_u64);
+        assert_eq!(
+            format!("{:#}", addr.format_for(x64_la57::ARCH)),
+            "fe00000000000000 (non-canonical)"
+        );
+    }
 }
@@ -2,8 +2,12 @@
 Trait for byte-swappable basic types.
 
 The trait is used in conjunction with the `#[derive(ByteSwap)]` derive macro.
+Types implementing `ByteSwap` and `Pod` can be read and written in the byte order of the target
+via `VirtualMemory::virt_read_swapped` and `PhysicalMemory::phys_read_swapped`.
 */
 
+use crate::architecture::Endianess;
+
 use core::marker::PhantomData;
 
 /// A trait specifying that a type/struct can be byte swapped.
@@ -34,6 +38,16 @@ use core::marker::PhantomData;
 /// ```
 pub trait ByteSwap {
     fn byte_swap(&mut self);
+
+    /// Converts the value between the given byte order and the byte order of the host.
+    ///
+    /// The conversion is symmetric, it is used to convert values after reading them from a target
+    /// as well as before writing them back.
+    fn byte_swap_endianess(&mut self, endianess: Endianess) {
+        if endianess != Endianess::host() {
+            self.byte_swap();
+        }
+    }
 }
 
 // signed types
@@ -252,4 +266,18 @@ mod tests {
         slice.byte_swap();
         assert_eq!(slice[0], 1234);
     }
+
+    #[test]
+    fn swap_endianess() {
+        let mut num = 1234u32;
+        num.byte_swap_endianess(Endianess::host());
+        assert_eq!(num, 1234);
+
+        let foreign = match Endianess::host() {
+            Endianess::LittleEndian => Endianess::BigEndian,
+            Endianess::BigEndian => Endianess::LittleEndian,
+        };
+        num.byte_swap_endianess(foreign);
+        assert_eq!(num, 1234u32.swap_bytes());
+    }
 }